@@ -1,89 +1,198 @@
 use std::{
     collections::HashMap,
     sync::Arc,
-    time::{Duration, Instant},
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use def::{cube, Boxel, ChunkCoords, RayTravel};
+use def::{cube, Block, BlockCoords, ChunkCoords};
+use glium::Program;
 use glium::{
+    framebuffer::SimpleFrameBuffer,
     glutin::{
         event::{
-            DeviceEvent, ElementState, Event, KeyboardInput, StartCause, VirtualKeyCode,
-            WindowEvent,
+            DeviceEvent, ElementState, Event, KeyboardInput, MouseButton, MouseScrollDelta,
+            StartCause, VirtualKeyCode, WindowEvent,
         },
         event_loop::{ControlFlow, EventLoop},
         window::WindowBuilder,
         ContextBuilder,
     },
     index::PrimitiveType,
-    texture::RawImage2d,
+    texture::{
+        DepthTexture2d, MipmapsOption, RawImage2d, SrgbTexture2d, Texture2d,
+        UncompressedFloatFormat,
+    },
     DepthTest, Display, Frame, Surface,
 };
-use glium::{texture::SrgbTexture2dArray, Program};
-use mat::{Affine, AffineTrait, MatrixTrait, VectorTrait};
-use tokio::sync::mpsc::Receiver;
+use image::RgbaImage;
+use mat::{Affine, AffineTrait, MatrixTrait, Projection, VectorTrait};
+use tokio::sync::{
+    broadcast::{self, error::TryRecvError},
+    mpsc::Receiver,
+};
 
+mod chest;
+use chest::ChestView;
 mod control;
 use control::Control;
-mod chunk_loader;
-use chunk_loader::ChunkLoader;
+pub(crate) mod chunk_loader;
+use chunk_loader::{ChunkLoader, RenderedChunk};
+mod entity;
+use entity::{EntityRenderer, PlayerPose};
+mod feedback;
+use feedback::Feedback;
+mod grid;
+mod hotbar;
+use hotbar::Hotbar;
+mod icons;
+use icons::Icons;
+mod minimap;
+use minimap::{MapView, Minimap};
+mod palette;
+mod particles;
+use particles::Particles;
+mod photo;
+use photo::{DepthOfField, Photo, FOCUS_RANGE, SUPERSAMPLING};
+mod picker;
+use picker::Picker;
+mod overlay;
+mod profiler;
+mod render_distance;
+use render_distance::AutoDistance;
+mod spectator;
+mod stamina;
+use spectator::Spectator;
+mod text;
+use text::{Anchor, FloatingTexts, Label, Toasts};
+mod view_model;
+use view_model::ViewModel;
+mod weather;
+use weather::WeatherRenderer;
 
 use crate::{
-    mesh::{ColoredMesh, Drawable, TexturedMesh},
-    world::World,
+    atlas::Textures,
+    camera::Camera,
+    config::{Palette, RenderDistance},
+    i18n::tr,
+    mesh::{ColoredMesh, Drawable, TexturedMesh, TexturedUniforms},
+    pack,
+    world::{
+        self, glide, Entity, EntityKind, Event as WorldEvent, Exertion, Facing, GameMode, Phase,
+        Player, RayHit, World, ALL_SECTIONS, SECTIONS,
+    },
     AristideCmd, Cmd,
 };
 
 const FRAME_DURATION: Duration = Duration::from_nanos(16_666_667);
 
+/// Scales the HUD (drawn from -1.0 to 1.0 in height) to the screen
 fn aspect_ratio((width, height): (u32, u32)) -> [[f32; 4]; 4] {
     [
         [(height as f32 / width as f32), 0.0, 0.0, 0.0],
         [0.0, 1.0, 0.0, 0.0],
-        [0.0, 0.0, 1.0, 1.0],
+        [0.0, 0.0, 1.0, 0.0],
         [0.0, 0.0, 0.0, 1.0],
     ]
 }
 
-fn perspective(fov: f32) -> [[f32; 4]; 4] {
-    let f = 1.0 / (fov / 2.0).tan();
-    let zfar = 1024.0;
-    let znear = 0.1;
-    let deno = zfar - znear;
+/// The x of the camera goes to its left, the x of the screen to its right
+const MIRROR: [[f32; 4]; 4] = [
+    [-1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// Vertical field of view (in degrees) when walking
+const FOV: f32 = 70.0;
+/// The view widens when sprinting
+const SPRINT_FOV: f32 = 80.0;
+/// The view narrows when zooming
+const ZOOM_FOV: f32 = 30.0;
+
+/// Colors of the name tags, of the state of the entities (when debugging)
+/// and of the damage numbers
+const NAME_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+const DEBUG_COLOR: [f32; 3] = [1.0, 0.9, 0.3];
+const DAMAGE_COLOR: [f32; 3] = [1.0, 0.25, 0.2];
+
+/// Below this upward speed, the player stops bouncing and stands
+const MIN_BOUNCE: f32 = 0.05;
+
+/// Where the label of an entity floats, above its middle
+fn label_pos(entity: &Entity) -> [f32; 3] {
+    let hit_box = entity.hit_box();
+    let [x, _, z] = entity.pos;
+    [x, hit_box.pos[1] + hit_box.dimensions[1] + 0.2, z]
+}
+
+/// The sprites of the game followed by the textures of the content packs
+fn load_sprites(packs: Vec<RgbaImage>) -> Vec<RgbaImage> {
+    // Textures are directly embeded in the executable
     [
-        [f, 0.0, 0.0, 0.0],
-        [0.0, -f, 0.0, 0.0],
-        [0.0, 0.0, (zfar + znear) / deno, 1.0],
-        [0.0, 0.0, -(2.0 * zfar * znear) / deno, 0.0],
+        include_bytes!("aristide/textures/0.png").as_slice(),
+        include_bytes!("aristide/textures/1.png").as_slice(),
+        include_bytes!("aristide/textures/2.png").as_slice(),
+        include_bytes!("aristide/textures/3.png").as_slice(),
+        include_bytes!("aristide/textures/4.png").as_slice(),
+        include_bytes!("aristide/textures/5.png").as_slice(),
+        include_bytes!("aristide/textures/6.png").as_slice(),
+        include_bytes!("aristide/textures/7.png").as_slice(),
+        include_bytes!("aristide/textures/8.png").as_slice(),
+        include_bytes!("aristide/textures/9.png").as_slice(),
+        include_bytes!("aristide/textures/10.png").as_slice(),
+        include_bytes!("aristide/textures/11.png").as_slice(),
+        include_bytes!("aristide/textures/12.png").as_slice(),
+        include_bytes!("aristide/textures/13.png").as_slice(),
+        include_bytes!("aristide/textures/14.png").as_slice(),
+        include_bytes!("aristide/textures/15.png").as_slice(),
+        include_bytes!("aristide/textures/16.png").as_slice(),
+        include_bytes!("aristide/textures/17.png").as_slice(),
+        include_bytes!("aristide/textures/18.png").as_slice(),
+        include_bytes!("aristide/textures/19.png").as_slice(),
+        include_bytes!("aristide/textures/20.png").as_slice(),
+        include_bytes!("aristide/textures/21.png").as_slice(),
+        include_bytes!("aristide/textures/22.png").as_slice(),
+        include_bytes!("aristide/textures/23.png").as_slice(),
+        include_bytes!("aristide/textures/24.png").as_slice(),
+        include_bytes!("aristide/textures/25.png").as_slice(),
+        include_bytes!("aristide/textures/26.png").as_slice(),
+        include_bytes!("aristide/textures/27.png").as_slice(),
+        include_bytes!("aristide/textures/28.png").as_slice(),
+        include_bytes!("aristide/textures/29.png").as_slice(),
+        include_bytes!("aristide/textures/30.png").as_slice(),
+        include_bytes!("aristide/textures/31.png").as_slice(),
+        include_bytes!("aristide/textures/32.png").as_slice(),
+        include_bytes!("aristide/textures/33.png").as_slice(),
     ]
+    .iter()
+    .map(std::io::Cursor::new)
+    .map(|v| image::load(v, image::ImageFormat::Png).unwrap().to_rgba8())
+    .chain(packs)
+    .collect()
 }
 
-fn load_textures(display: &Display) -> SrgbTexture2dArray {
-    // Textures are directly embeded in the executable
-    SrgbTexture2dArray::new(
+/// The outline of the pointed block
+fn block_select(display: &Display, color: [f32; 3]) -> ColoredMesh {
+    ColoredMesh::new(
         display,
-        [
-            include_bytes!("aristide/textures/0.png").as_slice(),
-            include_bytes!("aristide/textures/1.png").as_slice(),
-            include_bytes!("aristide/textures/2.png").as_slice(),
-            include_bytes!("aristide/textures/3.png").as_slice(),
-            include_bytes!("aristide/textures/4.png").as_slice(),
-            include_bytes!("aristide/textures/5.png").as_slice(),
-            include_bytes!("aristide/textures/6.png").as_slice(),
-            include_bytes!("aristide/textures/7.png").as_slice(),
-            include_bytes!("aristide/textures/8.png").as_slice(),
-            include_bytes!("aristide/textures/9.png").as_slice(),
-        ]
-        .iter()
-        .map(std::io::Cursor::new)
-        .map(|v| {
-            let v = image::load(v, image::ImageFormat::Png).unwrap().to_rgba8();
-            let dimensions = v.dimensions();
-            RawImage2d::from_raw_rgba_reversed(&v.into_raw(), dimensions)
-        })
-        .collect(),
+        &cube::LINE_VERTICES.map(|v| (v.map(|c| c as f32), color).into()),
+        &cube::LINE_INDICES,
+        PrimitiveType::LinesList,
     )
-    .unwrap()
+    .depth_test(DepthTest::IfLessOrEqual)
+    .line_width(2.0)
+}
+
+fn cursor(display: &Display, color: [f32; 3]) -> ColoredMesh {
+    ColoredMesh::new(
+        display,
+        &[([0.0, 0.0, 0.0], color).into()],
+        &[0],
+        PrimitiveType::Points,
+    )
+    .point_size(4.0)
 }
 
 struct Renderer {
@@ -94,74 +203,182 @@ struct Renderer {
     world: Arc<World>,
     receiver_cmd: Receiver<AristideCmd>, // Receive commands from other threads
     chunk_loader: ChunkLoader,
-    rendered_chunk: HashMap<ChunkCoords, TexturedMesh>,
-    textures: SrgbTexture2dArray,
+    rendered_chunk: HashMap<ChunkCoords, RenderedChunk>,
+    textures: Textures,
+    /// The blocks as shown in the HUD
+    icons: Icons,
+    /// The blocks of the number keys
+    hotbar: Hotbar,
+    /// The screen listing the blocks, while it is open
+    picker: Option<Picker>,
+    /// The screen of the chest opened, while it is open
+    chest: Option<ChestView>,
+    entities: EntityRenderer,
+    /// The camera is behind the player, who is drawn
+    third_person: bool,
+    /// The arm holding the block, seen in first person
+    view_model: ViewModel,
+    /// Hurt marks and screen shake
+    feedback: Feedback,
+    /// Walking animation of the player (see `PlayerPose`)
+    walk: f32,
+    stride: f32,
+    /// Field of view, easing toward the one of the current movement
+    fov: f32,
+    /// Horizontal movement of the last tick, kept on slippery floors
+    momentum: [f32; 2],
+    /// The last floor stood on, its surface changes the steps (see
+    /// `Block::friction` and `Block::speed`)
+    floor: Option<Block>,
+    /// What happens in the world (like impacts)
+    events: broadcast::Receiver<WorldEvent>,
+    /// Debris of the projectiles
+    debris: Particles,
+    /// Fire of the explosions
+    fire: Particles,
+    /// The damage numbers of the hits
+    floating: FloatingTexts,
+    /// The achievements unlocked
+    toasts: Toasts,
+    weather: WeatherRenderer,
+    minimap: Minimap,
+    /// The full screen map, while it is open
+    map: Option<MapView>,
+    /// The durations of the phases of the frames and where the player is
+    /// are shown
+    debug: bool,
+    /// Colors of the outline, the crosshair and the HUD (from the settings)
+    palette: Palette,
+    /// The free camera, while the player is left behind
+    spectator: Option<Spectator>,
+    /// The photo mode, while taking pictures
+    photo: Option<Photo>,
+    depth_of_field: DepthOfField,
+    /// Where the photo is rendered before the depth of field, kept as long
+    /// as the size asked does not change
+    photo_targets: Option<(Texture2d, DepthTexture2d)>,
+    /// Tunes the render distance, if asked to in the settings
+    auto_distance: Option<AutoDistance>,
+    /// The chunks to render (or to drop, with None) and their sections,
+    /// some waiting for the next frames when the uploads are limited
+    backlog: HashMap<ChunkCoords, Option<world::Sections>>,
 }
 impl Renderer {
     fn new(
         display: &Display,
         world: Arc<World>,
         receiver_from_cassiope_chunk: Receiver<AristideCmd>,
+        packs: Vec<RgbaImage>,
     ) -> Self {
+        let (atlas, palette, render_distance) = {
+            let config = world.config.read().unwrap();
+            (config.atlas, config.palette, config.render_distance)
+        };
+        let sprites = load_sprites(packs);
+        let textures = Textures::new(display, &sprites, atlas);
+        let colors = palette.colors();
         Self {
             // Load shader for colored mesh
             colored_program: ColoredMesh::program(display),
             // Load shader for textured mesh
-            textured_program: TexturedMesh::program(display),
+            textured_program: TexturedMesh::program(display, &textures),
             // Load mesh for cube highlighting
-            block_select: {
-                ColoredMesh::new(
-                    &display,
-                    &cube::LINE_VERTICES.map(|v| (v.map(|c| c as f32), [0.0, 0.0, 0.0]).into()),
-                    &cube::LINE_INDICES,
-                    PrimitiveType::LinesList,
-                )
-                .depth_test(DepthTest::IfLessOrEqual)
-                .line_width(2.0)
-            },
+            block_select: block_select(display, colors.outline),
             // Load cursor mesh
-            cursor: ColoredMesh::new(
-                &display,
-                &[([0.0, 0.0, 0.0], [0.0, 0.0, 0.0]).into()],
-                &[0],
-                PrimitiveType::Points,
-            )
-            .point_size(4.0),
-            world,
+            cursor: cursor(display, colors.crosshair),
             receiver_cmd: receiver_from_cassiope_chunk,
             chunk_loader: ChunkLoader::new(),
             rendered_chunk: HashMap::new(),
-            textures: load_textures(&display),
+            textures,
+            icons: Icons::new(display, &sprites),
+            hotbar: Hotbar::new(world.pull_player().block_placing),
+            picker: None,
+            chest: None,
+            entities: EntityRenderer::new(display),
+            third_person: false,
+            view_model: ViewModel::new(world.pull_player().block_placing),
+            feedback: Feedback::new(),
+            walk: 0.0,
+            stride: 0.0,
+            fov: FOV,
+            momentum: [0.0; 2],
+            floor: None,
+            events: world.subscribe(),
+            minimap: Minimap::new(display, &world, colors.accent),
+            world,
+            debris: Particles::new([0.9, 0.9, 1.0]),
+            fire: Particles::new([1.0, 0.6, 0.1]),
+            floating: FloatingTexts::new(),
+            toasts: Toasts::new(),
+            weather: WeatherRenderer::new(),
+            map: None,
+            debug: false,
+            palette,
+            spectator: None,
+            photo: None,
+            depth_of_field: DepthOfField::new(display),
+            photo_targets: None,
+            auto_distance: (render_distance == RenderDistance::Auto)
+                .then(|| AutoDistance::new(RenderDistance::DEFAULT)),
+            backlog: HashMap::new(),
         }
     }
 
-    fn render(&self, mut target: Frame) {
-        // it's definitely not the field of view
-        // the field of view can be tweaked with it
-        // but it's not actual degrees
-        const FOV: f32 = 80.6;
+    fn render(&mut self, mut target: Frame, display: &Display) {
+        let _span = tracing::trace_span!("render").entered();
+        let start = Instant::now();
 
         // window dimension in pixels
         let (width, height) = target.get_dimensions();
-        target.clear_color_and_depth((0.5, 0.5, 1.0, 1.0), 1.0);
+        if self.photo.is_some() {
+            // only the world, without the HUD
+            self.render_photo(display, &mut target, (width, height));
+            self.world.add_time(Phase::Draw, start.elapsed());
+            self.world.measure(Phase::Swap, || target.finish()).unwrap();
+            self.world.end_frame();
+            return;
+        }
+        let sun = self.weather.sun(self.world.time_of_day());
+        let uniforms = TexturedUniforms {
+            textures: &self.textures,
+            sun,
+        };
+        target.clear_color_and_depth(self.weather.sky_color(sun), 1.0);
 
         // fetch player info (because it's memory shared between threads)
-        let camera = self.world.pull_player().camera;
-        let camera_project = camera.projector();
-
-        // render all the chunks
-        for (&cc, mesh) in self.rendered_chunk.iter() {
-            let [cx, cz]: [i32; 2] = cc.into();
-            mesh.draw(
-                &self.textured_program, // The shader handling textured mesh
-                &mut target,            // the window (OpenGL canvas)
-                aspect_ratio((width, height)) // The transform matrix
-                    .matrix_mul(perspective(FOV)) // Apply screen view (with field of view)
-                    .matrix_mul(camera_project) // Apply camera transform (player position and orientation)
-                    .affine_translate([cx * 16, 0, cz * 16].map(|v| v as f32)), // Apply local transform (chunk position)
-                &self.textures,
+        let player = self.world.pull_player();
+        let player_camera = player.camera;
+        let camera = match &self.spectator {
+            Some(spectator) => spectator.camera,
+            None => player_camera,
+        };
+        let (camera_project, eye) = if self.third_person && self.spectator.is_none() {
+            // the camera moves back, behind the player
+            let eye = camera.matrix().transform_point([0.0, 0.0, -4.0]);
+            (
+                Affine::identity()
+                    .affine_translate([0.0, 0.0, 4.0])
+                    .matrix_mul(camera.projector()),
+                eye,
             )
-        }
+        } else {
+            (camera.projector(), camera.pos)
+        };
+        let projection = Projection::new(self.fov.to_radians(), width as f32 / height as f32)
+            .matrix()
+            .matrix_mul(MIRROR);
+        let view = projection
+            .matrix_mul(self.feedback.shake())
+            .matrix_mul(camera_project);
+
+        let player_shown = self.third_person || self.spectator.is_some();
+        self.draw_world(
+            display,
+            &mut target,
+            view,
+            uniforms,
+            player_shown.then_some(player_camera),
+        );
         {
             // This wall part is only there to render the highlight on the pointed cube
             // When the player points a cube and the cube is at reach (less than 10 meters)
@@ -170,45 +387,471 @@ impl Renderer {
             // Player's forward vector (where player is looking at)
             let [cx, cy, cz, _] = camera.matrix().vector_z();
 
-            // Iterate over all voxel coordinates the vector is traversing
-            for position in RayTravel::new(camera.pos, [cx, cy, cz], 10.0) {
-                // Check if the obtained coordinate is not out of the world
-                if let Some((position, _direction)) = position {
-                    // Check if a block is present at this coordinate
-                    if let Some(Some(_)) = self.world.get_block(position) {
-                        // If yes, draw the highlight
-                        self.block_select.draw(
-                            &self.colored_program,
-                            &mut target,
-                            aspect_ratio((width, height))
-                                .matrix_mul(perspective(FOV))
-                                .matrix_mul(camera_project)
-                                .affine_translate(position.into())
-                                .affine_translate([0.5; 3])
-                                .affine_scale(1.001)
-                                .affine_translate([-0.5; 3]),
-                            (),
-                        );
-                        break;
-                    }
+            // Check if a block is pointed (and not an entity in front of it)
+            let hit = self.world.raycast(camera.pos, [cx, cy, cz], world::REACH);
+            if let Some(RayHit::Block { coords, .. }) = hit {
+                // If yes, draw the highlight
+                self.block_select.draw(
+                    &self.colored_program,
+                    &mut target,
+                    view.affine_translate(coords.into())
+                        .affine_translate([0.5; 3])
+                        .affine_scale(1.001)
+                        .affine_translate([-0.5; 3]),
+                    (),
+                );
+            }
+        }
+        // the name tags are hidden by the world, the state of the entities
+        // (when debugging) and the damage numbers are seen through it
+        let mut labels = Vec::new();
+        for entity in self.world.pull_entities() {
+            if entity.kind == EntityKind::Mob {
+                labels.push((
+                    label_pos(&entity),
+                    Label::new(tr!("entity.mob"), NAME_COLOR),
+                ));
+            }
+            if self.debug {
+                let state = format!(
+                    "{:?} {}/{} {}",
+                    entity.kind,
+                    entity.health,
+                    entity.kind.max_health(),
+                    entity.age
+                );
+                let pos = label_pos(&entity).vector_add([0.0, 0.3, 0.0]);
+                labels.push((pos, Label::new(state, DEBUG_COLOR).on_top()));
+            }
+        }
+        if self.third_person || self.spectator.is_some() {
+            let pos = player_camera.pos.vector_add([0.0, 0.5, 0.0]);
+            labels.push((pos, Label::new(tr!("entity.player"), NAME_COLOR)));
+        }
+        labels.extend(
+            self.floating
+                .iter()
+                .map(|(pos, label)| (pos, label.clone())),
+        );
+        labels.sort_by_key(|(_, label)| label.on_top);
+        for (pos, label) in labels {
+            label.draw(
+                display,
+                &self.colored_program,
+                &mut target,
+                view,
+                &camera,
+                pos,
+            );
+        }
+        if !self.third_person && self.spectator.is_none() {
+            // the arm is drawn over the world, never in a wall
+            target.clear_depth(1.0);
+            let bob = -self.walk.sin().abs() * 0.04 * self.stride;
+            self.entities.draw_hand(
+                &self.textured_program,
+                &mut target,
+                projection,
+                self.view_model.transform(bob),
+                self.view_model.held(),
+                uniforms,
+            );
+        }
+        // the eyes in a block (or under water) see it instead of the world
+        if let Ok(bc) = BlockCoords::try_from(eye) {
+            if let Some(Some(block)) = self.world.get_block(bc) {
+                if overlay::is_inside(block, eye[1] - eye[1].floor()) {
+                    overlay::draw_inside(
+                        display,
+                        &self.textured_program,
+                        &mut target,
+                        uniforms,
+                        block,
+                    );
                 }
             }
         }
-        self.cursor
-            .draw(&self.colored_program, &mut target, Affine::identity(), ());
-        target.finish().unwrap();
+        let colors = self.palette.colors();
+        match self.map {
+            Some(view) => self.minimap.draw_full(
+                display,
+                &self.colored_program,
+                &mut target,
+                aspect_ratio((width, height)),
+                &self.world,
+                view,
+            ),
+            None => self.minimap.draw(
+                display,
+                &self.colored_program,
+                &mut target,
+                aspect_ratio((width, height)),
+                &self.world,
+            ),
+        }
+        self.hotbar.draw(
+            display,
+            &self.colored_program,
+            &self.icons,
+            &mut target,
+            aspect_ratio((width, height)),
+            colors.accent,
+        );
+        if self.spectator.is_none() {
+            self.feedback.draw(
+                display,
+                &self.colored_program,
+                &mut target,
+                aspect_ratio((width, height)),
+                &player_camera,
+            );
+        }
+        if self.world.mode() == GameMode::Survival && !player.fly {
+            stamina::draw_stamina(
+                display,
+                &self.colored_program,
+                &mut target,
+                aspect_ratio((width, height)),
+                player.stamina,
+                colors.accent,
+            );
+        }
+        if self.debug {
+            profiler::draw_stats(
+                display,
+                &self.colored_program,
+                &mut target,
+                aspect_ratio((width, height)),
+                &self.world.stats(),
+                colors.series,
+            );
+            let feet = player.feet();
+            let chunk = ChunkCoords::from_position(player.camera.pos);
+            let facing = Facing::from_angle(player.camera.h_angle);
+            let lines = [
+                tr!("debug.seed", seed = self.world.seed()),
+                tr!(
+                    "debug.block",
+                    position = format!("{} {} {}", feet[0], feet[1], feet[2])
+                ),
+                tr!("debug.chunk", chunk = format!("{} {}", chunk.x, chunk.z)),
+                tr!("debug.distance", distance = self.world.render_distance()),
+                tr!(
+                    "debug.memory",
+                    memory = self.world.memory_usage().total() >> 20,
+                    budget = self.world.config.read().unwrap().memory_budget
+                ),
+                tr!("debug.facing", facing = tr!(&format!("facing.{facing}"))),
+            ];
+            text::draw_lines(
+                display,
+                &self.colored_program,
+                &mut target,
+                aspect_ratio((width, height)),
+                &lines,
+                DEBUG_COLOR,
+                Anchor::TopLeft,
+            );
+        }
+        if let Some(toast) = self.toasts.current() {
+            text::draw_lines(
+                display,
+                &self.colored_program,
+                &mut target,
+                aspect_ratio((width, height)),
+                std::slice::from_ref(toast),
+                colors.accent,
+                Anchor::TopCenter,
+            );
+        }
+        match (&self.picker, &self.chest) {
+            (Some(picker), _) => picker.draw(
+                display,
+                &self.colored_program,
+                &self.icons,
+                &mut target,
+                aspect_ratio((width, height)),
+                colors.accent,
+            ),
+            (None, Some(view)) => view.draw(
+                display,
+                &self.colored_program,
+                &self.icons,
+                &mut target,
+                aspect_ratio((width, height)),
+                colors.accent,
+            ),
+            (None, None) => {
+                self.cursor
+                    .draw(&self.colored_program, &mut target, Affine::identity(), ())
+            }
+        }
+        self.world.add_time(Phase::Draw, start.elapsed());
+        self.world.measure(Phase::Swap, || target.finish()).unwrap();
+        self.world.end_frame();
+    }
+
+    /// Draws the chunks, the entities, the particles, and the player when
+    /// seen from outside (`player` being its camera)
+    fn draw_world(
+        &self,
+        display: &Display,
+        target: &mut impl Surface,
+        view: [[f32; 4]; 4],
+        uniforms: TexturedUniforms,
+        player: Option<Camera>,
+    ) {
+        // render all the chunks
+        for (&cc, sections) in self.rendered_chunk.iter() {
+            let [cx, cz]: [i32; 2] = cc.into();
+            for mesh in sections.iter().filter_map(|(mesh, _)| mesh.as_ref()) {
+                mesh.draw(
+                    &self.textured_program, // The shader handling textured mesh
+                    target,                 // the window, or the photo being taken
+                    view // Camera transform and screen view (with field of view)
+                        .affine_translate([cx * 16, 0, cz * 16].map(|v| v as f32)), // Apply local transform (chunk position)
+                    uniforms,
+                )
+            }
+        }
+        for entity in self.world.pull_entities() {
+            self.entities
+                .draw_entity(&self.colored_program, target, view, &entity);
+            self.entities
+                .draw_item(&self.textured_program, target, view, &entity, uniforms);
+        }
+        self.debris
+            .draw(display, &self.colored_program, target, view);
+        self.fire.draw(display, &self.colored_program, target, view);
+        self.weather
+            .draw(display, &self.colored_program, target, view);
+        if let Some(camera) = player {
+            self.entities.draw_player(
+                &self.textured_program,
+                target,
+                view,
+                PlayerPose {
+                    pos: camera.pos,
+                    h_angle: camera.h_angle,
+                    v_angle: camera.v_angle,
+                    walk: self.walk,
+                    stride: self.stride,
+                },
+                uniforms,
+            );
+        }
+    }
+
+    /// Draws what the photo camera sees on `target` (of the given size),
+    /// the world being rendered apart first for the depth of field
+    fn render_photo(
+        &mut self,
+        display: &Display,
+        target: &mut impl Surface,
+        (width, height): (u32, u32),
+    ) {
+        let resized = self
+            .photo_targets
+            .as_ref()
+            .is_none_or(|(scene, _)| scene.dimensions() != (width, height));
+        if resized {
+            let scene = Texture2d::empty_with_format(
+                display,
+                UncompressedFloatFormat::F16F16F16F16,
+                MipmapsOption::NoMipmap,
+                width,
+                height,
+            )
+            .unwrap();
+            let depth = DepthTexture2d::empty(display, width, height).unwrap();
+            self.photo_targets = Some((scene, depth));
+        }
+        let (Some(photo), Some((scene, depth))) = (&self.photo, &self.photo_targets) else {
+            return;
+        };
+        let sun = self.weather.sun(self.world.time_of_day());
+        let uniforms = TexturedUniforms {
+            textures: &self.textures,
+            sun,
+        };
+        let mut framebuffer = SimpleFrameBuffer::with_depth_buffer(display, scene, depth).unwrap();
+        framebuffer.clear_color_and_depth(self.weather.sky_color(sun), 1.0);
+        let projection = Projection::new(self.fov.to_radians(), width as f32 / height as f32);
+        let view = projection
+            .matrix()
+            .matrix_mul(MIRROR)
+            .matrix_mul(photo.projector());
+        let player = self.world.pull_player().camera;
+        self.draw_world(display, &mut framebuffer, view, uniforms, Some(player));
+
+        // sharp at what is in the middle of the view
+        let camera = photo.camera();
+        let focus = photo.depth_of_field.then(|| {
+            let [x, y, z, _] = camera.matrix().vector_z();
+            match self.world.raycast(camera.pos, [x, y, z], FOCUS_RANGE) {
+                Some(RayHit::Block { point, .. } | RayHit::Entity { point, .. }) => {
+                    let offset = point.vector_sub(camera.pos);
+                    offset.vector_dot(offset).sqrt()
+                }
+                None => FOCUS_RANGE,
+            }
+        });
+        self.depth_of_field
+            .draw(target, scene, depth, projection, focus);
+    }
+
+    /// Saves what the photo camera sees in the `screenshots` folder of the
+    /// world, rendered larger than the window and scaled down
+    fn screenshot(&mut self, display: &Display) {
+        let (width, height) = display.get_framebuffer_dimensions();
+        let (max_width, max_height) = display.get_max_viewport_dimensions();
+        let scale = (1..=SUPERSAMPLING)
+            .rev()
+            .find(|scale| width * scale <= max_width && height * scale <= max_height)
+            .unwrap_or(1);
+        let size = (width * scale, height * scale);
+        let output = SrgbTexture2d::empty(display, size.0, size.1).unwrap();
+        let mut framebuffer = SimpleFrameBuffer::new(display, &output).unwrap();
+        self.render_photo(display, &mut framebuffer, size);
+        let raw: RawImage2d<u8> = output.read();
+        let image = RgbaImage::from_raw(raw.width, raw.height, raw.data.into_owned()).unwrap();
+        let image = photo::downsample(image, scale);
+
+        let dir = self.world.save_dir().join("screenshots");
+        let name = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis());
+        let path = dir.join(format!("{name}.png"));
+        let saved = std::fs::create_dir_all(&dir)
+            .map_err(image::ImageError::from)
+            .and_then(|()| image.save(&path));
+        match saved {
+            Ok(()) => tracing::info!(?path, scale, "screenshot saved"),
+            Err(err) => tracing::warn!(%err, "screenshot not saved"),
+        }
     }
 
     fn update(&mut self, control: &Control, display: &Display) {
+        let _span = tracing::trace_span!("update").entered();
+
+        // the world stands still while taking photos
+        if let Some(photo) = &mut self.photo {
+            photo.update(control);
+            return;
+        }
+
+        // One frame is one tick of the world clock
+        self.world.measure(Phase::Simulation, || self.world.tick());
+        let physics = self.world.physics();
+        self.debris.update(physics.gravity);
+        self.fire.update(physics.gravity);
+        self.floating.update();
+        self.toasts.update();
+        self.feedback.update();
+        self.weather.update(&self.world);
+        // the palette is changed by a command
+        let palette = self.world.config.read().unwrap().palette;
+        if palette != self.palette {
+            let colors = palette.colors();
+            self.block_select = block_select(display, colors.outline);
+            self.cursor = cursor(display, colors.crosshair);
+            self.minimap.accent = colors.accent;
+            self.palette = palette;
+        }
+        // the held block may be changed by a command, or taken from a chest
+        let held = self.world.pull_player().block_placing;
+        if held != self.hotbar.held() {
+            self.hotbar.slots[self.hotbar.selected] = held;
+        }
+        self.view_model.update(held);
+        loop {
+            match self.events.try_recv() {
+                Ok(WorldEvent::Impact {
+                    kind: EntityKind::Projectile,
+                    block,
+                    face,
+                }) => {
+                    // the debris start from the middle of the hit face
+                    let face: [i32; 3] = face.into();
+                    let pos = <[f32; 3]>::from(block)
+                        .vector_add([0.5; 3])
+                        .vector_add(face.map(|v| v as f32 * 0.5));
+                    self.debris.burst(pos, 24, 0.05);
+                }
+                Ok(WorldEvent::Explosion { pos, radius }) => {
+                    self.fire.burst(pos, 200, radius * 0.05);
+                    if self.world.config.read().unwrap().shake {
+                        let eye = self.world.pull_player().camera.pos;
+                        self.feedback.explosion(pos, radius, eye);
+                    }
+                }
+                Ok(WorldEvent::PlayerHurt { from }) => self.feedback.hurt(from),
+                Ok(WorldEvent::ChestOpened(at)) => self.open_chest(at, display),
+                Ok(WorldEvent::Achieved(achievement)) => {
+                    let name = tr!(&format!("achievement.{achievement}")).to_string();
+                    self.toasts
+                        .push(tr!("achievement.unlocked", name = name).to_string());
+                }
+                // only projectiles can hit a block for now
+                Ok(WorldEvent::Impact { .. }) => {}
+                Ok(WorldEvent::BlockBroken { .. } | WorldEvent::PlayerMoved { .. }) => {}
+                // some events were missed, the next ones are still useful
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+
+        if let Some(view) = &mut self.chest {
+            // a chest broken (or blown up) is closed
+            if !view.update(&self.world) {
+                self.close_screen(display);
+            }
+        }
+
+        // The zoom is held whatever the movement keys are used for
+        let zoom = control.zoom;
+
+        // While the map is open, the movement keys move the map instead, and
+        // while spectating they move the free camera
+        let idle = Control::default();
+        let control = match &mut self.spectator {
+            Some(spectator) => {
+                spectator.update(control);
+                &idle
+            }
+            None => control,
+        };
+        let control = match &mut self.map {
+            Some(view) => {
+                const PAN: f32 = 0.02;
+                let axis = |plus: bool, minus: bool| PAN * (plus as i8 - minus as i8) as f32;
+                view.pan([
+                    axis(control.right, control.left),
+                    axis(control.back, control.front),
+                ]);
+                &idle
+            }
+            None => control,
+        };
+
         // Fetch player data because it is shared by multiple threads
         let mut player = self.world.pull_player();
+        // In a boat, the movement keys steer it and control gets out of it
+        let boat = player.riding.and_then(|id| self.world.entity(id));
+        if player.riding.is_some() && (boat.is_none() || control.down) {
+            player.dismount(boat.as_ref());
+        }
+        let riding = player.riding.zip(boat);
         let camera = player.camera;
+        // in survival, sprinting and jumping are tiring
+        let tiring = self.world.mode() == GameMode::Survival && !player.fly && riding.is_none();
+        let sprinting = !player.fly && control.shift && (!tiring || player.can_sprint());
         let speed = if player.fly {
-            1.0
-        } else if control.shift {
-            0.15
+            physics.fly
+        } else if sprinting {
+            physics.sprint
         } else {
-            0.075
+            physics.walk
         };
 
         // Given user input, player movement is determined
@@ -232,30 +875,97 @@ impl Renderer {
             if control.down {
                 vector.vector_sub_assign([0.0, speed, 0.0]);
             }
-        } else {
+        } else if riding.is_none() {
             if control.up && player.on_ground {
-                player.gravity = def::constant::JUMP;
+                player.gravity = physics.jump;
                 player.on_ground = false;
+                if tiring {
+                    player.exert(Exertion::Jump);
+                }
             }
 
             vector.vector_add_assign([0.0, player.gravity, 0.0]);
-            player.gravity += def::constant::GRAVITY;
+            player.gravity = (player.gravity + physics.gravity).max(-physics.max_fall);
         }
 
         let [vector] = camera.move_matrix().matrix_mul([vector]);
+        player.update_glide();
 
-        let vector = if player.fly {
+        let vector = if let Some((id, boat)) = riding {
+            if vector != [0.0; 3] {
+                self.world.steer(id, [vector.vector_x(), vector.vector_z()]);
+            }
+            self.momentum = [0.0; 2];
+            // the player sits in the boat, wherever it goes
+            Player::seat(&boat).vector_sub(camera.pos)
+        } else if player.fly {
+            self.momentum = [0.0; 2];
             // If player is flying, ignore collisions
             vector
         } else {
-            // If player is walking, compute collisions
-            let hit_box = Boxel::new([0.6, 1.8, 0.6], [0.3, 1.6, 0.3], camera.pos);
+            let hit_box = Player::hit_box(camera.pos);
+            let vector = if player.gliding {
+                // The glider carries the player where it looks, the keys do
+                // nothing
+                let [x, z] = self.momentum;
+                let velocity = glide([x, vector.vector_y(), z], &camera, physics.gravity);
+                let length = velocity.vector_dot(velocity).sqrt();
+                let velocity = velocity.vector_scale((physics.max_fall / length).min(1.0));
+                self.momentum = [velocity.vector_x(), velocity.vector_z()];
+                player.gravity = velocity.vector_y();
+                velocity
+            } else {
+                if player.on_ground {
+                    self.floor = self.floor(camera.pos, hit_box.pos[1]);
+                }
+                // On a slow floor the steps are shorter, on a slippery one
+                // they change the movement slowly
+                let [x, y, z] = vector;
+                let speed = match self.floor {
+                    Some(floor) if player.on_ground => floor.speed(),
+                    _ => 1.0,
+                };
+                let friction = self.floor.map_or(1.0, Block::friction);
+                self.momentum.vector_add_assign(
+                    [x, z]
+                        .vector_scale(speed)
+                        .vector_sub(self.momentum)
+                        .vector_scale(friction),
+                );
+                let [x, z] = self.momentum;
+                [x, y, z]
+            };
+            // If player is walking or gliding, compute collisions (landing
+            // closes the glider)
             // Because it is a voxel terrain, hit box overlapping only occurs on bases axis
             // Here tx, ty and tz are the time where a collision was found (from 0.0 to 1.0)
-            let tx = self.world.find_collision_x(hit_box, vector);
-            let ty = self.world.find_collision_y(hit_box, vector);
-            let tz = self.world.find_collision_z(hit_box, vector);
-            if ty < 1.0 {
+            let [tx, ty, tz] = self.world.measure(Phase::Collision, || {
+                [
+                    self.world.find_collision_x(hit_box, vector),
+                    self.world.find_collision_y(hit_box, vector),
+                    self.world.find_collision_z(hit_box, vector),
+                ]
+            });
+            // hitting a wall stops the slide
+            if tx < 1.0 {
+                self.momentum[0] = 0.0;
+            }
+            if tz < 1.0 {
+                self.momentum[1] = 0.0;
+            }
+            if ty < 1.0 && vector.vector_y() < 0.0 {
+                // landing on a bouncy floor sends the player back up
+                let feet = hit_box.pos[1] + vector.vector_y() * ty;
+                self.floor = self.floor(camera.pos, feet);
+                let bounce = -vector.vector_y() * self.floor.map_or(0.0, Block::bounciness);
+                if bounce > MIN_BOUNCE {
+                    player.on_ground = false;
+                    player.gravity = bounce;
+                } else {
+                    player.on_ground = true;
+                    player.gravity = 0.0;
+                }
+            } else if ty < 1.0 {
                 player.on_ground = true;
                 player.gravity = 0.0;
             }
@@ -268,73 +978,362 @@ impl Renderer {
         };
         // Apply player movement
         player.camera.delta_pos(vector);
-        // Update player data to all threads
-        self.world.push_player(player);
+        // The limbs swing with the distance walked (a step is about a block)
+        let distance = vector.vector_x().hypot(vector.vector_z());
+        self.walk += distance * 3.0;
+        let target = if player.on_ground && riding.is_none() && distance > 0.01 {
+            1.0
+        } else {
+            0.0
+        };
+        self.stride += (target - self.stride) * 0.2;
+        if tiring {
+            player.exert(if distance <= 0.01 {
+                Exertion::Rest
+            } else if sprinting {
+                Exertion::Sprint
+            } else {
+                Exertion::Walk
+            });
+        }
+        let fov = if zoom {
+            ZOOM_FOV
+        } else if sprinting && distance > 0.01 {
+            SPRINT_FOV
+        } else {
+            FOV
+        };
+        self.fov += (fov - self.fov) * 0.2;
+        // Update player data to all threads (a move the world refuses puts
+        // the player back)
+        if let Err(err) = self.world.move_player(player) {
+            tracing::warn!(%err, "move refused");
+            self.momentum = [0.0; 2];
+        }
+
+        // the work of the last frames, without waiting for the screen
+        // (the loading is done by another thread)
+        if let Some(auto) = &mut self.auto_distance {
+            let stats = self.world.stats();
+            let work: Duration = Phase::ALL
+                .into_iter()
+                .filter(|phase| !matches!(phase, Phase::Swap | Phase::Loading))
+                .map(|phase| stats.average(phase))
+                .sum();
+            if let Some(distance) = auto.frame(work.as_secs_f32() / FRAME_DURATION.as_secs_f32()) {
+                tracing::debug!(distance, "render distance tuned");
+                self.world.set_render_distance(distance);
+            }
+        }
 
-        // Unload out of range chunks (fawer then 256 meters)
+        // Unload out of range chunks (twice the render distance, like the
+        // loaders keep them)
+        let range = 2 * self.world.render_distance();
         self.rendered_chunk.retain(|&k, _| {
             let x = (player.camera.pos.vector_x().floor() as i32 >> 4) - k.x;
             let z = (player.camera.pos.vector_z().floor() as i32 >> 4) - k.z;
-            x * x + z * z < 16 * 16 // Thank you Pythagoras ! Thank you bro :)
+            x * x + z * z < range * range // Thank you Pythagoras ! Thank you bro :)
         });
 
-        // Process incoming commands from other threads
+        // Process incoming commands from other threads, gathering the
+        // sections requested for each chunk (the edits of a frame are
+        // rendered once)
+        let start = Instant::now();
+        let pending = &mut self.backlog;
+        let mut reload = false;
         while let Ok(cmd) = self.receiver_cmd.try_recv() {
             match cmd {
+                AristideCmd::Reload => reload = true,
                 AristideCmd::RenderChunk(cc, true) => {
-                    // The given chunk is in range for rendering (less then ? meters)
-                    // The appropriate mesh has been generated and sent to the GPU
-                    self.rendered_chunk
-                        .insert(cc, self.chunk_loader.build_mesh(cc, &self.world, display));
+                    pending.insert(cc, Some(ALL_SECTIONS));
                 }
                 AristideCmd::RenderChunk(cc, false) => {
-                    // The given chunk is out of range for rendering (more then 256 meters)
-                    // It's mesh is freed from GPU memory
-                    self.rendered_chunk.remove(&cc);
+                    pending.insert(cc, None);
+                }
+                AristideCmd::RenderSections(cc, sections) => {
+                    // nothing to update once the chunk is dropped
+                    if let Some(requested) = pending.entry(cc).or_insert(Some(0)) {
+                        *requested |= sections;
+                    }
+                }
+            }
+        }
+        if reload {
+            self.reload(display);
+            for &cc in self.rendered_chunk.keys() {
+                self.backlog.entry(cc).or_insert(Some(ALL_SECTIONS));
+            }
+        }
+        // the nearest chunks first, as the others may wait for the next
+        // frames
+        let center = ChunkCoords::from_position(player.camera.pos);
+        let mut pending: Vec<_> = self.backlog.drain().collect();
+        pending.sort_by_key(|(cc, _)| (cc.x - center.x).pow(2) + (cc.z - center.z).pow(2));
+        let budget = self
+            .auto_distance
+            .as_ref()
+            .map_or(usize::MAX, AutoDistance::uploads);
+        let mut uploads = 0;
+        for (cc, sections) in pending {
+            let Some(sections) = sections else {
+                // The given chunk is out of range for rendering (no loader
+                // keeps it). It's mesh is freed from GPU memory
+                self.rendered_chunk.remove(&cc);
+                continue;
+            };
+            // The given chunk is in range for rendering (less then ? meters)
+            // The meshes of its sections are generated and sent to the GPU,
+            // unless the ones built before are already up to date (a chunk
+            // not rendered yet is built whole)
+            let rendered = self.rendered_chunk.get(&cc);
+            let generations: [u64; SECTIONS] =
+                std::array::from_fn(|section| self.world.mesh_generation(cc, section));
+            let stale = match rendered {
+                Some(rendered) => (0..SECTIONS)
+                    .filter(|&section| sections >> section & 1 == 1)
+                    .filter(|&section| rendered[section].1 != generations[section])
+                    .fold(0, |stale, section| stale | 1 << section),
+                None => ALL_SECTIONS,
+            };
+            if stale == 0 {
+                continue;
+            }
+            if uploads == budget {
+                self.backlog.insert(cc, Some(sections));
+                continue;
+            }
+            uploads += 1;
+            if let Some(meshes) = self
+                .chunk_loader
+                .build_sections(cc, stale, &self.world, display)
+            {
+                let rendered = self
+                    .rendered_chunk
+                    .entry(cc)
+                    .or_insert_with(|| std::array::from_fn(|_| (None, 0)));
+                for (section, mesh) in meshes {
+                    rendered[section] = (mesh, generations[section]);
+                }
+            }
+            self.minimap.update_chunk(display, &self.world, cc);
+        }
+        self.world.add_time(Phase::Meshing, start.elapsed());
+        let meshes = self
+            .rendered_chunk
+            .values()
+            .flatten()
+            .filter_map(|(mesh, _)| mesh.as_ref())
+            .map(TexturedMesh::bytes)
+            .sum();
+        self.world.set_gpu_memory(meshes, self.textures.bytes());
+    }
+
+    /// The block right under the feet of the player
+    ///
+    /// Standing on an edge, the center of the hit box may be over the void:
+    /// then the floor is the first solid block under a corner of the hit
+    /// box, the one under the center otherwise.
+    fn floor(&self, pos: [f32; 3], feet: f32) -> Option<Block> {
+        let block = |x, z| {
+            BlockCoords::try_from([x, feet - 0.5, z])
+                .ok()
+                .and_then(|bc| self.world.get_block(bc))
+                .flatten()
+        };
+        let center = block(pos[0], pos[2]);
+        if center.is_some_and(Block::is_solid) {
+            return center;
+        }
+        let hit_box = Player::hit_box(pos);
+        let epsilon = self.world.physics().collision_epsilon;
+        let [x0, _, z0] = hit_box.pos.map(|v| v + epsilon);
+        let [x1, _, z1] = hit_box
+            .pos
+            .vector_add(hit_box.dimensions)
+            .map(|v| v - epsilon);
+        [[x0, z0], [x1, z0], [x0, z1], [x1, z1]]
+            .into_iter()
+            .map(|[x, z]| block(x, z))
+            .find(|block| block.is_some_and(Block::is_solid))
+            .unwrap_or(center)
+    }
+
+    /// Reads the content packs again, then rebuilds the textures, the
+    /// program sampling them, the icons and the meshes of the items (the
+    /// chunks are remeshed by the caller)
+    fn reload(&mut self, display: &Display) {
+        let _span = tracing::info_span!("reload").entered();
+        let packs = pack::load(pack::PACKS_PATH);
+        let atlas = self.world.config.read().unwrap().atlas;
+        let sprites = load_sprites(packs);
+        self.textures = Textures::new(display, &sprites, atlas);
+        self.icons = Icons::new(display, &sprites);
+        self.textured_program = TexturedMesh::program(display, &self.textures);
+        self.entities = EntityRenderer::new(display);
+        self.world.invalidate_meshes();
+    }
+
+    /// Selects a slot of the hotbar, the player holds its block
+    fn select(&mut self, slot: usize) {
+        self.hotbar.selected = slot;
+        self.world.player_set_block_placing(self.hotbar.held());
+    }
+
+    /// Shows the picker and the mouse, which stops turning the camera
+    fn open_picker(&mut self, display: &Display) {
+        self.picker = Some(Picker::new());
+        display.gl_window().window().set_cursor_visible(true);
+    }
+
+    /// Shows the content of a chest and the mouse, like the picker
+    fn open_chest(&mut self, at: BlockCoords, display: &Display) {
+        self.picker = None;
+        self.chest = Some(ChestView::new(at, self.hotbar.held()));
+        display.gl_window().window().set_cursor_visible(true);
+    }
+
+    /// Whether the picker or a chest takes the keyboard and the mouse
+    fn screen_open(&self) -> bool {
+        self.picker.is_some() || self.chest.is_some()
+    }
+
+    /// Closes the picker or the chest, the mouse turns the camera again
+    fn close_screen(&mut self, display: &Display) {
+        self.picker = None;
+        self.chest = None;
+        display.gl_window().window().set_cursor_visible(false);
+    }
+
+    /// A click in the chest: the left button puts the held block in the
+    /// slot pointed, the right one takes a block from it
+    fn click_chest(&mut self, button: MouseButton) {
+        let Some(view) = &self.chest else {
+            return;
+        };
+        let Some(slot) = view.pointed() else {
+            return;
+        };
+        let cmd = match button {
+            MouseButton::Left => Cmd::ChestPut(view.at, slot),
+            MouseButton::Right => Cmd::ChestTake(view.at, slot),
+            _ => return,
+        };
+        self.world.sender_cmd.try_send(cmd).ok();
+    }
+
+    /// Puts the block pointed in the picker in a slot of the hotbar, and
+    /// selects it
+    fn pick(&mut self, slot: usize) {
+        if let Some(block) = self.picker.as_ref().and_then(Picker::block) {
+            self.hotbar.slots[slot] = block;
+            self.select(slot);
+        }
+    }
+
+    /// A key pressed while the picker is open: the number keys put the
+    /// block pointed in their slot
+    fn picker_key(&mut self, keycode: VirtualKeyCode, display: &Display) {
+        use VirtualKeyCode as Key;
+        match keycode {
+            Key::Escape | Key::Return => self.close_screen(display),
+            Key::Back => {
+                if let Some(picker) = &mut self.picker {
+                    picker.erase();
+                }
+            }
+            key => {
+                if let Some(slot) = hotbar::slot(key) {
+                    self.pick(slot);
                 }
             }
         }
     }
 
     fn click_left(&mut self) {
+        self.view_model.swing();
         let camera = self.world.pull_player().camera;
         let [cx, cy, cz, _] = camera.matrix().vector_z();
 
-        for position in RayTravel::new(camera.pos, [cx, cy, cz], 10.0) {
-            if let Some((position, _direction)) = position {
-                if let Some(Some(_)) = self.world.get_block(position) {
-                    self.world
-                        .sender_cmd
-                        .try_send(Cmd::RemoveBlock(position))
-                        .ok();
-                    break;
+        match self.world.raycast(camera.pos, [cx, cy, cz], world::REACH) {
+            Some(RayHit::Entity { id, .. }) => {
+                let above = self.world.entity(id).map(|entity| label_pos(&entity));
+                if let (Some(damage), Some(pos)) = (self.world.attack(id, [cx, cy, cz]), above) {
+                    self.floating
+                        .push(pos, Label::new(format!("-{damage}"), DAMAGE_COLOR).on_top());
                 }
             }
+            Some(RayHit::Block { coords, .. }) => {
+                self.world
+                    .sender_cmd
+                    .try_send(Cmd::RemoveBlock(coords))
+                    .ok();
+            }
+            None => {}
         }
     }
 
+    fn click_middle(&mut self) {
+        let camera = self.world.pull_player().camera;
+        let [cx, cy, cz, _] = camera.matrix().vector_z();
+        self.world
+            .spawn_entity(Entity::projectile(camera.pos, [cx, cy, cz]));
+    }
+
     fn click_right(&mut self) {
         let player = self.world.pull_player();
         let camera = player.camera;
         let [cx, cy, cz, _] = camera.matrix().vector_z();
 
-        for position in RayTravel::new(camera.pos, [cx, cy, cz], 10.0) {
-            if let Some((position, direction)) = position {
-                if let Some(Some(_)) = self.world.get_block(position) {
-                    if let Some(position) = position.step(direction) {
-                        self.world
-                            .sender_cmd
-                            .try_send(Cmd::PlaceBlock(position, player.block_placing))
-                            .ok();
-                    }
-                    break;
+        match self.world.raycast(camera.pos, [cx, cy, cz], world::REACH) {
+            // getting in a boat (nothing happens with the other entities)
+            Some(RayHit::Entity { id, .. }) if player.riding.is_none() => {
+                self.world.mount(id);
+            }
+            Some(RayHit::Block { coords, face, .. }) => {
+                let Some(Some(block)) = self.world.get_block(coords) else {
+                    return;
+                };
+                // using an interactive block takes priority over placing
+                let cmd = if world::on_interact(block).is_some() {
+                    Some(Cmd::Interact(coords))
+                } else if world::tills(player.block_placing, block, face) {
+                    Some(Cmd::Till(coords))
+                } else {
+                    coords
+                        .step(face)
+                        .map(|position| Cmd::PlaceBlock(position, player.block_placing))
+                };
+                if let Some(cmd) = cmd {
+                    self.view_model.place();
+                    self.world.sender_cmd.try_send(cmd).ok();
                 }
             }
+            Some(RayHit::Entity { .. }) | None => {}
         }
     }
 }
 
-pub fn aristide(receiver_chunk_mesh: Receiver<AristideCmd>, world: Arc<World>) {
+/// Stops the other threads and saves the world
+fn close(world: &World, workers: &mut Vec<JoinHandle<()>>) {
+    // the other threads are stopped first, so nothing changes in the world
+    // while it is being saved
+    tracing::info!("closing");
+    world.shutdown();
+    for worker in workers.drain(..) {
+        worker.join().ok();
+    }
+    if let Err(err) = world.save() {
+        tracing::error!("save failed: {err}");
+        eprintln!("{}", tr!("error.save", error = err));
+    }
+}
+
+pub fn aristide(
+    receiver_chunk_mesh: Receiver<AristideCmd>,
+    world: Arc<World>,
+    mut workers: Vec<JoinHandle<()>>,
+    packs: Vec<RgbaImage>,
+) {
     let event_loop = EventLoop::new();
     let wb = WindowBuilder::new().with_maximized(true);
     let cb = ContextBuilder::new().with_depth_buffer(24);
@@ -342,111 +1341,278 @@ pub fn aristide(receiver_chunk_mesh: Receiver<AristideCmd>, world: Arc<World>) {
     display.gl_window().window().set_cursor_visible(false);
 
     let mut control = Control::default();
-    let mut renderer = Renderer::new(&display, world, receiver_chunk_mesh);
+    let mut renderer = Renderer::new(&display, world, receiver_chunk_mesh, packs);
 
-    event_loop.run(move |ev, _, control_flow| match ev {
-        Event::NewEvents(start_cause) => match start_cause {
-            StartCause::Init => {
-                *control_flow = ControlFlow::WaitUntil(Instant::now() + FRAME_DURATION);
-            }
-            StartCause::ResumeTimeReached {
-                requested_resume, ..
-            } => {
-                *control_flow = ControlFlow::WaitUntil(requested_resume + FRAME_DURATION);
-                display.gl_window().window().request_redraw();
-                renderer.update(&control, &display);
-            }
-            StartCause::WaitCancelled {
-                requested_resume, ..
-            } => {
-                *control_flow = if let Some(requested_resume) = requested_resume {
-                    ControlFlow::WaitUntil(requested_resume)
-                } else {
-                    ControlFlow::Wait
+    event_loop.run(move |ev, _, control_flow| {
+        let start = Instant::now();
+        let input = matches!(ev, Event::WindowEvent { .. } | Event::DeviceEvent { .. });
+        match ev {
+            Event::NewEvents(start_cause) => match start_cause {
+                StartCause::Init => {
+                    *control_flow = ControlFlow::WaitUntil(Instant::now() + FRAME_DURATION);
                 }
-            }
-            StartCause::Poll => {}
-        },
-        Event::WindowEvent { event, .. } => match event {
-            WindowEvent::CloseRequested => {
-                *control_flow = ControlFlow::Exit;
-            }
-            WindowEvent::KeyboardInput {
-                input:
-                    KeyboardInput {
+                StartCause::ResumeTimeReached {
+                    requested_resume, ..
+                } => {
+                    // the `stop` command
+                    if renderer.world.is_stopping() {
+                        close(&renderer.world, &mut workers);
+                        *control_flow = ControlFlow::Exit;
+                        return;
+                    }
+                    *control_flow = ControlFlow::WaitUntil(requested_resume + FRAME_DURATION);
+                    display.gl_window().window().request_redraw();
+                    renderer.update(&control, &display);
+                }
+                StartCause::WaitCancelled {
+                    requested_resume, ..
+                } => {
+                    *control_flow = if let Some(requested_resume) = requested_resume {
+                        ControlFlow::WaitUntil(requested_resume)
+                    } else {
+                        ControlFlow::Wait
+                    }
+                }
+                StartCause::Poll => {}
+            },
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::CloseRequested => {
+                    close(&renderer.world, &mut workers);
+                    *control_flow = ControlFlow::Exit;
+                }
+                // the picker and the chest take the keyboard while open
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(keycode),
+                            ..
+                        },
+                    ..
+                } if renderer.picker.is_some() => renderer.picker_key(keycode, &display),
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(VirtualKeyCode::Escape),
+                            ..
+                        },
+                    ..
+                } if renderer.chest.is_some() => renderer.close_screen(&display),
+                WindowEvent::KeyboardInput { .. } if renderer.screen_open() => {}
+                WindowEvent::ReceivedCharacter(c) => match &mut renderer.picker {
+                    Some(picker) => picker.type_char(c),
+                    None if renderer.chest.is_some() && c.eq_ignore_ascii_case(&'e') => {
+                        renderer.close_screen(&display);
+                    }
+                    None if renderer.chest.is_some() => {}
+                    // the picker opens on the character, so it is not typed
+                    // in its search (and the player stops walking)
+                    None if c.eq_ignore_ascii_case(&'e') => {
+                        control = Control::default();
+                        renderer.open_picker(&display);
+                    }
+                    None => {}
+                },
+                WindowEvent::CursorMoved { position, .. } => {
+                    // the HUD is 2.0 high, centered on the screen
+                    let size = display.gl_window().window().inner_size();
+                    let [width, height] = [size.width, size.height].map(|v| v.max(1) as f32);
+                    let cursor = [
+                        (position.x as f32 * 2.0 - width) / height,
+                        1.0 - position.y as f32 * 2.0 / height,
+                    ];
+                    if let Some(picker) = &mut renderer.picker {
+                        picker.cursor = cursor;
+                    }
+                    if let Some(view) = &mut renderer.chest {
+                        view.cursor = cursor;
+                    }
+                }
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button: MouseButton::Left,
+                    ..
+                } if renderer.picker.is_some() => {
+                    renderer.pick(renderer.hotbar.selected);
+                }
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button,
+                    ..
+                } if renderer.chest.is_some() => renderer.click_chest(button),
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            scancode,
+                            state,
+                            virtual_keycode,
+                            ..
+                        },
+                    ..
+                } => {
+                    control.update(
                         scancode,
-                        state,
-                        virtual_keycode,
-                        ..
-                    },
-                ..
-            } => {
-                control.update(
-                    scancode,
-                    match state {
-                        ElementState::Pressed => true,
-                        ElementState::Released => false,
-                    },
-                );
+                        match state {
+                            ElementState::Pressed => true,
+                            ElementState::Released => false,
+                        },
+                    );
 
-                if let ElementState::Pressed = state {
-                    if let Some(keycode) = virtual_keycode {
-                        use VirtualKeyCode as Key;
-                        let player = renderer.world.pull_player();
-                        match keycode {
-                            Key::F => {
-                                renderer.world.player_fly(!player.fly);
-                            }
-                            Key::Key1 => {
-                                renderer.world.player_set_block_placing(def::Block::Brick);
-                            }
-                            Key::Key2 => {
-                                renderer.world.player_set_block_placing(def::Block::Sand);
-                            }
-                            Key::Key3 => {
-                                renderer.world.player_set_block_placing(def::Block::Glass);
-                            }
-                            Key::Key4 => {
-                                renderer.world.player_set_block_placing(def::Block::Trunk);
+                    if let ElementState::Pressed = state {
+                        if let Some(keycode) = virtual_keycode {
+                            use VirtualKeyCode as Key;
+                            let player = renderer.world.pull_player();
+                            match keycode {
+                                Key::F => {
+                                    // flying is refused outside of creative mode
+                                    renderer.world.player_fly(!player.fly).ok();
+                                }
+                                Key::F1 => {
+                                    // the photo camera starts where the view is
+                                    let camera = match &renderer.spectator {
+                                        Some(spectator) => spectator.camera,
+                                        None => player.camera,
+                                    };
+                                    renderer.photo = match renderer.photo {
+                                        Some(_) => None,
+                                        None => Some(Photo::new(camera)),
+                                    };
+                                    // the textures of the photos are not kept
+                                    // out of the photo mode
+                                    if renderer.photo.is_none() {
+                                        renderer.photo_targets = None;
+                                    }
+                                }
+                                Key::F2 => {
+                                    if renderer.photo.is_some() {
+                                        renderer.screenshot(&display);
+                                    }
+                                }
+                                Key::F4 => {
+                                    if let Some(photo) = &mut renderer.photo {
+                                        photo.depth_of_field = !photo.depth_of_field;
+                                    }
+                                }
+                                Key::F3 => {
+                                    renderer.debug = !renderer.debug;
+                                }
+                                Key::F5 => {
+                                    renderer.third_person = !renderer.third_person;
+                                }
+                                Key::F6 => {
+                                    // the free camera starts where the player looks from
+                                    renderer.spectator = match renderer.spectator {
+                                        Some(_) => None,
+                                        None => Some(Spectator::new(player.camera)),
+                                    };
+                                }
+                                Key::K => {
+                                    if let Some(spectator) = &mut renderer.spectator {
+                                        spectator.record();
+                                    }
+                                }
+                                Key::P => {
+                                    if let Some(spectator) = &mut renderer.spectator {
+                                        spectator.toggle_playback();
+                                    }
+                                }
+                                Key::Back => {
+                                    if let Some(spectator) = &mut renderer.spectator {
+                                        spectator.clear();
+                                    }
+                                }
+                                Key::M => {
+                                    // the map opens on the player
+                                    let [x, _, z] = player.camera.pos;
+                                    renderer.map = match renderer.map {
+                                        Some(_) => None,
+                                        None => Some(MapView::new([x, z])),
+                                    };
+                                }
+                                Key::Equals | Key::Plus | Key::NumpadAdd => {
+                                    if let Some(view) = &mut renderer.map {
+                                        view.zoom(1.0);
+                                    }
+                                }
+                                Key::Minus | Key::NumpadSubtract => {
+                                    if let Some(view) = &mut renderer.map {
+                                        view.zoom(-1.0);
+                                    }
+                                }
+                                key => {
+                                    if let Some(slot) = hotbar::slot(key) {
+                                        renderer.select(slot);
+                                    }
+                                }
                             }
-                            Key::Key5 => {
-                                renderer.world.player_set_block_placing(def::Block::Grass);
-                            }
-                            Key::Key6 => {
-                                renderer.world.player_set_block_placing(def::Block::Water);
-                            }
-                            _ => (),
                         }
                     }
                 }
-            }
-            _ => {}
-        },
-        Event::RedrawRequested { .. } => renderer.render(display.draw()),
-        Event::DeviceEvent { event, .. } => match event {
-            DeviceEvent::Motion { axis, value } => {
-                let mut player = renderer.world.pull_player();
-                match axis {
-                    0 => player.camera.delta_angle_h(value as f32 * 0.005),
-                    1 => player.camera.delta_angle_v(-value as f32 * 0.005),
-                    _ => {}
+                _ => {}
+            },
+            Event::RedrawRequested { .. } => renderer.render(display.draw(), &display),
+            Event::DeviceEvent { event, .. } => match event {
+                DeviceEvent::MouseWheel { delta } => {
+                    let steps = match delta {
+                        MouseScrollDelta::LineDelta(_, lines) => lines,
+                        MouseScrollDelta::PixelDelta(pixels) => pixels.y as f32 / 32.0,
+                    };
+                    if let Some(picker) = &mut renderer.picker {
+                        picker.scroll(steps);
+                    } else if let Some(view) = &mut renderer.map {
+                        view.zoom(steps);
+                    } else if let Some(photo) = &mut renderer.photo {
+                        photo.roll(steps);
+                    }
                 }
-                renderer.world.push_player(player);
-            }
-            DeviceEvent::Button {
-                button: 1,
-                state: ElementState::Pressed,
-            } => {
-                renderer.click_left();
-            }
-            DeviceEvent::Button {
-                button: 3,
-                state: ElementState::Pressed,
-            } => {
-                renderer.click_right();
-            }
+                // the mouse is on the picker or the chest, not on the world
+                DeviceEvent::Motion { .. } | DeviceEvent::Button { .. }
+                    if renderer.screen_open() => {}
+                // nothing is done to the world while it stands still
+                DeviceEvent::Button { .. } if renderer.photo.is_some() => {}
+                DeviceEvent::Motion { axis, value } => {
+                    let mut player = renderer.world.pull_player();
+                    // while spectating, the mouse turns the free camera (unless
+                    // it follows the recorded path), and the photo one in the
+                    // photo mode
+                    let camera = match (&mut renderer.photo, &mut renderer.spectator) {
+                        (Some(photo), _) => Some(&mut photo.view.camera),
+                        (None, Some(spectator)) if spectator.playing() => None,
+                        (None, Some(spectator)) => Some(&mut spectator.camera),
+                        (None, None) => Some(&mut player.camera),
+                    };
+                    match (axis, camera) {
+                        (0, Some(camera)) => camera.delta_angle_h(value as f32 * 0.005),
+                        (1, Some(camera)) => camera.delta_angle_v(-value as f32 * 0.005),
+                        _ => {}
+                    }
+                    renderer.world.push_player(player);
+                }
+                DeviceEvent::Button {
+                    button: 1,
+                    state: ElementState::Pressed,
+                } => {
+                    renderer.click_left();
+                }
+                DeviceEvent::Button {
+                    button: 2,
+                    state: ElementState::Pressed,
+                } => {
+                    renderer.click_middle();
+                }
+                DeviceEvent::Button {
+                    button: 3,
+                    state: ElementState::Pressed,
+                } => {
+                    renderer.click_right();
+                }
+                _ => {}
+            },
             _ => {}
-        },
-        _ => {}
+        }
+        if input {
+            renderer.world.add_time(Phase::Input, start.elapsed());
+        }
     });
 }
@@ -0,0 +1,142 @@
+use def::{Block, BlockCoords};
+use glium::{Display, Frame, Program};
+
+use super::{
+    grid::SlotGrid,
+    icons::Icons,
+    text::{self, Anchor},
+};
+use crate::{
+    i18n::{block_name, tr},
+    world::{Slots, World, CHEST_SLOTS},
+};
+
+/// Slots in a row of the chest
+const COLUMNS: usize = 9;
+
+/// The slots, in rows of the chest
+const GRID: SlotGrid = SlotGrid {
+    columns: COLUMNS,
+    rows: CHEST_SLOTS / COLUMNS,
+    cell: 0.14,
+    padding: 0.012,
+};
+
+/// Color of the counts
+const COUNT: [f32; 3] = [1.0, 1.0, 1.0];
+
+/// The screen showing the content of an open chest
+///
+/// It only shows what the world holds: the clicks become commands, which
+/// the world checks before moving any block.
+#[derive(Debug, Clone)]
+pub struct ChestView {
+    /// Where the chest is
+    pub at: BlockCoords,
+    /// Its content, as of the last frame
+    slots: Slots,
+    /// The block put in the chest by a click
+    held: Block,
+    /// Where the mouse points, in the coordinates of the HUD
+    pub cursor: [f32; 2],
+}
+
+impl ChestView {
+    pub fn new(at: BlockCoords, held: Block) -> Self {
+        Self {
+            at,
+            slots: [None; CHEST_SLOTS],
+            held,
+            cursor: [0.0; 2],
+        }
+    }
+
+    /// Reads the content of the chest again, and the block held
+    ///
+    /// Returns false once the chest is not there anymore.
+    pub fn update(&mut self, world: &World) -> bool {
+        self.slots = world.chest(self.at);
+        self.held = world.pull_player().block_placing;
+        world.get_block(self.at) == Some(Some(Block::Chest))
+    }
+
+    /// Column and row of a slot
+    fn cell(slot: usize) -> (usize, usize) {
+        (slot % COLUMNS, slot / COLUMNS)
+    }
+
+    /// The slot under the mouse
+    pub fn pointed(&self) -> Option<usize> {
+        let (column, row) = GRID.pointed(self.cursor)?;
+        Some(row * COLUMNS + column)
+    }
+
+    /// Draws the slots in the middle of the screen with their count, and
+    /// what the clicks do at the top
+    pub fn draw(
+        &self,
+        display: &Display,
+        program: &Program,
+        icons: &Icons,
+        target: &mut Frame,
+        aspect_ratio: [[f32; 4]; 4],
+        accent: [f32; 3],
+    ) {
+        let pointed = self.pointed();
+        let cells: Vec<_> = (0..CHEST_SLOTS)
+            .map(|slot| (Self::cell(slot), pointed == Some(slot)))
+            .collect();
+        GRID.draw(display, program, target, aspect_ratio, &cells, accent);
+        let mut shown = Vec::new();
+        let mut counts = Vec::new();
+        for (slot, stack) in self.slots.iter().enumerate() {
+            let Some(stack) = stack else {
+                continue;
+            };
+            let (column, row) = Self::cell(slot);
+            let center @ [x, y] = GRID.center(column, row);
+            shown.push((stack.block, center, GRID.icon()));
+            let corner = [
+                x + GRID.cell / 2.0 - GRID.padding,
+                y - GRID.cell / 2.0 + GRID.padding,
+            ];
+            counts.push((stack.count.to_string(), corner));
+        }
+        icons.draw(display, target, aspect_ratio, &shown);
+        text::draw_at(display, program, target, aspect_ratio, &counts, COUNT);
+
+        let mut lines = vec![tr!("chest.hint", block = block_name(self.held))];
+        if let Some(stack) = pointed.and_then(|slot| self.slots[slot]) {
+            lines.push(block_name(stack.block).to_string());
+        }
+        text::draw_lines(
+            display,
+            program,
+            target,
+            aspect_ratio,
+            &lines,
+            accent,
+            Anchor::TopCenter,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pointed() {
+        let at = BlockCoords::try_from([0, 10, 0]).unwrap();
+        let mut view = ChestView::new(at, Block::Stone);
+        for slot in [0, 8, 9, CHEST_SLOTS - 1] {
+            let (column, row) = ChestView::cell(slot);
+            view.cursor = GRID.center(column, row);
+            assert_eq!(view.pointed(), Some(slot));
+        }
+        view.cursor = [GRID.cell * COLUMNS as f32, 0.0];
+        assert_eq!(view.pointed(), None);
+        view.cursor = [0.0, GRID.cell * GRID.rows as f32];
+        assert_eq!(view.pointed(), None);
+    }
+}
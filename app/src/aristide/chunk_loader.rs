@@ -1,74 +1,252 @@
+use std::collections::HashMap;
+
+use arrayvec::ArrayVec;
 use def::{
+    constant::MAX_LIGHT,
     cube::{self, FACE_INDICES},
-    ChunkCoords,
+    Block, BlockCoords, ChunkCoords, Direction,
 };
 use glium::{index::PrimitiveType, Display};
 use mat::VectorTrait;
 
 use crate::{
     mesh::{TexturedMesh, TexturedMeshVertex},
-    world::{ChunkState, World},
+    world::{Biome, ChunkState, Sections, SurfaceChunk, World, SECTIONS, SECTION_HEIGHT},
 };
 
-/// Allocated buffers used to build meshes
+/// The meshes of the sections of a chunk sent to the GPU (none when the
+/// section has no face), with the generation each was built at (see
+/// [`World::mesh_generation`])
+pub type RenderedChunk = [(Option<TexturedMesh>, u64); SECTIONS];
+
+/// Allocated buffers used to build meshes, one pair per section
 ///
 /// Keeping the buffers avoid reallocating new ones every time
 pub struct ChunkLoader {
-    vertices: Vec<TexturedMeshVertex>,
-    indices: Vec<u32>,
+    buffers: Vec<(Vec<TexturedMeshVertex>, Vec<u32>)>,
 }
 
 impl ChunkLoader {
     pub fn new() -> Self {
         Self {
-            vertices: Vec::with_capacity(1024),
-            indices: Vec::with_capacity(1024),
+            buffers: (0..SECTIONS)
+                .map(|_| (Vec::with_capacity(1024), Vec::with_capacity(1024)))
+                .collect(),
         }
     }
-    /// Build the mesh (vertices and triangles) of specified chunk
-    pub fn build_mesh(
+    /// Build the meshes (vertices and triangles) of the given sections of
+    /// specified chunk
+    ///
+    /// Returns `None` if the chunk is not meshed anymore (it was compressed
+    /// or unloaded after the request to render it).
+    pub fn build_sections(
         &mut self,
         cc: ChunkCoords,
+        sections: Sections,
         world: &World,
         display: &Display,
-    ) -> TexturedMesh {
-        if let ChunkState::Meshed(ref _blocks_chunk, ref faces_chunk) =
-            *world.chunks.get(&cc).unwrap()
-        {
-            for (&(bi, d), &block) in faces_chunk.iter() {
-                // block pos
-                let vector: [i32; 3] = bi.into();
-                // new vertex's index (will be pushed at the end of the list)
-                let indice = self.vertices.len() as u32;
-                // iterate over all faces of a cube
-                for (i, vertice) in d.face_vertices().into_iter().enumerate() {
-                    // how texture is map on cube side
-                    let [u, v] = cube::FACE_TEXTURE[i];
-                    // create a new vertex (position and texture info and light info)
-                    let vertex = TexturedMeshVertex {
-                        position: vertice.vector_add(vector).map(|v| v as f32),
-                        tex_pos: [u, v, block.sprite(d) as u32].map(|v| v as f32),
-                        light: d.light(),
-                    };
-                    self.vertices.push(vertex);
+    ) -> Option<Vec<(usize, Option<TexturedMesh>)>> {
+        let _span = tracing::debug_span!("upload", x = cc.x, z = cc.z, sections).entered();
+        self.fill_sections(cc, sections, world)?;
+        // the meshes are sent to the graphic card
+        let meshes = (0..SECTIONS)
+            .filter(|section| sections >> section & 1 == 1)
+            .map(|section| {
+                let (vertices, indices) = &mut self.buffers[section];
+                let mesh = (!indices.is_empty()).then(|| {
+                    TexturedMesh::new(display, vertices, indices, PrimitiveType::TrianglesList)
+                });
+                // clear the buffers for future use
+                vertices.clear();
+                indices.clear();
+                (section, mesh)
+            })
+            .collect();
+        Some(meshes)
+    }
+
+    /// Fills the buffers of the given sections with the faces of the chunk
+    /// (they are emptied by [`ChunkLoader::build_sections`])
+    ///
+    /// Returns `None` if the chunk is not meshed anymore.
+    pub fn fill_sections(
+        &mut self,
+        cc: ChunkCoords,
+        sections: Sections,
+        world: &World,
+    ) -> Option<()> {
+        let ChunkState::Meshed(_, ref faces_chunk) = *world.chunks.get(&cc)? else {
+            return None;
+        };
+        let mut surfaces = Surfaces::default();
+        let mut biomes = Biomes::default();
+        for (&(bi, d), &block) in faces_chunk.iter() {
+            // block pos
+            let vector: [i32; 3] = bi.into();
+            let section = (vector[1] / SECTION_HEIGHT) as usize;
+            if sections >> section & 1 == 0 {
+                continue;
+            }
+            let (vertices, indices) = &mut self.buffers[section];
+            // the part of the voxel filled by the block
+            let [min, max] = block.shape();
+            // the surface of water slopes toward where it flows
+            let surface = block
+                .fluid_height()
+                .map(|_| fluid_corners(world, BlockCoords(cc, bi)));
+            // a face is lit by the voxel in front of it, or by its own
+            // voxel when the block does not fill it
+            let lit = if block.is_full() {
+                BlockCoords(cc, bi).step(d)
+            } else {
+                Some(BlockCoords(cc, bi))
+            };
+            let block_light = lit.map_or(0, |bc| world.get_light(bc)) as f32 / MAX_LIGHT as f32;
+            let sky_light = match lit {
+                Some(bc) if !surfaces.sees_sky(world, bc) => 0.0,
+                _ => 1.0,
+            };
+            let tint = Tint::of(block, d);
+            // new vertex's index (will be pushed at the end of the list)
+            let indice = vertices.len() as u32;
+            // iterate over all faces of a cube
+            for (i, vertice) in d.face_vertices().into_iter().enumerate() {
+                // how texture is map on cube side
+                let [u, v] = cube::FACE_TEXTURE[i];
+                // create a new vertex (position and texture info and light info)
+                let mut position = vector.map(|v| v as f32);
+                for axis in 0..3 {
+                    position[axis] += min[axis] + vertice[axis] as f32 * (max[axis] - min[axis]);
+                }
+                if let (Some(corners), [x, 1, z]) = (surface, vertice) {
+                    position[1] += corners[x as usize][z as usize] - 1.0;
                 }
-                // add the cube face (one side, with 4 vertices and 2 triangles)
-                self.indices
-                    .extend(FACE_INDICES.into_iter().map(|n| n + indice));
+                let vertex = TexturedMeshVertex {
+                    position,
+                    tex_pos: [u, v, block.texture(d)].map(|v| v as f32),
+                    light: d.light(),
+                    sky_light,
+                    block_light,
+                    tint: tint.map_or([1.0; 3], |tint| {
+                        let [x, _, z] = vertice;
+                        biomes.tint(
+                            world,
+                            tint,
+                            [cc.x * 16 + vector[0] + x, cc.z * 16 + vector[2] + z],
+                        )
+                    }),
+                };
+                vertices.push(vertex);
             }
-            // the mesh is sent to the graphic card
-            let result = TexturedMesh::new(
-                display,
-                &self.vertices,
-                &self.indices,
-                PrimitiveType::TrianglesList,
-            );
-            // clear the buffers for future use
-            self.vertices.clear();
-            self.indices.clear();
-            result
-        } else {
-            unreachable!()
+            // add the cube face (one side, with 4 vertices and 2 triangles)
+            indices.extend(FACE_INDICES.into_iter().map(|n| n + indice));
+        }
+        Some(())
+    }
+}
+
+/// Tops of the columns of the chunks around the one being meshed, read
+/// once for all its faces
+///
+/// A face is under the sky when nothing is above the voxel lighting it. It
+/// is only told again when its section is meshed again: the faces below a
+/// roof built in another section keep the sun until then.
+#[derive(Default)]
+struct Surfaces(HashMap<ChunkCoords, Option<SurfaceChunk>>);
+
+impl Surfaces {
+    fn sees_sky(&mut self, world: &World, BlockCoords(cc, bi): BlockCoords) -> bool {
+        let [x, y, z]: [i32; 3] = bi.into();
+        let surface = self.0.entry(cc).or_insert_with(|| world.surface_blocks(cc));
+        // the voxel itself may hold the top block (like water)
+        surface
+            .as_ref()
+            .and_then(|surface| surface[x as usize][z as usize])
+            .is_none_or(|(top, _)| y >= top)
+    }
+}
+
+/// Which color of its biome a face is tinted with
+#[derive(Clone, Copy)]
+enum Tint {
+    Foliage,
+    Water,
+}
+
+impl Tint {
+    fn of(block: Block, direction: Direction) -> Option<Self> {
+        match (block, direction) {
+            (Block::Grass, Direction::Up) | (Block::Leaves, _) => Some(Self::Foliage),
+            (Block::Water(_), _) => Some(Self::Water),
+            _ => None,
+        }
+    }
+
+    /// Dry and yellow by the sea, pale and cold in the mountains
+    fn color(self, biome: Biome) -> [f32; 3] {
+        match (self, biome) {
+            (Self::Foliage, Biome::Beach) => [1.0, 0.95, 0.7],
+            (Self::Foliage, Biome::Plains) => [0.9, 1.0, 0.85],
+            (Self::Foliage, Biome::Mountains) => [0.75, 0.9, 0.9],
+            (Self::Water, Biome::Beach) => [0.8, 1.0, 0.95],
+            (Self::Water, Biome::Plains) => [0.9, 0.95, 1.0],
+            (Self::Water, Biome::Mountains) => [0.75, 0.8, 1.0],
+        }
+    }
+}
+
+/// Biomes of the columns around the chunk being meshed, asked to the
+/// generator once for all its faces
+#[derive(Default)]
+struct Biomes(HashMap<[i32; 2], Biome>);
+
+impl Biomes {
+    /// The tint at a corner between four columns, the average of theirs so
+    /// the colors blend across the borders of the biomes
+    fn tint(&mut self, world: &World, tint: Tint, [x, z]: [i32; 2]) -> [f32; 3] {
+        let mut sum = [0.0; 3];
+        for column in [[x - 1, z - 1], [x - 1, z], [x, z - 1], [x, z]] {
+            let biome = *self
+                .0
+                .entry(column)
+                .or_insert_with(|| world.generator.biome(column[0], column[1]));
+            sum = sum.vector_add(tint.color(biome));
+        }
+        sum.map(|v| v / 4.0)
+    }
+}
+
+/// Height of the surface of a fluid at the four top corners of its voxel
+///
+/// A corner is at the average height of the fluid in the voxels around it,
+/// and at the top of the voxel when fluid is above one of them.
+fn fluid_corners(world: &World, bc: BlockCoords) -> [[f32; 2]; 2] {
+    let [x, y, z]: [i32; 3] = bc.into();
+    let height = |x: i32, z: i32| {
+        let block = |y| {
+            BlockCoords::try_from([x, y, z])
+                .ok()
+                .and_then(|bc| world.get_block(bc))
+                .flatten()
+        };
+        match block(y + 1).and_then(Block::fluid_height) {
+            Some(_) => Some(1.0),
+            None => block(y).and_then(Block::fluid_height),
+        }
+    };
+    let mut corners = [[0.0; 2]; 2];
+    for (cx, row) in corners.iter_mut().enumerate() {
+        for (cz, corner) in row.iter_mut().enumerate() {
+            let heights: ArrayVec<f32, 4> = [(-1, -1), (-1, 0), (0, -1), (0, 0)]
+                .into_iter()
+                .filter_map(|(dx, dz)| height(x + cx as i32 + dx, z + cz as i32 + dz))
+                .collect();
+            *corner = if heights.contains(&1.0) {
+                1.0
+            } else {
+                heights.iter().sum::<f32>() / heights.len() as f32
+            };
         }
     }
+    corners
 }
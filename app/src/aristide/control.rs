@@ -8,6 +8,8 @@ pub struct Control {
     pub up: bool,
     pub down: bool,
     pub shift: bool,
+    /// Narrows the field of view, while held
+    pub zoom: bool,
 }
 impl Control {
     pub fn update(&mut self, key: u32, state: bool) {
@@ -20,6 +22,7 @@ impl Control {
             57 => &mut self.up,
             29 => &mut self.down,
             42 => &mut self.shift,
+            46 => &mut self.zoom,
             _ => return,
         } = state;
     }
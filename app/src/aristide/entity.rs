@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+
+use def::{cube::FACE_INDICES, cube::FACE_TEXTURE, Block, Direction, Sprite};
+use glium::{index::PrimitiveType, Display, Program, Surface};
+use mat::{AffineTrait, MatrixTrait, VectorTrait};
+
+use crate::{
+    mesh::{ColoredMesh, Drawable, TexturedMesh, TexturedMeshVertex, TexturedUniforms},
+    world::{Entity, EntityKind},
+};
+
+/// Size of a pixel of the model (the model is 32 pixels tall, like the player)
+const PIXEL: f32 = 1.8 / 32.0;
+
+/// Color of an entity just hit
+const HURT: [f32; 3] = [0.9, 0.15, 0.1];
+
+/// Height of the eyes above the feet, where the camera is
+const EYES: f32 = 1.6;
+
+/// The right arm among the parts of the player model
+const ARM: usize = 2;
+
+/// The blocks which can be dropped (see `Block::drops`), with the custom
+/// blocks
+const ITEMS: [Block; 19] = [
+    Block::Stone,
+    Block::Dirt,
+    Block::Sand,
+    Block::Brick,
+    Block::Trunk,
+    Block::Tnt,
+    Block::Door(0),
+    Block::Trapdoor(0),
+    Block::Torch(0),
+    Block::Sapling(0),
+    Block::Slime,
+    Block::Chest,
+    Block::Wire(0),
+    Block::Lever(0),
+    Block::Lamp(0),
+    Block::Piston(0),
+    Block::Crop(0),
+    Block::SoulSand,
+    Block::Glider,
+];
+
+/// How a player is standing, enough to draw it
+#[derive(Debug, Clone, Copy)]
+pub struct PlayerPose {
+    /// Position of the eyes (like the camera)
+    pub pos: [f32; 3],
+    pub h_angle: f32,
+    pub v_angle: f32,
+    /// Advances with the distance walked, the limbs swing with it
+    pub walk: f32,
+    /// How wide the limbs swing (0.0 when standing still, 1.0 when walking)
+    pub stride: f32,
+}
+
+/// A cell of the skin texture (4 by 4 cells, from the top left)
+#[derive(Debug, Clone, Copy)]
+struct Cell(u32, u32);
+
+impl Cell {
+    const FACE: Self = Self(0, 0);
+    const HEAD: Self = Self(1, 0);
+    const HAIR: Self = Self(2, 0);
+    const BODY: Self = Self(3, 0);
+    const ARM: Self = Self(0, 1);
+    const LEG: Self = Self(1, 1);
+
+    // texture coordinates of a face corner (the texture is upside down)
+    fn tex_pos(self, [u, v]: [u32; 2]) -> [f32; 3] {
+        let Self(col, row) = self;
+        [
+            (col + u) as f32 / 4.0,
+            (3 - row + v) as f32 / 4.0,
+            Sprite::Skin as u32 as f32,
+        ]
+    }
+}
+
+/// A box of the model, rotating around its joint
+struct Part {
+    mesh: TexturedMesh,
+    /// Position of the joint (in pixels, from the feet)
+    joint: [f32; 3],
+    /// Swing of the part when walking
+    swing: f32,
+    /// The head follows where the player looks
+    look: bool,
+}
+
+impl Part {
+    /// `offset` is the corner of the box from the joint, all in pixels
+    fn new(
+        display: &Display,
+        joint: [f32; 3],
+        offset: [f32; 3],
+        size: [f32; 3],
+        cell: impl Fn(Direction) -> Cell,
+    ) -> Self {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for direction in Direction::ALL {
+            let indice = vertices.len() as u32;
+            for (i, corner) in direction.face_vertices().into_iter().enumerate() {
+                let mut position = offset;
+                for axis in 0..3 {
+                    position[axis] += corner[axis] as f32 * size[axis];
+                }
+                vertices.push(TexturedMeshVertex {
+                    position,
+                    tex_pos: cell(direction).tex_pos(FACE_TEXTURE[i]),
+                    light: direction.light(),
+                    sky_light: 1.0,
+                    block_light: 0.0,
+                    tint: [1.0; 3],
+                });
+            }
+            indices.extend(FACE_INDICES.into_iter().map(|n| n + indice));
+        }
+        Self {
+            mesh: TexturedMesh::new(display, &vertices, &indices, PrimitiveType::TrianglesList),
+            joint,
+            swing: 0.0,
+            look: false,
+        }
+    }
+
+    fn swing(self, swing: f32) -> Self {
+        Self { swing, ..self }
+    }
+
+    fn look(self) -> Self {
+        Self { look: true, ..self }
+    }
+}
+
+/// Draws the players and the entities of the world
+///
+/// The player model is made of textured boxes (head, body and limbs)
+/// sharing the skin sprite of the texture array.
+pub struct EntityRenderer {
+    player: Vec<Part>,
+    /// Unit cubes, centered on the origin
+    projectile: ColoredMesh,
+    tnt: ColoredMesh,
+    mob: ColoredMesh,
+    /// The hull, from the middle of its bottom
+    boat: ColoredMesh,
+    /// The mob and the boat flash red while they are hurt
+    mob_hurt: ColoredMesh,
+    boat_hurt: ColoredMesh,
+    /// Unit cubes with the sprites of the blocks, centered on the origin
+    items: HashMap<Block, TexturedMesh>,
+}
+
+impl EntityRenderer {
+    pub fn new(display: &Display) -> Self {
+        let head = |direction| match direction {
+            // the model looks toward positive z, like the camera
+            Direction::South => Cell::FACE,
+            Direction::Up => Cell::HAIR,
+            _ => Cell::HEAD,
+        };
+        let body = |_| Cell::BODY;
+        // arms and legs swing in opposite directions
+        let limb = [4.0, 12.0, 4.0];
+        let arm = |x, swing| {
+            Part::new(display, [x, 22.0, 0.0], [-2.0, -10.0, -2.0], limb, |_| {
+                Cell::ARM
+            })
+            .swing(swing)
+        };
+        let leg = |x, swing| {
+            Part::new(display, [x, 12.0, 0.0], [-2.0, -12.0, -2.0], limb, |_| {
+                Cell::LEG
+            })
+            .swing(swing)
+        };
+        Self {
+            player: vec![
+                Part::new(display, [0.0, 24.0, 0.0], [-4.0, 0.0, -4.0], [8.0; 3], head).look(),
+                Part::new(
+                    display,
+                    [0.0, 12.0, 0.0],
+                    [-4.0, 0.0, -2.0],
+                    [8.0, 12.0, 4.0],
+                    body,
+                ),
+                arm(-6.0, 1.0),
+                arm(6.0, -1.0),
+                leg(-2.0, -1.0),
+                leg(2.0, 1.0),
+            ],
+            projectile: colored_cube(display, [0.9, 0.9, 1.0]),
+            tnt: colored_cube(display, [0.8, 0.15, 0.1]),
+            mob: colored_cube(display, [0.2, 0.55, 0.15]),
+            boat: boat(display, [0.45, 0.32, 0.18]),
+            mob_hurt: colored_cube(display, HURT),
+            boat_hurt: boat(display, HURT),
+            items: ITEMS
+                .into_iter()
+                .chain(def::custom::blocks())
+                .map(|block| (block, block_cube(display, block)))
+                .collect(),
+        }
+    }
+
+    /// Draws an entity, `view` being the projection of the camera
+    pub fn draw_entity(
+        &self,
+        program: &Program,
+        target: &mut impl Surface,
+        view: [[f32; 4]; 4],
+        entity: &Entity,
+    ) {
+        match entity.kind {
+            EntityKind::Projectile => self.projectile.draw(
+                program,
+                target,
+                view.affine_translate(entity.pos).affine_scale(0.2),
+                (),
+            ),
+            EntityKind::PrimedTnt => {
+                // it blinks faster and faster while the fuse burns
+                let blink = (entity.age as f32).powf(1.5) * 0.05;
+                let scale = if blink.sin() > 0.0 { 1.02 } else { 1.0 };
+                self.tnt.draw(
+                    program,
+                    target,
+                    view.affine_translate(entity.pos).affine_scale(scale),
+                    (),
+                )
+            }
+            EntityKind::Mob => {
+                let mesh = if entity.is_hurt() {
+                    &self.mob_hurt
+                } else {
+                    &self.mob
+                };
+                mesh.draw(
+                    program,
+                    target,
+                    view.affine_translate(entity.pos)
+                        .affine_scale(Entity::MOB_SIZE),
+                    (),
+                )
+            }
+            EntityKind::Boat => {
+                let mesh = if entity.is_hurt() {
+                    &self.boat_hurt
+                } else {
+                    &self.boat
+                };
+                mesh.draw(program, target, view.affine_translate(entity.pos), ())
+            }
+            // drawn with the textures (see `draw_item`)
+            EntityKind::Item(_) => {}
+        }
+    }
+
+    /// Draws a dropped item as a small block, spinning slowly
+    pub fn draw_item(
+        &self,
+        program: &Program,
+        target: &mut impl Surface,
+        view: [[f32; 4]; 4],
+        entity: &Entity,
+        uniforms: TexturedUniforms,
+    ) {
+        let EntityKind::Item(block) = entity.kind else {
+            return;
+        };
+        if let Some(mesh) = self.items.get(&block) {
+            mesh.draw(
+                program,
+                target,
+                view.affine_translate(entity.pos)
+                    .affine_scale(Entity::ITEM_SIZE)
+                    .affine_y_rotate(entity.age as f32 * 0.03),
+                uniforms,
+            );
+        }
+    }
+
+    /// Draws the right arm holding a block, seen in first person, `hand`
+    /// placing the hand from the eyes (see `ViewModel`)
+    pub fn draw_hand(
+        &self,
+        program: &Program,
+        target: &mut impl Surface,
+        projection: [[f32; 4]; 4],
+        hand: [[f32; 4]; 4],
+        held: Block,
+        uniforms: TexturedUniforms,
+    ) {
+        let hand = projection.matrix_mul(hand);
+        // from the hand, the arm goes back and down to the shoulder
+        let arm = &self.player[ARM];
+        arm.mesh.draw(
+            program,
+            target,
+            hand.affine_scale(PIXEL)
+                .affine_x_rotate(2.0)
+                .affine_translate([0.0, 10.0, 0.0]),
+            uniforms,
+        );
+        if let Some(mesh) = self.items.get(&held) {
+            mesh.draw(
+                program,
+                target,
+                hand.affine_translate([0.0, 0.1, 0.1])
+                    .affine_scale(0.3)
+                    .affine_y_rotate(0.8),
+                uniforms,
+            );
+        }
+    }
+
+    /// Draws a player, `view` being the projection of the camera
+    pub fn draw_player(
+        &self,
+        program: &Program,
+        target: &mut impl Surface,
+        view: [[f32; 4]; 4],
+        pose: PlayerPose,
+        uniforms: TexturedUniforms,
+    ) {
+        let feet = pose.pos.vector_sub([0.0, EYES, 0.0]);
+        let model = view
+            .affine_translate(feet)
+            .affine_y_rotate(pose.h_angle)
+            .affine_scale(PIXEL);
+        for part in &self.player {
+            let angle = if part.look {
+                pose.v_angle
+            } else {
+                pose.walk.sin() * 0.8 * pose.stride * part.swing
+            };
+            part.mesh.draw(
+                program,
+                target,
+                model.affine_translate(part.joint).affine_x_rotate(angle),
+                uniforms,
+            );
+        }
+    }
+}
+
+/// A cube with the sprites of a block, centered on the origin
+fn block_cube(display: &Display, block: Block) -> TexturedMesh {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for direction in Direction::ALL {
+        let indice = vertices.len() as u32;
+        for (i, corner) in direction.face_vertices().into_iter().enumerate() {
+            let [u, v] = FACE_TEXTURE[i];
+            vertices.push(TexturedMeshVertex {
+                position: corner.map(|v| v as f32 - 0.5),
+                tex_pos: [u, v, block.texture(direction)].map(|v| v as f32),
+                light: direction.light(),
+                sky_light: 1.0,
+                block_light: 0.0,
+                tint: [1.0; 3],
+            });
+        }
+        indices.extend(FACE_INDICES.into_iter().map(|n| n + indice));
+    }
+    TexturedMesh::new(display, &vertices, &indices, PrimitiveType::TrianglesList)
+}
+
+/// The hull of a boat, from the middle of its bottom
+fn boat(display: &Display, color: [f32; 3]) -> ColoredMesh {
+    let [w, h, d] = Entity::BOAT_SIZE;
+    colored_box(
+        display,
+        color,
+        [-w / 2.0, 0.0, -d / 2.0],
+        [w / 2.0, h, d / 2.0],
+    )
+}
+
+/// A cube of the given color, centered on the origin and lit like blocks
+fn colored_cube(display: &Display, color: [f32; 3]) -> ColoredMesh {
+    colored_box(display, color, [-0.5; 3], [0.5; 3])
+}
+
+/// A box of the given color between two corners, lit like blocks
+fn colored_box(display: &Display, color: [f32; 3], min: [f32; 3], max: [f32; 3]) -> ColoredMesh {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for direction in Direction::ALL {
+        let indice = vertices.len() as u32;
+        let color = color.vector_scale(0.6 + 0.4 * direction.light());
+        for corner in direction.face_vertices() {
+            let position = corner.vector_map_index(|v, i| min[i] + v as f32 * (max[i] - min[i]));
+            vertices.push((position, color).into());
+        }
+        indices.extend(FACE_INDICES.into_iter().map(|n| n + indice));
+    }
+    ColoredMesh::new(display, &vertices, &indices, PrimitiveType::TrianglesList)
+}
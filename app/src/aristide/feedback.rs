@@ -0,0 +1,171 @@
+use glium::{index::PrimitiveType, DepthTest, Display, Frame, Program};
+use mat::{Affine, AffineTrait, VectorTrait};
+
+use crate::{
+    camera::Camera,
+    mesh::{ColoredMesh, Drawable},
+};
+
+/// Ticks a hurt stays shown (1 second)
+const HURT_TICKS: u32 = 60;
+
+/// Distance of the hurt indicators from the middle of the screen, and
+/// their thickness when new (in screen heights)
+const RADIUS: f32 = 0.75;
+const THICKNESS: f32 = 0.08;
+
+/// Half the angle covered by a hurt indicator
+const SPREAD: f32 = 0.5;
+
+const RED: [f32; 3] = [0.8, 0.05, 0.05];
+
+/// Strongest shake, as an offset of the camera (in blocks)
+const MAX_SHAKE: f32 = 0.3;
+
+/// Part of the shake kept at each tick
+const SHAKE_DECAY: f32 = 0.9;
+
+/// Where the player was hurt from
+struct Hurt {
+    from: [f32; 3],
+    /// Ticks since it happened
+    age: u32,
+}
+
+/// Feedback on what happens to the player: red marks on the side it was
+/// hurt from, and the screen shaking with the explosions
+#[derive(Default)]
+pub struct Feedback {
+    hurts: Vec<Hurt>,
+    /// Strength of the shake, fading away
+    shake: f32,
+    /// Ticks since the start, driving the shake
+    tick: u32,
+}
+
+impl Feedback {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The player was hurt by something at `from`
+    pub fn hurt(&mut self, from: [f32; 3]) {
+        self.hurts.push(Hurt { from, age: 0 });
+    }
+
+    /// Shakes the screen, more for a bigger or closer explosion (`eye`
+    /// being the position of the camera)
+    pub fn explosion(&mut self, pos: [f32; 3], radius: f32, eye: [f32; 3]) {
+        let offset = pos.vector_sub(eye);
+        let distance = offset.vector_dot(offset).sqrt().max(1.0);
+        self.shake = (self.shake + MAX_SHAKE * radius / distance).min(MAX_SHAKE);
+    }
+
+    pub fn update(&mut self) {
+        self.tick += 1;
+        self.shake *= SHAKE_DECAY;
+        for hurt in &mut self.hurts {
+            hurt.age += 1;
+        }
+        self.hurts.retain(|hurt| hurt.age < HURT_TICKS);
+    }
+
+    /// Offsets the camera (to apply after its projector), shaking it
+    pub fn shake(&self) -> [[f32; 4]; 4] {
+        // a few sines of unrelated frequencies look random enough
+        let t = self.tick as f32;
+        let x = (t * 1.7).sin() + (t * 4.3).sin() * 0.5;
+        let y = (t * 2.3).cos() + (t * 5.9).sin() * 0.5;
+        Affine::identity().affine_translate([x, y, 0.0].vector_scale(self.shake / 1.5))
+    }
+
+    /// Draws a mark toward each hurt, on the side of the screen it came
+    /// from, thinner as it gets older
+    pub fn draw(
+        &self,
+        display: &Display,
+        program: &Program,
+        target: &mut Frame,
+        aspect_ratio: [[f32; 4]; 4],
+        camera: &Camera,
+    ) {
+        if self.hurts.is_empty() {
+            return;
+        }
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for hurt in &self.hurts {
+            let angle = screen_angle(camera, hurt.from);
+            let thickness = THICKNESS * (1.0 - hurt.age as f32 / HURT_TICKS as f32);
+            // an arc of a ring, as a strip of quads
+            const STEPS: u32 = 8;
+            let indice = vertices.len() as u32;
+            for step in 0..=STEPS {
+                let a = angle - SPREAD + 2.0 * SPREAD * step as f32 / STEPS as f32;
+                let (sin, cos) = a.sin_cos();
+                for radius in [RADIUS, RADIUS + thickness] {
+                    vertices.push(([sin * radius, cos * radius, 0.0], RED).into());
+                }
+            }
+            for step in 0..STEPS {
+                let n = indice + step * 2;
+                indices.extend([n, n + 1, n + 3, n, n + 3, n + 2]);
+            }
+        }
+        ColoredMesh::new(display, &vertices, &indices, PrimitiveType::TrianglesList)
+            .depth_test(DepthTest::Overwrite)
+            .draw(program, target, aspect_ratio, ());
+    }
+}
+
+/// Where something is on the screen, as an angle from the top (in front
+/// of the player) turning clockwise (to its right)
+fn screen_angle(camera: &Camera, pos: [f32; 3]) -> f32 {
+    // only the horizontal direction matters, wherever the player looks
+    let [x, _, z] = Affine::identity()
+        .affine_y_rotate(-camera.h_angle)
+        .transform_vector(pos.vector_sub(camera.pos));
+    // the x of the camera goes to its left
+    (-x).atan2(z)
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use super::*;
+
+    #[test]
+    fn test_screen_angle() {
+        let camera = Camera {
+            pos: [0.0; 3],
+            h_angle: 0.0,
+            v_angle: 0.3,
+        };
+        assert_eq!(screen_angle(&camera, [0.0, 5.0, 4.0]), 0.0);
+        assert_eq!(screen_angle(&camera, [2.0, 0.0, 0.0]), -FRAC_PI_2);
+        assert_eq!(screen_angle(&camera, [0.0, 0.0, -1.0]).abs(), PI);
+        // turned to its left, what was on its left is in front
+        let turned = Camera {
+            h_angle: camera.h_angle - FRAC_PI_2,
+            ..camera
+        };
+        assert!(screen_angle(&turned, [2.0, 0.0, 0.0]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fading() {
+        let mut feedback = Feedback::new();
+        feedback.hurt([0.0; 3]);
+        feedback.explosion([1.0, 0.0, 0.0], 4.0, [0.0; 3]);
+        assert_eq!(feedback.shake, MAX_SHAKE);
+        for _ in 0..HURT_TICKS {
+            feedback.update();
+        }
+        assert!(feedback.hurts.is_empty());
+        assert!(feedback.shake < 0.01);
+        // a small explosion far away barely shakes
+        feedback.explosion([100.0, 0.0, 0.0], 1.0, [0.0; 3]);
+        assert!(feedback.shake < 0.02);
+    }
+}
@@ -0,0 +1,107 @@
+use glium::{index::PrimitiveType, DepthTest, Display, Frame, Program};
+
+use crate::mesh::{ColoredMesh, Drawable};
+
+/// Color of the plate behind the grid, and of the cells
+const PLATE: [f32; 3] = [0.1, 0.1, 0.1];
+const BACKGROUND: [f32; 3] = [0.2, 0.2, 0.2];
+
+/// A grid of square cells on a plate, in the middle of the screen (the
+/// screens of the picker and of the chests)
+///
+/// Cells are given by their column and row, from the top left one.
+#[derive(Debug, Clone, Copy)]
+pub struct SlotGrid {
+    pub columns: usize,
+    pub rows: usize,
+    /// Side of a cell, in screen heights
+    pub cell: f32,
+    /// Room around an icon in its cell
+    pub padding: f32,
+}
+
+impl SlotGrid {
+    /// Center of a cell
+    pub fn center(&self, column: usize, row: usize) -> [f32; 2] {
+        let left = -self.cell * self.columns as f32 / 2.0;
+        let top = self.cell * self.rows as f32 / 2.0;
+        [
+            left + self.cell * (column as f32 + 0.5),
+            top - self.cell * (row as f32 + 0.5),
+        ]
+    }
+
+    /// The cell under a point, in the coordinates of the HUD
+    pub fn pointed(&self, [x, y]: [f32; 2]) -> Option<(usize, usize)> {
+        let column = (x / self.cell + self.columns as f32 / 2.0).floor();
+        let row = (self.rows as f32 / 2.0 - y / self.cell).floor();
+        if !(0.0..self.columns as f32).contains(&column) || !(0.0..self.rows as f32).contains(&row)
+        {
+            return None;
+        }
+        Some((column as usize, row as usize))
+    }
+
+    /// Side of an icon in its cell
+    pub fn icon(&self) -> f32 {
+        self.cell - 3.0 * self.padding
+    }
+
+    /// Draws the plate and the cells given, with whether they are pointed
+    /// (in the accent color)
+    pub fn draw(
+        &self,
+        display: &Display,
+        program: &Program,
+        target: &mut Frame,
+        aspect_ratio: [[f32; 4]; 4],
+        cells: &[((usize, usize), bool)],
+        accent: [f32; 3],
+    ) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut square = |[x, y]: [f32; 2], [w, h]: [f32; 2], color: [f32; 3]| {
+            let indice = vertices.len() as u32;
+            let [w, h] = [w / 2.0, h / 2.0];
+            for [dx, dy] in [[-w, -h], [w, -h], [w, h], [-w, h]] {
+                vertices.push(([x + dx, y + dy, 0.0], color).into());
+            }
+            indices.extend([0, 1, 2, 0, 2, 3].map(|n| n + indice));
+        };
+        let size = [self.columns, self.rows].map(|n| n as f32 * self.cell + 2.0 * self.padding);
+        square([0.0, 0.0], size, PLATE);
+        for &((column, row), pointed) in cells {
+            let color = if pointed { accent } else { BACKGROUND };
+            square(
+                self.center(column, row),
+                [self.cell - self.padding; 2],
+                color,
+            );
+        }
+        ColoredMesh::new(display, &vertices, &indices, PrimitiveType::TrianglesList)
+            .depth_test(DepthTest::Overwrite)
+            .draw(program, target, aspect_ratio, ());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pointed() {
+        let grid = SlotGrid {
+            columns: 3,
+            rows: 2,
+            cell: 0.5,
+            padding: 0.05,
+        };
+        for (column, row) in [(0, 0), (2, 0), (1, 1), (2, 1)] {
+            assert_eq!(grid.pointed(grid.center(column, row)), Some((column, row)));
+        }
+        // the grid is centered
+        assert_eq!(grid.center(0, 0), [-0.5, 0.25]);
+        assert_eq!(grid.pointed([1.0, 0.0]), None);
+        assert_eq!(grid.pointed([0.0, -0.75]), None);
+    }
+}
@@ -0,0 +1,131 @@
+use def::Block;
+use glium::{
+    glutin::event::VirtualKeyCode, index::PrimitiveType, DepthTest, Display, Frame, Program,
+};
+
+use super::icons::Icons;
+use crate::mesh::{ColoredMesh, Drawable};
+
+/// Blocks given by the number keys (from 1 to 0), until others are put in
+/// their slots
+const HOTBAR: [Block; 10] = [
+    Block::Brick,
+    Block::Sand,
+    Block::Glass,
+    Block::Trunk,
+    Block::Grass,
+    Block::Water(0),
+    Block::Tnt,
+    Block::Door(0),
+    Block::Trapdoor(0),
+    Block::Torch(0),
+];
+
+/// Side of a slot, in screen heights
+const SLOT: f32 = 0.1;
+
+/// Bottom of the slots, above the stamina bar
+const BOTTOM: f32 = -0.88;
+
+/// Room around an icon in its slot, and around the slot of the held block
+const PADDING: f32 = 0.01;
+
+/// The slot of a number key
+pub fn slot(key: VirtualKeyCode) -> Option<usize> {
+    use VirtualKeyCode as Key;
+    [
+        Key::Key1,
+        Key::Key2,
+        Key::Key3,
+        Key::Key4,
+        Key::Key5,
+        Key::Key6,
+        Key::Key7,
+        Key::Key8,
+        Key::Key9,
+        Key::Key0,
+    ]
+    .iter()
+    .position(|&k| k == key)
+}
+
+/// The blocks at hand, one per number key
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hotbar {
+    pub slots: [Block; 10],
+    /// The slot of the block held
+    pub selected: usize,
+}
+
+impl Hotbar {
+    /// The default blocks, the one held selected if it is among them
+    pub fn new(held: Block) -> Self {
+        Self {
+            slots: HOTBAR,
+            selected: HOTBAR.iter().position(|&block| block == held).unwrap_or(0),
+        }
+    }
+
+    pub fn held(&self) -> Block {
+        self.slots[self.selected]
+    }
+
+    /// Draws the slots at the bottom of the screen, the selected one in a
+    /// frame
+    pub fn draw(
+        &self,
+        display: &Display,
+        program: &Program,
+        icons: &Icons,
+        target: &mut Frame,
+        aspect_ratio: [[f32; 4]; 4],
+        accent: [f32; 3],
+    ) {
+        let left = -SLOT * self.slots.len() as f32 / 2.0;
+        let center = |i: usize| [left + SLOT * (i as f32 + 0.5), BOTTOM + SLOT / 2.0];
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut square = |[x, y]: [f32; 2], side: f32, color: [f32; 3]| {
+            let indice = vertices.len() as u32;
+            let half = side / 2.0;
+            for [dx, dy] in [[-half, -half], [half, -half], [half, half], [-half, half]] {
+                vertices.push(([x + dx, y + dy, 0.0], color).into());
+            }
+            indices.extend([0, 1, 2, 0, 2, 3].map(|n| n + indice));
+        };
+        for i in 0..self.slots.len() {
+            if i == self.selected {
+                square(center(i), SLOT, accent);
+            }
+            square(center(i), SLOT - 2.0 * PADDING, [0.15; 3]);
+        }
+        ColoredMesh::new(display, &vertices, &indices, PrimitiveType::TrianglesList)
+            .depth_test(DepthTest::Overwrite)
+            .draw(program, target, aspect_ratio, ());
+        let slots: Vec<_> = self
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(i, &block)| (block, center(i), SLOT - 4.0 * PADDING))
+            .collect();
+        icons.draw(display, target, aspect_ratio, &slots);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_slot() {
+        assert_eq!(slot(VirtualKeyCode::Key1), Some(0));
+        assert_eq!(slot(VirtualKeyCode::Key0), Some(9));
+        assert_eq!(slot(VirtualKeyCode::A), None);
+    }
+
+    #[test]
+    fn test_new() {
+        assert_eq!(Hotbar::new(Block::Grass).held(), Block::Grass);
+        assert_eq!(Hotbar::new(Block::Stone).held(), HOTBAR[0]);
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+
+use def::{Block, Direction};
+use glium::{index::PrimitiveType, DepthTest, Display, Frame, Program};
+use image::{Rgba, RgbaImage};
+
+use crate::{
+    atlas::Textures,
+    mesh::{Drawable, Sun, TexturedMesh, TexturedMeshVertex, TexturedUniforms},
+    pack,
+};
+
+/// Side of an icon, in pixels
+pub const ICON_SIZE: u32 = 64;
+
+/// Half the width of a cube seen from above one of its corners (cos 30°),
+/// its height being 2.0
+const HALF_WIDTH: f32 = 0.866;
+
+/// The icons are lit from above, the sides seen get darker
+const SHADES: [(Direction, f32); 3] = [
+    (Direction::Up, 1.0),
+    (Direction::South, 0.8),
+    (Direction::East, 0.6),
+];
+
+/// The pixel of a sprite at `[s, t]` (from its top left corner, between
+/// 0.0 and 1.0), darkened by the shade of its face
+fn sample(sprite: &RgbaImage, [s, t]: [f32; 2], shade: f32) -> Rgba<u8> {
+    let (width, height) = sprite.dimensions();
+    let x = ((s * width as f32) as u32).min(width - 1);
+    let y = ((t * height as f32) as u32).min(height - 1);
+    let Rgba([r, g, b, a]) = *sprite.get_pixel(x, y);
+    let dark = |v: u8| (v as f32 * shade).round() as u8;
+    Rgba([dark(r), dark(g), dark(b), a])
+}
+
+/// Draws the icon of a block from the sprites of its faces
+///
+/// A full block is a cube seen from above one of its corners, showing its
+/// top, south and east faces. The others (doors, torches, saplings, water)
+/// are their flat sprite, like they are held.
+pub fn render_icon(block: Block, sprites: &[RgbaImage]) -> RgbaImage {
+    let sprite = |direction: Direction| sprites.get(block.texture(direction) as usize);
+    let mut icon = RgbaImage::new(ICON_SIZE, ICON_SIZE);
+    if !block.is_full() {
+        if let Some(sprite) = sprite(Direction::North) {
+            for (x, y, pixel) in icon.enumerate_pixels_mut() {
+                let position = [x, y].map(|v| (v as f32 + 0.5) / ICON_SIZE as f32);
+                *pixel = sample(sprite, position, 1.0);
+            }
+        }
+        return icon;
+    }
+    for (x, y, pixel) in icon.enumerate_pixels_mut() {
+        // the cube spans from -1.0 to 1.0 in height, its top corner at the
+        // top of the icon
+        let [u, v] = [x, y].map(|v| (v as f32 + 0.5) / ICON_SIZE as f32 * 2.0 - 1.0);
+        let u = u / HALF_WIDTH;
+        // the point of each face seen through the pixel (x to the right,
+        // z to the left, y up)
+        let faces = [
+            [(u + 2.0 * (v + 1.0)) / 2.0, (2.0 * (v + 1.0) - u) / 2.0],
+            [u + 1.0, (u + 2.0) / 2.0 - v],
+            [1.0 - u, (2.0 - u) / 2.0 - v],
+        ];
+        let inside = |v: f32| (0.0..=1.0).contains(&v);
+        let seen = SHADES
+            .iter()
+            .zip(faces)
+            .find(|(_, [a, b])| inside(*a) && inside(*b));
+        let Some((&(direction, shade), [a, b])) = seen else {
+            continue;
+        };
+        let Some(sprite) = sprite(direction) else {
+            continue;
+        };
+        // the sides show their sprite upright
+        let position = match direction {
+            Direction::Up => [a, b],
+            Direction::South => [a, 1.0 - b],
+            _ => [1.0 - a, 1.0 - b],
+        };
+        *pixel = sample(sprite, position, shade);
+    }
+    icon
+}
+
+/// The icons of every block the player can hold, drawn once from the
+/// sprites when the game starts (and again when the packs are reloaded)
+pub struct Icons {
+    textures: Textures,
+    program: Program,
+    /// Index of the icon of each block in the textures
+    index: HashMap<Block, u32>,
+}
+
+impl Icons {
+    pub fn new(display: &Display, sprites: &[RgbaImage]) -> Self {
+        let blocks: Vec<Block> = pack::blocks().map(|(_, block)| block).collect();
+        let icons: Vec<RgbaImage> = blocks
+            .iter()
+            .map(|&block| render_icon(block, sprites))
+            .collect();
+        let textures = Textures::new(display, &icons, false);
+        let index = blocks
+            .into_iter()
+            .enumerate()
+            .map(|(i, block)| (block, i as u32))
+            .collect();
+        Self {
+            program: TexturedMesh::program(display, &textures),
+            textures,
+            index,
+        }
+    }
+
+    /// Draws the icons of blocks, each in the square of the given center
+    /// and side (a block without icon is skipped)
+    pub fn draw(
+        &self,
+        display: &Display,
+        target: &mut Frame,
+        projection: [[f32; 4]; 4],
+        icons: &[(Block, [f32; 2], f32)],
+    ) {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for &(block, [x, y], side) in icons {
+            let Some(&icon) = self.index.get(&block) else {
+                continue;
+            };
+            let indice = vertices.len() as u32;
+            for [u, v] in [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]] {
+                vertices.push(TexturedMeshVertex {
+                    position: [x + (u - 0.5) * side, y + (v - 0.5) * side, 0.0],
+                    tex_pos: [u, v, icon as f32],
+                    light: 1.0,
+                    sky_light: 1.0,
+                    block_light: 0.0,
+                    tint: [1.0; 3],
+                });
+            }
+            indices.extend([0, 1, 2, 0, 2, 3].map(|n| n + indice));
+        }
+        if indices.is_empty() {
+            return;
+        }
+        // the icons are already shaded, the sun of noon leaves them as is
+        let uniforms = TexturedUniforms {
+            textures: &self.textures,
+            sun: Sun {
+                color: [1.0; 3],
+                intensity: 1.0,
+            },
+        };
+        TexturedMesh::new(display, &vertices, &indices, PrimitiveType::TrianglesList)
+            .depth_test(DepthTest::Overwrite)
+            .draw(&self.program, target, projection, uniforms);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use def::Sprite;
+
+    use super::*;
+
+    /// A sprite of a single color per index, told by its alpha (which the
+    /// shades leave as is)
+    fn sprites() -> Vec<RgbaImage> {
+        (0..Sprite::COUNT)
+            .map(|i| RgbaImage::from_pixel(4, 4, Rgba([200, 100, 50, 255 - i as u8])))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_icon_cube() {
+        let icon = render_icon(Block::Stone, &sprites());
+        let at = |u: f32, v: f32| {
+            let [x, y] = [u, v].map(|v| ((v + 1.0) / 2.0 * ICON_SIZE as f32) as u32);
+            *icon.get_pixel(x, y)
+        };
+        // the top, then the left and right sides, darker and darker
+        assert_eq!(at(0.0, -0.5), Rgba([200, 100, 50, 255]));
+        assert_eq!(at(-0.4, 0.3), Rgba([160, 80, 40, 255]));
+        assert_eq!(at(0.4, 0.3), Rgba([120, 60, 30, 255]));
+        // the corners of the icon are not covered by the cube
+        assert_eq!(at(-0.95, -0.95)[3], 0);
+        assert_eq!(at(0.95, 0.95)[3], 0);
+    }
+
+    #[test]
+    fn test_render_icon_faces() {
+        let icon = render_icon(Block::Grass, &sprites());
+        let sprite = |x, y| 255 - icon.get_pixel(x, y)[3] as u32;
+        assert_eq!(
+            sprite(ICON_SIZE / 2, ICON_SIZE / 4),
+            Sprite::GrassTop as u32
+        );
+        assert_eq!(
+            sprite(ICON_SIZE / 4, ICON_SIZE * 3 / 4),
+            Sprite::GrassSide as u32
+        );
+    }
+
+    #[test]
+    fn test_render_icon_flat() {
+        let icon = render_icon(Block::Torch(0), &sprites());
+        let torch = Rgba([200, 100, 50, 255 - Sprite::Torch as u8]);
+        assert!(icon.pixels().all(|&pixel| pixel == torch));
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+
+use def::{Block, ChunkCoords};
+use glium::{index::PrimitiveType, DepthTest, Display, Frame, Program, Surface};
+use mat::{AffineTrait, VectorTrait};
+
+use crate::{
+    mesh::{ColoredMesh, ColoredMeshVertex, Drawable},
+    world::{SurfaceChunk, World},
+};
+
+/// Chunks shown around the chunk of the player, in every direction
+const RANGE: i32 = 3;
+
+/// Width of the minimap, in screen heights
+const SIZE: f32 = 0.5;
+
+/// Space between the minimap and the corner of the screen
+const MARGIN: f32 = 0.05;
+
+/// Color of a block seen from above
+fn map_color(block: Block) -> [f32; 3] {
+    match block {
+        Block::Stone => [0.45, 0.45, 0.45],
+        Block::Dirt => [0.5, 0.35, 0.2],
+        Block::Grass | Block::Sapling(_) => [0.3, 0.6, 0.2],
+        Block::Sand => [0.85, 0.8, 0.55],
+        Block::Water(_) => [0.2, 0.35, 0.8],
+        Block::Glass => [0.75, 0.85, 0.9],
+        Block::Brick => [0.6, 0.3, 0.25],
+        Block::Trunk | Block::Door(_) | Block::Trapdoor(_) => [0.45, 0.32, 0.18],
+        Block::Leaves => [0.15, 0.45, 0.15],
+        Block::Tnt => [0.8, 0.2, 0.2],
+        Block::Torch(_) => [1.0, 0.8, 0.3],
+        Block::Bedrock => [0.15, 0.15, 0.15],
+        Block::Ice => [0.6, 0.75, 0.95],
+        Block::Slime => [0.4, 0.8, 0.35],
+        Block::Chest => [0.55, 0.35, 0.15],
+        Block::Wire(_) => [0.7, 0.1, 0.1],
+        Block::Lever(_) => [0.5, 0.4, 0.3],
+        Block::Lamp(_) => [0.9, 0.75, 0.4],
+        Block::Piston(_) | Block::PistonHead(_) => [0.55, 0.5, 0.4],
+        Block::Farmland => [0.4, 0.27, 0.15],
+        Block::Crop(_) => [0.75, 0.7, 0.3],
+        Block::SoulSand => [0.35, 0.27, 0.2],
+        Block::Glider => [0.8, 0.75, 0.65],
+        Block::Custom(id) => def::custom::get(id).color,
+    }
+}
+
+/// The map is a square of chunks around the center
+fn in_view(cc: ChunkCoords, center: ChunkCoords) -> bool {
+    (cc.x - center.x).abs() <= RANGE && (cc.z - center.z).abs() <= RANGE
+}
+
+/// A square of the map, `x` going right and `z` going down (north is up)
+fn square(
+    vertices: &mut Vec<ColoredMeshVertex>,
+    indices: &mut Vec<u32>,
+    [x, z]: [f32; 2],
+    size: f32,
+    color: [f32; 3],
+) {
+    let indice = vertices.len() as u32;
+    for [dx, dz] in [[0.0, 0.0], [size, 0.0], [size, size], [0.0, size]] {
+        vertices.push(([x + dx, -(z + dz), 0.0], color).into());
+    }
+    // counter clockwise once on the screen, as the z axis goes down
+    indices.extend([0, 3, 2, 0, 2, 1].map(|n| n + indice));
+}
+
+/// Where the full screen map looks
+#[derive(Debug, Clone, Copy)]
+pub struct MapView {
+    /// Block (x, z) in the middle of the screen
+    pub center: [f32; 2],
+    /// Blocks from the middle to the top of the screen
+    pub scale: f32,
+}
+
+impl MapView {
+    const MIN_SCALE: f32 = 16.0;
+    const MAX_SCALE: f32 = 1024.0;
+
+    pub fn new(center: [f32; 2]) -> Self {
+        Self {
+            center,
+            scale: 128.0,
+        }
+    }
+
+    /// Zooms in (positive steps) or out (negative steps)
+    pub fn zoom(&mut self, steps: f32) {
+        self.scale = (self.scale * 0.8_f32.powf(steps)).clamp(Self::MIN_SCALE, Self::MAX_SCALE);
+    }
+
+    /// Moves the view by a fraction of the screen (x and z)
+    pub fn pan(&mut self, [x, z]: [f32; 2]) {
+        self.center = self.center.vector_add([x, z].vector_scale(self.scale));
+    }
+}
+
+/// Top-down map of the explored terrain
+///
+/// Each explored chunk has the colors of its surface, built again when the
+/// chunk changes. Higher ground is brighter. The map is shown in a corner of
+/// the screen around the player, or on the whole screen (see [`MapView`]).
+/// The player is an arrow and the anchors (see [`World::set_loader`]) are
+/// marked with the accent color of the palette.
+pub struct Minimap {
+    chunks: HashMap<ChunkCoords, ColoredMesh>,
+    /// Color of the anchors
+    pub accent: [f32; 3],
+}
+
+impl Minimap {
+    /// Builds the map of the chunks explored in previous sessions
+    pub fn new(display: &Display, world: &World, accent: [f32; 3]) -> Self {
+        let chunks = world
+            .explored_chunks()
+            .into_iter()
+            .map(|(cc, surface)| (cc, surface_mesh(display, &surface)))
+            .collect();
+        Self { chunks, accent }
+    }
+
+    /// Builds the map of a chunk again, from its current surface
+    pub fn update_chunk(&mut self, display: &Display, world: &World, cc: ChunkCoords) {
+        if let Some(surface) = world.explore(cc) {
+            self.chunks.insert(cc, surface_mesh(display, &surface));
+        }
+    }
+
+    /// Draws the map in the top right corner, centered on the chunk of the
+    /// player
+    pub fn draw(
+        &self,
+        display: &Display,
+        program: &Program,
+        target: &mut Frame,
+        aspect_ratio: [[f32; 4]; 4],
+        world: &World,
+    ) {
+        let center = ChunkCoords::from_position(world.pull_player().camera.pos);
+        let blocks = ((2 * RANGE + 1) * 16) as f32;
+        // the screen is `width / height` wide once scaled by the aspect ratio
+        let right = 1.0 / aspect_ratio[0][0];
+        let projection = aspect_ratio
+            .affine_translate([right - MARGIN - SIZE / 2.0, 1.0 - MARGIN - SIZE / 2.0, 0.0])
+            .affine_scale(SIZE / blocks)
+            .affine_translate([-(center.x * 16 + 8) as f32, (center.z * 16 + 8) as f32, 0.0]);
+
+        // the frame and the unknown terrain, then the known terrain over it
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let [x, z] = [center.x, center.z].map(|v| ((v - RANGE) * 16) as f32 - 1.0);
+        square(&mut vertices, &mut indices, [x, z], blocks + 2.0, [0.1; 3]);
+        ColoredMesh::new(display, &vertices, &indices, PrimitiveType::TrianglesList)
+            .depth_test(DepthTest::Overwrite)
+            .draw(program, target, projection, ());
+        self.draw_chunks(program, target, projection, |cc| in_view(cc, center));
+        draw_markers(display, program, target, projection, world, 1.0, |cc| {
+            in_view(cc, center).then_some(self.accent)
+        });
+    }
+
+    /// Draws the map on the whole screen
+    pub fn draw_full(
+        &self,
+        display: &Display,
+        program: &Program,
+        target: &mut Frame,
+        aspect_ratio: [[f32; 4]; 4],
+        world: &World,
+        view: MapView,
+    ) {
+        target.clear_color_and_depth((0.1, 0.1, 0.1, 1.0), 1.0);
+        let [x, z] = view.center;
+        let projection = aspect_ratio
+            .affine_scale(1.0 / view.scale)
+            .affine_translate([-x, z, 0.0]);
+        // only the chunks on the screen are drawn
+        let half_width = view.scale / aspect_ratio[0][0] + 16.0;
+        let half_height = view.scale + 16.0;
+        self.draw_chunks(program, target, projection, |cc| {
+            ((cc.x * 16 + 8) as f32 - x).abs() <= half_width
+                && ((cc.z * 16 + 8) as f32 - z).abs() <= half_height
+        });
+        // the markers stay the same size on the screen
+        let size = view.scale / 48.0;
+        draw_markers(display, program, target, projection, world, size, |_| {
+            Some(self.accent)
+        });
+    }
+
+    fn draw_chunks(
+        &self,
+        program: &Program,
+        target: &mut Frame,
+        projection: [[f32; 4]; 4],
+        visible: impl Fn(ChunkCoords) -> bool,
+    ) {
+        for (&cc, mesh) in &self.chunks {
+            if visible(cc) {
+                let offset = [(cc.x * 16) as f32, -(cc.z * 16) as f32, 0.0];
+                mesh.draw(program, target, projection.affine_translate(offset), ());
+            }
+        }
+    }
+}
+
+/// The colors of the top blocks of a chunk
+fn surface_mesh(display: &Display, surface: &SurfaceChunk) -> ColoredMesh {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for (x, column) in surface.iter().enumerate() {
+        for (z, top) in column.iter().enumerate() {
+            if let Some((y, block)) = *top {
+                let shade = 0.6 + 0.5 * (y as f32 / 128.0).min(1.0);
+                let color = map_color(block).vector_scale(shade);
+                square(
+                    &mut vertices,
+                    &mut indices,
+                    [x as f32, z as f32],
+                    1.0,
+                    color,
+                );
+            }
+        }
+    }
+    ColoredMesh::new(display, &vertices, &indices, PrimitiveType::TrianglesList)
+        .depth_test(DepthTest::Overwrite)
+}
+
+/// Draws the anchors and the player (an arrow pointing where it looks)
+///
+/// The anchors drawn are those given a color.
+fn draw_markers(
+    display: &Display,
+    program: &Program,
+    target: &mut Frame,
+    projection: [[f32; 4]; 4],
+    world: &World,
+    size: f32,
+    color: impl Fn(ChunkCoords) -> Option<[f32; 3]>,
+) {
+    let anchors: Vec<ColoredMeshVertex> = world
+        .named_loaders()
+        .into_iter()
+        .map(|(_, loader)| loader.center)
+        .filter_map(|cc| {
+            let position = [(cc.x * 16 + 8) as f32, -(cc.z * 16 + 8) as f32, 0.0];
+            Some((position, color(cc)?).into())
+        })
+        .collect();
+    if !anchors.is_empty() {
+        let indices: Vec<u32> = (0..anchors.len() as u32).collect();
+        ColoredMesh::new(display, &anchors, &indices, PrimitiveType::Points)
+            .point_size(6.0)
+            .depth_test(DepthTest::Overwrite)
+            .draw(program, target, projection, ());
+    }
+
+    let camera = world.pull_player().camera;
+    let [fx, _, fz, _] = camera.matrix().vector_z();
+    let length = fx.hypot(fz).max(f32::EPSILON);
+    let forward = [fx / length, -fz / length].vector_scale(size);
+    let left = [-forward[1], forward[0]];
+    let at = [camera.pos[0], -camera.pos[2]];
+    let point = |f: f32, l: f32| {
+        let [x, y] = at
+            .vector_add(forward.vector_scale(f))
+            .vector_add(left.vector_scale(l));
+        ([x, y, 0.0], [1.0, 1.0, 1.0]).into()
+    };
+    ColoredMesh::new(
+        display,
+        &[point(4.0, 0.0), point(-2.0, 2.5), point(-2.0, -2.5)],
+        &[0, 1, 2],
+        PrimitiveType::TrianglesList,
+    )
+    .depth_test(DepthTest::Overwrite)
+    .draw(program, target, projection, ());
+}
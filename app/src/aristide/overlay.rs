@@ -0,0 +1,69 @@
+use def::{Block, Direction};
+use glium::{index::PrimitiveType, DepthTest, Display, Frame, Program};
+use mat::Affine;
+
+use crate::mesh::{Drawable, TexturedMesh, TexturedMeshVertex, TexturedUniforms};
+
+/// Whether the eyes in a voxel see its block from the inside (`height`
+/// being their height in the voxel)
+///
+/// A transparent block (like glass) is seen through anyway, and a fluid
+/// only below its surface.
+pub fn is_inside(block: Block, height: f32) -> bool {
+    match block.fluid_height() {
+        Some(surface) => height < surface,
+        None => block.is_full() && !block.is_transparent(),
+    }
+}
+
+/// Covers the screen with the sprite of the block the eyes are in
+///
+/// The faces of a block are culled from the inside (and cut by the near
+/// plane), the world would show through it instead. An opaque block is
+/// dark, a fluid tints the view with its translucent sprite.
+pub fn draw_inside(
+    display: &Display,
+    program: &Program,
+    target: &mut Frame,
+    uniforms: TexturedUniforms,
+    block: Block,
+) {
+    let sprite = block.texture(Direction::North) as f32;
+    let light = if block.fluid_height().is_some() {
+        Direction::Up.light()
+    } else {
+        Direction::Down.light()
+    };
+    let vertices =
+        [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]].map(|[u, v]| TexturedMeshVertex {
+            position: [u * 2.0 - 1.0, 1.0 - v * 2.0, 0.0],
+            tex_pos: [u, v, sprite],
+            light,
+            sky_light: 1.0,
+            block_light: 0.0,
+            tint: [1.0; 3],
+        });
+    TexturedMesh::new(
+        display,
+        &vertices,
+        &[0, 2, 1, 0, 3, 2],
+        PrimitiveType::TrianglesList,
+    )
+    .depth_test(DepthTest::Overwrite)
+    .draw(program, target, Affine::identity(), uniforms);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_inside() {
+        assert!(is_inside(Block::Stone, 0.5));
+        assert!(!is_inside(Block::Glass, 0.5));
+        assert!(!is_inside(Block::Torch(0), 0.5));
+        // a source of water fills 14/16 of its voxel
+        assert!(is_inside(Block::Water(0), 0.5));
+        assert!(!is_inside(Block::Water(0), 0.9));
+    }
+}
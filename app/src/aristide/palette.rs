@@ -0,0 +1,73 @@
+use crate::config::Palette;
+
+/// Colors of the interface drawn over the world
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HudColors {
+    /// Outline of the pointed block
+    pub outline: [f32; 3],
+    pub crosshair: [f32; 3],
+    /// Markers of the map (the anchors)
+    pub accent: [f32; 3],
+    /// Colors told apart from each other (like the phases of the profiler)
+    pub series: [[f32; 3]; 7],
+}
+
+// Okabe and Ito's colors, distinct with any red or green weakness
+const ORANGE: [f32; 3] = [0.9, 0.62, 0.0];
+const SKY_BLUE: [f32; 3] = [0.34, 0.71, 0.91];
+const BLUISH_GREEN: [f32; 3] = [0.0, 0.62, 0.45];
+const YELLOW: [f32; 3] = [0.94, 0.89, 0.26];
+const BLUE: [f32; 3] = [0.0, 0.45, 0.7];
+const VERMILLION: [f32; 3] = [0.84, 0.37, 0.0];
+const REDDISH_PURPLE: [f32; 3] = [0.8, 0.47, 0.65];
+
+impl Palette {
+    pub fn colors(self) -> HudColors {
+        match self {
+            Self::Default => HudColors {
+                outline: [0.0; 3],
+                crosshair: [0.0; 3],
+                accent: [1.0, 0.9, 0.2],
+                series: [
+                    [0.9, 0.9, 0.3],
+                    [0.3, 0.8, 0.3],
+                    [0.3, 0.8, 0.8],
+                    [0.9, 0.5, 0.2],
+                    [0.3, 0.5, 0.9],
+                    [0.6, 0.6, 0.6],
+                    [0.8, 0.3, 0.8],
+                ],
+            },
+            // a bright outline stays visible on dark blocks as well
+            Self::Deuteranopia => HudColors {
+                outline: YELLOW,
+                crosshair: [1.0; 3],
+                accent: SKY_BLUE,
+                series: [
+                    YELLOW,
+                    BLUISH_GREEN,
+                    SKY_BLUE,
+                    ORANGE,
+                    BLUE,
+                    VERMILLION,
+                    REDDISH_PURPLE,
+                ],
+            },
+            // red is seen dark, it is replaced by a light grey
+            Self::Protanopia => HudColors {
+                outline: YELLOW,
+                crosshair: [1.0; 3],
+                accent: SKY_BLUE,
+                series: [
+                    YELLOW,
+                    BLUISH_GREEN,
+                    SKY_BLUE,
+                    ORANGE,
+                    BLUE,
+                    [0.8; 3],
+                    REDDISH_PURPLE,
+                ],
+            },
+        }
+    }
+}
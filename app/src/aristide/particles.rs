@@ -0,0 +1,80 @@
+use glium::{index::PrimitiveType, Display, Program, Surface};
+use mat::VectorTrait;
+
+use crate::mesh::{ColoredMesh, Drawable};
+
+struct Particle {
+    pos: [f32; 3],
+    velocity: [f32; 3],
+    /// Remaining ticks before it disappears
+    life: u32,
+}
+
+/// Short lived colored points, like the debris of an impact
+///
+/// They only exist for the eye (they don't collide), so they live in the
+/// renderer and not in the world.
+pub struct Particles {
+    particles: Vec<Particle>,
+    color: [f32; 3],
+}
+
+impl Particles {
+    pub fn new(color: [f32; 3]) -> Self {
+        Self {
+            particles: Vec::new(),
+            color,
+        }
+    }
+
+    /// Throws `count` particles in every direction from `pos`
+    ///
+    /// `speed` is in blocks per tick.
+    pub fn burst(&mut self, pos: [f32; 3], count: u32, speed: f32) {
+        // directions are spread on a sphere with the golden angle
+        let golden_angle = std::f32::consts::PI * (3.0 - 5f32.sqrt());
+        for i in 0..count {
+            let y = 1.0 - 2.0 * (i as f32 + 0.5) / count as f32;
+            let radius = (1.0 - y * y).sqrt();
+            let angle = golden_angle * i as f32;
+            self.particles.push(Particle {
+                pos,
+                velocity: [angle.cos() * radius, y, angle.sin() * radius].vector_scale(speed),
+                life: 20 + i % 10,
+            });
+        }
+    }
+
+    /// Moves the particles by one tick, they float in the air and fall at
+    /// half the `gravity`
+    pub fn update(&mut self, gravity: f32) {
+        for particle in &mut self.particles {
+            particle.velocity[1] += gravity / 2.0;
+            particle.pos.vector_add_assign(particle.velocity);
+            particle.life -= 1;
+        }
+        self.particles.retain(|particle| particle.life > 0);
+    }
+
+    pub fn draw(
+        &self,
+        display: &Display,
+        program: &Program,
+        target: &mut impl Surface,
+        projection: [[f32; 4]; 4],
+    ) {
+        if self.particles.is_empty() {
+            return;
+        }
+        // there are few particles and they all move, the mesh is built every frame
+        let vertices: Vec<_> = self
+            .particles
+            .iter()
+            .map(|particle| (particle.pos, self.color).into())
+            .collect();
+        let indices: Vec<u32> = (0..vertices.len() as u32).collect();
+        ColoredMesh::new(display, &vertices, &indices, PrimitiveType::Points)
+            .point_size(3.0)
+            .draw(program, target, projection, ());
+    }
+}
@@ -0,0 +1,215 @@
+use glium::{
+    implement_vertex,
+    index::{NoIndices, PrimitiveType},
+    texture::{DepthTexture2d, Texture2d},
+    uniform,
+    uniforms::{MagnifySamplerFilter, MinifySamplerFilter, SamplerWrapFunction},
+    Display, Program, Surface, VertexBuffer,
+};
+use image::{imageops, RgbaImage};
+use mat::{Affine, MatrixTrait, Projection};
+
+use super::{control::Control, spectator::Spectator};
+use crate::camera::Camera;
+
+/// Times larger than the window the screenshots are rendered, before being
+/// scaled down (less if the GPU can't draw that large)
+pub const SUPERSAMPLING: u32 = 4;
+
+/// Roll of the camera for a step of the mouse wheel
+const ROLL_STEP: f32 = 0.05;
+
+/// Farthest distance the focus can be at (where the sky is)
+pub const FOCUS_RANGE: f32 = 256.0;
+
+/// Blur of what is the farthest out of focus, as a part of the screen
+/// height
+const MAX_BLUR: f32 = 0.006;
+
+/// The photo mode: the world stands still, the HUD is hidden, and a free
+/// camera can roll and blur what is out of focus
+pub struct Photo {
+    /// The free camera, flying with the keyboard like the spectator one
+    pub view: Spectator,
+    /// Tilt of the camera around where it looks, toward its right
+    pub roll: f32,
+    pub depth_of_field: bool,
+}
+
+impl Photo {
+    pub fn new(camera: Camera) -> Self {
+        Self {
+            view: Spectator::new(camera),
+            roll: 0.0,
+            depth_of_field: false,
+        }
+    }
+
+    pub fn camera(&self) -> Camera {
+        self.view.camera
+    }
+
+    pub fn update(&mut self, control: &Control) {
+        self.view.update(control);
+    }
+
+    pub fn roll(&mut self, steps: f32) {
+        self.roll += steps * ROLL_STEP;
+    }
+
+    /// The projector of the camera, rolled
+    pub fn projector(&self) -> [[f32; 4]; 4] {
+        Affine::<f32, 4>::z_rotate(self.roll).matrix_mul(self.view.camera.projector())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: [f32; 2],
+}
+implement_vertex!(Vertex, position);
+
+const VERTEX_PROGRAM: &str = r#"
+    #version 140
+
+    in vec2 position;
+
+    out vec2 v_tex_pos;
+
+    void main() {
+        v_tex_pos = position * 0.5 + 0.5;
+        gl_Position = vec4(position, 0.0, 1.0);
+    }
+"#;
+
+const FRAGMENT_PROGRAM: &str = r#"
+    #version 140
+
+    in vec2 v_tex_pos;
+    out vec4 color;
+
+    uniform sampler2D scene;
+    uniform sampler2D depth;
+    uniform float near;
+    uniform float far;
+    uniform float focus;
+    // the largest blur, in texture coordinates
+    uniform vec2 blur;
+
+    // distance from the camera, from the value in the depth buffer
+    float distance_at(vec2 tex_pos) {
+        float z = texture(depth, tex_pos).r * 2.0 - 1.0;
+        return 2.0 * near * far / (far + near - z * (far - near));
+    }
+
+    void main() {
+        float out_of_focus = clamp(abs(distance_at(v_tex_pos) - focus) / focus, 0.0, 1.0);
+        vec3 sum = texture(scene, v_tex_pos).rgb;
+        // samples spread on a disk, along a golden angle spiral
+        const int SAMPLES = 24;
+        for (int i = 1; i <= SAMPLES; i++) {
+            float angle = float(i) * 2.39996;
+            float radius = sqrt(float(i) / float(SAMPLES)) * out_of_focus;
+            sum += texture(scene, v_tex_pos + vec2(cos(angle), sin(angle)) * radius * blur).rgb;
+        }
+        color = vec4(sum / float(SAMPLES + 1), 1.0);
+    }
+"#;
+
+/// Draws a rendered scene on a surface, blurring what is away from the
+/// focus (when enabled)
+pub struct DepthOfField {
+    program: Program,
+    quad: VertexBuffer<Vertex>,
+}
+
+impl DepthOfField {
+    pub fn new(display: &Display) -> Self {
+        let corners = [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]];
+        let quad = corners.map(|position| Vertex { position });
+        Self {
+            program: Program::from_source(display, VERTEX_PROGRAM, FRAGMENT_PROGRAM, None).unwrap(),
+            quad: VertexBuffer::new(display, &quad).unwrap(),
+        }
+    }
+
+    /// Draws the scene, `depth` being its depth buffer with the given
+    /// projection and `focus` the distance seen sharp (no blur without one)
+    pub fn draw(
+        &self,
+        target: &mut impl Surface,
+        scene: &Texture2d,
+        depth: &DepthTexture2d,
+        projection: Projection,
+        focus: Option<f32>,
+    ) {
+        let blur = match focus {
+            Some(_) => [MAX_BLUR / projection.aspect, MAX_BLUR],
+            None => [0.0; 2],
+        };
+        target
+            .draw(
+                &self.quad,
+                NoIndices(PrimitiveType::TriangleStrip),
+                &self.program,
+                &uniform! {
+                    scene: scene
+                        .sampled()
+                        .minify_filter(MinifySamplerFilter::Linear)
+                        .magnify_filter(MagnifySamplerFilter::Linear)
+                        .wrap_function(SamplerWrapFunction::Clamp),
+                    depth: depth
+                        .sampled()
+                        .minify_filter(MinifySamplerFilter::Nearest)
+                        .magnify_filter(MagnifySamplerFilter::Nearest),
+                    near: projection.near,
+                    far: projection.far,
+                    focus: focus.unwrap_or(FOCUS_RANGE),
+                    blur: blur,
+                },
+                &Default::default(),
+            )
+            .unwrap();
+    }
+}
+
+/// Scales a screenshot rendered `scale` times larger down to its size,
+/// turning it upside up (OpenGL reads the rows from the bottom)
+pub fn downsample(image: RgbaImage, scale: u32) -> RgbaImage {
+    let image = imageops::flip_vertical(&image);
+    let (width, height) = (image.width() / scale, image.height() / scale);
+    imageops::resize(&image, width, height, imageops::FilterType::Triangle)
+}
+
+#[cfg(test)]
+mod test {
+    use image::Rgba;
+    use mat::AffineTrait;
+
+    use super::*;
+
+    #[test]
+    fn test_roll() {
+        let mut photo = Photo::new(Camera::default());
+        // looking ahead, the up of the camera turns with the roll
+        photo.roll(std::f32::consts::FRAC_PI_2 / ROLL_STEP);
+        let [x, y, _] = photo.projector().transform_vector([0.0, 1.0, 0.0]);
+        assert!((x.abs() - 1.0).abs() < 1e-6 && y.abs() < 1e-6);
+        // the center of the view does not move
+        let [x, y, z] = photo.projector().transform_vector([0.0, 0.0, 1.0]);
+        assert!(x.abs() < 1e-6 && y.abs() < 1e-6 && z > 0.0);
+    }
+
+    #[test]
+    fn test_downsample() {
+        // the top half white, as read from the bottom
+        let image = RgbaImage::from_fn(8, 8, |_, y| match y {
+            0..=3 => Rgba([0, 0, 0, 255]),
+            _ => Rgba([255, 255, 255, 255]),
+        });
+        let image = downsample(image, 2);
+        assert_eq!(image.dimensions(), (4, 4));
+        assert_eq!(image.get_pixel(0, 0), &Rgba([255, 255, 255, 255]));
+        assert_eq!(image.get_pixel(3, 3), &Rgba([0, 0, 0, 255]));
+    }
+}
@@ -0,0 +1,176 @@
+use def::Block;
+use glium::{Display, Frame, Program};
+
+use super::{
+    grid::SlotGrid,
+    icons::Icons,
+    text::{self, Anchor},
+};
+use crate::{
+    i18n::{block_name, tr},
+    pack,
+};
+
+/// Cells in a row of the grid, and rows shown at once
+const COLUMNS: usize = 8;
+const ROWS: usize = 5;
+
+/// The cells of the blocks shown
+const GRID: SlotGrid = SlotGrid {
+    columns: COLUMNS,
+    rows: ROWS,
+    cell: 0.16,
+    padding: 0.015,
+};
+
+/// The screen listing every block, to put them in the hotbar (in creative
+/// mode, nothing is counted)
+#[derive(Debug, Clone, Default)]
+pub struct Picker {
+    /// Only the blocks whose name holds it are listed
+    pub search: String,
+    /// First row shown
+    scroll: usize,
+    /// Where the mouse points, in the coordinates of the HUD
+    pub cursor: [f32; 2],
+}
+
+impl Picker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The blocks matching the search, by their name in the commands or
+    /// the one shown to the player
+    pub fn blocks(&self) -> Vec<Block> {
+        let search = self.search.to_lowercase();
+        pack::blocks()
+            .filter(|&(name, block)| {
+                name.contains(&search) || block_name(block).to_lowercase().contains(&search)
+            })
+            .map(|(_, block)| block)
+            .collect()
+    }
+
+    /// Adds a typed character to the search (only letters and spaces, the
+    /// digits go to the hotbar)
+    pub fn type_char(&mut self, c: char) {
+        if c.is_alphabetic() || c == ' ' {
+            self.search.push(c);
+            self.scroll = 0;
+        }
+    }
+
+    pub fn erase(&mut self) {
+        self.search.pop();
+        self.scroll = 0;
+    }
+
+    /// Scrolls the grid by rows (up for positive steps), within its rows
+    pub fn scroll(&mut self, steps: f32) {
+        let rows = self.blocks().len().div_ceil(COLUMNS);
+        let scroll = self.scroll as f32 - steps.round();
+        self.scroll = (scroll.max(0.0) as usize).min(rows.saturating_sub(ROWS));
+    }
+
+    /// Index in the blocks listed of a cell of the grid shown
+    fn index(&self, column: usize, row: usize) -> usize {
+        (self.scroll + row) * COLUMNS + column
+    }
+
+    /// Index in the blocks listed of the cell under the mouse
+    fn pointed(&self) -> Option<usize> {
+        let (column, row) = GRID.pointed(self.cursor)?;
+        Some(self.index(column, row))
+    }
+
+    /// The block under the mouse
+    pub fn block(&self) -> Option<Block> {
+        self.blocks().get(self.pointed()?).copied()
+    }
+
+    /// Draws the grid in the middle of the screen, with the search and the
+    /// name of the block pointed at the top
+    pub fn draw(
+        &self,
+        display: &Display,
+        program: &Program,
+        icons: &Icons,
+        target: &mut Frame,
+        aspect_ratio: [[f32; 4]; 4],
+        accent: [f32; 3],
+    ) {
+        let blocks = self.blocks();
+        let pointed = self.pointed();
+        let mut cells = Vec::new();
+        let mut shown = Vec::new();
+        for row in 0..ROWS {
+            for column in 0..COLUMNS {
+                let index = self.index(column, row);
+                let Some(&block) = blocks.get(index) else {
+                    continue;
+                };
+                cells.push(((column, row), pointed == Some(index)));
+                shown.push((block, GRID.center(column, row), GRID.icon()));
+            }
+        }
+        GRID.draw(display, program, target, aspect_ratio, &cells, accent);
+        icons.draw(display, target, aspect_ratio, &shown);
+
+        let mut lines = vec![tr!("picker.search", search = self.search)];
+        if let Some(block) = self.block() {
+            lines.push(block_name(block).to_string());
+        }
+        text::draw_lines(
+            display,
+            program,
+            target,
+            aspect_ratio,
+            &lines,
+            accent,
+            Anchor::TopCenter,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        let mut picker = Picker::new();
+        assert_eq!(picker.blocks().len(), pack::blocks().count());
+        for c in "Bri".chars() {
+            picker.type_char(c);
+        }
+        assert_eq!(picker.blocks(), [Block::Brick]);
+        // the digits are not typed
+        picker.type_char('1');
+        assert_eq!(picker.search, "Bri");
+        picker.erase();
+        picker.erase();
+        assert!(picker.blocks().contains(&Block::Bedrock));
+    }
+
+    #[test]
+    fn test_pointed() {
+        let mut picker = Picker::new();
+        picker.cursor = GRID.center(0, 0);
+        assert_eq!(picker.block(), picker.blocks().first().copied());
+        picker.cursor = GRID.center(2, 1);
+        assert_eq!(picker.pointed(), Some(COLUMNS + 2));
+        picker.cursor = [GRID.cell * COLUMNS as f32, 0.0];
+        assert_eq!(picker.block(), None);
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut picker = Picker::new();
+        picker.scroll(-100.0);
+        let rows = picker.blocks().len().div_ceil(COLUMNS);
+        assert_eq!(picker.scroll, rows.saturating_sub(ROWS));
+        picker.scroll(100.0);
+        assert_eq!(picker.scroll, 0);
+    }
+}
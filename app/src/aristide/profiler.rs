@@ -0,0 +1,57 @@
+use glium::{index::PrimitiveType, DepthTest, Display, Frame, Program};
+
+use crate::{
+    mesh::{ColoredMesh, Drawable},
+    world::{Phase, Stats},
+};
+
+use super::FRAME_DURATION;
+
+/// Length of the bar of a whole frame, in screen heights
+const WIDTH: f32 = 0.8;
+
+/// Thickness of a bar, and space between two bars
+const BAR: f32 = 0.04;
+const GAP: f32 = 0.01;
+
+/// Draws the time spent in each phase per frame as a bar chart, in the
+/// bottom left corner
+///
+/// The bars are in the order of [`Phase::ALL`], from the top, each of its
+/// color of the series. The white
+/// line is the duration of a frame: a bar reaching it takes a whole frame
+/// by itself.
+pub fn draw_stats(
+    display: &Display,
+    program: &Program,
+    target: &mut Frame,
+    aspect_ratio: [[f32; 4]; 4],
+    stats: &Stats,
+    series: [[f32; 3]; 7],
+) {
+    // the screen is `width / height` wide once scaled by the aspect ratio
+    let left = -1.0 / aspect_ratio[0][0] + GAP;
+    let bottom = -1.0 + GAP;
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut rectangle = |[x0, y0, x1, y1]: [f32; 4], color: [f32; 3]| {
+        let indice = vertices.len() as u32;
+        for [x, y] in [[x0, y0], [x1, y0], [x1, y1], [x0, y1]] {
+            vertices.push(([x, y, 0.0], color).into());
+        }
+        indices.extend([0, 1, 2, 0, 2, 3].map(|n| n + indice));
+    };
+    let rows = Phase::ALL.len() as f32;
+    for (row, phase) in Phase::ALL.into_iter().enumerate() {
+        let share = stats.average(phase).as_secs_f32() / FRAME_DURATION.as_secs_f32();
+        // a very long hitch does not cross the whole screen
+        let length = share.min(2.0) * WIDTH;
+        let y = bottom + (rows - 1.0 - row as f32) * (BAR + GAP);
+        rectangle([left, y, left + length, y + BAR], series[row]);
+    }
+    let top = bottom + rows * (BAR + GAP);
+    rectangle([left + WIDTH, bottom, left + WIDTH + 0.005, top], [1.0; 3]);
+    ColoredMesh::new(display, &vertices, &indices, PrimitiveType::TrianglesList)
+        .depth_test(DepthTest::Overwrite)
+        .draw(program, target, aspect_ratio, ());
+}
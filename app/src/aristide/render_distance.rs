@@ -0,0 +1,96 @@
+/// Render distances the tuning stays between (in chunks)
+pub const MIN_DISTANCE: i32 = 4;
+pub const MAX_DISTANCE: i32 = 16;
+
+/// Above this share of the frame spent working, the distance shrinks
+const HIGH: f32 = 0.9;
+/// Below this share, it grows (the gap between the two keeps it from
+/// going back and forth)
+const LOW: f32 = 0.5;
+
+/// Frames between two changes, so the chunks loaded or dropped settle and
+/// the averages catch up before deciding again
+const COOLDOWN: u32 = 120;
+
+/// Most chunks meshed and uploaded in a frame
+const MAX_UPLOADS: usize = 8;
+
+/// Grows or shrinks the render distance to hold the frame rate
+///
+/// The chunks meshed per frame are limited too, as a burst of uploads is
+/// what makes a frame late when new chunks come in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoDistance {
+    distance: i32,
+    uploads: usize,
+    cooldown: u32,
+}
+
+impl AutoDistance {
+    pub fn new(distance: i32) -> Self {
+        Self {
+            distance: distance.clamp(MIN_DISTANCE, MAX_DISTANCE),
+            uploads: MAX_UPLOADS / 2,
+            cooldown: COOLDOWN,
+        }
+    }
+
+    /// Chunks to mesh in this frame, the others wait for the next ones
+    pub fn uploads(&self) -> usize {
+        self.uploads
+    }
+
+    /// Takes a frame into account, `load` being the share of the frame
+    /// duration spent working (averaged over the last frames)
+    ///
+    /// Returns the new distance when it changes.
+    pub fn frame(&mut self, load: f32) -> Option<i32> {
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return None;
+        }
+        let distance = if load > HIGH {
+            self.uploads = (self.uploads / 2).max(1);
+            self.distance - 1
+        } else if load < LOW {
+            self.uploads = (self.uploads + 1).min(MAX_UPLOADS);
+            self.distance + 1
+        } else {
+            return None;
+        };
+        self.cooldown = COOLDOWN;
+        let distance = distance.clamp(MIN_DISTANCE, MAX_DISTANCE);
+        (distance != self.distance).then(|| {
+            self.distance = distance;
+            distance
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_auto_distance() {
+        let mut auto = AutoDistance::new(8);
+        let frames = |auto: &mut AutoDistance, count, load| {
+            (0..count)
+                .filter_map(|_| auto.frame(load))
+                .collect::<Vec<_>>()
+        };
+        // nothing changes until the averages settle
+        assert!(frames(&mut auto, COOLDOWN, 2.0).is_empty());
+        // a slow frame rate shrinks the distance, one step per cooldown
+        assert_eq!(frames(&mut auto, COOLDOWN + 1, 2.0), [7]);
+        assert_eq!(auto.uploads(), MAX_UPLOADS / 4);
+        // in between, it holds
+        assert!(frames(&mut auto, 10 * COOLDOWN, 0.7).is_empty());
+        // a fast one grows it, up to the maximum
+        let grown = frames(&mut auto, 100 * COOLDOWN, 0.1);
+        assert_eq!(grown.len() as i32, MAX_DISTANCE - 7);
+        assert_eq!(grown.last(), Some(&MAX_DISTANCE));
+        assert_eq!(auto.uploads(), MAX_UPLOADS);
+        assert_eq!(AutoDistance::new(64).distance, MAX_DISTANCE);
+    }
+}
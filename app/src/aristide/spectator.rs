@@ -0,0 +1,186 @@
+use mat::{MatrixTrait, VectorTrait};
+
+use super::control::Control;
+use crate::camera::Camera;
+
+/// Frames to fly from a keyframe to the next one (2 seconds)
+const SEGMENT: f32 = 120.0;
+
+/// A camera flying freely, apart from the player, which can replay a path
+///
+/// The path goes through the recorded keyframes: a Catmull-Rom spline
+/// through their positions, and a slerp between their orientations.
+pub struct Spectator {
+    pub camera: Camera,
+    keyframes: Vec<Camera>,
+    /// Frames since the start of the playback, while playing
+    playback: Option<u32>,
+}
+
+impl Spectator {
+    pub fn new(camera: Camera) -> Self {
+        Self {
+            camera,
+            keyframes: Vec::new(),
+            playback: None,
+        }
+    }
+
+    pub fn playing(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    /// Adds the current camera at the end of the path
+    pub fn record(&mut self) {
+        self.keyframes.push(self.camera);
+        tracing::info!(keyframes = self.keyframes.len(), "keyframe recorded");
+    }
+
+    pub fn clear(&mut self) {
+        self.keyframes.clear();
+        self.playback = None;
+    }
+
+    /// Plays the path from its start, or stops playing it
+    pub fn toggle_playback(&mut self) {
+        self.playback = match self.playback {
+            None if self.keyframes.len() >= 2 => Some(0),
+            _ => None,
+        };
+    }
+
+    /// Moves the camera along the path, or with the keyboard
+    pub fn update(&mut self, control: &Control) {
+        if let Some(frame) = &mut self.playback {
+            *frame += 1;
+            let end = (self.keyframes.len() - 1) as f32;
+            let progress = *frame as f32 / SEGMENT;
+            self.camera = path(&self.keyframes, progress.min(end));
+            if progress >= end {
+                self.playback = None;
+            }
+            return;
+        }
+        let speed = if control.shift { 1.5 } else { 0.5 };
+        let axis = |plus: bool, minus: bool| speed * (plus as i8 - minus as i8) as f32;
+        let vector = [
+            axis(control.left, control.right),
+            axis(control.up, control.down),
+            axis(control.front, control.back),
+        ];
+        // free of collisions and gravity
+        let [vector] = self.camera.move_matrix().matrix_mul([vector]);
+        self.camera.delta_pos(vector);
+    }
+}
+
+/// Catmull-Rom spline between `p1` and `p2` (`t` from 0.0 to 1.0), going
+/// through every point with a continuous speed
+fn catmull_rom(p0: [f32; 3], p1: [f32; 3], p2: [f32; 3], p3: [f32; 3], t: f32) -> [f32; 3] {
+    let (t2, t3) = (t * t, t * t * t);
+    p1.vector_scale(2.0)
+        .vector_add(p2.vector_sub(p0).vector_scale(t))
+        .vector_add(
+            p0.vector_scale(2.0)
+                .vector_sub(p1.vector_scale(5.0))
+                .vector_add(p2.vector_scale(4.0))
+                .vector_sub(p3)
+                .vector_scale(t2),
+        )
+        .vector_add(
+            p1.vector_scale(3.0)
+                .vector_sub(p0)
+                .vector_sub(p2.vector_scale(3.0))
+                .vector_add(p3)
+                .vector_scale(t3),
+        )
+        .vector_scale(0.5)
+}
+
+/// The camera at a point of the path through the keyframes (`progress`
+/// from 0.0, the first one, to the index of the last one)
+fn path(keyframes: &[Camera], progress: f32) -> Camera {
+    let last = keyframes.len() - 1;
+    let i = (progress.floor() as usize).min(last.saturating_sub(1));
+    let t = progress - i as f32;
+    // the ends are repeated, for the spline to have points around them
+    let at = |i: usize| keyframes[i.min(last)].pos;
+    let before = at(i.saturating_sub(1));
+    let mut camera = keyframes[i];
+    camera.pos = catmull_rom(before, at(i), at(i + 1), at(i + 2), t);
+    let to = keyframes[(i + 1).min(last)].orientation();
+    camera.set_orientation(keyframes[i].orientation().slerp(to, t));
+    camera
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn camera(pos: [f32; 3], h_angle: f32, v_angle: f32) -> Camera {
+        Camera {
+            pos,
+            h_angle,
+            v_angle,
+        }
+    }
+
+    #[test]
+    fn test_orientation() {
+        for (h, v) in [(0.0, 0.0), (1.0, 0.5), (4.0, -1.2), (6.0, 0.1)] {
+            let original = camera([0.0; 3], h, v);
+            let mut copy = camera([0.0; 3], 0.0, 0.0);
+            copy.set_orientation(original.orientation());
+            assert!(
+                (copy.h_angle - h).abs() < 1e-4,
+                "{h} became {}",
+                copy.h_angle
+            );
+            assert!(
+                (copy.v_angle - v).abs() < 1e-4,
+                "{v} became {}",
+                copy.v_angle
+            );
+            // the rotation points the camera where its matrix does
+            let [x, y, z, _] = original.matrix()[2];
+            let error = original
+                .orientation()
+                .rotate([0.0, 0.0, 1.0])
+                .vector_sub([x, y, z]);
+            assert!(error.vector_dot(error) < 1e-8);
+        }
+    }
+
+    #[test]
+    fn test_path() {
+        let keyframes = [
+            camera([0.0, 10.0, 0.0], 0.0, 0.0),
+            camera([10.0, 10.0, 0.0], 1.0, 0.0),
+            camera([10.0, 20.0, 10.0], 1.0, 0.6),
+        ];
+        // the path goes through every keyframe
+        for (i, keyframe) in keyframes.iter().enumerate() {
+            let camera = path(&keyframes, i as f32);
+            let error = camera.pos.vector_sub(keyframe.pos);
+            assert!(error.vector_dot(error) < 1e-8);
+            assert!((camera.h_angle - keyframe.h_angle).abs() < 1e-4);
+        }
+        // halfway, it turned halfway
+        let half = path(&keyframes, 0.5);
+        assert!((half.h_angle - 0.5).abs() < 1e-4);
+        assert!((half.pos[0] - 5.0).abs() < 1.0);
+        assert!((half.pos[1] - 10.0).abs() < 1.0);
+
+        let mut spectator = Spectator::new(keyframes[0]);
+        spectator.keyframes = keyframes.to_vec();
+        spectator.toggle_playback();
+        let mut frames = 0;
+        while spectator.playing() {
+            spectator.update(&Control::default());
+            frames += 1;
+        }
+        assert_eq!(frames, 2 * SEGMENT as usize);
+        let error = spectator.camera.pos.vector_sub(keyframes[2].pos);
+        assert!(error.vector_dot(error) < 1e-8);
+    }
+}
@@ -0,0 +1,46 @@
+use glium::{index::PrimitiveType, DepthTest, Display, Frame, Program};
+
+use crate::{
+    mesh::{ColoredMesh, Drawable},
+    world::MAX_STAMINA,
+};
+
+/// Length of the bar of a rested player, in screen heights
+const WIDTH: f32 = 0.6;
+
+/// Thickness of the bar, and space below it
+const BAR: f32 = 0.02;
+const GAP: f32 = 0.05;
+
+/// Draws the stamina of the player as a bar, at the bottom of the screen
+///
+/// The spent part stays dark, so the bar keeps its length.
+pub fn draw_stamina(
+    display: &Display,
+    program: &Program,
+    target: &mut Frame,
+    aspect_ratio: [[f32; 4]; 4],
+    stamina: f32,
+    color: [f32; 3],
+) {
+    let left = -WIDTH / 2.0;
+    let bottom = -1.0 + GAP;
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut rectangle = |[x0, y0, x1, y1]: [f32; 4], color: [f32; 3]| {
+        let indice = vertices.len() as u32;
+        for [x, y] in [[x0, y0], [x1, y0], [x1, y1], [x0, y1]] {
+            vertices.push(([x, y, 0.0], color).into());
+        }
+        indices.extend([0, 1, 2, 0, 2, 3].map(|n| n + indice));
+    };
+    let length = WIDTH * stamina / MAX_STAMINA;
+    rectangle([left, bottom, left + length, bottom + BAR], color);
+    rectangle(
+        [left + length, bottom, left + WIDTH, bottom + BAR],
+        [0.15; 3],
+    );
+    ColoredMesh::new(display, &vertices, &indices, PrimitiveType::TrianglesList)
+        .depth_test(DepthTest::Overwrite)
+        .draw(program, target, aspect_ratio, ());
+}
@@ -0,0 +1,390 @@
+use std::collections::VecDeque;
+
+use glium::{index::PrimitiveType, DepthTest, Display, Frame, Program};
+use mat::AffineTrait;
+
+use crate::{
+    camera::Camera,
+    mesh::{ColoredMesh, Drawable},
+};
+
+/// Size of a pixel of the glyphs, in blocks
+const PIXEL: f32 = 0.04;
+
+/// Glyphs are 3 pixels wide and 5 tall, with a pixel between two of them
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+
+/// Size of a pixel of the glyphs on the screen, and space between the text
+/// and the corner, in screen heights
+const HUD_PIXEL: f32 = 0.006;
+const HUD_MARGIN: f32 = 0.02;
+
+/// Color of the plate behind the text, so it reads on any background
+const PLATE: [f32; 3] = [0.1, 0.1, 0.1];
+
+/// A floating text vanishes after this many ticks
+const FLOATING_LIFE: u32 = 40;
+
+/// Rising speed of a floating text (in blocks per tick)
+const FLOATING_SPEED: f32 = 0.02;
+
+/// A toast is shown for this many ticks
+const TOAST_LIFE: u32 = 180;
+
+/// The lit pixels of a character, row by row from the top left corner
+///
+/// Letters are drawn uppercase (without their accent), a character
+/// without a glyph is drawn as a full box.
+fn glyph(c: char) -> u16 {
+    let c = match c {
+        'á' | 'à' | 'Á' => 'A',
+        'é' | 'è' | 'É' => 'E',
+        'í' | 'Í' => 'I',
+        'ó' | 'Ó' => 'O',
+        'ú' | 'ü' | 'Ú' => 'U',
+        'ñ' | 'Ñ' => 'N',
+        c => c.to_ascii_uppercase(),
+    };
+    match c {
+        'A' => 0b010_101_111_101_101,
+        'B' => 0b110_101_110_101_110,
+        'C' => 0b011_100_100_100_011,
+        'D' => 0b110_101_101_101_110,
+        'E' => 0b111_100_110_100_111,
+        'F' => 0b111_100_110_100_100,
+        'G' => 0b011_100_101_101_011,
+        'H' => 0b101_101_111_101_101,
+        'I' => 0b111_010_010_010_111,
+        'J' => 0b001_001_001_101_010,
+        'K' => 0b101_101_110_101_101,
+        'L' => 0b100_100_100_100_111,
+        'M' => 0b101_111_111_101_101,
+        'N' => 0b110_101_101_101_101,
+        'O' => 0b010_101_101_101_010,
+        'P' => 0b110_101_110_100_100,
+        'Q' => 0b010_101_101_110_011,
+        'R' => 0b110_101_110_101_101,
+        'S' => 0b011_100_010_001_110,
+        'T' => 0b111_010_010_010_010,
+        'U' => 0b101_101_101_101_111,
+        'V' => 0b101_101_101_101_010,
+        'W' => 0b101_101_111_111_101,
+        'X' => 0b101_101_010_101_101,
+        'Y' => 0b101_101_010_010_010,
+        'Z' => 0b111_001_010_100_111,
+        '0' => 0b111_101_101_101_111,
+        '1' => 0b010_110_010_010_111,
+        '2' => 0b110_001_010_100_111,
+        '3' => 0b110_001_010_001_110,
+        '4' => 0b101_101_111_001_001,
+        '5' => 0b111_100_110_001_110,
+        '6' => 0b011_100_111_101_111,
+        '7' => 0b111_001_010_010_010,
+        '8' => 0b111_101_111_101_111,
+        '9' => 0b111_101_111_001_110,
+        '-' => 0b000_000_111_000_000,
+        '+' => 0b000_010_111_010_000,
+        ':' => 0b000_010_000_010_000,
+        '.' => 0b000_000_000_000_010,
+        '/' => 0b001_001_010_100_100,
+        '(' => 0b010_100_100_100_010,
+        ')' => 0b010_001_001_001_010,
+        ' ' => 0b000_000_000_000_000,
+        _ => 0b111_111_111_111_111,
+    }
+}
+
+/// The lit pixels of a line of text, as `[x, y]` from its bottom left
+/// corner, and the width of the line (in pixels)
+fn pixels(text: &str) -> (Vec<[usize; 2]>, usize) {
+    let mut pixels = Vec::new();
+    let mut width = 0;
+    for (i, c) in text.chars().enumerate() {
+        let glyph = glyph(c);
+        let left = i * (GLYPH_WIDTH + 1);
+        for row in 0..GLYPH_HEIGHT {
+            for col in 0..GLYPH_WIDTH {
+                let bit = GLYPH_WIDTH * GLYPH_HEIGHT - 1 - (row * GLYPH_WIDTH + col);
+                if glyph >> bit & 1 == 1 {
+                    pixels.push([left + col, GLYPH_HEIGHT - 1 - row]);
+                }
+            }
+        }
+        width = left + GLYPH_WIDTH;
+    }
+    (pixels, width)
+}
+
+/// A line of text in the world, always facing the camera
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub text: String,
+    pub color: [f32; 3],
+    /// Seen through the blocks and the entities (otherwise hidden behind
+    /// them like the rest of the world)
+    pub on_top: bool,
+}
+
+impl Label {
+    pub fn new(text: impl Into<String>, color: [f32; 3]) -> Self {
+        Self {
+            text: text.into(),
+            color,
+            on_top: false,
+        }
+    }
+
+    pub fn on_top(self) -> Self {
+        Self {
+            on_top: true,
+            ..self
+        }
+    }
+
+    /// Draws the label centered above `pos`, facing the camera
+    pub fn draw(
+        &self,
+        display: &Display,
+        program: &Program,
+        target: &mut Frame,
+        view: [[f32; 4]; 4],
+        camera: &Camera,
+        pos: [f32; 3],
+    ) {
+        let (pixels, width) = pixels(&self.text);
+        let half = width as f32 / 2.0;
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        // the camera looks toward positive z and its left is positive x, so
+        // the text goes toward negative x; both sides of the quads are drawn
+        let mut quad = |[x0, y0, x1, y1]: [f32; 4], z: f32, color: [f32; 3]| {
+            let indice = vertices.len() as u32;
+            for [x, y] in [[x0, y0], [x1, y0], [x1, y1], [x0, y1]] {
+                vertices.push(([half - x, y, z], color).into());
+            }
+            indices.extend([0, 1, 2, 0, 2, 3, 0, 2, 1, 0, 3, 2].map(|n| n + indice));
+        };
+        quad(
+            [-1.0, -1.0, width as f32 + 1.0, GLYPH_HEIGHT as f32 + 1.0],
+            0.5,
+            PLATE,
+        );
+        for [x, y] in pixels {
+            let [x, y] = [x as f32, y as f32];
+            quad([x, y, x + 1.0, y + 1.0], 0.0, self.color);
+        }
+        let depth_test = if self.on_top {
+            DepthTest::Overwrite
+        } else {
+            DepthTest::IfLess
+        };
+        ColoredMesh::new(display, &vertices, &indices, PrimitiveType::TrianglesList)
+            .depth_test(depth_test)
+            .draw(
+                program,
+                target,
+                view.affine_translate(pos)
+                    .affine_y_rotate(camera.h_angle)
+                    .affine_x_rotate(camera.v_angle)
+                    .affine_scale(PIXEL),
+                (),
+            );
+    }
+}
+
+/// Where lines of text are drawn on the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    /// Each line centered
+    TopCenter,
+}
+
+/// Draws lines of text at the top of the screen, over the rest
+pub fn draw_lines(
+    display: &Display,
+    program: &Program,
+    target: &mut Frame,
+    aspect_ratio: [[f32; 4]; 4],
+    lines: &[String],
+    color: [f32; 3],
+    anchor: Anchor,
+) {
+    let top = 1.0 - HUD_MARGIN;
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut rectangle = |left: f32, [x0, y0, x1, y1]: [f32; 4], color: [f32; 3]| {
+        let indice = vertices.len() as u32;
+        for [x, y] in [[x0, y0], [x1, y0], [x1, y1], [x0, y1]] {
+            vertices.push(([left + x * HUD_PIXEL, top + y * HUD_PIXEL, 0.0], color).into());
+        }
+        indices.extend([0, 1, 2, 0, 2, 3].map(|n| n + indice));
+    };
+    let line_height = GLYPH_HEIGHT as f32 + 2.0;
+    for (row, line) in lines.iter().enumerate() {
+        let (pixels, width) = pixels(line);
+        // the screen is `width / height` wide once scaled by the aspect ratio
+        let left = match anchor {
+            Anchor::TopLeft => -1.0 / aspect_ratio[0][0] + HUD_MARGIN,
+            Anchor::TopCenter => -(width as f32) * HUD_PIXEL / 2.0,
+        };
+        let bottom = -(row as f32 + 1.0) * line_height;
+        rectangle(
+            left,
+            [
+                -1.0,
+                bottom - 1.0,
+                width as f32 + 1.0,
+                bottom + line_height - 1.0,
+            ],
+            PLATE,
+        );
+        for [x, y] in pixels {
+            let [x, y] = [x as f32, bottom + y as f32];
+            rectangle(left, [x, y, x + 1.0, y + 1.0], color);
+        }
+    }
+    ColoredMesh::new(display, &vertices, &indices, PrimitiveType::TrianglesList)
+        .depth_test(DepthTest::Overwrite)
+        .draw(program, target, aspect_ratio, ());
+}
+
+/// Draws short texts over the HUD (like the counts of the slots), each
+/// ending at the bottom right corner given, without plate
+pub fn draw_at(
+    display: &Display,
+    program: &Program,
+    target: &mut Frame,
+    aspect_ratio: [[f32; 4]; 4],
+    texts: &[(String, [f32; 2])],
+    color: [f32; 3],
+) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for (text, [right, bottom]) in texts {
+        let (pixels, width) = pixels(text);
+        let left = right - width as f32 * HUD_PIXEL;
+        for [x, y] in pixels {
+            let [x, y] = [left + x as f32 * HUD_PIXEL, bottom + y as f32 * HUD_PIXEL];
+            let indice = vertices.len() as u32;
+            for [x, y] in [
+                [x, y],
+                [x + HUD_PIXEL, y],
+                [x + HUD_PIXEL, y + HUD_PIXEL],
+                [x, y + HUD_PIXEL],
+            ] {
+                vertices.push(([x, y, 0.0], color).into());
+            }
+            indices.extend([0, 1, 2, 0, 2, 3].map(|n| n + indice));
+        }
+    }
+    ColoredMesh::new(display, &vertices, &indices, PrimitiveType::TrianglesList)
+        .depth_test(DepthTest::Overwrite)
+        .draw(program, target, aspect_ratio, ());
+}
+
+/// Notifications (like an achievement unlocked), shown one after the other
+/// for a while
+pub struct Toasts {
+    queue: VecDeque<String>,
+    /// Ticks left to the one shown
+    life: u32,
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            life: TOAST_LIFE,
+        }
+    }
+
+    pub fn push(&mut self, text: String) {
+        self.queue.push_back(text);
+    }
+
+    /// Moves the toasts by one tick
+    pub fn update(&mut self) {
+        if self.queue.is_empty() {
+            return;
+        }
+        self.life -= 1;
+        if self.life == 0 {
+            self.queue.pop_front();
+            self.life = TOAST_LIFE;
+        }
+    }
+
+    /// The toast to show
+    pub fn current(&self) -> Option<&String> {
+        self.queue.front()
+    }
+}
+
+/// Labels rising from where something happened (like the damage of a
+/// hit), for a short while
+pub struct FloatingTexts {
+    texts: Vec<([f32; 3], Label, u32)>,
+}
+
+impl FloatingTexts {
+    pub fn new() -> Self {
+        Self { texts: Vec::new() }
+    }
+
+    pub fn push(&mut self, pos: [f32; 3], label: Label) {
+        self.texts.push((pos, label, FLOATING_LIFE));
+    }
+
+    /// Moves the texts by one tick
+    pub fn update(&mut self) {
+        for (pos, _, life) in &mut self.texts {
+            pos[1] += FLOATING_SPEED;
+            *life -= 1;
+        }
+        self.texts.retain(|(_, _, life)| *life > 0);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ([f32; 3], &Label)> {
+        self.texts.iter().map(|(pos, label, _)| (*pos, label))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pixels() {
+        let (lit, width) = pixels("-1");
+        assert_eq!(width, 7);
+        // the dash is the middle row of the first glyph
+        assert!(lit.contains(&[0, 2]) && lit.contains(&[2, 2]));
+        assert!(!lit.contains(&[1, 4]));
+        // the foot of the one, from the second glyph
+        assert!(lit.contains(&[4, 0]) && lit.contains(&[6, 0]));
+        assert_eq!(lit.len(), 3 + 8);
+        assert_eq!(glyph('é'), glyph('E'));
+        assert!(pixels(" ").0.is_empty());
+        assert_eq!(pixels("").1, 0);
+    }
+
+    #[test]
+    fn test_toasts() {
+        let mut toasts = Toasts::new();
+        toasts.update();
+        toasts.push("first".to_string());
+        toasts.push("second".to_string());
+        for _ in 1..TOAST_LIFE {
+            toasts.update();
+        }
+        assert_eq!(toasts.current().map(String::as_str), Some("first"));
+        toasts.update();
+        assert_eq!(toasts.current().map(String::as_str), Some("second"));
+        for _ in 0..TOAST_LIFE {
+            toasts.update();
+        }
+        assert!(toasts.current().is_none());
+    }
+}
@@ -0,0 +1,241 @@
+use def::Block;
+use mat::{easing, Affine, AffineTrait, MatrixTrait, Quaternion, VectorTrait};
+
+/// What the arm seen in first person is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    Idle,
+    /// Hitting, on a left click
+    Swing,
+    /// Putting a block down (or using one), on a right click
+    Place,
+    /// Lowering the arm to take another block, and raising it back
+    Equip,
+}
+
+/// A pose of the arm at some point of a motion
+#[derive(Debug, Clone, Copy)]
+struct Key {
+    /// Progress of the motion, from 0.0 to 1.0
+    at: f32,
+    /// Position of the hand from the eyes (the player looks toward +z, +x
+    /// being on its left)
+    offset: [f32; 3],
+    /// Tilt of the hand, its front going down
+    pitch: f32,
+    /// Turn of the hand, its front going left
+    yaw: f32,
+}
+
+/// The arm at rest, down on the right
+const REST: Key = Key {
+    at: 0.0,
+    offset: [-0.55, -0.5, 0.9],
+    pitch: 0.0,
+    yaw: 0.0,
+};
+
+const fn rest(at: f32) -> Key {
+    Key { at, ..REST }
+}
+
+const IDLE: [Key; 2] = [rest(0.0), rest(1.0)];
+
+const SWING: [Key; 3] = [
+    rest(0.0),
+    Key {
+        at: 0.4,
+        offset: [-0.3, -0.55, 1.1],
+        pitch: 0.9,
+        yaw: 0.5,
+    },
+    rest(1.0),
+];
+
+const PLACE: [Key; 3] = [
+    rest(0.0),
+    Key {
+        at: 0.3,
+        offset: [-0.5, -0.6, 1.05],
+        pitch: 0.35,
+        yaw: 0.0,
+    },
+    rest(1.0),
+];
+
+const EQUIP: [Key; 3] = [
+    rest(0.0),
+    Key {
+        at: 0.5,
+        offset: [-0.55, -1.3, 0.8],
+        pitch: -0.5,
+        yaw: 0.0,
+    },
+    rest(1.0),
+];
+
+impl Key {
+    fn rotation(self) -> Quaternion {
+        Quaternion::from_axis_angle([0.0, 1.0, 0.0], self.yaw)
+            * Quaternion::from_axis_angle([1.0, 0.0, 0.0], self.pitch)
+    }
+}
+
+impl Motion {
+    /// Duration, in ticks
+    fn ticks(self) -> u32 {
+        match self {
+            Self::Idle => 1,
+            Self::Swing => 8,
+            Self::Place => 6,
+            Self::Equip => 12,
+        }
+    }
+
+    /// The poses it goes through, the first and last ones at rest
+    fn keys(self) -> &'static [Key] {
+        match self {
+            Self::Idle => &IDLE,
+            Self::Swing => &SWING,
+            Self::Place => &PLACE,
+            Self::Equip => &EQUIP,
+        }
+    }
+}
+
+/// The arm and the held block seen in first person, moving when the player
+/// does something
+///
+/// A motion eases from one key pose to the next one, and goes back to idle
+/// when done. Taking another block is not interrupted, the block in the
+/// hand changes once the arm is down.
+pub struct ViewModel {
+    motion: Motion,
+    /// Ticks since the motion started
+    tick: u32,
+    /// The block drawn in the hand
+    held: Block,
+    /// The block the player holds, in the hand once equipped
+    next: Block,
+}
+
+impl ViewModel {
+    pub fn new(held: Block) -> Self {
+        Self {
+            motion: Motion::Idle,
+            tick: 0,
+            held,
+            next: held,
+        }
+    }
+
+    /// The block drawn in the hand
+    pub fn held(&self) -> Block {
+        self.held
+    }
+
+    pub fn swing(&mut self) {
+        self.play(Motion::Swing);
+    }
+
+    pub fn place(&mut self) {
+        self.play(Motion::Place);
+    }
+
+    fn play(&mut self, motion: Motion) {
+        if self.motion != Motion::Equip {
+            self.motion = motion;
+            self.tick = 0;
+        }
+    }
+
+    /// Advances the motion by a tick, the player holding the given block
+    pub fn update(&mut self, holding: Block) {
+        if holding != self.next {
+            self.next = holding;
+            // already lowered, the arm comes back with this one
+            if self.motion != Motion::Equip {
+                self.motion = Motion::Equip;
+                self.tick = 0;
+            }
+        }
+        self.tick += 1;
+        let ticks = self.motion.ticks();
+        if self.motion == Motion::Equip && self.tick >= ticks / 2 {
+            self.held = self.next;
+        }
+        if self.tick >= ticks {
+            self.motion = Motion::Idle;
+            self.tick = 0;
+        }
+    }
+
+    /// Places the hand from the eyes, `bob` moving it up or down (with the
+    /// steps)
+    pub fn transform(&self, bob: f32) -> [[f32; 4]; 4] {
+        let keys = self.motion.keys();
+        let progress = self.tick as f32 / self.motion.ticks() as f32;
+        // the keys around the current progress
+        let i = keys[1..]
+            .iter()
+            .position(|key| progress <= key.at)
+            .unwrap_or(keys.len() - 2);
+        let (from, to) = (keys[i], keys[i + 1]);
+        let t = easing::ease_in_out((progress - from.at) / (to.at - from.at));
+        let offset = from
+            .offset
+            .vector_lerp(to.offset, t)
+            .vector_add([0.0, bob, 0.0]);
+        let rotation = from.rotation().slerp(to.rotation(), t);
+        Affine::identity()
+            .affine_translate(offset)
+            .matrix_mul(rotation.to_affine())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hand(view_model: &ViewModel) -> [f32; 3] {
+        view_model.transform(0.0).transform_point([0.0; 3])
+    }
+
+    #[test]
+    fn test_swing() {
+        let mut view_model = ViewModel::new(Block::Stone);
+        assert_eq!(hand(&view_model), REST.offset);
+        view_model.swing();
+        let mut moved = false;
+        for _ in 0..Motion::Swing.ticks() {
+            view_model.update(Block::Stone);
+            moved |= hand(&view_model) != REST.offset;
+        }
+        assert!(moved);
+        // back at rest once done
+        assert_eq!(view_model.motion, Motion::Idle);
+        assert_eq!(hand(&view_model), REST.offset);
+    }
+
+    #[test]
+    fn test_equip() {
+        let mut view_model = ViewModel::new(Block::Stone);
+        view_model.update(Block::Sand);
+        assert_eq!(view_model.motion, Motion::Equip);
+        // a click does not interrupt it
+        view_model.swing();
+        assert_eq!(view_model.motion, Motion::Equip);
+        // the block changes once the arm is down
+        assert_eq!(view_model.held(), Block::Stone);
+        for _ in 1..Motion::Equip.ticks() / 2 {
+            view_model.update(Block::Sand);
+        }
+        assert_eq!(view_model.held(), Block::Sand);
+        let [_, lowest, _] = hand(&view_model);
+        assert!(lowest < REST.offset[1] - 0.5, "{lowest}");
+        for _ in Motion::Equip.ticks() / 2..Motion::Equip.ticks() {
+            view_model.update(Block::Sand);
+        }
+        assert_eq!(view_model.motion, Motion::Idle);
+    }
+}
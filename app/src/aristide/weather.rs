@@ -0,0 +1,188 @@
+use glium::{index::PrimitiveType, Display, Program, Surface};
+use mat::VectorTrait;
+
+use crate::{
+    mesh::{ColoredMesh, Drawable, Sun},
+    world::{Precipitation, Rng, World},
+};
+
+/// Drops fall in a square of this half width around the player
+const RADIUS: i32 = 20;
+
+/// Drops appear this high above the player
+const HEIGHT: f32 = 16.0;
+
+struct Drop {
+    pos: [f32; 3],
+    /// Where it stops falling, on the top block of its column
+    floor: f32,
+}
+
+/// Draws the rain and the snow falling around the player, and darkens
+/// the sky when the weather is bad
+///
+/// Like particles, the drops only exist for the eye.
+pub struct WeatherRenderer {
+    drops: Vec<Drop>,
+    falling: Option<Precipitation>,
+    /// From 0.0 (clear sky) to 1.0 (completely overcast)
+    overcast: f32,
+    rng: Rng,
+}
+
+impl WeatherRenderer {
+    /// Ticks for the sky to turn grey (or blue again)
+    const TRANSITION: f32 = 300.0;
+
+    /// Intensity of the light at night, relative to noon
+    const MOONLIGHT: f32 = 0.15;
+
+    pub fn new() -> Self {
+        Self {
+            drops: Vec::new(),
+            falling: None,
+            overcast: 0.0,
+            rng: Rng::new(0x5eed),
+        }
+    }
+
+    /// Moves the drops by one tick and makes new ones fall
+    pub fn update(&mut self, world: &World) {
+        let [x, y, z] = world.pull_player().camera.pos;
+        let [bx, bz] = [x, z].map(|v| v.floor() as i32);
+        self.falling = world.weather().precipitation(world.generator.biome(bx, bz));
+        let target = if self.falling.is_some() { 1.0 } else { 0.0 };
+        let step = 1.0 / Self::TRANSITION;
+        self.overcast =
+            (self.overcast + (target - self.overcast).clamp(-step, step)).clamp(0.0, 1.0);
+
+        let (count, velocity) = match self.falling {
+            Some(Precipitation::Rain) => (40, [0.0, -0.6, 0.0]),
+            Some(Precipitation::Snow) => (12, [0.0, -0.06, 0.0]),
+            None => (0, [0.0; 3]),
+        };
+        for _ in 0..count {
+            let [dx, dz] = [(); 2].map(|_| self.rng.below(2 * RADIUS as u32) as i32 - RADIUS);
+            let [column_x, column_z] = [bx + dx, bz + dz];
+            // the drops stop on the terrain (not on the blocks of unloaded chunks)
+            let Some(surface) = world.surface(column_x, column_z) else {
+                continue;
+            };
+            let floor = surface as f32 + 1.0;
+            let top = y + HEIGHT * (0.5 + self.rng.below(100) as f32 / 200.0);
+            if floor < top {
+                self.drops.push(Drop {
+                    pos: [column_x as f32 + 0.5, top, column_z as f32 + 0.5],
+                    floor,
+                });
+            }
+        }
+        for drop in &mut self.drops {
+            // snow flakes flutter a little
+            let flutter = (drop.pos[1] * 2.0).sin() * 0.02;
+            drop.pos
+                .vector_add_assign(velocity.vector_add([flutter, 0.0, flutter]));
+        }
+        self.drops.retain(|drop| drop.pos[1] > drop.floor);
+        if self.falling.is_none() && self.overcast == 0.0 {
+            self.drops.clear();
+        }
+    }
+
+    /// The sky color, greyer with bad weather and darker with the sun
+    pub fn sky_color(&self, sun: Sun) -> (f32, f32, f32, f32) {
+        let [r, g, b] = [0.5, 0.5, 1.0]
+            .vector_scale(1.0 - self.overcast)
+            .vector_add([0.35, 0.37, 0.45].vector_scale(self.overcast))
+            .vector_scale(sun.intensity);
+        (r, g, b, 1.0)
+    }
+
+    /// The light of the sun at the time of day (from 0.0, midnight, to 1.0),
+    /// dimmed by the clouds
+    ///
+    /// It reddens as the sun gets low, and the moon lights the night with a
+    /// faint blue.
+    pub fn sun(&self, time_of_day: f32) -> Sun {
+        // from -1.0 (midnight) to 1.0 (noon), the sun rises at 0.0
+        let elevation = -(time_of_day * std::f32::consts::TAU).cos();
+        let day = ((elevation + 0.1) / 0.4).clamp(0.0, 1.0);
+        let high = (elevation / 0.5).clamp(0.0, 1.0);
+        let sun = [1.0, 0.6, 0.4]
+            .vector_scale(1.0 - high)
+            .vector_add([1.0, 1.0, 0.95].vector_scale(high));
+        let color = [0.5, 0.6, 1.0]
+            .vector_scale(1.0 - day)
+            .vector_add(sun.vector_scale(day));
+        let intensity = Self::MOONLIGHT + (1.0 - Self::MOONLIGHT) * day;
+        Sun {
+            color,
+            intensity: intensity * (1.0 - 0.4 * self.overcast),
+        }
+    }
+
+    pub fn draw(
+        &self,
+        display: &Display,
+        program: &Program,
+        target: &mut impl Surface,
+        projection: [[f32; 4]; 4],
+    ) {
+        if self.drops.is_empty() {
+            return;
+        }
+        // rain drops are short streaks, snow flakes are points
+        let mesh = match self.falling {
+            Some(Precipitation::Snow) => {
+                let vertices: Vec<_> = self
+                    .drops
+                    .iter()
+                    .map(|drop| (drop.pos, [1.0; 3]).into())
+                    .collect();
+                let indices: Vec<u32> = (0..vertices.len() as u32).collect();
+                ColoredMesh::new(display, &vertices, &indices, PrimitiveType::Points)
+                    .point_size(3.0)
+            }
+            _ => {
+                let color = [0.6, 0.65, 0.8];
+                let vertices: Vec<_> = self
+                    .drops
+                    .iter()
+                    .flat_map(|drop| {
+                        [drop.pos, drop.pos.vector_add([0.0, 0.6, 0.0])]
+                            .map(|pos| (pos, color).into())
+                    })
+                    .collect();
+                let indices: Vec<u32> = (0..vertices.len() as u32).collect();
+                ColoredMesh::new(display, &vertices, &indices, PrimitiveType::LinesList)
+            }
+        };
+        mesh.draw(program, target, projection, ());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sun() {
+        let weather = WeatherRenderer::new();
+        let noon = weather.sun(0.5);
+        let midnight = weather.sun(0.0);
+        assert!((noon.intensity - 1.0).abs() < 1e-6);
+        assert!((midnight.intensity - WeatherRenderer::MOONLIGHT).abs() < 1e-6);
+        // the sun rises and sets the same way
+        assert!((weather.sun(0.3).intensity - weather.sun(0.7).intensity).abs() < 1e-5);
+        // warmer at dusk, bluer at night
+        let dusk = weather.sun(0.26);
+        assert!(dusk.color[2] < noon.color[2]);
+        assert!(midnight.color[2] > midnight.color[0]);
+        // dimmed by the clouds
+        let overcast = WeatherRenderer {
+            overcast: 1.0,
+            ..WeatherRenderer::new()
+        };
+        assert!(overcast.sun(0.5).intensity < noon.intensity);
+    }
+}
@@ -0,0 +1,190 @@
+use glium::{
+    texture::{RawImage2d, SrgbTexture2d, SrgbTexture2dArray},
+    uniforms::{MagnifySamplerFilter, MinifySamplerFilter, SamplerBehavior},
+    Display,
+};
+use image::RgbaImage;
+
+/// Sprites an atlas can hold (the size of the array of rectangles in the
+/// shader)
+pub const MAX_SPRITES: usize = 64;
+
+/// Pixels around each sprite, copied from its border so the neighbours do
+/// not bleed on its edges
+const GUTTER: u32 = 1;
+
+/// The sprites used by the textured meshes, indexed by [`def::Sprite`]
+pub enum Textures {
+    /// One layer per sprite, all of the same size
+    Array(SrgbTexture2dArray),
+    /// All the sprites in a single texture (see [`Atlas`])
+    Atlas(Atlas),
+}
+
+impl Textures {
+    /// Uploads the sprites in a texture array, or in an atlas if asked to,
+    /// if they are not of the same size or if the driver refuses the array
+    pub fn new(display: &Display, sprites: &[RgbaImage], atlas: bool) -> Self {
+        let uniform = sprites
+            .windows(2)
+            .all(|pair| pair[0].dimensions() == pair[1].dimensions());
+        if !atlas && uniform {
+            let layers = sprites
+                .iter()
+                .map(|sprite| {
+                    RawImage2d::from_raw_rgba_reversed(sprite.as_raw(), sprite.dimensions())
+                })
+                .collect();
+            match SrgbTexture2dArray::new(display, layers) {
+                Ok(array) => return Self::Array(array),
+                Err(error) => {
+                    tracing::warn!(%error, "texture array refused, falling back on an atlas")
+                }
+            }
+        }
+        Self::Atlas(Atlas::new(display, sprites))
+    }
+
+    /// Bytes of the texture on the GPU (4 per pixel, and a third more for
+    /// the mipmaps)
+    pub fn bytes(&self) -> usize {
+        let (width, height, layers) = match self {
+            Self::Array(array) => (array.width(), array.height(), array.array_size()),
+            Self::Atlas(atlas) => (atlas.texture.width(), atlas.texture.height(), 1),
+        };
+        4 * width as usize * height as usize * layers as usize * 4 / 3
+    }
+}
+
+/// Sprites of any size packed in a single 2D texture
+///
+/// A mesh still gives the sprite as the third texture coordinate, the
+/// shader picks its rectangle in the registry.
+pub struct Atlas {
+    pub texture: SrgbTexture2d,
+    /// Rectangle of each sprite in the texture: corner (u, v) then size,
+    /// between 0.0 and 1.0
+    pub registry: Vec<[f32; 4]>,
+}
+
+impl Atlas {
+    pub fn new(display: &Display, sprites: &[RgbaImage]) -> Self {
+        assert!(
+            sprites.len() <= MAX_SPRITES,
+            "too many sprites for an atlas"
+        );
+        let sizes: Vec<[u32; 2]> = sprites.iter().map(|s| s.dimensions().into()).collect();
+        let Packing { size, positions } = pack(&sizes);
+        let [width, height] = size;
+        let mut image = RgbaImage::new(width, height);
+        for (sprite, &[x, y]) in sprites.iter().zip(&positions) {
+            let (w, h) = sprite.dimensions();
+            for dy in 0..h + 2 * GUTTER {
+                for dx in 0..w + 2 * GUTTER {
+                    // the gutter repeats the closest pixel of the sprite
+                    let sx = (dx.saturating_sub(GUTTER)).min(w - 1);
+                    let sy = (dy.saturating_sub(GUTTER)).min(h - 1);
+                    let pixel = *sprite.get_pixel(sx, sy);
+                    image.put_pixel(x + dx - GUTTER, y + dy - GUTTER, pixel);
+                }
+            }
+        }
+        // the image is flipped when uploaded, so is the v axis of the rectangles
+        let registry = sizes
+            .iter()
+            .zip(&positions)
+            .map(|(&[w, h], &[x, y])| {
+                [
+                    x as f32 / width as f32,
+                    (height - y - h) as f32 / height as f32,
+                    w as f32 / width as f32,
+                    h as f32 / height as f32,
+                ]
+            })
+            .collect();
+        let raw = RawImage2d::from_raw_rgba_reversed(image.as_raw(), (width, height));
+        tracing::info!(
+            width,
+            height,
+            sprites = sprites.len(),
+            "texture atlas stitched"
+        );
+        Self {
+            texture: SrgbTexture2d::new(display, raw).unwrap(),
+            registry,
+        }
+    }
+
+    /// Sampling without mipmaps nor smoothing, which would mix the sprites
+    pub fn sampler() -> SamplerBehavior {
+        SamplerBehavior {
+            magnify_filter: MagnifySamplerFilter::Nearest,
+            minify_filter: MinifySamplerFilter::Nearest,
+            ..Default::default()
+        }
+    }
+}
+
+/// Where the sprites go in the atlas
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Packing {
+    /// Dimensions of the atlas (powers of two)
+    size: [u32; 2],
+    /// Top left corner of each sprite (without its gutter)
+    positions: Vec<[u32; 2]>,
+}
+
+/// Packs rectangles on shelves, the tallest first
+///
+/// The atlas is about square: its width is the smallest power of two that
+/// could hold the area of all the sprites.
+fn pack(sizes: &[[u32; 2]]) -> Packing {
+    let padded = |[w, h]: [u32; 2]| [w + 2 * GUTTER, h + 2 * GUTTER];
+    let area: u32 = sizes.iter().map(|&s| padded(s)).map(|[w, h]| w * h).sum();
+    let widest = sizes.iter().map(|&s| padded(s)[0]).max().unwrap_or(1);
+    let width = widest.max(area.isqrt()).next_power_of_two();
+
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i][1]));
+    let mut positions = vec![[0; 2]; sizes.len()];
+    let [mut x, mut y, mut shelf] = [0; 3];
+    for i in order {
+        let [w, h] = padded(sizes[i]);
+        if x + w > width {
+            x = 0;
+            y += shelf;
+            shelf = 0;
+        }
+        positions[i] = [x + GUTTER, y + GUTTER];
+        x += w;
+        shelf = shelf.max(h);
+    }
+    Packing {
+        size: [width, (y + shelf).max(1).next_power_of_two()],
+        positions,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pack() {
+        let sizes = [[16, 16], [64, 32], [16, 16], [8, 40], [16, 16], [3, 5]];
+        let Packing { size, positions } = pack(&sizes);
+        assert_eq!(size, [128, 64]);
+        let rects: Vec<[u32; 4]> = sizes
+            .iter()
+            .zip(&positions)
+            .map(|(&[w, h], &[x, y])| [x - GUTTER, y - GUTTER, x + w + GUTTER, y + h + GUTTER])
+            .collect();
+        for (i, a) in rects.iter().enumerate() {
+            assert!(a[2] <= size[0] && a[3] <= size[1], "{a:?} out of the atlas");
+            for b in &rects[i + 1..] {
+                let apart = a[2] <= b[0] || b[2] <= a[0] || a[3] <= b[1] || b[3] <= a[1];
+                assert!(apart, "{a:?} overlaps {b:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::{fs, io, path::Path};
+
+use def::ChunkCoords;
+use tokio::sync::mpsc;
+
+use crate::{
+    cli::Options,
+    config::Config,
+    world::{Audit, ChunkStage, GameMode, Save, World, DAY_LENGTH, DEFAULT_STAGES},
+};
+
+/// Ticks simulated, a whole day so the night comes with its mobs
+const TICKS: u64 = DAY_LENGTH;
+
+/// Chunks meshed around the origin (only they get random ticks)
+const RADIUS: u8 = 2;
+
+/// Runs the simulation without a window and prints the rolling hash of the
+/// world every `every` ticks, one `<tick> <hash>` line each
+///
+/// The world is created from the seed, the mode and the generation of the
+/// options, like a new world would be. With a reference (the output of a
+/// previous run), the run stops at the first hash differing from it.
+/// Returns whether the run matched the reference.
+pub fn audit(options: &Options, every: u64, reference: Option<&Path>) -> io::Result<bool> {
+    let reference = match reference {
+        Some(path) => Some(read_reference(path)?),
+        None => None,
+    };
+    let dir = std::env::temp_dir().join("artcraft-audit");
+    fs::remove_dir_all(&dir).ok();
+    let meta = World::new_meta(
+        Some(options.seed.unwrap_or(0)),
+        options.mode.unwrap_or(GameMode::Creative),
+        options.generation.as_deref().unwrap_or(DEFAULT_STAGES),
+    );
+    // the renderer is not running, its commands are dropped
+    let (sender_cmd, _receiver_cmd) = mpsc::channel(40);
+    let (sender_aristide, _receiver_aristide) = mpsc::channel(4096);
+    let world = World::new(
+        Config::default(),
+        Save::new(&dir),
+        meta,
+        sender_cmd,
+        sender_aristide,
+    );
+    for cc in (ChunkCoords { x: 0, z: 0 }).iter_range(RADIUS) {
+        world.advance_chunk_stage(cc, ChunkStage::Meshed);
+    }
+
+    let mut audit = Audit::new(every);
+    let mut checked = 0;
+    let mut matched = true;
+    for _ in 0..TICKS {
+        world.tick();
+        let Some((tick, hash)) = audit.tick(&world) else {
+            continue;
+        };
+        println!("{tick} {hash:016x}");
+        let Some(reference) = &reference else {
+            continue;
+        };
+        match reference.get(checked) {
+            Some(&expected) if expected == (tick, hash) => checked += 1,
+            Some(&(_, expected)) => {
+                println!("diverged at tick {tick}: {expected:016x} expected");
+                matched = false;
+                break;
+            }
+            None => break,
+        }
+    }
+    fs::remove_dir_all(&dir).ok();
+    if matched && reference.is_some() {
+        println!("{checked} hashes matched");
+    }
+    Ok(matched)
+}
+
+/// The `<tick> <hash>` lines of a previous run, the others are skipped
+fn read_reference(path: &Path) -> io::Result<Vec<(u64, u64)>> {
+    let content = fs::read_to_string(path)?;
+    Ok(content
+        .lines()
+        .filter_map(|line| {
+            let (tick, hash) = line.split_once(' ')?;
+            Some((tick.parse().ok()?, u64::from_str_radix(hash, 16).ok()?))
+        })
+        .collect())
+}
@@ -1,40 +1,95 @@
-use std::sync::Arc;
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
+    net::TcpListener,
     runtime,
-    sync::mpsc::Receiver,
+    sync::{broadcast::error::RecvError, mpsc::Receiver},
     task::LocalSet,
 };
 
-use crate::{grammar::CmdParser, world::World, Cmd};
+use def::{plugin::PluginError, Block, BlockCoords, ChunkCoords};
+use lalrpop_util::{lexer::Token, ParseError};
+
+use crate::{
+    clipboard,
+    grammar::CmdParser,
+    i18n::{self, tr},
+    logging, metrics,
+    plugins::{Plugins, PLUGINS_PATH},
+    world::{tp_command, Author, ChunkLoader, Edit, World, MAX_ANCHOR_RANGE},
+    AristideCmd, Cmd, CmdError, CmdOutput,
+};
+
+mod alias;
+mod completion;
 
 pub fn beatrice(mut cmd_receiver: Receiver<Cmd>, world: Arc<World>) {
     // use asynchronous runtime to simulate multiple threads in one system thread
     let rt = runtime::Builder::new_current_thread()
         .enable_time()
+        .enable_io()
         .build()
         .unwrap();
     rt.block_on(async {
         let local = LocalSet::new();
         let world2 = world.clone();
+        let world3 = world.clone();
+        let world4 = world.clone();
+        let world5 = world.clone();
+        // shared by the console and the events, both run on this thread
+        let grants = world.config.read().unwrap().plugins.clone();
+        let plugins = Rc::new(RefCell::new(Plugins::load(PLUGINS_PATH, &grants)));
+        let plugins2 = plugins.clone();
+        let metrics = world.config.read().unwrap().metrics;
 
         local.spawn_local(async move {
-            // receive global program command and dispatch them
+            // receive global program command and dispatch them, with the
+            // ones already waiting behind (a script sends many at once)
             while let Some(cmd) = cmd_receiver.recv().await {
-                match cmd {
-                    Cmd::BlockPlacing(block) => {
-                        world.player_set_block_placing(block);
-                    }
-                    Cmd::RemoveBlock(bc) => {
-                        world.remove_block(bc);
+                let mut cmds = vec![cmd];
+                while let Ok(cmd) = cmd_receiver.try_recv() {
+                    cmds.push(cmd);
+                }
+                // the player sees the outcome of its own actions,
+                // so only failures are reported
+                for result in execute_all(&world, cmds) {
+                    if let Err(err) = result {
+                        tracing::warn!("command failed: {err}");
                     }
-                    Cmd::PlaceBlock(bc, block) => {
-                        world.place_block(bc, block);
+                }
+            }
+        });
+
+        if let Some(address) = metrics {
+            local.spawn_local(async move {
+                match TcpListener::bind(address).await {
+                    Ok(listener) => {
+                        tracing::info!(%address, "serving the metrics");
+                        metrics::serve(world5, listener).await;
                     }
-                    Cmd::Fly(b) => {
-                        world.player_fly(b);
+                    Err(error) => tracing::warn!(%address, %error, "metrics not served"),
+                }
+            });
+        }
+
+        local.spawn_local(async move {
+            // the achievements and the plugins follow what happens in the world
+            let mut events = world4.subscribe();
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        world4.track(&event);
+                        plugins2.borrow_mut().dispatch(&world4, &event);
                     }
+                    // some events were missed, the next ones are still useful
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
                 }
             }
         });
@@ -44,15 +99,334 @@ pub fn beatrice(mut cmd_receiver: Receiver<Cmd>, world: Arc<World>) {
             let mut buffer = String::new();
             let parser = CmdParser::new();
             let mut reader = BufReader::new(tokio::io::stdin());
-            while let Ok(_) = reader.read_line(&mut buffer).await {
-                match parser.parse(buffer.as_str()) {
-                    Ok(cmd) => world2.sender_cmd.send(cmd).await.unwrap(),
-                    Err(err) => println!("{err}"),
+            while let Ok(1..) = reader.read_line(&mut buffer).await {
+                let line = buffer.trim_end_matches(['\n', '\r']);
+                if let Some(line) = line.strip_suffix('\t') {
+                    // the terminal is line buffered, so completion is asked
+                    // by ending the line with a tab (then pressing enter)
+                    let completion = completion::complete(&parser, line);
+                    match completion.candidates.as_slice() {
+                        [] => println!("{}", tr!("completion.none")),
+                        [_] => println!(
+                            "{}{}",
+                            &line[..completion.start],
+                            completion.common_prefix()
+                        ),
+                        candidates => println!("{}", candidates.join(" ")),
+                    }
+                } else {
+                    // commands typed in the console are executed right away
+                    // so their outcome can be displayed
+                    let aliases = world2.config.read().unwrap().aliases.clone();
+                    match alias::expand(line, &aliases) {
+                        Ok(commands) => run(&world2, &parser, &mut plugins.borrow_mut(), &commands),
+                        Err(err) => println!("{err}"),
+                    }
                 }
                 buffer.clear();
             }
         });
 
-        local.await;
+        local.run_until(world3.stopped()).await;
     });
+    // reading stdin can't be cancelled, so the runtime doesn't wait for it
+    rt.shutdown_background();
+}
+
+/// Runs the commands of a console line, a macro stopping at its first
+/// failure
+///
+/// The commands of the plugins come before the ones of the game.
+fn run(world: &World, parser: &CmdParser, plugins: &mut Plugins, commands: &[String]) {
+    for command in commands {
+        if let Some(result) = plugins.command(world, command) {
+            match result {
+                Ok(message) => println!("{message}"),
+                Err(PluginError::Denied(capability)) => {
+                    println!("{}", tr!("error.plugin_denied", capability = capability));
+                    return;
+                }
+                Err(PluginError::Failed(message)) => {
+                    println!("{message}");
+                    return;
+                }
+            }
+            continue;
+        }
+        // `~` and `^` coordinates are relative to where the player is when
+        // the command runs (an earlier one may have moved it)
+        let camera = world.pull_player().camera;
+        match parser.parse(camera, command).map(|cmd| execute(world, cmd)) {
+            Ok(Ok(output)) => println!("{output}"),
+            Ok(Err(err)) => {
+                println!("{err}");
+                return;
+            }
+            Err(err) => {
+                println!("{}", parse_error_message(err));
+                return;
+            }
+        }
+    }
+}
+
+/// Executes commands in a row and reports what each one did
+///
+/// The blocks removed and placed one after the other are a single edit,
+/// their meshes being updated once, before the next command.
+pub fn execute_all(world: &World, cmds: Vec<Cmd>) -> Vec<Result<CmdOutput, CmdError>> {
+    let mut results = Vec::with_capacity(cmds.len());
+    let mut edit = None;
+    for cmd in cmds {
+        let (bc, block) = match cmd {
+            Cmd::RemoveBlock(bc) => (bc, None),
+            Cmd::PlaceBlock(bc, block) => (bc, Some(block)),
+            cmd => {
+                // the other commands see the meshes up to date
+                edit = None;
+                results.push(execute(world, cmd));
+                continue;
+            }
+        };
+        let edit = edit.get_or_insert_with(|| world.edit());
+        results.push(edit_block(world, edit, bc, block));
+    }
+    results
+}
+
+/// Removes a block (`None`) or places one, as part of an edit
+fn edit_block(
+    world: &World,
+    edit: &mut Edit,
+    bc: BlockCoords,
+    block: Option<Block>,
+) -> Result<CmdOutput, CmdError> {
+    world.check_edit(bc, block)?;
+    match block {
+        Some(block) => {
+            edit.place_block(bc, block)?;
+            Ok(CmdOutput::BlockPlaced(bc, block))
+        }
+        None => {
+            let block = edit.remove_block(bc)?;
+            Ok(CmdOutput::BlockRemoved(bc, block))
+        }
+    }
+}
+
+/// Executes a command and reports what was done
+pub fn execute(world: &World, cmd: Cmd) -> Result<CmdOutput, CmdError> {
+    match cmd {
+        Cmd::BlockPlacing(block) => {
+            world.player_set_block_placing(block);
+            Ok(CmdOutput::BlockPlacing(block))
+        }
+        Cmd::RemoveBlock(bc) => edit_block(world, &mut world.edit(), bc, None),
+        Cmd::PlaceBlock(bc, block) => edit_block(world, &mut world.edit(), bc, Some(block)),
+        Cmd::Interact(bc) => {
+            world.check_edit(bc, None)?;
+            world.interact(bc)
+        }
+        // the chest checks the transaction, whoever asks for it
+        Cmd::ChestPut(bc, slot) => {
+            world.check_edit(bc, None)?;
+            let block = world.pull_player().block_placing;
+            let count = world.chest_put(bc, slot, block)?;
+            Ok(CmdOutput::Stored(block, slot, count))
+        }
+        Cmd::ChestTake(bc, slot) => {
+            world.check_edit(bc, None)?;
+            let (block, count) = world.chest_take(bc, slot)?;
+            world.player_set_block_placing(block);
+            Ok(CmdOutput::Taken(block, slot, count))
+        }
+        Cmd::Till(bc) => {
+            world.check_edit(bc, None)?;
+            world.till(bc)?;
+            Ok(CmdOutput::Tilled(bc))
+        }
+        Cmd::Boat(bc) => {
+            world.check_edit(bc, None)?;
+            world.spawn_boat(bc);
+            Ok(CmdOutput::BoatPlaced(bc))
+        }
+        Cmd::Tp(pos, look) => {
+            world.teleport(pos, look)?;
+            Ok(CmdOutput::Teleported(pos))
+        }
+        Cmd::Where => {
+            let command = tp_command(&world.pull_player().camera);
+            let copied = clipboard::copy(&command);
+            Ok(CmdOutput::Location(command, copied))
+        }
+        Cmd::Fly(b) => {
+            world.player_fly(b)?;
+            Ok(CmdOutput::Fly(b))
+        }
+        Cmd::Music(b) => {
+            world.update_config(|config| config.music = b)?;
+            Ok(CmdOutput::Music(b))
+        }
+        Cmd::Volume(volume) => {
+            if !(0.0..=1.0).contains(&volume) {
+                return Err(CmdError::InvalidVolume(volume));
+            }
+            world.update_config(|config| config.volume = volume)?;
+            Ok(CmdOutput::Volume(volume))
+        }
+        Cmd::Language(code) => {
+            if !i18n::set_language(&code) {
+                return Err(CmdError::UnknownLanguage(code));
+            }
+            world.update_config(|config| config.language = code)?;
+            Ok(CmdOutput::Language)
+        }
+        Cmd::Log(filter) => {
+            logging::set_filter(&filter).map_err(CmdError::InvalidLogFilter)?;
+            world.update_config(|config| config.log = filter.clone())?;
+            Ok(CmdOutput::Log(filter))
+        }
+        Cmd::Palette(palette) => {
+            world.update_config(|config| config.palette = palette)?;
+            Ok(CmdOutput::Palette(palette))
+        }
+        Cmd::Spawning(b) => {
+            world.update_config(|config| config.spawning = b)?;
+            Ok(CmdOutput::Spawning(b))
+        }
+        Cmd::Shake(b) => {
+            world.update_config(|config| config.shake = b)?;
+            Ok(CmdOutput::Shake(b))
+        }
+        Cmd::Weather(weather) => {
+            world.set_weather(weather);
+            Ok(CmdOutput::Weather(weather))
+        }
+        Cmd::Anchor(name, x, z, range) => {
+            if !(0..=MAX_ANCHOR_RANGE).contains(&range) {
+                return Err(CmdError::InvalidRange(range));
+            }
+            let loader = ChunkLoader {
+                center: ChunkCoords::from_position([x as f32, 0.0, z as f32]),
+                range,
+            };
+            world.set_loader(name.clone(), loader);
+            Ok(CmdOutput::Anchored(name, loader))
+        }
+        Cmd::Count(block, from, to) => {
+            // the state of the blocks (like the rotation of doors) does not
+            // matter, but the one of a custom block is its id
+            let kind = |other: Block| match block {
+                Block::Custom(_) => other == block,
+                _ => other.with_state(0) == block.with_state(0),
+            };
+            let count = world.count_blocks_in_region(from, to, kind, true);
+            Ok(CmdOutput::Counted(block, count))
+        }
+        Cmd::Copy(from, to, name) => {
+            let count = world.save_structure(&name, from, to)?;
+            Ok(CmdOutput::Copied(name, count))
+        }
+        Cmd::Paste(name, degrees, mirror) => {
+            if degrees % 90 != 0 {
+                return Err(CmdError::InvalidRotation(degrees));
+            }
+            let mut structure = world.load_structure(&name)?;
+            if mirror {
+                structure = structure.mirrored();
+            }
+            structure = structure.rotated(degrees / 90);
+            let feet = world.pull_player().feet();
+            let origin = BlockCoords::try_from(feet).map_err(|()| CmdError::OutOfWorld(feet))?;
+            world.paste(origin, &structure, Author::Player)?;
+            Ok(CmdOutput::Pasted(name, origin))
+        }
+        Cmd::Fill(from, to, block) => {
+            let count = world.fill(from, to, block)?;
+            Ok(CmdOutput::Filled(block, count))
+        }
+        Cmd::Rollback(minutes, region) => {
+            if minutes < 0 {
+                return Err(CmdError::InvalidNumber(minutes.to_string()));
+            }
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let count = world.rollback(now.saturating_sub(minutes as u64 * 60), region);
+            Ok(CmdOutput::RolledBack(count))
+        }
+        Cmd::Unanchor(name) => {
+            if !world.remove_loader(&name) {
+                return Err(CmdError::UnknownAnchor(name));
+            }
+            Ok(CmdOutput::Unanchored(name))
+        }
+        Cmd::Alias(name, commands) if commands.is_empty() => {
+            let mut removed = false;
+            world.update_config(|config| removed = config.aliases.remove(&name).is_some())?;
+            if !removed {
+                return Err(CmdError::UnknownAlias(name));
+            }
+            Ok(CmdOutput::Unaliased(name))
+        }
+        Cmd::Alias(name, commands) => {
+            world.update_config(|config| {
+                config.aliases.insert(name.clone(), commands.clone());
+            })?;
+            Ok(CmdOutput::Aliased(name, commands))
+        }
+        Cmd::Aliases => {
+            let aliases = world.config.read().unwrap().aliases.clone();
+            Ok(CmdOutput::Aliases(aliases.into_iter().collect()))
+        }
+        Cmd::Save => {
+            world
+                .save()
+                .map_err(|err| CmdError::Save(err.to_string()))?;
+            Ok(CmdOutput::Saved)
+        }
+        Cmd::Stop => {
+            // the window closes the game (see `aristide`), as it would when
+            // closed by the player
+            world.shutdown();
+            Ok(CmdOutput::Stopping)
+        }
+        Cmd::Reload => {
+            // the window owns the textures, it swaps them between two frames
+            world.try_aristide_cmd(AristideCmd::Reload);
+            Ok(CmdOutput::Reloading)
+        }
+        Cmd::Help => {
+            // the commands are the words accepted at the start of a line
+            let commands = completion::complete(&CmdParser::new(), "").candidates;
+            Ok(CmdOutput::Help(commands))
+        }
+    }
+}
+
+/// Translates a syntax error (lalrpop messages are only in english)
+fn parse_error_message(err: ParseError<usize, Token<'_>, CmdError>) -> String {
+    let expected = |expected: Vec<String>| expected.join(", ");
+    match err {
+        ParseError::InvalidToken { location } => {
+            tr!("parse.invalid_token", location = location)
+        }
+        ParseError::UnrecognizedEof { expected: e, .. } => {
+            tr!("parse.unexpected_end", expected = expected(e))
+        }
+        ParseError::UnrecognizedToken {
+            token: (location, token, _),
+            expected: e,
+        } => tr!(
+            "parse.unexpected_token",
+            token = token,
+            location = location,
+            expected = expected(e)
+        ),
+        ParseError::ExtraToken {
+            token: (location, token, _),
+        } => tr!("parse.extra_token", token = token, location = location),
+        ParseError::User { error } => error.to_string(),
+    }
 }
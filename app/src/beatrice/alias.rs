@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+
+use crate::CmdError;
+
+/// Aliases standing for aliases deeper than this are taken as a loop
+const MAX_DEPTH: usize = 8;
+
+/// The commands a console line stands for
+///
+/// Commands are separated by `;`. A command starting with the name of an
+/// alias is replaced by the commands of the alias, the words following the
+/// name being appended to the last of them (so `p stone` stands for
+/// `placing stone` with `alias p = placing`). An `alias` command is kept
+/// whole, as its commands are defined there and not run.
+pub fn expand(line: &str, aliases: &BTreeMap<String, String>) -> Result<Vec<String>, CmdError> {
+    let mut commands = Vec::new();
+    expand_into(line, aliases, 0, &mut commands)?;
+    Ok(commands)
+}
+
+fn expand_into(
+    line: &str,
+    aliases: &BTreeMap<String, String>,
+    depth: usize,
+    commands: &mut Vec<String>,
+) -> Result<(), CmdError> {
+    if line.split_whitespace().next() == Some("alias") {
+        commands.push(line.trim().to_string());
+        return Ok(());
+    }
+    for command in line.split(';').map(str::trim).filter(|c| !c.is_empty()) {
+        let (name, args) = command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, ""));
+        let Some(body) = aliases.get(name) else {
+            commands.push(command.to_string());
+            continue;
+        };
+        if depth == MAX_DEPTH {
+            return Err(CmdError::AliasLoop(name.to_string()));
+        }
+        expand_into(&format!("{body} {args}"), aliases, depth + 1, commands)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let aliases = BTreeMap::from(
+            [
+                ("wall", "fill ~ ~ ~ ~10 ~3 ~ brick"),
+                ("p", "placing"),
+                ("night", "weather rain; music false"),
+                ("both", "wall; night"),
+                ("ping", "pong"),
+                ("pong", "ping"),
+            ]
+            .map(|(name, body)| (name.to_string(), body.to_string())),
+        );
+        let expand = |line| expand(line, &aliases);
+
+        assert_eq!(expand("wall").unwrap(), ["fill ~ ~ ~ ~10 ~3 ~ brick"]);
+        assert_eq!(expand("p stone").unwrap(), ["placing stone"]);
+        assert_eq!(expand(" fly true ").unwrap(), ["fly true"]);
+        // the arguments go to the last command, aliases expand aliases
+        assert_eq!(
+            expand("both; p glass;").unwrap(),
+            [
+                "fill ~ ~ ~ ~10 ~3 ~ brick",
+                "weather rain",
+                "music false",
+                "placing glass"
+            ]
+        );
+        // only the first word is an alias
+        assert_eq!(
+            expand("copy 0 0 0 1 1 1 wall").unwrap(),
+            ["copy 0 0 0 1 1 1 wall"]
+        );
+        assert_eq!(
+            expand("alias more = wall; p"),
+            Ok(vec!["alias more = wall; p".to_string()])
+        );
+        assert_eq!(expand("ping"), Err(CmdError::AliasLoop("ping".to_string())));
+        assert!(expand("").unwrap().is_empty());
+    }
+}
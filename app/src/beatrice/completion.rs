@@ -0,0 +1,130 @@
+use lalrpop_util::ParseError;
+
+use crate::{camera::Camera, grammar::CmdParser};
+
+/// Suggestions for the word being typed at the end of a command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// Byte offset in the line where the completed word starts
+    pub start: usize,
+    /// Every word accepted by the grammar at this place (sorted)
+    pub candidates: Vec<String>,
+}
+
+impl Completion {
+    /// The longest prefix shared by all candidates
+    ///
+    /// It is what a tab press can safely insert without choosing for the user.
+    pub fn common_prefix(&self) -> &str {
+        let Some(first) = self.candidates.first() else {
+            return "";
+        };
+        let len = self
+            .candidates
+            .iter()
+            .skip(1)
+            .fold(first.chars().count(), |len, other| {
+                first
+                    .chars()
+                    .zip(other.chars())
+                    .take(len)
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            });
+        &first[..first
+            .char_indices()
+            .nth(len)
+            .map_or(first.len(), |(i, _)| i)]
+    }
+}
+
+/// Completes the last word of the given command line
+///
+/// The candidates are not hard-coded, they are derived from the parser: the line is
+/// parsed up to the word being typed and the parser reports which tokens it expected
+/// next. This way, any command or block added to the grammar is completed for free.
+pub fn complete(parser: &CmdParser, line: &str) -> Completion {
+    // the word being typed starts after the last whitespace
+    let start = line
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let (head, word) = line.split_at(start);
+
+    // relative coordinates are never completed, so their origin does not matter
+    let expected = match parser.parse(Camera::default(), head) {
+        Err(ParseError::UnrecognizedEof { expected, .. }) => expected,
+        // either a complete command (nothing more can follow)
+        // or already invalid, in both cases, nothing to suggest
+        _ => Vec::new(),
+    };
+
+    let mut candidates: Vec<String> = expected
+        .iter()
+        // regex terminals (like numbers) cannot be suggested
+        .filter_map(|token| literal(token))
+        .filter(|candidate| candidate.starts_with(word))
+        .map(str::to_string)
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    Completion { start, candidates }
+}
+
+/// Extracts the literal word of a terminal as reported by lalrpop
+///
+/// Literal terminals are reported quoted (`"fly"`), regex terminals as `r#"..."#`.
+fn literal(token: &str) -> Option<&str> {
+    token.strip_prefix('"')?.strip_suffix('"')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_complete() {
+        let parser = CmdParser::new();
+        let candidates = |line| complete(&parser, line).candidates;
+
+        assert_eq!(
+            candidates(""),
+            [
+                "alias", "anchor", "boat", "copy", "count", "fill", "fly", "help", "interact",
+                "language", "log", "music", "palette", "paste", "place", "placing", "reload",
+                "remove", "rollback", "save", "shake", "spawning", "stop", "till", "tp",
+                "unanchor", "volume", "weather", "where",
+            ]
+        );
+        assert_eq!(candidates("f"), ["fill", "fly"]);
+        assert_eq!(candidates("pla"), ["place", "placing"]);
+        assert_eq!(candidates("s"), ["save", "shake", "spawning", "stop"]);
+        assert_eq!(candidates("remove 1 2 "), Vec::<String>::new());
+        assert_eq!(candidates("fly "), ["false", "true"]);
+        assert_eq!(
+            candidates("placing s"),
+            ["sand", "sapling", "slime", "soul_sand", "stone"]
+        );
+        assert_eq!(candidates("placing stone "), Vec::<String>::new());
+        assert_eq!(candidates("unknown "), Vec::<String>::new());
+        // anchor names are free, so nothing is suggested for them
+        assert_eq!(candidates("unanchor "), Vec::<String>::new());
+
+        let completion = complete(&parser, "placing gr");
+        assert_eq!(completion.start, 8);
+        assert_eq!(completion.common_prefix(), "grass");
+
+        let completion = complete(&parser, "placing s");
+        assert_eq!(completion.common_prefix(), "s");
+    }
+
+    #[test]
+    fn test_help_complete() {
+        // the help command lists the commands given by completion
+        for command in complete(&CmdParser::new(), "").candidates {
+            let key = format!("help.{command}");
+            assert_ne!(crate::i18n::translate(&key), key);
+        }
+    }
+}
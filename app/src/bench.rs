@@ -0,0 +1,118 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use def::{BlockCoords, ChunkCoords};
+use tokio::sync::mpsc;
+
+use crate::{
+    config::Config,
+    world::{ChunkStage, GameMode, Save, World, DEFAULT_STAGES},
+};
+
+/// Seed used by the benchmark, so runs can be compared
+const SEED: u32 = 0;
+
+/// Largest radius measured, as the chunks one further are generated too
+pub const MAX_RADIUS: u8 = u8::MAX - 1;
+
+/// Durations of one step of the chunk pipeline
+#[derive(Default)]
+struct Timings {
+    count: u32,
+    total: Duration,
+    max: Duration,
+}
+
+impl Timings {
+    fn measure<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        let elapsed = start.elapsed();
+        self.count += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+        result
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let average = ms(self.total) / self.count.max(1) as f64;
+        write!(
+            f,
+            "{average:.3} ms average, {:.3} ms max ({} samples)",
+            ms(self.max),
+            self.count
+        )
+    }
+}
+
+/// Runs the chunk pipeline without a window and prints its timings
+///
+/// Every chunk within `radius` of the origin is generated then meshed,
+/// next the player walks in a straight line loading the chunks around
+/// it (as Cassiope does), finally blocks are removed and placed again
+/// to measure how long updating the mesh takes.
+///
+/// # Panics
+///
+/// If `radius` is larger than [`MAX_RADIUS`].
+pub fn bench(radius: u8) {
+    assert!(radius <= MAX_RADIUS, "radius too large");
+    // nothing is saved, the directory is never written
+    let save = Save::new(std::env::temp_dir().join("artcraft-bench"));
+    let meta = World::new_meta(Some(SEED), GameMode::Creative, DEFAULT_STAGES);
+    // the renderer is not running, its commands are dropped
+    let (sender_cmd, _receiver_cmd) = mpsc::channel(40);
+    let (sender_aristide, _receiver_aristide) = mpsc::channel(40);
+    let world = World::new(Config::default(), save, meta, sender_cmd, sender_aristide);
+    let origin = ChunkCoords { x: 0, z: 0 };
+    let radius = radius as i32;
+
+    // meshing a chunk requires its neighbours to be loaded
+    let mut generation = Timings::default();
+    for cc in origin.iter_range(radius as u8 + 1) {
+        generation.measure(|| world.chunk_stage_stored_to_loaded(cc));
+    }
+    let mut meshing = Timings::default();
+    for cc in origin.iter_range(radius as u8) {
+        meshing.measure(|| world.chunk_stage_loaded_to_meshed(cc));
+    }
+
+    // one step per block, the loading of each step is measured
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut walk = Timings::default();
+    for x in 0..radius * 16 * 2 {
+        let center = ChunkCoords::from_position([x as f32, 0.0, 0.0]);
+        walk.measure(|| {
+            for cc in center.iter_range(radius as u8) {
+                if cc.in_range(center, radius) {
+                    runtime.block_on(world.request_chunk_stage(cc, ChunkStage::Meshed));
+                }
+            }
+        });
+    }
+
+    // edits the surface of the chunk at the origin
+    let mut remesh = Timings::default();
+    for bx in 0..16 {
+        for bz in 0..16 {
+            let y = world.generator.altitude(bx, bz);
+            let bc = BlockCoords::try_from([bx, y, bz]).unwrap();
+            let block = remesh.measure(|| world.remove_block(bc)).unwrap();
+            remesh.measure(|| world.place_block(bc, block)).unwrap();
+        }
+    }
+
+    println!("seed: {SEED}, radius: {radius} chunks");
+    println!("generation per chunk: {generation}");
+    println!("meshing per chunk: {meshing}");
+    println!("walk step: {walk}");
+    println!("remesh latency: {remesh}");
+}
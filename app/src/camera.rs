@@ -1,8 +1,8 @@
-use mat::{Affine, AffineTrait, VectorTrait};
+use mat::{Affine, AffineTrait, Quaternion, VectorTrait};
 
 const RADIAN: f32 = 2.0 * std::f32::consts::PI;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default)]
 pub struct Camera {
     pub pos: [f32; 3],
     pub h_angle: f32, // horizontal
@@ -40,7 +40,7 @@ impl Camera {
     }
     // rotate player vertically by given delta
     pub fn delta_angle_v(&mut self, d: f32) {
-        self.v_angle = (self.v_angle + d).max(-RADIAN / 4.0).min(RADIAN / 4.0);
+        self.v_angle = (self.v_angle + d).clamp(-RADIAN / 4.0, RADIAN / 4.0);
     }
 
     // as vertical orientation does not affect movement
@@ -53,4 +53,18 @@ impl Camera {
     pub fn delta_pos(&mut self, vector: [f32; 3]) {
         self.pos.vector_add_assign(vector);
     }
+
+    // orientation as a single rotation, which can be interpolated
+    // (the matrices turn by the opposite of the angles)
+    pub fn orientation(&self) -> Quaternion {
+        Quaternion::from_axis_angle([0.0, 1.0, 0.0], -self.h_angle)
+            * Quaternion::from_axis_angle([1.0, 0.0, 0.0], -self.v_angle)
+    }
+
+    // look where the rotation points the z axis (a roll is lost)
+    pub fn set_orientation(&mut self, orientation: Quaternion) {
+        let [x, y, z] = orientation.rotate([0.0, 0.0, 1.0]);
+        self.h_angle = (-x).atan2(z).rem_euclid(RADIAN);
+        self.v_angle = y.clamp(-1.0, 1.0).asin();
+    }
 }
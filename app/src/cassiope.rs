@@ -1,70 +1,215 @@
-use std::collections::HashSet;
-use std::{sync::Arc, time::Duration};
+use std::collections::{HashMap, HashSet};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use def::ChunkCoords;
-use tokio::{runtime, task::LocalSet};
+use tokio::{runtime, sync::broadcast::error::TryRecvError, task::LocalSet};
 
-use crate::world::{ChunkStage, World};
+use crate::music::{MusicManager, Silent, Track};
+use crate::world::{ChunkLoader, ChunkStage, Phase, World};
 use crate::AristideCmd;
 
+/// Chunks waiting to be loaded, the nearest to a loader first
+///
+/// Requests are sorted by their distance to the nearest loader. When the
+/// loaders move, the requests none of them want anymore are cancelled, so
+/// flying fast does not load the chunks left behind.
+struct ChunkQueue {
+    loaders: Vec<ChunkLoader>,
+    /// The farthest first, so the nearest is popped
+    requests: Vec<ChunkCoords>,
+    queued: HashSet<ChunkCoords>,
+}
+
+impl ChunkQueue {
+    fn new() -> Self {
+        Self {
+            loaders: Vec::new(),
+            requests: Vec::new(),
+            queued: HashSet::new(),
+        }
+    }
+
+    fn distance(&self, cc: ChunkCoords) -> i32 {
+        self.loaders
+            .iter()
+            .map(|loader| {
+                let [dx, dz] = [cc.x - loader.center.x, cc.z - loader.center.z];
+                dx * dx + dz * dz
+            })
+            .min()
+            .unwrap_or(i32::MAX)
+    }
+
+    /// Moves the loaders, cancelling the requests none of them load
+    ///
+    /// Returns the number of cancelled requests.
+    fn recenter(&mut self, loaders: &[ChunkLoader]) -> usize {
+        let before = self.requests.len();
+        self.loaders = loaders.to_vec();
+        let wanted = |cc: &ChunkCoords| loaders.iter().any(|loader| loader.loads(*cc));
+        self.requests.retain(wanted);
+        self.queued.retain(wanted);
+        let mut requests = std::mem::take(&mut self.requests);
+        requests.sort_by_key(|&cc| std::cmp::Reverse(self.distance(cc)));
+        self.requests = requests;
+        before - self.requests.len()
+    }
+
+    /// Requests a chunk, once even if asked again before it is loaded
+    fn push(&mut self, cc: ChunkCoords) {
+        if self.queued.insert(cc) {
+            let distance = self.distance(cc);
+            let index = self
+                .requests
+                .partition_point(|&other| self.distance(other) > distance);
+            self.requests.insert(index, cc);
+        }
+    }
+
+    fn pop(&mut self) -> Option<ChunkCoords> {
+        let cc = self.requests.pop()?;
+        self.queued.remove(&cc);
+        Some(cc)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
 async fn chunk_loader(world: &World) -> Option<()> {
     let mut rendered_chunk: HashSet<ChunkCoords> = HashSet::new();
+    let mut queue = ChunkQueue::new();
 
-    // loop every 200 milliseconds and check for player pos to load or unload chunks
+    // check for the loaders to load or unload chunks, every 200 milliseconds
+    // once everything around them is loaded
     loop {
-        // player pos
-        let center = ChunkCoords::from_position({
-            let player = world.pull_player();
-            player.camera.pos
-        });
+        // chunks are loaded for this long before looking at the loaders again
+        const BUDGET: Duration = Duration::from_millis(50);
 
-        // unload is further than 16 chunks
-        const POP_OUT: i32 = 16;
-        // load if clother than 8 chunks
-        const POP_IN: i32 = 8;
+        // one loader per player, plus the named ones
+        let mut loaders = vec![ChunkLoader {
+            center: ChunkCoords::from_position(world.pull_player().camera.pos),
+            range: world.render_distance(),
+        }];
+        loaders.extend(world.named_loaders().into_iter().map(|(_, loader)| loader));
 
-        for chunk in rendered_chunk
+        // a chunk is unloaded once no loader keeps it anymore
+        let references: HashMap<ChunkCoords, usize> = rendered_chunk
             .iter()
-            .filter(|v| !v.in_range(center, POP_OUT))
-        {
+            .map(|&cc| (cc, loaders.iter().filter(|l| l.keeps(cc)).count()))
+            .collect();
+        for (&chunk, _) in references.iter().filter(|(_, &count)| count == 0) {
             // ask Aristide to drop associated mesh
             // only Aristide can do it as the handle to OpenGL
             // cannot be shared between threads
             world
-                .aristide_cmd(AristideCmd::RenderChunk(*chunk, false))
+                .aristide_cmd(AristideCmd::RenderChunk(chunk, false))
                 .await;
+            // now forgot about it
+            rendered_chunk.remove(&chunk);
+        }
+        // and pack them, they are meshed again if a loader comes back
+        world.compress_far_chunks(&loaders);
+        // past the memory budget, the meshes beyond the render distance go
+        // first, then the far chunks themselves
+        world.measure_chunks();
+        if world.over_budget() {
+            let beyond: Vec<ChunkCoords> = rendered_chunk
+                .iter()
+                .filter(|&&cc| !loaders.iter().any(|loader| loader.loads(cc)))
+                .copied()
+                .collect();
+            if beyond.is_empty() {
+                world.unload_far_chunks(&loaders);
+            }
+            for chunk in beyond {
+                world
+                    .aristide_cmd(AristideCmd::RenderChunk(chunk, false))
+                    .await;
+                rendered_chunk.remove(&chunk);
+            }
         }
 
-        // now forgot about them
-        rendered_chunk.retain(|v| v.in_range(center, POP_OUT));
-
-        // iterate over visible area (square area)
-        for x in center.x - POP_IN..=center.x + POP_IN {
-            for z in center.z - POP_IN..=center.z + POP_IN {
-                let chunk = ChunkCoords { x, z };
-                // only take if inside inscribed circle (circular area)
-                if chunk.in_range(center, POP_IN) {
-                    if !rendered_chunk.contains(&chunk) {
-                        // if not rendered, generate mesh
-                        rendered_chunk.insert(chunk);
-                        world.request_chunk_stage(chunk, ChunkStage::Meshed);
-                        // and inform Aristide it can upload mesh to GPU and render it
-                        world
-                            .aristide_cmd(AristideCmd::RenderChunk(chunk, true))
-                            .await;
-                    }
+        let cancelled = queue.recenter(&loaders);
+        if cancelled > 0 {
+            tracing::debug!(cancelled, "chunk requests cancelled");
+        }
+        for loader in &loaders {
+            // iterate over the area of the loader (circular area)
+            for chunk in loader.center.iter_range(loader.range as u8) {
+                if loader.loads(chunk) && !rendered_chunk.contains(&chunk) {
+                    queue.push(chunk);
                 }
             }
         }
-        tokio::time::sleep(Duration::from_millis(200)).await
+
+        let start = Instant::now();
+        while let Some(chunk) = queue.pop() {
+            rendered_chunk.insert(chunk);
+            world.request_chunk_stage(chunk, ChunkStage::Meshed).await;
+            // and inform Aristide it can upload mesh to GPU and render it
+            world
+                .aristide_cmd(AristideCmd::RenderChunk(chunk, true))
+                .await;
+            if start.elapsed() > BUDGET {
+                break;
+            }
+        }
+        world.add_time(Phase::Loading, start.elapsed());
+        if queue.is_empty() {
+            tokio::time::sleep(Duration::from_millis(200)).await
+        } else {
+            // lets the ambience run before the next chunks
+            tokio::task::yield_now().await
+        }
+    }
+}
+
+async fn ambience(world: &World) {
+    // there is no sound device support yet, the manager still
+    // follows the player so a real output can be plugged here
+    let mut manager = MusicManager::new(Silent);
+    let mut sounds = world.listen();
+
+    // loop every 100 milliseconds and fade toward the ambience matching the player pos
+    const PERIOD: Duration = Duration::from_millis(100);
+    loop {
+        let listener = world.pull_player().camera.pos;
+        let [x, y, z] = listener;
+        let [x, z] = [x, z].map(|v| v.floor() as i32);
+        let (music, volume) = {
+            let config = world.config.read().unwrap();
+            (config.music, config.volume)
+        };
+        let target = music.then(|| {
+            let biome = world.generator.biome(x, z);
+            Track::choose(
+                biome,
+                world.generator.altitude(x, z),
+                y,
+                world.time_of_day(),
+                world.weather().precipitation(biome),
+            )
+        });
+        manager.update(target, volume, PERIOD.as_secs_f32());
+        loop {
+            match sounds.try_recv() {
+                Ok(sound) => manager.play(sound, listener, volume),
+                // some sounds were missed, the next ones are still played
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+        tokio::time::sleep(PERIOD).await
     }
 }
 
 pub fn cassiope(world: Arc<World>) {
     // use asynchronous runtime simulating multiple threads with only one system thread
-    //
-    // in the current state, it's not mandatory because only one task is spawned,
-    // but it allows additional tasks to be added in future
     let rt = runtime::Builder::new_current_thread()
         .enable_time()
         .build()
@@ -74,8 +219,54 @@ pub fn cassiope(world: Arc<World>) {
         let world_ref = world.as_ref();
         local
             .run_until(async move {
-                chunk_loader(world_ref).await;
+                // both loops run until the game closes
+                tokio::select! {
+                    _ = async { tokio::join!(chunk_loader(world_ref), ambience(world_ref)) } => {}
+                    _ = world_ref.stopped() => {}
+                }
             })
             .await;
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_chunk_queue() {
+        let origin = ChunkCoords { x: 0, z: 0 };
+        let mut queue = ChunkQueue::new();
+        queue.recenter(&[ChunkLoader {
+            center: origin,
+            range: 5,
+        }]);
+        for cc in origin.iter_range(3) {
+            queue.push(cc);
+        }
+        // asked again, still queued once
+        queue.push(origin);
+        assert_eq!(queue.requests.len(), 49);
+        assert_eq!(queue.pop(), Some(origin));
+
+        // the player moved away, only 4 of the chunks are still in range,
+        // and a named loader keeps one more
+        let player = ChunkLoader {
+            center: ChunkCoords { x: 4, z: 0 },
+            range: 2,
+        };
+        let anchor = ChunkLoader {
+            center: ChunkCoords { x: -3, z: -3 },
+            range: 0,
+        };
+        assert_eq!(queue.recenter(&[player, anchor]), 48 - 5);
+        let mut previous = 0;
+        while let Some(cc) = queue.pop() {
+            assert!(player.loads(cc) || cc == anchor.center);
+            // the nearest first
+            assert!(queue.distance(cc) >= previous);
+            previous = queue.distance(cc);
+        }
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+
+use crate::{
+    bench::MAX_RADIUS,
+    world::{GameMode, Generator},
+};
+
+/// Options given on the command line
+///
+/// ```text
+/// app [--bench [radius]] [--audit <ticks> [--reference <file>]] [--world <name>]
+///     [--seed <number>] [--mode creative|survival] [--generation <preset|stages>]
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    /// Measures the chunk pipeline (in the given radius) instead of playing
+    pub bench: Option<Option<u8>>,
+    /// Runs the simulation instead of playing, hashing the world every
+    /// given number of ticks (see [`crate::audit`])
+    pub audit: Option<u64>,
+    /// Output of a previous audit, the run is compared with
+    pub reference: Option<PathBuf>,
+    /// The world to play, the startup menu asks for it if not given
+    pub world: Option<String>,
+    /// Seed of the world, if it is created
+    pub seed: Option<u32>,
+    /// Game mode of the world, if it is created
+    pub mode: Option<GameMode>,
+    /// Generation of the world (see [`Generator::from_spec`]), if it is created
+    pub generation: Option<String>,
+}
+
+impl Options {
+    /// Parses the arguments (without the name of the program)
+    ///
+    /// On error, the invalid argument is returned.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| arg.clone());
+            match arg.as_str() {
+                "--world" => options.world = Some(value()?),
+                "--seed" => options.seed = Some(value()?.parse().map_err(|_| arg.clone())?),
+                "--mode" => options.mode = Some(value()?.parse()?),
+                "--generation" => {
+                    let spec = value()?;
+                    Generator::from_spec(0, &spec)?;
+                    options.generation = Some(spec);
+                }
+                "--audit" => options.audit = Some(value()?.parse().map_err(|_| arg.clone())?),
+                "--reference" => options.reference = Some(value()?.into()),
+                "--bench" => {
+                    // the radius is optional
+                    let radius = args.peek().and_then(|radius| radius.parse().ok());
+                    if let Some(radius) = radius {
+                        let value = args.next().unwrap();
+                        if radius > MAX_RADIUS {
+                            return Err(value);
+                        }
+                    }
+                    options.bench = Some(radius);
+                }
+                _ => return Err(arg),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// A world name is used as a directory name, so it is kept simple
+pub fn valid_world_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Options, String> {
+        Options::parse(args.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(""), Ok(Options::default()));
+        assert_eq!(
+            parse("--world build --seed 12 --mode survival"),
+            Ok(Options {
+                world: Some("build".to_string()),
+                seed: Some(12),
+                mode: Some(GameMode::Survival),
+                ..Default::default()
+            })
+        );
+        assert_eq!(parse("--bench").unwrap().bench, Some(None));
+        assert_eq!(parse("--bench 4").unwrap().bench, Some(Some(4)));
+        assert_eq!(parse("--bench 255"), Err("255".to_string()));
+        assert_eq!(
+            parse("--audit 600 --reference run.txt"),
+            Ok(Options {
+                audit: Some(600),
+                reference: Some("run.txt".into()),
+                ..Default::default()
+            })
+        );
+        assert_eq!(parse("--audit often"), Err("--audit".to_string()));
+        assert_eq!(parse("--seed abc"), Err("--seed".to_string()));
+        assert_eq!(parse("--mode flying"), Err("flying".to_string()));
+        assert_eq!(parse("--world"), Err("--world".to_string()));
+        assert_eq!(
+            parse("--generation superflat").unwrap().generation,
+            Some("superflat".to_string())
+        );
+        assert_eq!(parse("--generation flat"), Err("flat".to_string()));
+        assert_eq!(parse("--unknown"), Err("--unknown".to_string()));
+    }
+
+    #[test]
+    fn test_world_name() {
+        assert!(valid_world_name("my_world-2"));
+        assert!(!valid_world_name(""));
+        assert!(!valid_world_name("../world"));
+    }
+}
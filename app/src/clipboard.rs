@@ -0,0 +1,37 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Programs setting the clipboard from their input, tried in order (the
+/// windowing library has no access to the clipboard)
+const TOOLS: [&[&str]; 5] = [
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+    &["pbcopy"],
+    &["clip"],
+];
+
+/// Puts the text in the clipboard of the desktop, returns whether one of
+/// the tools did it
+pub fn copy(text: &str) -> bool {
+    TOOLS.iter().any(|tool| {
+        let child = Command::new(tool[0])
+            .args(&tool[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = child else {
+            return false;
+        };
+        let written = child
+            .stdin
+            .take()
+            .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        let copied = child.wait().is_ok_and(|status| status.success());
+        tracing::debug!(tool = tool[0], copied, "clipboard");
+        written && copied
+    })
+}
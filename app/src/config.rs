@@ -0,0 +1,250 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    net::SocketAddr,
+    path::Path,
+};
+
+use def::plugin::Capability;
+
+use crate::world::Physics;
+
+/// User settings, persisted between sessions
+///
+/// The file is a list of `key = value` lines, unknown keys are ignored
+/// and missing ones keep their default value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Is the music and ambience enabled
+    pub music: bool,
+    /// Master volume (from 0.0 to 1.0)
+    pub volume: f32,
+    /// Code of the language of the texts (like `en`)
+    pub language: String,
+    /// Which logs are written (like `info` or `app::world=debug`)
+    pub log: String,
+    /// Generation of new worlds: a preset (like `superflat` or `archipelago`)
+    /// or the stages of the terrain generator (like `density, surface`)
+    pub generation: String,
+    /// The sprites are packed in a single texture instead of an array of
+    /// textures, which some drivers do not handle well
+    pub atlas: bool,
+    /// Colors of the block outline, the crosshair and the HUD
+    pub palette: Palette,
+    /// Chunks loaded around the player, or tuned to hold the frame rate
+    pub render_distance: RenderDistance,
+    /// Memory the chunks, the meshes and the textures may take together (in
+    /// MiB), the farthest ones being let go past it
+    pub memory_budget: usize,
+    /// Mobs spawn in the dark (builders may prefer to be left alone)
+    pub spawning: bool,
+    /// The screen shakes with the explosions (players sensitive to motion
+    /// may turn it off)
+    pub shake: bool,
+    /// Gravity, jump and collisions (see [`Physics`])
+    pub physics: Physics,
+    /// Console commands standing for others, like `wall` for
+    /// `fill ~ ~ ~ ~10 ~3 ~ brick` (written as `alias.wall = ...`)
+    pub aliases: BTreeMap<String, String>,
+    /// What each plugin is allowed to do (written as
+    /// `plugin.name = read_blocks, commands`), nothing for the others
+    pub plugins: BTreeMap<String, BTreeSet<Capability>>,
+    /// Where the metrics are served for Prometheus (like `127.0.0.1:9100`),
+    /// not at all if empty
+    pub metrics: Option<SocketAddr>,
+}
+
+pub const CONFIG_PATH: &str = "artcraft.cfg";
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            music: true,
+            volume: 0.8,
+            language: "en".to_string(),
+            log: "info".to_string(),
+            generation: crate::world::DEFAULT_STAGES.to_string(),
+            atlas: false,
+            palette: Palette::Default,
+            render_distance: RenderDistance::Fixed(RenderDistance::DEFAULT),
+            memory_budget: 2048,
+            spawning: true,
+            shake: true,
+            physics: Physics::default(),
+            aliases: BTreeMap::new(),
+            plugins: BTreeMap::new(),
+            metrics: None,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config, falling back on defaults if there is no file yet
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let mut config = Self::default();
+        if let Ok(content) = fs::read_to_string(path) {
+            for (key, value) in content
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .map(|(key, value)| (key.trim(), value.trim()))
+            {
+                config.set(key, value);
+            }
+        }
+        config
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    // a bad value is ignored rather than preventing the game to start
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "music" => self.music = value.parse().unwrap_or(self.music),
+            "volume" => self.volume = value.parse().unwrap_or(self.volume),
+            "language" => self.language = value.to_string(),
+            "log" => self.log = value.to_string(),
+            "generation" => self.generation = value.to_string(),
+            "atlas" => self.atlas = value.parse().unwrap_or(self.atlas),
+            "palette" => self.palette = value.parse().unwrap_or(self.palette),
+            "render_distance" => {
+                self.render_distance = value.parse().unwrap_or(self.render_distance)
+            }
+            "memory_budget" => self.memory_budget = value.parse().unwrap_or(self.memory_budget),
+            "spawning" => self.spawning = value.parse().unwrap_or(self.spawning),
+            "shake" => self.shake = value.parse().unwrap_or(self.shake),
+            "metrics" => self.metrics = value.parse().ok(),
+            "gravity" => self.physics.gravity = value.parse().unwrap_or(self.physics.gravity),
+            "jump" => self.physics.jump = value.parse().unwrap_or(self.physics.jump),
+            "collision_epsilon" => {
+                self.physics.collision_epsilon =
+                    value.parse().unwrap_or(self.physics.collision_epsilon)
+            }
+            "walk" => self.physics.walk = value.parse().unwrap_or(self.physics.walk),
+            "sprint" => self.physics.sprint = value.parse().unwrap_or(self.physics.sprint),
+            "fly" => self.physics.fly = value.parse().unwrap_or(self.physics.fly),
+            "max_fall" => self.physics.max_fall = value.parse().unwrap_or(self.physics.max_fall),
+            _ => {
+                if let Some(name) = key.strip_prefix("alias.") {
+                    self.aliases.insert(name.to_string(), value.to_string());
+                } else if let Some(name) = key.strip_prefix("plugin.") {
+                    // an unknown capability is not granted
+                    let capabilities = value
+                        .split(',')
+                        .filter_map(|capability| capability.trim().parse().ok())
+                        .collect();
+                    self.plugins.insert(name.to_string(), capabilities);
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "music = {}", self.music)?;
+        writeln!(f, "volume = {}", self.volume)?;
+        writeln!(f, "language = {}", self.language)?;
+        writeln!(f, "log = {}", self.log)?;
+        writeln!(f, "generation = {}", self.generation)?;
+        writeln!(f, "atlas = {}", self.atlas)?;
+        writeln!(f, "palette = {}", self.palette)?;
+        writeln!(f, "render_distance = {}", self.render_distance)?;
+        writeln!(f, "memory_budget = {}", self.memory_budget)?;
+        writeln!(f, "spawning = {}", self.spawning)?;
+        writeln!(f, "shake = {}", self.shake)?;
+        match self.metrics {
+            Some(address) => writeln!(f, "metrics = {address}")?,
+            None => writeln!(f, "metrics =")?,
+        }
+        writeln!(f, "gravity = {}", self.physics.gravity)?;
+        writeln!(f, "jump = {}", self.physics.jump)?;
+        writeln!(f, "collision_epsilon = {}", self.physics.collision_epsilon)?;
+        writeln!(f, "walk = {}", self.physics.walk)?;
+        writeln!(f, "sprint = {}", self.physics.sprint)?;
+        writeln!(f, "fly = {}", self.physics.fly)?;
+        writeln!(f, "max_fall = {}", self.physics.max_fall)?;
+        for (name, commands) in &self.aliases {
+            writeln!(f, "alias.{name} = {commands}")?;
+        }
+        for (name, capabilities) in &self.plugins {
+            let capabilities: Vec<String> = capabilities.iter().map(|c| c.to_string()).collect();
+            writeln!(f, "plugin.{name} = {}", capabilities.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Colors of the interface, some telling states apart for players with a
+/// color vision deficiency
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Palette {
+    Default,
+    /// Green is weak (the most common deficiency)
+    Deuteranopia,
+    /// Red is weak, and looks darker
+    Protanopia,
+}
+
+impl std::str::FromStr for Palette {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(Self::Default),
+            "deuteranopia" => Ok(Self::Deuteranopia),
+            "protanopia" => Ok(Self::Protanopia),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Palette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::Deuteranopia => write!(f, "deuteranopia"),
+            Self::Protanopia => write!(f, "protanopia"),
+        }
+    }
+}
+
+/// How far the chunks are loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderDistance {
+    /// Always this many chunks around the player
+    Fixed(i32),
+    /// Grows or shrinks with the frame rate
+    Auto,
+}
+
+impl RenderDistance {
+    /// Distance without settings, and the one the tuning starts from
+    pub const DEFAULT: i32 = 8;
+    /// Distances which can be set, in chunks
+    pub const RANGE: std::ops::RangeInclusive<i32> = 2..=32;
+}
+
+impl std::str::FromStr for RenderDistance {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            _ => match s.parse() {
+                Ok(distance) if Self::RANGE.contains(&distance) => Ok(Self::Fixed(distance)),
+                _ => Err(()),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for RenderDistance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fixed(distance) => write!(f, "{distance}"),
+            Self::Auto => write!(f, "auto"),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use std::{
+    backtrace::Backtrace,
+    fmt::Write,
+    fs,
+    panic::{self, PanicHookInfo},
+    sync::Arc,
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{i18n::tr, world::World};
+
+/// Saves what can be saved when a thread panics, and writes a crash report
+///
+/// The report (`crash-<timestamp>.txt`) holds what is needed to reproduce
+/// the crash: the panic message, the world seed and where the player was.
+pub fn install_hook(world: Arc<World>) {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        tracing::error!("{info}");
+        let save = world.save();
+        if let Err(err) = &save {
+            eprintln!("{}", tr!("error.save", error = err));
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+        let path = format!("crash-{timestamp}.txt");
+        match fs::write(&path, report(&world, info, save.err())) {
+            Ok(()) => eprintln!("{}", tr!("error.crash", path = path)),
+            Err(err) => eprintln!("{err}"),
+        }
+    }));
+}
+
+fn report(world: &World, info: &PanicHookInfo, save: Option<std::io::Error>) -> String {
+    let mut report = String::new();
+    let thread = thread::current();
+    // writing to a String never fails
+    writeln!(report, "thread: {}", thread.name().unwrap_or("unnamed")).ok();
+    writeln!(report, "panic: {info}").ok();
+    writeln!(report, "seed: {}", world.seed()).ok();
+    match world.try_pull_player() {
+        Some(player) => writeln!(report, "position: {:?}", player.camera.pos),
+        None => writeln!(report, "position: unknown (player locked)"),
+    }
+    .ok();
+    match save {
+        None => writeln!(report, "emergency save: done"),
+        Some(err) => writeln!(report, "emergency save: failed ({err})"),
+    }
+    .ok();
+    writeln!(report, "\n{}", Backtrace::force_capture()).ok();
+    report
+}
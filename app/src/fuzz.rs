@@ -0,0 +1,77 @@
+//! Entry points of the fuzz targets (see `fuzz/`)
+//!
+//! They take what the game reads from outside: the chunk files of a save
+//! and the lines typed in the console. Whatever the input, they must not
+//! panic. The packets will be added here with the network.
+
+use crate::{
+    camera::Camera,
+    grammar::CmdParser,
+    world::{decode_chunk, encode_chunk},
+};
+
+/// Reads a chunk file, a chunk read must be written back as it was read
+pub fn chunk(bytes: &[u8]) {
+    if let Some(blocks) = decode_chunk(bytes) {
+        let encoded = encode_chunk(&blocks);
+        assert_eq!(decode_chunk(&encoded), Some(blocks));
+    }
+}
+
+/// Where the lines are typed from, standing in the world so the relative
+/// coordinates reach the blocks
+const CAMERA: Camera = Camera {
+    pos: [0.5, 80.0, 0.5],
+    h_angle: 0.0,
+    v_angle: 0.0,
+};
+
+/// Parses a console line
+pub fn command(line: &str) {
+    if let Ok(cmd) = CmdParser::new().parse(CAMERA, line) {
+        // the commands are printed in the logs
+        let _ = format!("{cmd:?}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path};
+
+    use super::*;
+
+    fn corpus(target: &str) -> Vec<Vec<u8>> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fuzz/corpus")
+            .join(target);
+        let mut seeds: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| fs::read(entry.unwrap().path()).unwrap())
+            .collect();
+        assert!(!seeds.is_empty());
+        seeds.sort();
+        seeds
+    }
+
+    #[test]
+    fn test_chunk_corpus() {
+        for seed in corpus("chunk") {
+            chunk(&seed);
+        }
+        // every id with every state
+        for entry in 0..=u16::MAX {
+            let [id, state] = entry.to_le_bytes();
+            chunk(&[0, 0, id, state]);
+        }
+    }
+
+    #[test]
+    fn test_command_corpus() {
+        for seed in corpus("command") {
+            let line = String::from_utf8(seed).unwrap();
+            command(&line);
+            // the seeds are commands the game knows
+            assert!(CmdParser::new().parse(CAMERA, &line).is_ok(), "{line}");
+        }
+    }
+}
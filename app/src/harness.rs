@@ -0,0 +1,383 @@
+//! A world driven without a window, for the tests
+//!
+//! [`Harness`] holds a superflat world, the renderer and the supervisor being
+//! replaced by the channels they would listen to. A test runs commands and
+//! ticks as the game does, then checks the blocks, the faces meshed, the
+//! sections sent to render and the events.
+//!
+//! A test needing another world (survival, without mobs, or with nothing
+//! loaded to insert its own chunks) describes it with a [`Setup`].
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use def::{Block, BlockCoords, ChunkCoords, Direction};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::{
+    beatrice,
+    camera::Camera,
+    config::Config,
+    grammar::CmdParser,
+    world::{ChunkStage, ChunkState, Event, GameMode, Player, Save, Sections, World, ALL_SECTIONS},
+    AristideCmd, CmdError, CmdOutput,
+};
+
+/// Top of the ground of the superflat world (grass)
+pub const GROUND: i32 = 5;
+
+/// Chunks meshed around the origin
+const RADIUS: u8 = 1;
+
+/// How the world of a [`Harness`] starts
+#[derive(Debug, Clone)]
+pub struct Setup {
+    pub config: Config,
+    pub mode: GameMode,
+    pub generation: &'static str,
+    /// Chunks meshed around the origin, the player standing in the middle of
+    /// chunk (0, 0) (None leaves every chunk unloaded, and the player where
+    /// the world spawns it)
+    pub radius: Option<u8>,
+}
+
+impl Default for Setup {
+    fn default() -> Self {
+        Self {
+            config: Config::default(),
+            mode: GameMode::Creative,
+            generation: "superflat",
+            radius: Some(RADIUS),
+        }
+    }
+}
+
+pub struct Harness {
+    pub world: Arc<World>,
+    /// What Aristide would have been asked to render
+    rendered: mpsc::Receiver<AristideCmd>,
+    events: broadcast::Receiver<Event>,
+    parser: CmdParser,
+    setup: Setup,
+    dir: PathBuf,
+}
+
+impl Harness {
+    /// A creative superflat world with the chunks around the origin meshed,
+    /// the player standing in the middle of chunk (0, 0)
+    ///
+    /// Its save goes in a directory of its own (named after the test), which
+    /// is removed with the harness.
+    pub fn new(name: &str) -> Self {
+        Self::with(name, Setup::default())
+    }
+
+    /// A creative superflat world with nothing loaded, for the tests
+    /// inserting their own chunks
+    pub fn empty(name: &str) -> Self {
+        Self::with(
+            name,
+            Setup {
+                radius: None,
+                ..Setup::default()
+            },
+        )
+    }
+
+    /// A world started as told by the setup
+    pub fn with(name: &str, setup: Setup) -> Self {
+        let dir = std::env::temp_dir().join(format!("artcraft-harness-{name}"));
+        std::fs::remove_dir_all(&dir).ok();
+        let (world, rendered) = Self::open(&dir, &setup);
+        if let Some(radius) = setup.radius {
+            let origin = ChunkCoords { x: 0, z: 0 };
+            for cc in origin.iter_range(radius) {
+                world.advance_chunk_stage(cc, ChunkStage::Meshed);
+            }
+        }
+        let mut harness = Self {
+            events: world.subscribe(),
+            world,
+            rendered,
+            parser: CmdParser::new(),
+            setup,
+            dir,
+        };
+        if harness.setup.radius.is_some() {
+            harness.teleport([8.5, GROUND as f32 + 1.0 + Player::EYES, 8.5]);
+        }
+        // the tests start from there
+        harness.events();
+        harness
+    }
+
+    fn open(dir: &Path, setup: &Setup) -> (Arc<World>, mpsc::Receiver<AristideCmd>) {
+        let (sender, rendered) = mpsc::channel(4096);
+        let world = World::new(
+            setup.config.clone(),
+            Save::new(dir),
+            World::new_meta(Some(0), setup.mode, setup.generation),
+            mpsc::channel(16).0,
+            sender,
+        );
+        (Arc::new(world), rendered)
+    }
+
+    /// Opens the world again from its save, as after quitting the game
+    ///
+    /// Nothing is loaded, and what was not saved is lost.
+    pub fn reopen(&mut self) {
+        let (world, rendered) = Self::open(&self.dir, &self.setup);
+        self.events = world.subscribe();
+        self.world = world;
+        self.rendered = rendered;
+    }
+
+    /// Runs a console command, as typed by the player
+    ///
+    /// # Panics
+    ///
+    /// If the command does not parse, a test is expected to type it right.
+    pub fn run(&self, command: &str) -> Result<CmdOutput, CmdError> {
+        let camera = self.world.pull_player().camera;
+        let cmd = self
+            .parser
+            .parse(camera, command)
+            .unwrap_or_else(|err| panic!("`{command}` does not parse: {err}"));
+        beatrice::execute(&self.world, cmd)
+    }
+
+    pub fn tick(&self, ticks: u32) {
+        for _ in 0..ticks {
+            self.world.tick();
+        }
+    }
+
+    /// Moves the eyes of the player
+    pub fn teleport(&self, pos: [f32; 3]) {
+        let mut player = self.world.pull_player();
+        player.camera = Camera {
+            pos,
+            ..player.camera
+        };
+        self.world.push_player(player);
+    }
+
+    /// The block at the coordinates (None for air)
+    ///
+    /// # Panics
+    ///
+    /// If its chunk is not loaded.
+    pub fn block(&self, coords: [i32; 3]) -> Option<Block> {
+        let bc = BlockCoords::try_from(coords).unwrap();
+        self.world.get_block(bc).expect("chunk not loaded")
+    }
+
+    /// The faces of the block in the mesh of its chunk
+    pub fn faces(&self, coords: [i32; 3]) -> Vec<Direction> {
+        let BlockCoords(cc, bi) = coords.try_into().unwrap();
+        let chunk = self.world.chunks.get(&cc);
+        let Some(ChunkState::Meshed(_, faces)) = chunk.as_deref() else {
+            panic!("chunk not meshed");
+        };
+        Direction::ALL
+            .into_iter()
+            .filter(|&direction| faces.contains_key(&(bi, direction)))
+            .collect()
+    }
+
+    /// The sections asked to be rendered since the last call, a request at a
+    /// time
+    pub fn render_requests(&mut self) -> Vec<(ChunkCoords, Sections)> {
+        let mut requests = Vec::new();
+        while let Ok(cmd) = self.rendered.try_recv() {
+            match cmd {
+                AristideCmd::RenderChunk(cc, true) => requests.push((cc, ALL_SECTIONS)),
+                AristideCmd::RenderSections(cc, sections) => requests.push((cc, sections)),
+                AristideCmd::RenderChunk(_, false) | AristideCmd::Reload => {}
+            }
+        }
+        requests
+    }
+
+    /// The sections asked to be rendered since the last call, by chunk
+    pub fn rendered(&mut self) -> HashMap<ChunkCoords, Sections> {
+        let mut rendered = HashMap::new();
+        for (cc, sections) in self.render_requests() {
+            *rendered.entry(cc).or_default() |= sections;
+        }
+        rendered
+    }
+
+    /// The events of the world since the last call
+    pub fn events(&mut self) -> Vec<Event> {
+        std::iter::from_fn(|| self.events.try_recv().ok()).collect()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use def::Boxel;
+
+    use super::*;
+    use crate::world::{EntityKind, RayHit, REACH};
+
+    const ABOVE: i32 = GROUND + 1;
+
+    #[test]
+    fn test_place_and_remove_faces() {
+        let mut harness = Harness::new("faces");
+        assert_eq!(harness.block([3, GROUND, 3]), Some(Block::Grass));
+        assert_eq!(harness.faces([3, GROUND, 3]), [Direction::Up]);
+        harness.rendered();
+
+        harness.run(&format!("place 3 {ABOVE} 3 stone")).unwrap();
+        assert_eq!(harness.block([3, ABOVE, 3]), Some(Block::Stone));
+        // the face against the grass is hidden, and so is the grass top
+        let faces = harness.faces([3, ABOVE, 3]);
+        assert_eq!(faces.len(), 5);
+        assert!(!faces.contains(&Direction::Down));
+        assert!(harness.faces([3, GROUND, 3]).is_empty());
+        let origin = ChunkCoords { x: 0, z: 0 };
+        assert_eq!(harness.rendered().get(&origin), Some(&1));
+
+        assert!(harness.run(&format!("place 3 {ABOVE} 3 stone")).is_err());
+        harness.run(&format!("remove 3 {ABOVE} 3")).unwrap();
+        assert_eq!(harness.block([3, ABOVE, 3]), None);
+        assert!(harness.faces([3, ABOVE, 3]).is_empty());
+        assert_eq!(harness.faces([3, GROUND, 3]), [Direction::Up]);
+        assert!(matches!(
+            harness.events()[..],
+            [Event::BlockBroken {
+                broken: Block::Stone
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_cross_chunk_meshing() {
+        let mut harness = Harness::new("cross-chunk");
+        harness.rendered();
+        // on both sides of the border between chunks 0 and 1
+        harness.run(&format!("place 15 {ABOVE} 3 stone")).unwrap();
+        assert!(harness.faces([15, ABOVE, 3]).contains(&Direction::East));
+        harness.run(&format!("place 16 {ABOVE} 3 brick")).unwrap();
+        assert!(!harness.faces([15, ABOVE, 3]).contains(&Direction::East));
+        assert!(!harness.faces([16, ABOVE, 3]).contains(&Direction::West));
+        let rendered = harness.rendered();
+        assert!(rendered.contains_key(&ChunkCoords { x: 0, z: 0 }));
+        assert!(rendered.contains_key(&ChunkCoords { x: 1, z: 0 }));
+
+        // the face comes back once its neighbour is gone
+        harness.run(&format!("remove 16 {ABOVE} 3")).unwrap();
+        assert!(harness.faces([15, ABOVE, 3]).contains(&Direction::East));
+        assert!(harness.rendered().contains_key(&ChunkCoords { x: 0, z: 0 }));
+    }
+
+    #[test]
+    fn test_collision_edges() {
+        let harness = Harness::new("collision");
+        harness
+            .run(&format!("fill 10 {ABOVE} 0 10 {} 15 stone", ABOVE + 2))
+            .unwrap();
+        let standing = |x: f32, z: f32| {
+            let eyes = [x, GROUND as f32 + 1.0 + Player::EYES, z];
+            Player::hit_box(eyes)
+        };
+        let right_edge = |hit_box: Boxel| hit_box.pos[0] + hit_box.dimensions[0];
+
+        // walking into the wall stops flush against it
+        let hit_box = standing(8.5, 8.5);
+        let time = harness.world.find_collision_x(hit_box, [2.0, 0.0, 0.0]);
+        assert!((right_edge(hit_box) + 2.0 * time - 10.0).abs() < 1e-4);
+        // already touching it, it can't move in but slides along it
+        let hit_box = standing(9.7, 8.5);
+        assert_eq!(
+            harness.world.find_collision_x(hit_box, [0.5, 0.0, 0.0]),
+            0.0
+        );
+        assert_eq!(
+            harness.world.find_collision_z(hit_box, [0.0, 0.0, 3.0]),
+            1.0
+        );
+        // standing on the ground, it can't fall, but nothing is above
+        assert_eq!(
+            harness.world.find_collision_y(hit_box, [0.0, -1.0, 0.0]),
+            0.0
+        );
+        assert_eq!(
+            harness.world.find_collision_y(hit_box, [0.0, 1.0, 0.0]),
+            1.0
+        );
+        // past the end of the wall, the way is free
+        let hit_box = standing(9.7, 16.5);
+        assert_eq!(
+            harness.world.find_collision_x(hit_box, [1.0, 0.0, 0.0]),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_ray_picking() {
+        let harness = Harness::new("raycast");
+        let eyes = harness.world.pull_player().camera.pos;
+        // straight down, the grass under the feet
+        match harness.world.raycast(eyes, [0.0, -1.0, 0.0], REACH) {
+            Some(RayHit::Block { coords, face, .. }) => {
+                assert_eq!(<[i32; 3]>::from(coords), [8, GROUND, 8]);
+                assert_eq!(face, Direction::Up);
+            }
+            hit => panic!("unexpected {hit:?}"),
+        }
+        // across the border of the chunk, the side of a block
+        let y = eyes[1].floor() as i32;
+        harness.run(&format!("place 17 {y} 8 glass")).unwrap();
+        match harness.world.raycast(eyes, [1.0, 0.0, 0.0], REACH) {
+            Some(RayHit::Block {
+                coords,
+                face,
+                point,
+            }) => {
+                assert_eq!(<[i32; 3]>::from(coords), [17, y, 8]);
+                assert_eq!(face, Direction::West);
+                assert!((point[0] - 17.0).abs() < 1e-4);
+            }
+            hit => panic!("unexpected {hit:?}"),
+        }
+        // out of reach, and looking at the sky
+        harness.run(&format!("remove 17 {y} 8")).unwrap();
+        harness.run(&format!("place 30 {y} 8 glass")).unwrap();
+        assert_eq!(harness.world.raycast(eyes, [1.0, 0.0, 0.0], REACH), None);
+        assert_eq!(harness.world.raycast(eyes, [0.0, 1.0, 0.0], REACH), None);
+    }
+
+    #[test]
+    fn test_tnt_ticks() {
+        let mut harness = Harness::new("tnt");
+        harness.run(&format!("place 4 {ABOVE} 4 tnt")).unwrap();
+        harness.run(&format!("interact 4 {ABOVE} 4")).unwrap();
+        assert_eq!(harness.block([4, ABOVE, 4]), None);
+        harness.events();
+        harness.tick(200);
+        let events = harness.events();
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, Event::Explosion { .. })));
+        // a crater in the ground
+        assert_eq!(harness.block([4, GROUND, 4]), None);
+        assert!(harness
+            .world
+            .pull_entities()
+            .iter()
+            .all(|entity| entity.kind != EntityKind::PrimedTnt));
+    }
+}
@@ -0,0 +1,146 @@
+//! Translation of the user facing text
+//!
+//! Every message is identified by a key and looked up in the file of the
+//! current language (`lang/<code>.lang`, a list of `key = value` lines).
+//! Messages can have named parameters written as `{name}`.
+//! A message missing in a language falls back on english.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        OnceLock,
+    },
+};
+
+use def::Block;
+
+/// Languages embedded in the executable (the first one is the fallback)
+const LANGUAGES: [(&str, &str); 2] = [
+    ("en", include_str!("../lang/en.lang")),
+    ("es", include_str!("../lang/es.lang")),
+];
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the translated message or formats it with the given parameters
+///
+/// ```ignore
+/// tr!("cmd.volume", volume = 0.5)
+/// ```
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::i18n::translate($key)
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::format(
+            $key,
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+pub(crate) use tr;
+
+fn tables() -> &'static [HashMap<&'static str, &'static str>] {
+    static TABLES: OnceLock<Vec<HashMap<&'static str, &'static str>>> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        LANGUAGES
+            .iter()
+            .map(|(_, content)| {
+                content
+                    .lines()
+                    .filter(|line| !line.starts_with('#'))
+                    .filter_map(|line| line.split_once('='))
+                    .map(|(key, value)| (key.trim(), value.trim()))
+                    .collect()
+            })
+            .collect()
+    })
+}
+
+/// Selects the current language from its code, returns false if unknown
+pub fn set_language(code: &str) -> bool {
+    match LANGUAGES.iter().position(|&(c, _)| c == code) {
+        Some(index) => {
+            CURRENT.store(index, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Translates the message with the given key
+///
+/// If the key is unknown in every language, the key itself is returned
+/// so the missing translation is noticed.
+pub fn translate(key: &str) -> &str {
+    let tables = tables();
+    tables[CURRENT.load(Ordering::Relaxed)]
+        .get(key)
+        .or_else(|| tables[0].get(key))
+        .copied()
+        .unwrap_or(key)
+}
+
+/// Translates the message and replaces its parameters
+pub fn format(key: &str, parameters: &[(&str, &dyn Display)]) -> String {
+    parameters
+        .iter()
+        .fold(translate(key).to_string(), |message, (name, value)| {
+            message.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+}
+
+/// The translated name of a block
+pub fn block_name(block: Block) -> &'static str {
+    let key = match block {
+        Block::Stone => "block.stone",
+        Block::Dirt => "block.dirt",
+        Block::Grass => "block.grass",
+        Block::Sand => "block.sand",
+        Block::Water(_) => "block.water",
+        Block::Glass => "block.glass",
+        Block::Brick => "block.brick",
+        Block::Trunk => "block.trunk",
+        Block::Leaves => "block.leaves",
+        Block::Tnt => "block.tnt",
+        Block::Door(_) => "block.door",
+        Block::Trapdoor(_) => "block.trapdoor",
+        Block::Torch(_) => "block.torch",
+        Block::Sapling(_) => "block.sapling",
+        Block::Bedrock => "block.bedrock",
+        Block::Ice => "block.ice",
+        Block::Slime => "block.slime",
+        Block::Chest => "block.chest",
+        Block::Wire(_) => "block.wire",
+        Block::Lever(_) => "block.lever",
+        Block::Lamp(_) => "block.lamp",
+        Block::Piston(_) | Block::PistonHead(_) => "block.piston",
+        Block::Farmland => "block.farmland",
+        Block::Crop(_) => "block.crop",
+        Block::SoulSand => "block.soul_sand",
+        Block::Glider => "block.glider",
+        // the packs name their blocks in a single language
+        Block::Custom(id) if def::custom::is_registered(id) => return &def::custom::get(id).label,
+        Block::Custom(_) => "block.unknown",
+    };
+    translate(key)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_languages_complete() {
+        // every message must be translated in every language
+        let tables = tables();
+        for (table, (code, _)) in tables.iter().zip(LANGUAGES) {
+            for key in tables[0].keys() {
+                assert!(table.contains_key(key), "{key} missing in {code}");
+            }
+            assert_eq!(table.len(), tables[0].len(), "unknown keys in {code}");
+        }
+    }
+}
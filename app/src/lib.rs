@@ -0,0 +1,555 @@
+// noise 0.7 glob-exports two different `Perlin` (the regular and the surflet one)
+#![allow(ambiguous_glob_imports)]
+
+#[macro_use]
+extern crate lalrpop_util;
+lalrpop_mod!(#[allow(clippy::all)] pub grammar);
+
+use std::{fmt, sync::Arc, thread};
+
+use cli::Options;
+use config::{Config, Palette, CONFIG_PATH};
+use def::{Block, BlockCoords, ChunkCoords};
+use i18n::tr;
+use tokio::sync::mpsc;
+use world::{ChunkLoader, RegionCount, Weather, World};
+
+mod aristide;
+mod atlas;
+mod audit;
+mod beatrice;
+mod bench;
+mod camera;
+mod cassiope;
+mod cli;
+mod clipboard;
+mod config;
+mod crash;
+#[doc(hidden)]
+pub mod fuzz;
+#[cfg(test)]
+mod harness;
+mod i18n;
+mod logging;
+mod menu;
+mod mesh;
+mod metrics;
+mod music;
+mod pack;
+mod plugins;
+mod world;
+
+#[derive(Debug, Clone)]
+pub enum Cmd {
+    RemoveBlock(BlockCoords),
+    PlaceBlock(BlockCoords, Block),
+    /// Uses a block, like with a right click
+    Interact(BlockCoords),
+    /// Puts one of the held block in a slot of a chest
+    ChestPut(BlockCoords, usize),
+    /// Takes one block from a slot of a chest, the player holds it
+    ChestTake(BlockCoords, usize),
+    /// Turns dirt or grass into farmland, like with a hoe
+    Till(BlockCoords),
+    /// Places a boat, which floats on water and can be ridden
+    Boat(BlockCoords),
+    /// Moves the feet of the player, and turns it toward the horizontal and
+    /// vertical angles (in degrees) if given
+    Tp([f32; 3], Option<[f32; 2]>),
+    /// Gives the `tp` command bringing back where the player is
+    Where,
+    Fly(bool),
+    BlockPlacing(Block),
+    Music(bool),
+    Volume(f32),
+    Language(String),
+    Log(String),
+    Weather(Weather),
+    Palette(Palette),
+    /// Whether mobs spawn
+    Spawning(bool),
+    /// Whether the screen shakes with the explosions
+    Shake(bool),
+    /// Keeps the chunks around a block column (x, z) loaded, in a range
+    Anchor(String, i32, i32, i32),
+    Unanchor(String),
+    /// Counts a kind of block in a region (between two corners)
+    Count(Block, BlockCoords, BlockCoords),
+    /// Saves the blocks of a region (between two corners) as a structure
+    Copy(BlockCoords, BlockCoords, String),
+    /// Places a block everywhere there is air in a region (between two
+    /// corners)
+    Fill(BlockCoords, BlockCoords, Block),
+    /// Pastes a structure at the feet of the player, turned by the given
+    /// degrees and maybe mirrored
+    Paste(String, i32, bool),
+    /// Reverts the edits of the last minutes, in a region or everywhere
+    Rollback(i32, Option<(BlockCoords, BlockCoords)>),
+    /// Names a list of commands (separated by `;`), an empty one removing
+    /// the alias
+    Alias(String, String),
+    /// Lists the aliases
+    Aliases,
+    /// Writes the world to the save right away
+    Save,
+    /// Closes the game, saving the world
+    Stop,
+    /// Reads the content packs again and rebuilds what is drawn with them
+    Reload,
+    Help,
+}
+
+/// What a successfully executed command did
+#[derive(Debug, Clone)]
+pub enum CmdOutput {
+    BlockRemoved(BlockCoords, Block),
+    BlockPlaced(BlockCoords, Block),
+    /// A TNT block was ignited
+    Ignited(BlockCoords),
+    /// A door or trapdoor was opened (or closed)
+    Opened(BlockCoords, bool),
+    /// A lever was switched on (or off)
+    Switched(BlockCoords, bool),
+    /// A block was put in a slot of a chest, which now holds this many
+    Stored(Block, usize, u8),
+    /// A block was taken from a slot of a chest, which has this many left
+    Taken(Block, usize, u8),
+    /// Dirt or grass became farmland
+    Tilled(BlockCoords),
+    BoatPlaced(BlockCoords),
+    Teleported([f32; 3]),
+    /// The `tp` command bringing back where the player is, and whether it
+    /// was copied to the clipboard
+    Location(String, bool),
+    Fly(bool),
+    BlockPlacing(Block),
+    Music(bool),
+    Volume(f32),
+    /// The language changed (its name is translated in the new one)
+    Language,
+    Log(String),
+    Weather(Weather),
+    Palette(Palette),
+    Spawning(bool),
+    Shake(bool),
+    /// A named chunk loader was added (or moved)
+    Anchored(String, ChunkLoader),
+    Unanchored(String),
+    /// Blocks counted in a region
+    Counted(Block, RegionCount),
+    /// A structure was saved, with this many blocks
+    Copied(String, usize),
+    Pasted(String, BlockCoords),
+    /// This many blocks were placed in a region
+    Filled(Block, usize),
+    /// This many edits were reverted
+    RolledBack(usize),
+    Aliased(String, String),
+    Unaliased(String),
+    /// The aliases, with the commands they stand for
+    Aliases(Vec<(String, String)>),
+    Saved,
+    /// The game is closing
+    Stopping,
+    /// The resources are reloaded at the next frame
+    Reloading,
+    /// The available commands, to print their help
+    Help(Vec<String>),
+}
+
+/// Why a command could not be executed
+#[derive(Debug, Clone, PartialEq)]
+pub enum CmdError {
+    /// The chunk is not loaded (or not yet meshed) so it can't be edited
+    ChunkNotLoaded(ChunkCoords),
+    /// The coordinates are above or below the world
+    OutOfWorld([i32; 3]),
+    /// There is no block to remove
+    NoBlock(BlockCoords),
+    /// There is already a block where placing was requested
+    Occupied(BlockCoords, Block),
+    /// A number in the command does not fit
+    InvalidNumber(String),
+    /// The volume must be between 0.0 and 1.0
+    InvalidVolume(f32),
+    /// There is no translation for this language code
+    UnknownLanguage(String),
+    /// The log filter could not be parsed
+    InvalidLogFilter(String),
+    /// The settings could not be saved
+    Config(String),
+    /// Not allowed by the game mode of the world
+    CreativeOnly,
+    /// Nothing happens when using this block
+    NotInteractive(BlockCoords, Block),
+    /// The block needs a floor or a wall to be attached to
+    NoSupport(BlockCoords),
+    /// The block can't be removed
+    Unbreakable(BlockCoords, Block),
+    /// Only dirt and grass can be tilled
+    NotTillable(BlockCoords, Block),
+    /// There is no chest to put blocks in (or take them from)
+    NotAChest(BlockCoords),
+    /// A chest is only broken once empty, so its content is not lost
+    ChestNotEmpty(BlockCoords),
+    /// A chest has fewer slots
+    InvalidSlot(usize),
+    /// The slot holds another block, or a full stack
+    SlotFull(usize, Block),
+    SlotEmpty(usize),
+    /// The range of an anchor must be between 0 and the maximum
+    InvalidRange(i32),
+    /// There is no anchor with this name
+    UnknownAnchor(String),
+    /// Too many blocks to copy at once
+    RegionTooLarge(i64),
+    /// There is no structure with this name
+    UnknownStructure(String),
+    /// Structures are turned by quarter turns only
+    InvalidRotation(i32),
+    /// A file of the world could not be written
+    Save(String),
+    /// The block is too far from the player to be edited
+    OutOfReach(BlockCoords),
+    /// A solid block can't be placed where the player stands
+    InsidePlayer(BlockCoords),
+    /// The player moved farther than it can in a tick
+    TooFast(f32),
+    /// The player moved into a solid block
+    InsideBlock(BlockCoords),
+    /// There is no alias with this name
+    UnknownAlias(String),
+    /// No content pack adds a block with this name
+    UnknownBlock(String),
+    /// Local coordinates (`^`) can't be mixed with the others
+    MixedCoordinates,
+    /// An alias expands into itself (maybe through other aliases)
+    AliasLoop(String),
+}
+
+impl fmt::Display for CmdOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = |bc: &BlockCoords| format!("{:?}", <[i32; 3]>::from(*bc));
+        match self {
+            Self::BlockRemoved(bc, block) => {
+                let block = i18n::block_name(*block);
+                write!(
+                    f,
+                    "{}",
+                    tr!("cmd.removed", block = block, position = position(bc))
+                )
+            }
+            Self::BlockPlaced(bc, block) => {
+                let block = i18n::block_name(*block);
+                write!(
+                    f,
+                    "{}",
+                    tr!("cmd.placed", block = block, position = position(bc))
+                )
+            }
+            Self::Ignited(bc) => write!(f, "{}", tr!("cmd.ignited", position = position(bc))),
+            Self::Opened(bc, true) => write!(f, "{}", tr!("cmd.opened", position = position(bc))),
+            Self::Opened(bc, false) => write!(f, "{}", tr!("cmd.closed", position = position(bc))),
+            Self::Switched(bc, true) => {
+                write!(f, "{}", tr!("cmd.switched_on", position = position(bc)))
+            }
+            Self::Switched(bc, false) => {
+                write!(f, "{}", tr!("cmd.switched_off", position = position(bc)))
+            }
+            Self::Stored(block, slot, count) => write!(
+                f,
+                "{}",
+                tr!(
+                    "cmd.stored",
+                    block = i18n::block_name(*block),
+                    slot = slot,
+                    count = count
+                )
+            ),
+            Self::Taken(block, slot, count) => write!(
+                f,
+                "{}",
+                tr!(
+                    "cmd.taken",
+                    block = i18n::block_name(*block),
+                    slot = slot,
+                    count = count
+                )
+            ),
+            Self::Tilled(bc) => write!(f, "{}", tr!("cmd.tilled", position = position(bc))),
+            Self::BoatPlaced(bc) => write!(f, "{}", tr!("cmd.boat", position = position(bc))),
+            Self::Teleported([x, y, z]) => write!(
+                f,
+                "{}",
+                tr!(
+                    "cmd.teleported",
+                    position = format!("[{x:.2}, {y:.2}, {z:.2}]")
+                )
+            ),
+            Self::Location(command, true) => {
+                write!(f, "{}", tr!("cmd.where.copied", command = command))
+            }
+            Self::Location(command, false) => {
+                write!(f, "{}", tr!("cmd.where", command = command))
+            }
+            Self::Fly(true) => write!(f, "{}", tr!("cmd.fly.on")),
+            Self::Fly(false) => write!(f, "{}", tr!("cmd.fly.off")),
+            Self::BlockPlacing(block) => {
+                let block = i18n::block_name(*block);
+                write!(f, "{}", tr!("cmd.placing", block = block))
+            }
+            Self::Music(true) => write!(f, "{}", tr!("cmd.music.on")),
+            Self::Music(false) => write!(f, "{}", tr!("cmd.music.off")),
+            Self::Volume(volume) => write!(f, "{}", tr!("cmd.volume", volume = volume)),
+            Self::Language => {
+                write!(f, "{}", tr!("cmd.language", language = tr!("lang.name")))
+            }
+            Self::Log(filter) => write!(f, "{}", tr!("cmd.log", filter = filter)),
+            Self::Weather(weather) => write!(
+                f,
+                "{}",
+                tr!("cmd.weather", weather = tr!(&format!("weather.{weather}")))
+            ),
+            Self::Palette(palette) => write!(
+                f,
+                "{}",
+                tr!("cmd.palette", palette = tr!(&format!("palette.{palette}")))
+            ),
+            Self::Spawning(true) => write!(f, "{}", tr!("cmd.spawning.on")),
+            Self::Spawning(false) => write!(f, "{}", tr!("cmd.spawning.off")),
+            Self::Shake(true) => write!(f, "{}", tr!("cmd.shake.on")),
+            Self::Shake(false) => write!(f, "{}", tr!("cmd.shake.off")),
+            Self::Anchored(name, ChunkLoader { center, range }) => write!(
+                f,
+                "{}",
+                tr!(
+                    "cmd.anchored",
+                    name = name,
+                    chunk = format!("[{}, {}]", center.x, center.z),
+                    range = range
+                )
+            ),
+            Self::Unanchored(name) => write!(f, "{}", tr!("cmd.unanchored", name = name)),
+            Self::Counted(
+                block,
+                RegionCount {
+                    blocks,
+                    missing_chunks: 0,
+                },
+            ) => write!(
+                f,
+                "{}",
+                tr!(
+                    "cmd.counted",
+                    count = blocks,
+                    block = i18n::block_name(*block)
+                )
+            ),
+            Self::Counted(block, count) => write!(
+                f,
+                "{}",
+                tr!(
+                    "cmd.counted.missing",
+                    count = count.blocks,
+                    block = i18n::block_name(*block),
+                    missing = count.missing_chunks
+                )
+            ),
+            Self::Copied(name, count) => {
+                write!(f, "{}", tr!("cmd.copied", name = name, count = count))
+            }
+            Self::Pasted(name, bc) => write!(
+                f,
+                "{}",
+                tr!("cmd.pasted", name = name, position = position(bc))
+            ),
+            Self::Filled(block, count) => write!(
+                f,
+                "{}",
+                tr!(
+                    "cmd.filled",
+                    count = count,
+                    block = i18n::block_name(*block)
+                )
+            ),
+            Self::RolledBack(count) => write!(f, "{}", tr!("cmd.rolled_back", count = count)),
+            Self::Aliased(name, commands) => write!(
+                f,
+                "{}",
+                tr!("cmd.aliased", name = name, commands = commands)
+            ),
+            Self::Unaliased(name) => write!(f, "{}", tr!("cmd.unaliased", name = name)),
+            Self::Aliases(aliases) if aliases.is_empty() => {
+                write!(f, "{}", tr!("cmd.aliases.none"))
+            }
+            Self::Aliases(aliases) => {
+                for (name, commands) in aliases {
+                    writeln!(f, "{name} = {commands}")?;
+                }
+                Ok(())
+            }
+            Self::Saved => write!(f, "{}", tr!("cmd.saved")),
+            Self::Stopping => write!(f, "{}", tr!("cmd.stopping")),
+            Self::Reloading => write!(f, "{}", tr!("cmd.reloading")),
+            Self::Help(commands) => {
+                for command in commands {
+                    writeln!(f, "{}", tr!(&format!("help.{command}")))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for CmdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = |bc: &BlockCoords| format!("{:?}", <[i32; 3]>::from(*bc));
+        let message = match self {
+            Self::ChunkNotLoaded(ChunkCoords { x, z }) => {
+                tr!("error.chunk_not_loaded", chunk = format!("[{x}, {z}]"))
+            }
+            Self::OutOfWorld(p) => tr!("error.out_of_world", position = format!("{p:?}")),
+            Self::NoBlock(bc) => tr!("error.no_block", position = position(bc)),
+            Self::Occupied(bc, block) => tr!(
+                "error.occupied",
+                block = i18n::block_name(*block),
+                position = position(bc)
+            ),
+            Self::InvalidNumber(number) => tr!("error.invalid_number", number = number),
+            Self::InvalidVolume(volume) => tr!("error.invalid_volume", volume = volume),
+            Self::UnknownLanguage(code) => tr!("error.unknown_language", language = code),
+            Self::InvalidLogFilter(err) => tr!("error.invalid_log_filter", error = err),
+            Self::Config(err) => tr!("error.config", error = err),
+            Self::CreativeOnly => tr!("error.creative_only").to_string(),
+            Self::NotInteractive(bc, block) => tr!(
+                "error.not_interactive",
+                block = i18n::block_name(*block),
+                position = position(bc)
+            ),
+            Self::NoSupport(bc) => tr!("error.no_support", position = position(bc)),
+            Self::OutOfReach(bc) => tr!("error.out_of_reach", position = position(bc)),
+            Self::InsidePlayer(bc) => tr!("error.inside_player", position = position(bc)),
+            Self::TooFast(distance) => tr!("error.too_fast", distance = distance),
+            Self::InsideBlock(bc) => tr!("error.inside_block", position = position(bc)),
+            Self::Unbreakable(bc, block) => tr!(
+                "error.unbreakable",
+                block = i18n::block_name(*block),
+                position = position(bc)
+            ),
+            Self::NotTillable(bc, block) => tr!(
+                "error.not_tillable",
+                block = i18n::block_name(*block),
+                position = position(bc)
+            ),
+            Self::NotAChest(bc) => tr!("error.not_a_chest", position = position(bc)),
+            Self::ChestNotEmpty(bc) => tr!("error.chest_not_empty", position = position(bc)),
+            Self::InvalidSlot(slot) => tr!("error.invalid_slot", slot = slot),
+            Self::SlotFull(slot, block) => tr!(
+                "error.slot_full",
+                slot = slot,
+                block = i18n::block_name(*block)
+            ),
+            Self::SlotEmpty(slot) => tr!("error.slot_empty", slot = slot),
+            Self::InvalidRange(range) => tr!(
+                "error.invalid_range",
+                range = range,
+                max = world::MAX_ANCHOR_RANGE
+            ),
+            Self::UnknownAnchor(name) => tr!("error.unknown_anchor", name = name),
+            Self::RegionTooLarge(volume) => tr!(
+                "error.region_too_large",
+                volume = volume,
+                max = world::MAX_COPY_VOLUME
+            ),
+            Self::UnknownStructure(name) => tr!("error.unknown_structure", name = name),
+            Self::InvalidRotation(degrees) => tr!("error.invalid_rotation", degrees = degrees),
+            Self::Save(err) => tr!("error.save", error = err),
+            Self::MixedCoordinates => tr!("error.mixed_coordinates").to_string(),
+            Self::UnknownAlias(name) => tr!("error.unknown_alias", name = name),
+            Self::UnknownBlock(name) => tr!("error.unknown_block", name = name),
+            Self::AliasLoop(name) => tr!("error.alias_loop", name = name),
+        };
+        write!(f, "{message}")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum AristideCmd {
+    RenderChunk(ChunkCoords, bool),
+    /// Some sections of a rendered chunk changed (see [`world::Sections`])
+    RenderSections(ChunkCoords, world::Sections),
+    /// The content packs are read again, with the textures and the meshes
+    /// depending on them
+    Reload,
+}
+
+/// Runs the game, with the arguments of the command line
+pub fn run() {
+    let config = Config::load(CONFIG_PATH);
+    i18n::set_language(&config.language);
+
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(arg) => {
+            eprintln!("{}", tr!("error.argument", argument = arg));
+            eprintln!("{}", tr!("error.usage"));
+            std::process::exit(2);
+        }
+    };
+    // measures the chunk pipeline without opening a window
+    if let Some(radius) = options.bench {
+        bench::bench(radius.unwrap_or(8));
+        return;
+    }
+    // checks the simulation is deterministic, against a previous run if any
+    if let Some(every) = options.audit {
+        match audit::audit(&options, every, options.reference.as_deref()) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(err) => {
+                eprintln!("{}", tr!("error.reference", error = err));
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if let Err(err) = logging::init(&config.log) {
+        eprintln!("{}", tr!("error.log_file", error = err));
+    }
+    tracing::info!("starting");
+    // before the world, whose generation may use the blocks of the packs
+    let textures = pack::load(pack::PACKS_PATH);
+    let (save, meta) = match menu::choose_world(&options, &config.generation) {
+        Ok(world) => world,
+        Err(err) => {
+            eprintln!("{}", tr!("error.world", error = err));
+            std::process::exit(1);
+        }
+    };
+
+    let (sender_chunk_mesh, receiver_chunk_mesh) = mpsc::channel(40);
+    let (sender_cmd, receiver_cmd) = mpsc::channel(40);
+    let world_a = Arc::new(World::new(
+        config,
+        save,
+        meta,
+        sender_cmd,
+        sender_chunk_mesh,
+    ));
+    let world_b = world_a.clone();
+    let world_c = world_a.clone();
+    crash::install_hook(world_a.clone());
+
+    // threads are named so the logs tell which one is doing what
+    let workers = vec![
+        thread::Builder::new()
+            .name("beatrice".to_string())
+            .spawn(move || beatrice::beatrice(receiver_cmd, world_b))
+            .unwrap(),
+        thread::Builder::new()
+            .name("cassiope".to_string())
+            .spawn(move || cassiope::cassiope(world_c))
+            .unwrap(),
+    ];
+    aristide::aristide(receiver_chunk_mesh, world_a, workers, textures);
+}
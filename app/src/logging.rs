@@ -0,0 +1,50 @@
+use std::{
+    fs::File,
+    io,
+    sync::{Mutex, OnceLock},
+};
+
+use tracing_subscriber::{
+    filter::EnvFilter,
+    fmt::{self, format::FmtSpan},
+    prelude::*,
+    reload, Registry,
+};
+
+pub const LOG_PATH: &str = "artcraft.log";
+
+/// Allows to replace the filter while the game is running
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Starts writing logs to the log file
+///
+/// The filter uses the `RUST_LOG` syntax (like `info` or `app::world=debug`).
+/// Spans are logged when closed, with the time they were busy, this way
+/// the slow steps of the chunk pipeline (and on which thread) stand out.
+pub fn init(filter: &str) -> io::Result<()> {
+    let filter = EnvFilter::try_new(filter).unwrap_or_else(|_| EnvFilter::new("info"));
+    let (filter, handle) = reload::Layer::new(filter);
+    let file = File::create(LOG_PATH)?;
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            fmt::layer()
+                .with_writer(Mutex::new(file))
+                .with_ansi(false)
+                .with_thread_names(true)
+                .with_span_events(FmtSpan::CLOSE),
+        )
+        .init();
+    FILTER.set(handle).ok();
+    Ok(())
+}
+
+/// Replaces the filter of the logs
+pub fn set_filter(filter: &str) -> Result<(), String> {
+    let filter = EnvFilter::try_new(filter).map_err(|err| err.to_string())?;
+    FILTER
+        .get()
+        .ok_or_else(|| "logs are disabled".to_string())?
+        .reload(filter)
+        .map_err(|err| err.to_string())
+}
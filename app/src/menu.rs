@@ -0,0 +1,91 @@
+use std::{
+    io::{self, BufRead, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    cli::{self, Options},
+    i18n::tr,
+    world::{GameMode, Meta, Save, World},
+};
+
+/// Directory holding one directory per world
+pub const WORLDS_PATH: &str = "worlds";
+
+/// Opens the world given on the command line, or asks which one to play
+///
+/// A world that does not exist yet is created with the seed and game mode
+/// of the options, and their generation (or else the given one). Its metadata
+/// is written right away, so it is listed even if the game crashes before
+/// the first save.
+pub fn choose_world(options: &Options, generation: &str) -> io::Result<(Save, Meta)> {
+    let name = match &options.world {
+        Some(name) if !cli::valid_world_name(name) => {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, name.clone()));
+        }
+        Some(name) => name.clone(),
+        None => ask_world()?,
+    };
+    let save = Save::new(Path::new(WORLDS_PATH).join(&name));
+    let mut meta = save.load_meta().unwrap_or_else(|| {
+        tracing::info!("creating world {name}");
+        World::new_meta(
+            options.seed,
+            options.mode.unwrap_or(GameMode::Creative),
+            options.generation.as_deref().unwrap_or(generation),
+        )
+    });
+    meta.last_played = now();
+    save.save_meta(&meta)?;
+    Ok((save, meta))
+}
+
+fn ask_world() -> io::Result<String> {
+    let worlds = Save::list(WORLDS_PATH);
+    let now = now();
+    if !worlds.is_empty() {
+        println!("{}", tr!("menu.title"));
+    }
+    for (index, (name, meta)) in worlds.iter().enumerate() {
+        println!(
+            "{}",
+            tr!(
+                "menu.world",
+                index = index + 1,
+                name = name,
+                mode = tr!(&format!("mode.{}", meta.mode)),
+                seed = meta.seed,
+                days = now.saturating_sub(meta.last_played) / (24 * 60 * 60)
+            )
+        );
+    }
+    let mut stdin = io::stdin().lock();
+    loop {
+        print!("{} ", tr!("menu.prompt"));
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let line = line.trim();
+        // a number selects a listed world, anything else is a new name
+        if let Ok(index) = line.parse::<usize>() {
+            if let Some((name, _)) = index.checked_sub(1).and_then(|i| worlds.get(i)) {
+                return Ok(name.clone());
+            }
+        }
+        if cli::valid_world_name(line) {
+            return Ok(line.to_string());
+        }
+        println!("{}", tr!("menu.invalid_name", name = line));
+    }
+}
+
+// seconds since the unix epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
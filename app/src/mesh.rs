@@ -1,8 +1,13 @@
 use glium::{
-    implement_vertex, index::PrimitiveType, texture::SrgbTexture2dArray, uniform, Blend, DepthTest,
-    Display, Frame, IndexBuffer, Program, Surface, VertexBuffer,
+    implement_vertex,
+    index::PrimitiveType,
+    uniform,
+    uniforms::{UniformValue, Uniforms},
+    Blend, DepthTest, Display, IndexBuffer, Program, Surface, VertexBuffer,
 };
 
+use crate::atlas::{Atlas, Textures, MAX_SPRITES};
+
 #[derive(Debug, Clone, Copy)]
 pub struct ColoredMeshVertex {
     pub position: [f32; 3],
@@ -59,7 +64,7 @@ impl ColoredMesh {
         primitive: PrimitiveType,
     ) -> Self {
         Self {
-            vertices: VertexBuffer::new(display, &vertices).unwrap(),
+            vertices: VertexBuffer::new(display, vertices).unwrap(),
             indices: IndexBuffer::new(display, primitive, indices).unwrap(),
             point_size: None,
             line_width: None,
@@ -96,9 +101,42 @@ impl ColoredMesh {
 pub struct TexturedMeshVertex {
     pub position: [f32; 3],
     pub tex_pos: [f32; 3],
+    /// Shade of the face, from the direction it is turned to
     pub light: f32,
+    /// Whether the face is under the open sky (1.0) or under a roof (0.0),
+    /// the light of the sun only reaches it in the first case
+    pub sky_light: f32,
+    /// Light of torches and such (between 0.0 and 1.0)
+    pub block_light: f32,
+    /// Color the texture is multiplied by (white for most faces, the color
+    /// of the biome for grass, leaves and water)
+    pub tint: [f32; 3],
+}
+implement_vertex!(
+    TexturedMeshVertex,
+    position,
+    tex_pos,
+    light,
+    sky_light,
+    block_light,
+    tint
+);
+
+/// Light of the sun (or the moon) over the world, the same for every mesh
+/// of a frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sun {
+    pub color: [f32; 3],
+    /// From the darkest night (above 0.0) to noon (1.0)
+    pub intensity: f32,
+}
+
+/// What the textured meshes are drawn with
+#[derive(Clone, Copy)]
+pub struct TexturedUniforms<'a> {
+    pub textures: &'a Textures,
+    pub sun: Sun,
 }
-implement_vertex!(TexturedMeshVertex, position, tex_pos, light);
 
 pub struct TexturedMesh {
     vertices: VertexBuffer<TexturedMeshVertex>,
@@ -114,15 +152,24 @@ const TEXTURED_MESH_VERTEX_PROGRAM: &str = r#"
     in vec3 position;
     in vec3 tex_pos;
     in float light;
+    in float sky_light;
+    in float block_light;
+    in vec3 tint;
 
     out vec3 v_tex_pos;
     out float v_light;
+    out float v_sky_light;
+    out float v_block_light;
+    out vec3 v_tint;
 
     uniform mat4 projection;
 
     void main() {
         v_tex_pos = tex_pos;
         v_light = light;
+        v_sky_light = sky_light;
+        v_block_light = block_light;
+        v_tint = tint;
         gl_Position = projection * vec4(position, 1.0);
     }
 "#;
@@ -132,34 +179,75 @@ const TEXTURED_MESH_FRAGMENT_PROGRAM: &str = r#"
 
     in vec3 v_tex_pos;
     in float v_light;
+    in float v_sky_light;
+    in float v_block_light;
+    in vec3 v_tint;
     out vec4 color;
 
-    uniform sampler2DArray textures;
+    uniform vec3 sun_color;
+    uniform float sun;
+
+    SAMPLING
 
     void main() {
-        vec4 rgba = texture(textures, v_tex_pos);
-    
-        float rl = rgba.r * ((1.0 * v_light) * 0.8 + (0.4) * 0.2);
-        float gl = rgba.g * ((0.6 * v_light) * 0.8 + (0.8) * 0.2);
-        float bl = rgba.b * ((0.3 * v_light) * 0.8 + (1.0) * 0.2);
-
-        float rd = 1.0 - (1.0 - rl) * (1.0 - v_light);
-        float gd = 1.0 - (1.0 - gl) * (1.0 - v_light);
-        float bd = 1.0 - (1.0 - bl) * (1.0 - v_light);
-    
-        float rf = 0.7 * rl + 0.3 * rd;
-        float gf = 0.8 * gl + 0.2 * gd;
-        float bf = 0.9 * bl + 0.1 * bd;
-
-        color = vec4(
-            rf,
-            gf,
-            bf,
-            rgba.a
-        );
+        vec4 rgba = sample_sprite(v_tex_pos);
+        rgba.rgb *= v_tint;
+
+        // the faces turned away from the sun are darker, never black
+        float shade = 0.55 + 0.45 * v_light;
+        // under a roof, only the light bouncing in from outside
+        float exposure = mix(0.35, 1.0, v_sky_light);
+        vec3 sky = sun_color * (sun * exposure * shade);
+        // the warm light of torches does not change with the time of day
+        vec3 torch = vec3(1.0, 0.8, 0.5) * pow(v_block_light, 1.5);
+
+        color = vec4(rgba.rgb * max(sky, torch), rgba.a);
     }
 "#;
 
+/// Sampling of a layer of the texture array
+const ARRAY_SAMPLING: &str = r#"
+    uniform sampler2DArray textures;
+
+    vec4 sample_sprite(vec3 tex_pos) {
+        return texture(textures, tex_pos);
+    }
+"#;
+
+/// Sampling of the rectangle of the sprite in the atlas
+const ATLAS_SAMPLING: &str = r#"
+    uniform sampler2D atlas;
+    uniform vec4 rects[MAX_SPRITES];
+
+    vec4 sample_sprite(vec3 tex_pos) {
+        vec4 rect = rects[int(tex_pos.z + 0.5)];
+        return texture(atlas, rect.xy + clamp(tex_pos.xy, 0.0, 1.0) * rect.zw);
+    }
+"#;
+
+/// The uniforms of a textured mesh drawn with an atlas (an array of
+/// rectangles, which `uniform!` cannot name)
+struct AtlasUniforms<'a> {
+    projection: [[f32; 4]; 4],
+    atlas: &'a Atlas,
+    sun: Sun,
+}
+
+impl Uniforms for AtlasUniforms<'_> {
+    fn visit_values<'a, F: FnMut(&str, UniformValue<'a>)>(&'a self, mut visit: F) {
+        visit("projection", UniformValue::Mat4(self.projection));
+        visit("sun_color", UniformValue::Vec3(self.sun.color));
+        visit("sun", UniformValue::Float(self.sun.intensity));
+        visit(
+            "atlas",
+            UniformValue::SrgbTexture2d(&self.atlas.texture, Some(Atlas::sampler())),
+        );
+        for (i, &rect) in self.atlas.registry.iter().enumerate() {
+            visit(&format!("rects[{i}]"), UniformValue::Vec4(rect));
+        }
+    }
+}
+
 impl TexturedMesh {
     pub fn new(
         display: &Display,
@@ -168,18 +256,23 @@ impl TexturedMesh {
         primitive: PrimitiveType,
     ) -> Self {
         Self {
-            vertices: VertexBuffer::new(display, &vertices).unwrap(),
+            vertices: VertexBuffer::new(display, vertices).unwrap(),
             indices: IndexBuffer::new(display, primitive, indices).unwrap(),
             point_size: None,
             line_width: None,
             depth_test: DepthTest::IfLess,
         }
     }
-    pub fn program(display: &Display) -> Program {
+    /// The shader sampling the given kind of textures
+    pub fn program(display: &Display, textures: &Textures) -> Program {
+        let sampling = match textures {
+            Textures::Array(_) => ARRAY_SAMPLING.to_string(),
+            Textures::Atlas(_) => ATLAS_SAMPLING.replace("MAX_SPRITES", &MAX_SPRITES.to_string()),
+        };
         Program::from_source(
             display,
             TEXTURED_MESH_VERTEX_PROGRAM,
-            TEXTURED_MESH_FRAGMENT_PROGRAM,
+            &TEXTURED_MESH_FRAGMENT_PROGRAM.replace("SAMPLING", &sampling),
             None,
         )
         .unwrap()
@@ -190,17 +283,21 @@ impl TexturedMesh {
     // pub fn line_width(self, line_width: f32) -> Self {
     //     Self {line_width: Some(line_width), .. self }
     // }
-    // pub fn depth_test(self, depth_test: DepthTest) -> Self {
-    //     Self {depth_test, .. self}
-    // }
+    pub fn depth_test(self, depth_test: DepthTest) -> Self {
+        Self { depth_test, ..self }
+    }
+    /// Bytes of its buffers on the GPU
+    pub fn bytes(&self) -> usize {
+        self.vertices.get_size() + self.indices.get_size()
+    }
 }
 
 pub trait Drawable<T> {
-    fn draw(&self, program: &Program, target: &mut Frame, projection: [[f32; 4]; 4], uniform: T);
+    fn draw(&self, program: &Program, target: &mut impl Surface, projection: [[f32; 4]; 4], uniform: T);
 }
 
 impl Drawable<()> for ColoredMesh {
-    fn draw(&self, program: &Program, target: &mut Frame, projection: [[f32; 4]; 4], _uniform: ()) {
+    fn draw(&self, program: &Program, target: &mut impl Surface, projection: [[f32; 4]; 4], _uniform: ()) {
         let params = glium::DrawParameters {
             depth: glium::Depth {
                 test: self.depth_test,
@@ -227,14 +324,15 @@ impl Drawable<()> for ColoredMesh {
     }
 }
 
-impl Drawable<&SrgbTexture2dArray> for TexturedMesh {
+impl Drawable<TexturedUniforms<'_>> for TexturedMesh {
     fn draw(
         &self,
         program: &Program,
-        target: &mut Frame,
+        target: &mut impl Surface,
         projection: [[f32; 4]; 4],
-        uniform: &SrgbTexture2dArray,
+        uniform: TexturedUniforms,
     ) {
+        let Sun { color, intensity } = uniform.sun;
         let params = glium::DrawParameters {
             depth: glium::Depth {
                 test: self.depth_test,
@@ -248,17 +346,31 @@ impl Drawable<&SrgbTexture2dArray> for TexturedMesh {
             blend: Blend::alpha_blending(),
             ..Default::default()
         };
-        target
-            .draw(
+        match uniform.textures {
+            Textures::Array(textures) => target.draw(
                 &self.vertices,
                 &self.indices,
                 program,
                 &uniform! {
                     projection: projection,
-                    textures: uniform,
+                    textures: textures,
+                    sun_color: color,
+                    sun: intensity,
                 },
                 &params,
-            )
-            .unwrap();
+            ),
+            Textures::Atlas(atlas) => target.draw(
+                &self.vertices,
+                &self.indices,
+                program,
+                &AtlasUniforms {
+                    projection,
+                    atlas,
+                    sun: uniform.sun,
+                },
+                &params,
+            ),
+        }
+        .unwrap();
     }
 }
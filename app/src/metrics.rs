@@ -0,0 +1,158 @@
+//! Metrics of the world served over HTTP, in the text format of Prometheus
+//!
+//! Only `GET /metrics` is answered, one connection at a time (a scrape is
+//! small and rare).
+
+use std::{fmt::Write, sync::Arc, time::Duration};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time,
+};
+
+use crate::world::{Phase, World};
+
+/// A client slower than this to send its request is dropped
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The metrics, with their help and type
+pub fn render(world: &World) -> String {
+    let stats = world.stats();
+    let mut text = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, samples: &[(String, f64)]| {
+        writeln!(text, "# HELP artcraft_{name} {help}").unwrap();
+        writeln!(text, "# TYPE artcraft_{name} {kind}").unwrap();
+        for (labels, value) in samples {
+            writeln!(text, "artcraft_{name}{labels} {value}").unwrap();
+        }
+    };
+    // the world ticks in the simulation phase
+    let phases: Vec<(String, f64)> = Phase::ALL
+        .into_iter()
+        .map(|phase| {
+            let name = format!("{phase:?}").to_lowercase();
+            let average = stats.average(phase).as_secs_f64();
+            (format!("{{phase=\"{name}\"}}"), average)
+        })
+        .collect();
+    metric(
+        "phase_seconds",
+        "gauge",
+        "Average time spent in a phase per frame",
+        &phases,
+    );
+    metric(
+        "ticks_total",
+        "counter",
+        "Ticks of the world clock",
+        &[(String::new(), world.ticks() as f64)],
+    );
+    metric(
+        "loaded_chunks",
+        "gauge",
+        "Chunks in memory",
+        &[(String::new(), world.loaded_chunks() as f64)],
+    );
+    metric(
+        "entities",
+        "gauge",
+        "Entities in the loaded chunks",
+        &[(String::new(), world.entity_count() as f64)],
+    );
+    let memory = world.memory_usage();
+    metric(
+        "memory_bytes",
+        "gauge",
+        "Memory taken by the chunks, the meshes and the textures",
+        &[
+            ("{kind=\"chunks\"}".to_string(), memory.chunks as f64),
+            ("{kind=\"meshes\"}".to_string(), memory.meshes as f64),
+            ("{kind=\"textures\"}".to_string(), memory.textures as f64),
+        ],
+    );
+    if let Some(last_save) = stats.last_save {
+        metric(
+            "last_save_seconds",
+            "gauge",
+            "Time taken by the last save",
+            &[(String::new(), last_save.as_secs_f64())],
+        );
+    }
+    text
+}
+
+async fn respond(world: &World, mut stream: TcpStream) -> std::io::Result<()> {
+    // the request line is enough, the rest of the request is ignored
+    let mut buffer = [0; 1024];
+    let read = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let response = match request.lines().next() {
+        Some(line) if line.starts_with("GET /metrics ") => {
+            let body = render(world);
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+        }
+        _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Answers the scrapes until the listener fails
+pub async fn serve(world: Arc<World>, listener: TcpListener) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(error) => {
+                tracing::warn!(%error, "metrics endpoint closed");
+                return;
+            }
+        };
+        match time::timeout(TIMEOUT, respond(&world, stream)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => tracing::debug!(%error, "metrics not sent"),
+            Err(_) => tracing::debug!("metrics client too slow"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+
+    #[tokio::test]
+    async fn test_serve() {
+        let harness = Harness::empty("metrics");
+        let world = harness.world.clone();
+        let text = render(&world);
+        let ticks = world.ticks();
+        assert!(text.contains(&format!(
+            "# TYPE artcraft_ticks_total counter\nartcraft_ticks_total {ticks}\n"
+        )));
+        assert!(text.contains("artcraft_phase_seconds{phase=\"simulation\"} 0\n"));
+        assert!(text.contains("artcraft_loaded_chunks 0\n"));
+        assert!(text.contains("artcraft_memory_bytes{kind=\"meshes\"} 0\n"));
+        // no save yet
+        assert!(!text.contains("last_save"));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(serve(world, listener));
+        let get = |path: &'static str| async move {
+            let mut stream = TcpStream::connect(address).await.unwrap();
+            let request = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+        let response = get("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(&text));
+        assert!(get("/").await.starts_with("HTTP/1.1 404"));
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use mat::VectorTrait;
+
+use crate::world::{Biome, Precipitation, WorldSound};
+
+/// Looping background sounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Track {
+    Waves,
+    Birds,
+    Crickets,
+    Wind,
+    Cave,
+    Rain,
+}
+
+impl Track {
+    /// Picks the ambience matching where the player is, and when
+    ///
+    /// `surface` is the altitude of the terrain under the player.
+    pub fn choose(
+        biome: Biome,
+        surface: i32,
+        y: f32,
+        time_of_day: f32,
+        falling: Option<Precipitation>,
+    ) -> Self {
+        // the time of day goes from 0.0 (midnight) to 1.0 (next midnight)
+        let day = (0.25..0.75).contains(&time_of_day);
+        if y < surface as f32 - 4.0 {
+            Self::Cave
+        } else if falling == Some(Precipitation::Rain) {
+            Self::Rain
+        } else if y > 90.0 || falling == Some(Precipitation::Snow) {
+            Self::Wind
+        } else {
+            match biome {
+                Biome::Beach => Self::Waves,
+                Biome::Plains if day => Self::Birds,
+                Biome::Plains => Self::Crickets,
+                Biome::Mountains => Self::Wind,
+            }
+        }
+    }
+}
+
+/// Where the tracks are played
+pub trait AudioOutput {
+    /// Sets the gain of a looping track, a gain of 0.0 stops it
+    fn set_gain(&mut self, track: Track, gain: f32);
+    /// Plays a sound once, `offset` being its position from the listener
+    fn play(&mut self, sound: WorldSound, offset: [f32; 3], gain: f32);
+}
+
+/// Output used when no sound device is available
+pub struct Silent;
+
+impl AudioOutput for Silent {
+    fn set_gain(&mut self, _track: Track, _gain: f32) {}
+    fn play(&mut self, _sound: WorldSound, _offset: [f32; 3], _gain: f32) {}
+}
+
+/// Crossfades between ambience tracks
+///
+/// When the target track changes, the previous one fades out while the
+/// new one fades in, so moving along a biome border never cuts the sound.
+pub struct MusicManager<O: AudioOutput> {
+    output: O,
+    /// gain of all the tracks currently audible (before volume is applied)
+    gains: HashMap<Track, f32>,
+}
+
+impl<O: AudioOutput> MusicManager<O> {
+    /// Time (in seconds) for a track to fade in or out completely
+    const FADE: f32 = 4.0;
+    /// Distance (in blocks) from which a sound is not heard anymore
+    const HEARING: f32 = 32.0;
+
+    pub fn new(output: O) -> Self {
+        Self {
+            output,
+            gains: HashMap::new(),
+        }
+    }
+
+    /// Moves all tracks toward their target gain after `delta` seconds
+    ///
+    /// With no target (music disabled), everything fades out.
+    pub fn update(&mut self, target: Option<Track>, volume: f32, delta: f32) {
+        let step = delta / Self::FADE;
+        if let Some(target) = target {
+            self.gains.entry(target).or_insert(0.0);
+        }
+        for (&track, gain) in self.gains.iter_mut() {
+            *gain = if Some(track) == target {
+                (*gain + step).min(1.0)
+            } else {
+                (*gain - step).max(0.0)
+            };
+            self.output.set_gain(track, *gain * volume);
+        }
+        // forget about the tracks that completely faded out
+        self.gains.retain(|_, gain| *gain > 0.0);
+    }
+
+    /// Plays a sound of the world, fainter as it is farther from the
+    /// listener (an explosion is heard from farther away)
+    pub fn play(&mut self, sound: WorldSound, listener: [f32; 3], volume: f32) {
+        let offset = sound.pos().vector_sub(listener);
+        let hearing = match sound {
+            WorldSound::Explosion { radius, .. } => Self::HEARING * radius.max(1.0),
+            _ => Self::HEARING,
+        };
+        let gain = 1.0 - offset.vector_dot(offset).sqrt() / hearing;
+        if gain > 0.0 {
+            self.output.play(sound, offset, gain * volume);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // keeps the last gain set for each track
+    impl AudioOutput for HashMap<Track, f32> {
+        fn set_gain(&mut self, track: Track, gain: f32) {
+            self.insert(track, gain);
+        }
+        fn play(&mut self, _sound: WorldSound, _offset: [f32; 3], _gain: f32) {}
+    }
+
+    // keeps the sounds played, with their gain
+    impl AudioOutput for Vec<(WorldSound, f32)> {
+        fn set_gain(&mut self, _track: Track, _gain: f32) {}
+        fn play(&mut self, sound: WorldSound, _offset: [f32; 3], gain: f32) {
+            self.push((sound, gain));
+        }
+    }
+
+    #[test]
+    fn test_crossfade() {
+        let mut manager = MusicManager::new(HashMap::new());
+        for _ in 0..50 {
+            manager.update(Some(Track::Birds), 0.5, 0.1);
+        }
+        assert_eq!(manager.output[&Track::Birds], 0.5);
+
+        // half way through the crossfade, both are audible
+        for _ in 0..20 {
+            manager.update(Some(Track::Wind), 0.5, 0.1);
+        }
+        assert!((manager.output[&Track::Birds] - 0.25).abs() < 0.01);
+        assert!((manager.output[&Track::Wind] - 0.25).abs() < 0.01);
+
+        for _ in 0..50 {
+            manager.update(None, 0.5, 0.1);
+        }
+        assert_eq!(manager.output[&Track::Birds], 0.0);
+        assert_eq!(manager.output[&Track::Wind], 0.0);
+    }
+
+    #[test]
+    fn test_sound_distance() {
+        let mut manager = MusicManager::new(Vec::new());
+        let sound = |pos| WorldSound::Placed {
+            pos,
+            material: def::Block::Stone.into(),
+        };
+        manager.play(sound([16.0, 0.0, 0.0]), [0.0; 3], 0.5);
+        manager.play(sound([0.0, 40.0, 0.0]), [0.0; 3], 0.5);
+        // an explosion is heard from farther away
+        let explosion = WorldSound::Explosion {
+            pos: [0.0, 40.0, 0.0],
+            radius: 4.0,
+        };
+        manager.play(explosion, [0.0; 3], 0.5);
+        assert_eq!(manager.output.len(), 2);
+        assert_eq!(manager.output[0].1, 0.25);
+        assert_eq!(manager.output[1].0, explosion);
+    }
+}
@@ -0,0 +1,419 @@
+//! Content packs, adding blocks to the game
+//!
+//! A pack is a directory of [`PACKS_PATH`] holding a `pack.txt` manifest and
+//! the textures of its blocks. Each block of the manifest is a section:
+//!
+//! ```text
+//! [marble]
+//! label = Marble
+//! texture = marble.png
+//! light = 4
+//! drops = marble, sapling 0.1
+//! ```
+//!
+//! Besides `texture`, every key is optional: `solid`, `transparent`,
+//! `breakable` (true or false), `light` (0 to 15), `friction`, `bounciness`,
+//! `speed` (0.0 to 1.0) and `drops` (blocks, each with its chance, the block
+//! itself by default).
+//!
+//! The packs are read in the order of their directory names, which gives
+//! the ids of their blocks: renaming or removing a pack changes the blocks
+//! of the worlds using it.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use def::{
+    custom::{self, CustomBlock},
+    Block, Sprite,
+};
+use image::RgbaImage;
+
+use crate::atlas::MAX_SPRITES;
+
+/// Directory holding one directory per content pack
+pub const PACKS_PATH: &str = "packs";
+
+/// Each block of the packs has its sprite, after the ones of the game
+const MAX_BLOCKS: usize = MAX_SPRITES - Sprite::COUNT as usize;
+
+/// Names of the blocks of the game, which the packs can't take
+const BUILTIN: [(&str, Block); 26] = [
+    ("stone", Block::Stone),
+    ("dirt", Block::Dirt),
+    ("grass", Block::Grass),
+    ("sand", Block::Sand),
+    ("water", Block::Water(0)),
+    ("glass", Block::Glass),
+    ("brick", Block::Brick),
+    ("trunk", Block::Trunk),
+    ("leaves", Block::Leaves),
+    ("tnt", Block::Tnt),
+    ("door", Block::Door(0)),
+    ("trapdoor", Block::Trapdoor(0)),
+    ("torch", Block::Torch(0)),
+    ("sapling", Block::Sapling(0)),
+    ("bedrock", Block::Bedrock),
+    ("ice", Block::Ice),
+    ("slime", Block::Slime),
+    ("chest", Block::Chest),
+    ("wire", Block::Wire(0)),
+    ("lever", Block::Lever(0)),
+    ("lamp", Block::Lamp(0)),
+    ("piston", Block::Piston(0)),
+    ("farmland", Block::Farmland),
+    ("crop", Block::Crop(0)),
+    ("soul_sand", Block::SoulSand),
+    ("glider", Block::Glider),
+];
+
+/// A block as written in a manifest
+#[derive(Debug, Clone, PartialEq)]
+struct Definition {
+    name: String,
+    label: String,
+    /// Path of the texture, from the directory of the pack
+    texture: PathBuf,
+    solid: bool,
+    transparent: bool,
+    light: u8,
+    friction: f32,
+    bounciness: f32,
+    speed: f32,
+    breakable: bool,
+    /// Names of the blocks left, each with its chance
+    drops: Vec<(String, f32)>,
+}
+
+impl Definition {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            label: name.to_string(),
+            texture: PathBuf::new(),
+            solid: true,
+            transparent: false,
+            light: 0,
+            friction: 1.0,
+            bounciness: 0.0,
+            speed: 1.0,
+            breakable: true,
+            drops: vec![(name.to_string(), 1.0)],
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Option<()> {
+        // a part of the movement, the checks of the moves rely on it
+        let unit = |value: &f32| (0.0..=1.0).contains(value);
+        match key {
+            "label" => self.label = value.to_string(),
+            "texture" => self.texture = value.into(),
+            "solid" => self.solid = value.parse().ok()?,
+            "transparent" => self.transparent = value.parse().ok()?,
+            "light" => self.light = value.parse().ok().filter(|&light| light <= 15)?,
+            "friction" => self.friction = value.parse().ok().filter(unit)?,
+            "bounciness" => self.bounciness = value.parse().ok().filter(unit)?,
+            "speed" => self.speed = value.parse().ok().filter(unit)?,
+            "breakable" => self.breakable = value.parse().ok()?,
+            "drops" => {
+                self.drops = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|drop| !drop.is_empty())
+                    .map(|drop| match drop.split_once(char::is_whitespace) {
+                        Some((name, chance)) => {
+                            Some((name.to_string(), chance.trim().parse().ok()?))
+                        }
+                        None => Some((drop.to_string(), 1.0)),
+                    })
+                    .collect::<Option<_>>()?
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+/// Same as the names of the commands, so the blocks can be used there
+fn valid_name(name: &str) -> bool {
+    name.len() >= 3
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// The blocks of a manifest, or the number of its first bad line (the
+/// header of a block without texture)
+fn parse_manifest(content: &str) -> Result<Vec<Definition>, usize> {
+    let mut definitions: Vec<Definition> = Vec::new();
+    let mut header = 0;
+    let textured = |definitions: &[Definition], header| match definitions.last() {
+        Some(definition) if definition.texture.as_os_str().is_empty() => Err(header),
+        _ => Ok(()),
+    };
+    for (number, line) in (1..).zip(content.lines().map(str::trim)) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let name = name.trim();
+            let taken = BUILTIN.iter().any(|&(builtin, _)| builtin == name)
+                || definitions.iter().any(|definition| definition.name == name);
+            if !valid_name(name) || taken {
+                return Err(number);
+            }
+            textured(&definitions, header)?;
+            header = number;
+            definitions.push(Definition::new(name));
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or(number)?;
+        definitions
+            .last_mut()
+            .and_then(|definition| definition.set(key.trim(), value.trim()))
+            .ok_or(number)?;
+    }
+    textured(&definitions, header)?;
+    Ok(definitions)
+}
+
+/// Average color of a texture, ignoring its transparent pixels
+fn average_color(texture: &RgbaImage) -> [f32; 3] {
+    let (sum, count) =
+        texture
+            .pixels()
+            .filter(|pixel| pixel[3] > 0)
+            .fold(([0.0; 3], 0), |(sum, count), pixel| {
+                let [r, g, b] = sum;
+                (
+                    [
+                        r + pixel[0] as f32,
+                        g + pixel[1] as f32,
+                        b + pixel[2] as f32,
+                    ],
+                    count + 1,
+                )
+            });
+    match count {
+        0 => [0.5; 3],
+        _ => sum.map(|channel| channel / 255.0 / count as f32),
+    }
+}
+
+/// The blocks of a pack with their texture
+fn read_pack(dir: &Path) -> io::Result<Vec<(Definition, RgbaImage)>> {
+    let content = fs::read_to_string(dir.join("pack.txt"))?;
+    let definitions = parse_manifest(&content).map_err(|line| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("bad manifest line {line}"),
+        )
+    })?;
+    definitions
+        .into_iter()
+        .map(|definition| {
+            let texture = image::open(dir.join(&definition.texture))
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?
+                .to_rgba8();
+            Ok((definition, texture))
+        })
+        .collect()
+}
+
+/// The blocks of every pack and their textures, in the order of their id
+///
+/// A pack which can't be read is skipped, as well as the blocks beyond the
+/// room left in the atlas and the drops naming unknown blocks.
+fn read(dir: &Path) -> (Vec<CustomBlock>, Vec<RgbaImage>) {
+    let mut packs: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    packs.sort();
+    let mut loaded: Vec<(Definition, RgbaImage)> = Vec::new();
+    for pack in packs {
+        match read_pack(&pack) {
+            Ok(blocks) => {
+                for (definition, texture) in blocks {
+                    if loaded
+                        .iter()
+                        .any(|(other, _)| other.name == definition.name)
+                    {
+                        tracing::warn!(pack = %pack.display(), name = definition.name, "block already added by a pack");
+                    } else {
+                        loaded.push((definition, texture));
+                    }
+                }
+                tracing::info!(pack = %pack.display(), "content pack loaded");
+            }
+            Err(error) => tracing::warn!(pack = %pack.display(), %error, "content pack skipped"),
+        }
+    }
+    if loaded.len() > MAX_BLOCKS {
+        tracing::warn!(
+            blocks = loaded.len(),
+            max = MAX_BLOCKS,
+            "too many blocks in the content packs"
+        );
+        loaded.truncate(MAX_BLOCKS);
+    }
+
+    let names: Vec<String> = loaded.iter().map(|(d, _)| d.name.clone()).collect();
+    let find = |name: &str| {
+        BUILTIN
+            .iter()
+            .find(|&&(builtin, _)| builtin == name)
+            .map(|&(_, block)| block)
+            .or_else(|| {
+                let id = names.iter().position(|other| other == name)?;
+                Some(Block::Custom(id as u8))
+            })
+    };
+    loaded
+        .into_iter()
+        .map(|(definition, texture)| {
+            let drops = definition
+                .drops
+                .iter()
+                .filter_map(|(name, chance)| match find(name) {
+                    Some(block) => Some((block, *chance)),
+                    None => {
+                        tracing::warn!(block = definition.name, drop = name, "unknown drop");
+                        None
+                    }
+                })
+                .collect();
+            let block = CustomBlock {
+                color: average_color(&texture),
+                name: definition.name,
+                label: definition.label,
+                solid: definition.solid,
+                transparent: definition.transparent,
+                emission: definition.light,
+                friction: definition.friction,
+                bounciness: definition.bounciness,
+                speed: definition.speed,
+                breakable: definition.breakable,
+                drops,
+            };
+            (block, texture)
+        })
+        .unzip()
+}
+
+/// Every block the player can hold, with its name in the commands: the ones
+/// of the game, then the ones of the packs registered
+pub fn blocks() -> impl Iterator<Item = (&'static str, Block)> {
+    let custom = custom::blocks()
+        .zip(0..)
+        .map(|(block, id)| (custom::get(id).name.as_str(), block));
+    BUILTIN.iter().copied().chain(custom)
+}
+
+/// Registers the blocks of the packs of the directory, returns their
+/// textures (to be drawn after the sprites of the game)
+pub fn load(dir: impl AsRef<Path>) -> Vec<RgbaImage> {
+    let (blocks, textures) = read(dir.as_ref());
+    if !blocks.is_empty() {
+        tracing::info!(blocks = blocks.len(), "custom blocks registered");
+    }
+    custom::register(blocks);
+    textures
+}
+
+#[cfg(test)]
+mod test {
+    use image::Rgba;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = "
+            # polished stones
+            [marble]
+            label = Polished marble
+            texture = marble.png
+            light = 4
+            drops = marble, sapling 0.1
+
+            [jelly]
+            texture = textures/jelly.png
+            solid = false
+            transparent = true
+            bounciness = 0.5
+            speed = 0.6
+        ";
+        let definitions = parse_manifest(manifest).unwrap();
+        assert_eq!(definitions.len(), 2);
+        let marble = &definitions[0];
+        assert_eq!(marble.label, "Polished marble");
+        assert_eq!(marble.light, 4);
+        assert_eq!(
+            marble.drops,
+            [("marble".to_string(), 1.0), ("sapling".to_string(), 0.1)]
+        );
+        let jelly = &definitions[1];
+        // it drops itself by default
+        assert_eq!(jelly.drops, [("jelly".to_string(), 1.0)]);
+        assert_eq!(jelly.texture, Path::new("textures/jelly.png"));
+        assert!(!jelly.solid && jelly.transparent && jelly.breakable);
+        assert_eq!((jelly.bounciness, jelly.speed), (0.5, 0.6));
+
+        // the lines are counted from 1
+        assert_eq!(parse_manifest("texture = stone.png"), Err(1));
+        assert_eq!(parse_manifest("[stone]\ntexture = stone.png"), Err(1));
+        assert_eq!(parse_manifest("[Marble]"), Err(1));
+        assert_eq!(parse_manifest("[marble]\nlight = 16"), Err(2));
+        assert_eq!(parse_manifest("[marble]\nspeed = 2"), Err(2));
+        assert_eq!(parse_manifest("[marble]\ncolor = red"), Err(2));
+        assert_eq!(parse_manifest("[marble]\ndrops = sand often"), Err(2));
+        assert_eq!(parse_manifest("\n[marble]\nlabel = Marble"), Err(2));
+        assert_eq!(
+            parse_manifest("[marble]\n[granite]\ntexture = a.png"),
+            Err(1)
+        );
+        assert_eq!(parse_manifest(""), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_read() {
+        let dir = std::env::temp_dir().join("artcraft-test-packs");
+        let pack = dir.join("stones");
+        fs::create_dir_all(&pack).unwrap();
+        fs::write(
+            pack.join("pack.txt"),
+            "[marble]\ntexture = marble.png\ndrops = granite, sand 0.5, gold\n\
+             [granite]\ntexture = granite.png\n",
+        )
+        .unwrap();
+        RgbaImage::from_pixel(4, 4, Rgba([255, 0, 51, 255]))
+            .save(pack.join("marble.png"))
+            .unwrap();
+        RgbaImage::from_pixel(8, 8, Rgba([0, 0, 0, 255]))
+            .save(pack.join("granite.png"))
+            .unwrap();
+        // a pack missing its texture is skipped
+        fs::create_dir_all(dir.join("broken")).unwrap();
+        fs::write(dir.join("broken/pack.txt"), "[ruby]\ntexture = ruby.png").unwrap();
+
+        let (blocks, textures) = read(&dir);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(textures[1].dimensions(), (8, 8));
+        assert_eq!(blocks[0].color, [1.0, 0.0, 0.2]);
+        // the unknown drop is left out
+        assert_eq!(
+            blocks[0].drops,
+            [(Block::Custom(1), 1.0), (Block::Sand, 0.5)]
+        );
+        assert_eq!(blocks[1].drops, [(Block::Custom(1), 1.0)]);
+        assert_eq!(read(&dir.join("nothing")).0, Vec::new());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,258 @@
+//! Plugins loaded from dynamic libraries (see [`def::plugin`])
+//!
+//! Every library of [`PLUGINS_PATH`] is loaded at startup. A plugin is only
+//! given the capabilities granted to it in the config, it is told about the
+//! events of the world either way.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use def::{
+    plugin::{Capability, Host, Plugin, PluginError, WorldEvent, API_VERSION},
+    Block, BlockCoords,
+};
+use libloading::{Library, Symbol};
+
+use crate::world::{Event, World};
+
+/// Directory holding the libraries of the plugins
+pub const PLUGINS_PATH: &str = "plugins";
+
+struct Loaded {
+    // dropped before the library holding its code
+    plugin: Box<dyn Plugin>,
+    capabilities: BTreeSet<Capability>,
+    _library: Option<Library>,
+}
+
+/// The world, through the capabilities of a plugin
+struct Scoped<'a> {
+    world: &'a World,
+    capabilities: &'a BTreeSet<Capability>,
+}
+
+impl Scoped<'_> {
+    fn check(&self, capability: Capability) -> Result<(), PluginError> {
+        match self.capabilities.contains(&capability) {
+            true => Ok(()),
+            false => Err(PluginError::Denied(capability)),
+        }
+    }
+}
+
+impl Host for Scoped<'_> {
+    fn block(&self, coords: BlockCoords) -> Result<Option<Block>, PluginError> {
+        self.check(Capability::ReadBlocks)?;
+        self.world.get_block(coords).ok_or_else(|| {
+            PluginError::Failed(crate::CmdError::ChunkNotLoaded(coords.0).to_string())
+        })
+    }
+
+    fn set_block(&self, coords: BlockCoords, block: Option<Block>) -> Result<(), PluginError> {
+        self.check(Capability::EditBlocks)?;
+        match block {
+            Some(block) => self.world.place_block(coords, block),
+            None => self.world.remove_block(coords).map(|_| ()),
+        }
+        .map_err(|err| PluginError::Failed(err.to_string()))
+    }
+}
+
+/// What the plugins are told about an event (some are kept to the game)
+fn world_event(event: &Event) -> Option<WorldEvent> {
+    match *event {
+        Event::BlockBroken { broken } => Some(WorldEvent::BlockBroken(broken)),
+        Event::PlayerMoved { to, .. } => Some(WorldEvent::PlayerMoved(to)),
+        Event::Explosion { pos, radius } => Some(WorldEvent::Explosion { pos, radius }),
+        Event::Impact { .. }
+        | Event::Achieved(_)
+        | Event::ChestOpened(_)
+        | Event::PlayerHurt { .. } => None,
+    }
+}
+
+/// Loads the plugin of a library, checking it was built for this game
+fn open(path: &Path) -> Result<(Library, Box<dyn Plugin>), String> {
+    // SAFETY: a plugin is trusted like the game itself (it is installed by
+    // the player), its version is checked before calling into it and the
+    // library outlives the plugin (see `Loaded`)
+    unsafe {
+        let library = Library::new(path).map_err(|err| err.to_string())?;
+        let version: Symbol<*const u32> = library
+            .get(b"ARTCRAFT_API_VERSION")
+            .map_err(|err| err.to_string())?;
+        if **version != API_VERSION {
+            return Err(format!("built for version {}", **version));
+        }
+        let constructor: Symbol<fn() -> Box<dyn Plugin>> = library
+            .get(b"artcraft_plugin")
+            .map_err(|err| err.to_string())?;
+        let plugin = constructor();
+        Ok((library, plugin))
+    }
+}
+
+/// The plugins running, with what they are allowed to do
+#[derive(Default)]
+pub struct Plugins {
+    loaded: Vec<Loaded>,
+}
+
+impl Plugins {
+    /// Loads the libraries of the directory, a library which can't be loaded
+    /// is skipped
+    pub fn load(dir: impl AsRef<Path>, grants: &BTreeMap<String, BTreeSet<Capability>>) -> Self {
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension() == Some(std::env::consts::DLL_EXTENSION.as_ref()))
+            .collect();
+        paths.sort();
+        let mut plugins = Self::default();
+        for path in paths {
+            match open(&path) {
+                Ok((library, plugin)) => {
+                    let capabilities = grants.get(plugin.name()).cloned().unwrap_or_default();
+                    plugins.add(plugin, capabilities, Some(library));
+                }
+                Err(error) => tracing::warn!(path = %path.display(), error, "plugin skipped"),
+            }
+        }
+        plugins
+    }
+
+    fn add(
+        &mut self,
+        plugin: Box<dyn Plugin>,
+        capabilities: BTreeSet<Capability>,
+        library: Option<Library>,
+    ) {
+        tracing::info!(name = plugin.name(), ?capabilities, "plugin loaded");
+        self.loaded.push(Loaded {
+            plugin,
+            capabilities,
+            _library: library,
+        });
+    }
+
+    /// Tells every plugin about an event of the world
+    pub fn dispatch(&mut self, world: &World, event: &Event) {
+        let Some(event) = world_event(event) else {
+            return;
+        };
+        for loaded in &mut self.loaded {
+            let host = Scoped {
+                world,
+                capabilities: &loaded.capabilities,
+            };
+            loaded.plugin.on_event(&host, &event);
+        }
+    }
+
+    /// Runs a command of a plugin allowed to have commands, None if none of
+    /// them has it
+    pub fn command(&mut self, world: &World, command: &str) -> Option<Result<String, PluginError>> {
+        let (name, args) = command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, ""));
+        let loaded = self.loaded.iter_mut().find(|loaded| {
+            loaded.capabilities.contains(&Capability::Commands)
+                && loaded
+                    .plugin
+                    .commands()
+                    .iter()
+                    .any(|command| command == name)
+        })?;
+        let host = Scoped {
+            world,
+            capabilities: &loaded.capabilities,
+        };
+        Some(loaded.plugin.on_command(&host, name, args.trim()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+
+    /// Counts the broken blocks, and tells the block at the origin
+    #[derive(Default)]
+    struct Counter {
+        broken: usize,
+    }
+
+    impl Plugin for Counter {
+        fn name(&self) -> &str {
+            "counter"
+        }
+
+        fn commands(&self) -> Vec<String> {
+            vec!["broken".to_string(), "below".to_string()]
+        }
+
+        fn on_event(&mut self, _host: &dyn Host, event: &WorldEvent) {
+            if let WorldEvent::BlockBroken(_) = event {
+                self.broken += 1;
+            }
+        }
+
+        fn on_command(
+            &mut self,
+            host: &dyn Host,
+            name: &str,
+            args: &str,
+        ) -> Result<String, PluginError> {
+            match name {
+                "broken" => Ok(format!("{} {args}", self.broken)),
+                _ => {
+                    let coords = BlockCoords::try_from([0, 0, 0]).unwrap();
+                    Ok(format!("{:?}", host.block(coords)?))
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_plugins() {
+        let harness = Harness::empty("plugins");
+        let world = &harness.world;
+        let mut plugins = Plugins::default();
+        plugins.add(
+            Box::<Counter>::default(),
+            BTreeSet::from([Capability::Commands]),
+            None,
+        );
+        plugins.dispatch(
+            world,
+            &Event::BlockBroken {
+                broken: Block::Stone,
+            },
+        );
+        plugins.dispatch(world, &Event::Achieved(crate::world::Achievement::Heights));
+        assert_eq!(
+            plugins.command(world, "broken  blocks"),
+            Some(Ok("1 blocks".to_string()))
+        );
+        // reading the world was not granted
+        assert_eq!(
+            plugins.command(world, "below"),
+            Some(Err(PluginError::Denied(Capability::ReadBlocks)))
+        );
+        assert_eq!(plugins.command(world, "fly true"), None);
+
+        // neither were the commands
+        let mut plugins = Plugins::default();
+        plugins.add(Box::<Counter>::default(), BTreeSet::new(), None);
+        assert_eq!(plugins.command(world, "broken"), None);
+        assert_eq!(
+            Plugins::load("no-plugins", &BTreeMap::new()).loaded.len(),
+            0
+        );
+    }
+}
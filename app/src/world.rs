@@ -1,16 +1,110 @@
-use std::{collections::HashMap, sync::RwLock};
+use std::{
+    collections::HashMap,
+    io,
+    path::Path,
+    sync::{
+        atomic::{AtomicI32, AtomicU64, Ordering},
+        Mutex, RwLock, TryLockError,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use arrayvec::ArrayVec;
-use dashmap::DashMap;
-use def::{Block, BlockCoords, BlockIndex, Boxel, ChunkCoords, Direction};
+use dashmap::{try_result::TryResult, DashMap, DashSet};
+use def::{state, Block, BlockCoords, BlockIndex, Boxel, ChunkCoords, Direction};
 use mat::VectorTrait;
 
+mod achievements;
+pub use achievements::{Achievement, Progress};
+mod audit;
+pub use audit::Audit;
+mod chest;
+pub use chest::{Slots, Stack, CHEST_SLOTS};
+mod entity;
+use entity::Step;
+pub use entity::{Entity, EntityId, EntityKind, Event};
 mod generator;
-use generator::Generator;
-use tokio::sync::mpsc::Sender;
+mod glider;
+pub use generator::{Biome, Generator, DEFAULT_STAGES};
+pub use glider::glide;
+mod interact;
+pub use interact::{on_interact, tills};
+mod light;
+use light::{LightChunk, LightQueue, LIGHT_BUDGET};
+mod location;
+pub use location::{resolve, tp_command, Coord, Facing};
+mod physics;
+pub use physics::Physics;
+mod random_tick;
+mod raycast;
+pub use raycast::RayHit;
+mod riding;
+pub use random_tick::Rng;
+mod piston;
+mod signal;
+mod structure;
+pub use structure::{Structure, MAX_COPY_VOLUME};
+mod combat;
+mod compress;
+use compress::CompressedChunk;
+mod count;
+pub use count::RegionCount;
+mod edit;
+pub use edit::Edit;
+mod history;
+mod memory;
+pub use history::{Author, ChunkDiff};
+pub use memory::MemoryUsage;
+mod sound;
+pub use sound::WorldSound;
+mod spawning;
+use spawning::SPAWN_PERIOD;
+mod stamina;
+pub use stamina::{Exertion, MAX_STAMINA};
+mod stats;
+pub use stats::{Phase, Stats};
+mod validate;
+pub use validate::REACH;
+mod weather;
+use weather::WeatherState;
+pub use weather::{Precipitation, Weather};
+mod save;
+pub use save::{decode_chunk, encode_chunk, Meta, Save};
+use tokio::sync::{broadcast, mpsc::Sender, watch};
 
-use crate::AristideCmd;
+use crate::config::{Config, RenderDistance, CONFIG_PATH};
 use crate::{camera::Camera, Cmd};
+use crate::{AristideCmd, CmdError, CmdOutput};
+
+/// What the player is allowed to do in a world
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    /// The player can fly
+    Creative,
+    /// The player walks
+    Survival,
+}
+
+impl std::str::FromStr for GameMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "creative" => Ok(Self::Creative),
+            "survival" => Ok(Self::Survival),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for GameMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Creative => write!(f, "creative"),
+            Self::Survival => write!(f, "survival"),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct Player {
@@ -19,11 +113,34 @@ pub struct Player {
     pub gravity: f32,
     pub on_ground: bool,
     pub block_placing: Block,
+    /// Spent by sprinting and jumping, restored by resting (in survival)
+    pub stamina: f32,
+    /// The boat the player sits in, if any
+    pub riding: Option<EntityId>,
+    /// Falling with its glider open (see [`Player::update_glide`])
+    pub gliding: bool,
+}
+
+impl Player {
+    /// Height of the eyes (the camera) above the feet
+    pub const EYES: f32 = 1.6;
+
+    /// The box colliding with the terrain, the eyes being at `pos`
+    pub fn hit_box(pos: [f32; 3]) -> Boxel {
+        Boxel::new([0.6, 1.8, 0.6], [0.3, 1.6, 0.3], pos)
+    }
+
+    /// The block the feet are in (maybe above or below the world)
+    pub fn feet(&self) -> [i32; 3] {
+        let [x, y, z] = self.camera.pos;
+        [x, y - Self::EYES, z].map(|v| v.floor() as i32)
+    }
 }
 
 /// State of a chunk
 ///
-/// First, the chunk data is loaded (generated), next
+/// First, its save is read (the chunk is stored), then the
+/// chunk data is loaded (generated if it was never saved), next
 /// its mesh is built. Different stage are requiered as
 /// building the mesh requires to know neighbours chunk data
 /// which would themself require their neighbour to be
@@ -32,38 +149,60 @@ pub struct Player {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ChunkStage {
     None,
+    Stored,
     Loaded,
     Meshed,
 }
-impl ChunkStage {
-    fn previous(self) -> Option<Self> {
-        match self {
-            Self::None => None,
-            Self::Loaded => Some(Self::None),
-            Self::Meshed => Some(Self::Loaded),
-        }
-    }
-}
 
 pub enum ChunkState {
     Loaded(BlocksChunk),
     Meshed(BlocksChunk, FacesChunk),
+    /// Loaded, but far from the player and packed to save memory
+    Compressed(CompressedChunk),
 }
 impl ChunkState {
     fn get_block(&self, bi: BlockIndex) -> Option<Block> {
         match self {
             ChunkState::Loaded(blocks_chunk) => blocks_chunk.get(&bi).copied(),
             ChunkState::Meshed(blocks_chunk, _) => blocks_chunk.get(&bi).copied(),
+            ChunkState::Compressed(compressed) => compressed.get(bi),
         }
     }
     fn get_stage(&self) -> ChunkStage {
         match self {
             ChunkState::Loaded(_) => ChunkStage::Loaded,
             ChunkState::Meshed(_, _) => ChunkStage::Meshed,
+            ChunkState::Compressed(_) => ChunkStage::Loaded,
         }
     }
 }
 
+/// Farthest an anchor (a named chunk loader) reaches, in chunks
+pub const MAX_ANCHOR_RANGE: i32 = 4;
+
+/// Keeps the chunks around a center loaded (and meshed)
+///
+/// There is one per player, and the named ones force chunks to stay
+/// loaded while nobody is around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkLoader {
+    pub center: ChunkCoords,
+    /// Chunks in this range are loaded
+    pub range: i32,
+}
+
+impl ChunkLoader {
+    pub fn loads(self, cc: ChunkCoords) -> bool {
+        cc.in_range(self.center, self.range)
+    }
+
+    /// Chunks stay loaded until twice as far, so moving back and forth
+    /// around a border does not load them again and again
+    pub fn keeps(self, cc: ChunkCoords) -> bool {
+        cc.in_range(self.center, self.range * 2)
+    }
+}
+
 pub struct World {
     /// send command to the supervisor (Beatrice)
     pub sender_cmd: Sender<Cmd>,
@@ -72,52 +211,479 @@ pub struct World {
     // a concurrent hashmap is used here (dashmap), allowing
     // different threads to read and update the chunks.
     pub chunks: DashMap<ChunkCoords, ChunkState>,
+    /// block light, computed when chunks are loaded and kept up to date
+    lights: DashMap<ChunkCoords, LightChunk>,
+    /// light changes propagated a bit every tick
+    light_queue: Mutex<LightQueue>,
+    /// blocks next to an edit, given a block update at the next tick
+    updates: Mutex<Vec<BlockCoords>>,
     player: RwLock<Player>,
+    /// horizontal distance of the last accepted move of the player (a slide
+    /// on ice or after a glide goes faster than a sprint, and slows down)
+    last_move: Mutex<f32>,
     /// terrain generator (holds perlin noise configuration)
     pub generator: Generator,
+    /// user settings
+    pub config: RwLock<Config>,
+    /// number of simulation ticks since the world was created (one per frame)
+    ticks: AtomicU64,
+    /// where the world is persisted
+    save: Save,
+    /// seed of the terrain generator
+    seed: u32,
+    mode: GameMode,
+    /// stages of the terrain generator, saved with the world
+    generation: String,
+    /// chunks whose save was read, with their blocks if they were saved
+    /// (the ones never saved are generated when loaded)
+    stored: DashMap<ChunkCoords, Option<BlocksChunk>>,
+    /// recent edits of each chunk, to roll them back
+    edits: DashMap<ChunkCoords, Vec<ChunkDiff>>,
+    /// content of the chests holding blocks, by coordinates
+    chests: DashMap<[i32; 3], Slots>,
+    /// surface of the chunks seen by the player, for the map
+    explored: DashMap<ChunkCoords, SurfaceChunk>,
+    /// chunk loaders not following a player, by name
+    loaders: RwLock<HashMap<String, ChunkLoader>>,
+    /// parts of generated structures waiting for their chunk to be generated
+    pending: DashMap<ChunkCoords, BlocksChunk>,
+    /// chunks edited since they were last saved
+    dirty: DashSet<ChunkCoords>,
+    /// set to true when the game is closing, so the threads stop
+    shutdown: watch::Sender<bool>,
+    entities: Mutex<HashMap<EntityId, Entity>>,
+    /// entities of the far chunks, frozen until their chunk is meshed again
+    parked: DashMap<ChunkCoords, Vec<Entity>>,
+    /// chunks with entities in the save (their file is removed once empty)
+    entity_chunks: DashSet<ChunkCoords>,
+    next_entity: AtomicU64,
+    /// every thread can subscribe to what happens in the world
+    events: broadcast::Sender<Event>,
+    /// tracked from the events, saved with the world
+    achievements: Mutex<Progress>,
+    /// the sounds go to the audio, if any
+    sounds: broadcast::Sender<WorldSound>,
+    /// picks the voxels getting a random tick
+    rng: Mutex<Rng>,
+    weather: RwLock<WeatherState>,
+    /// time spent in each phase of the frames
+    stats: Mutex<Stats>,
+    /// chunks loaded around the player (tuned by Aristide, if asked to)
+    render_distance: AtomicI32,
+    /// bumped each time a section of a chunk is to be rendered again, so
+    /// Aristide skips the requests older than the mesh it already built
+    mesh_generations: DashMap<ChunkCoords, [u64; SECTIONS]>,
+    /// memory taken by the chunks, the meshes and the textures
+    memory: Mutex<MemoryUsage>,
+}
+
+/// A face is hidden when both the block and its neighbour fill their voxel
+///
+/// Water is only seen from the outside: its faces against water or against
+/// a full block are hidden.
+fn face_hidden(block: Block, neighbour: Option<Block>) -> bool {
+    match (block, neighbour) {
+        (_, None) => false,
+        (Block::Water(_), Some(neighbour)) => {
+            matches!(neighbour, Block::Water(_)) || neighbour.is_full()
+        }
+        (block, Some(neighbour)) => block.is_full() && neighbour.is_full(),
+    }
+}
+
+/// Where the other half of a door is
+fn other_half(bc: BlockCoords, block: Block) -> Option<BlockCoords> {
+    match block {
+        Block::Door(state) if state & state::UPPER != 0 => bc.step(Direction::Down),
+        Block::Door(_) => bc.step(Direction::Up),
+        Block::Piston(state) if state & state::ON != 0 => bc.step(block.facing()),
+        Block::PistonHead(_) => bc.step(block.facing().oposit()),
+        _ => None,
+    }
+}
+
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
 }
 
 pub type BlocksChunk = HashMap<BlockIndex, Block>;
 pub type FacesChunk = HashMap<(BlockIndex, Direction), Block>;
+/// Highest block of every column of a chunk with its altitude, by x then z
+pub type SurfaceChunk = [[Option<(i32, Block)>; 16]; 16];
+
+/// Chunks are meshed by vertical sections of 16 blocks, so an edit only
+/// builds again the sections around it
+pub const SECTION_HEIGHT: i32 = 16;
+pub const SECTIONS: usize = 16;
+
+/// Some sections of a chunk, a bit per section from the bottom
+pub type Sections = u16;
+pub const ALL_SECTIONS: Sections = Sections::MAX;
+
+/// The section of the voxel, and the ones of the voxels above and below it
+/// (their faces touch the voxel)
+pub fn sections_around(BlockCoords(_, bi): BlockCoords) -> Sections {
+    let [_, y, _]: [i32; 3] = bi.into();
+    [y - 1, y, y + 1]
+        .into_iter()
+        .filter(|y| (0..SECTION_HEIGHT * SECTIONS as i32).contains(y))
+        .fold(0, |sections, y| sections | 1 << (y / SECTION_HEIGHT))
+}
+
+/// The chunks next to a voxel on the border of its chunk
+///
+/// Their faces against the voxel are hidden and lit by it, so their meshes
+/// change with it.
+pub fn across_border(BlockCoords(cc, bi): BlockCoords) -> impl Iterator<Item = ChunkCoords> {
+    let [x, _, z]: [i32; 3] = bi.into();
+    [
+        (x == 0, -1, 0),
+        (x == 15, 1, 0),
+        (z == 0, 0, -1),
+        (z == 15, 0, 1),
+    ]
+    .into_iter()
+    .filter(|&(border, _, _)| border)
+    .map(move |(_, dx, dz)| ChunkCoords {
+        x: cc.x + dx,
+        z: cc.z + dz,
+    })
+}
+
+/// Number of ticks in a day (10 minutes at 60 frames per seconds)
+pub const DAY_LENGTH: u64 = 36_000;
 
 impl World {
-    /// create a new world
-    pub fn new(sender_cmd: Sender<Cmd>, update_chunk_mesh: Sender<AristideCmd>) -> Self {
+    /// Opens the world persisted in the given save
+    ///
+    /// For a new world, `meta` comes from [`World::new_meta`].
+    pub fn new(
+        config: Config,
+        save: Save,
+        meta: Meta,
+        sender_cmd: Sender<Cmd>,
+        update_chunk_mesh: Sender<AristideCmd>,
+    ) -> Self {
+        let generator = Generator::from_spec(meta.seed, &meta.generation).unwrap_or_else(|error| {
+            tracing::warn!(error, "invalid generation, using the default one");
+            Generator::new(meta.seed)
+        });
+        let mut player = Player {
+            camera: Camera {
+                pos: [0.0, 20.0, 0.0],
+                h_angle: 0.0,
+                v_angle: 0.0,
+            },
+            fly: meta.mode == GameMode::Creative,
+            gravity: 0.0,
+            on_ground: false,
+            block_placing: Block::Stone,
+            stamina: MAX_STAMINA,
+            riding: None,
+            gliding: false,
+        };
+        // a new player appears on the ground, somewhere safe
+        if !save.load_player(&mut player) {
+            if let Some([x, y, z]) = generator.find_spawn() {
+                // the camera is at the height of the eyes
+                player.camera.pos = [x as f32 + 0.5, y as f32 + 1.6, z as f32 + 0.5];
+            }
+        }
+        tracing::info!(seed = meta.seed, mode = %meta.mode, save = ?save.dir(), "world opened");
         Self {
             sender_cmd,
             aristide_cmd: update_chunk_mesh,
             chunks: DashMap::new(),
-            player: RwLock::new(Player {
-                camera: Camera {
-                    pos: [0.0, 20.0, 0.0],
-                    h_angle: 0.0,
-                    v_angle: 0.0,
-                },
-                fly: true,
-                gravity: 0.0,
-                on_ground: false,
-                block_placing: Block::Stone,
+            lights: DashMap::new(),
+            light_queue: Mutex::new(LightQueue::default()),
+            updates: Mutex::new(Vec::new()),
+            stored: DashMap::new(),
+            edits: DashMap::new(),
+            chests: save.load_chests().into_iter().collect(),
+            explored: save.load_explored().into_iter().collect(),
+            loaders: RwLock::new(save.load_loaders().into_iter().collect()),
+            pending: save.load_pending().into_iter().collect(),
+            achievements: Mutex::new(save.load_achievements()),
+            player: RwLock::new(player),
+            last_move: Mutex::new(0.0),
+            generator,
+            render_distance: AtomicI32::new(match config.render_distance {
+                RenderDistance::Fixed(distance) => distance,
+                RenderDistance::Auto => RenderDistance::DEFAULT,
             }),
-            generator: Generator::new(),
+            config: RwLock::new(config),
+            ticks: AtomicU64::new(meta.ticks),
+            save,
+            seed: meta.seed,
+            mode: meta.mode,
+            generation: meta.generation,
+            dirty: DashSet::new(),
+            shutdown: watch::channel(false).0,
+            entities: Mutex::new(HashMap::new()),
+            parked: DashMap::new(),
+            entity_chunks: DashSet::new(),
+            next_entity: AtomicU64::new(0),
+            events: broadcast::channel(64).0,
+            sounds: broadcast::channel(64).0,
+            rng: Mutex::new(Rng::new(meta.seed as u64 ^ meta.ticks)),
+            weather: RwLock::new(WeatherState::new(meta.ticks)),
+            stats: Mutex::new(Stats::default()),
+            mesh_generations: DashMap::new(),
+            memory: Mutex::new(MemoryUsage::default()),
+        }
+    }
+
+    /// Metadata of a world that was never played
+    ///
+    /// Without a seed, a random one is chosen.
+    pub fn new_meta(seed: Option<u32>, mode: GameMode, generation: &str) -> Meta {
+        Meta {
+            // the clock is a good enough source of randomness for a seed
+            seed: seed.unwrap_or_else(|| now().subsec_nanos()),
+            // a new world starts in the morning
+            ticks: DAY_LENGTH / 4,
+            last_played: now().as_secs(),
+            mode,
+            generation: generation.to_string(),
         }
     }
 
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    /// Asks the threads sharing the world to stop
+    pub fn shutdown(&self) {
+        self.shutdown.send_replace(true);
+    }
+
+    /// Chunks loaded around the player
+    pub fn render_distance(&self) -> i32 {
+        self.render_distance.load(Ordering::Relaxed)
+    }
+
+    pub fn set_render_distance(&self, distance: i32) {
+        self.render_distance.store(distance, Ordering::Relaxed);
+    }
+
+    /// Whether the game is closing
+    pub fn is_stopping(&self) -> bool {
+        *self.shutdown.borrow()
+    }
+
+    /// Completes once the game is closing
+    pub async fn stopped(&self) {
+        self.shutdown.subscribe().wait_for(|&stop| stop).await.ok();
+    }
+
+    /// The folder the world is saved in
+    pub fn save_dir(&self) -> &Path {
+        self.save.dir()
+    }
+
+    /// Writes the edited chunks, the entities, the player and the clock to
+    /// the save
+    ///
+    /// It never waits for a lock, so it can be called from the panic hook
+    /// while the panicking thread still holds one. What can't be accessed
+    /// is not saved (and an error is returned).
+    pub fn save(&self) -> io::Result<()> {
+        let _span = tracing::info_span!("save").entered();
+        let start = Instant::now();
+        self.save.save_meta(&Meta {
+            seed: self.seed,
+            ticks: self.ticks.load(Ordering::Relaxed),
+            last_played: now().as_secs(),
+            mode: self.mode,
+            generation: self.generation.clone(),
+        })?;
+        let player = self
+            .try_pull_player()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::WouldBlock, "player is locked"))?;
+        self.save.save_player(&player)?;
+        self.save.save_pending(&self.pending_structures())?;
+        self.save.save_loaders(&self.named_loaders())?;
+        self.save.save_explored(&self.explored_chunks())?;
+        self.save.save_chests(&self.chests())?;
+        self.save_entities()?;
+        self.save_achievements()?;
+        // collected first, as removing while iterating would deadlock
+        let dirty: Vec<ChunkCoords> = self.dirty.iter().map(|cc| *cc).collect();
+        let mut locked = 0;
+        for cc in dirty {
+            match self.chunks.try_get(&cc) {
+                TryResult::Present(chunk) => {
+                    let decompressed;
+                    let blocks = match &*chunk {
+                        ChunkState::Loaded(blocks) | ChunkState::Meshed(blocks, _) => blocks,
+                        ChunkState::Compressed(compressed) => {
+                            decompressed = compressed.decompress();
+                            &decompressed
+                        }
+                    };
+                    self.save.save_chunk(cc, blocks)?;
+                    self.dirty.remove(&cc);
+                }
+                TryResult::Absent => {}
+                TryResult::Locked => locked += 1,
+            }
+        }
+        if locked > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                format!("{locked} chunks are locked"),
+            ));
+        }
+        if let Ok(mut stats) = self.stats.try_lock() {
+            stats.last_save = Some(start.elapsed());
+        }
+        Ok(())
+    }
+
+    /// Applies a change to the settings and saves them
+    pub fn update_config(&self, f: impl FnOnce(&mut Config)) -> Result<(), CmdError> {
+        let mut config = self.config.write().unwrap();
+        f(&mut config);
+        config
+            .save(CONFIG_PATH)
+            .map_err(|err| CmdError::Config(err.to_string()))
+    }
+
+    /// Advances the world clock by one tick, and moves the entities
+    pub fn tick(&self) {
+        let ticks = self.ticks.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut events = Vec::new();
+        let gravity = self.physics().gravity;
+        self.entities.lock().unwrap().retain(|&id, entity| {
+            match entity.step(gravity, |bc| self.get_block(bc)) {
+                Step::Moved => true,
+                Step::Done(event) => {
+                    events.push((id, event));
+                    false
+                }
+                Step::Vanished => false,
+            }
+        });
+        // in the order of the entities, so a run is the same every time
+        events.sort_by_key(|&(id, _)| id);
+        for (_, event) in events {
+            match event {
+                Event::Explosion { pos, radius } => {
+                    self.play(WorldSound::Explosion { pos, radius });
+                    self.explode(pos, radius);
+                    self.blast_player(pos, radius);
+                }
+                Event::Impact { kind, block, face } => {
+                    if let Some(Some(hit)) = self.get_block(block) {
+                        self.play(WorldSound::landed(kind, block, face, hit));
+                    }
+                }
+                _ => {}
+            }
+            // nobody listening is not an error
+            self.events.send(event).ok();
+        }
+        self.propagate_light(LIGHT_BUDGET);
+        self.block_updates();
+        self.random_tick();
+        if ticks.is_multiple_of(SPAWN_PERIOD) {
+            self.spawn_mobs();
+        }
+        self.update_weather();
+    }
+
+    /// Removes every block in the sphere
+    fn explode(&self, pos: [f32; 3], radius: f32) {
+        let r = radius.ceil() as i32;
+        let mut positions = Vec::new();
+        for x in -r..=r {
+            for y in -r..=r {
+                for z in -r..=r {
+                    let offset = [x, y, z].map(|v| v as f32);
+                    if offset.vector_dot(offset) <= radius * radius {
+                        if let Ok(bc) = BlockCoords::try_from(pos.vector_add(offset)) {
+                            positions.push(bc);
+                        }
+                    }
+                }
+            }
+        }
+        self.remove_blocks(&positions, Author::Explosion);
+    }
+
+    /// Interacts with the block (like with a right click)
+    pub fn interact(&self, bc: BlockCoords) -> Result<CmdOutput, CmdError> {
+        let BlockCoords(cc, _) = bc;
+        let block = self
+            .get_block(bc)
+            .ok_or(CmdError::ChunkNotLoaded(cc))?
+            .ok_or(CmdError::NoBlock(bc))?;
+        let interact = on_interact(block).ok_or(CmdError::NotInteractive(bc, block))?;
+        interact(self, bc)
+    }
+
+    pub fn spawn_entity(&self, entity: Entity) -> EntityId {
+        let id = self.next_entity.fetch_add(1, Ordering::Relaxed);
+        self.entities.lock().unwrap().insert(id, entity);
+        id
+    }
+
+    /// fetch all the entities
+    pub fn pull_entities(&self) -> Vec<Entity> {
+        self.entities.lock().unwrap().values().copied().collect()
+    }
+
+    /// Receives the events happening from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    /// From 0.0 (midnight) to 1.0 (next midnight), noon being 0.5
+    pub fn time_of_day(&self) -> f32 {
+        (self.ticks.load(Ordering::Relaxed) % DAY_LENGTH) as f32 / DAY_LENGTH as f32
+    }
+
     pub fn player_set_block_placing(&self, block: Block) {
         self.player.write().unwrap().block_placing = block;
     }
 
-    pub fn player_fly(&self, b: bool) {
+    pub fn player_fly(&self, b: bool) -> Result<(), CmdError> {
+        if b && self.mode != GameMode::Creative {
+            return Err(CmdError::CreativeOnly);
+        }
         self.player.write().unwrap().fly = b;
-        println!("player.fly set to {:?}", b);
+        Ok(())
     }
 
     /// fetch player data
     pub fn pull_player(&self) -> Player {
         *self.player.read().unwrap()
     }
+    /// fetch player data, unless another thread is updating it
+    pub fn try_pull_player(&self) -> Option<Player> {
+        match self.player.try_read() {
+            Ok(player) => Some(*player),
+            // a panic while updating the player leaves it usable anyway
+            Err(TryLockError::Poisoned(player)) => Some(*player.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
     /// update player data
     pub fn push_player(&self, player: Player) {
-        *self.player.write().unwrap() = player;
+        let from = std::mem::replace(&mut *self.player.write().unwrap(), player)
+            .camera
+            .pos;
+        let to = player.camera.pos;
+        if from != to {
+            // nobody listening is not an error
+            self.events.send(Event::PlayerMoved { from, to }).ok();
+        }
     }
 
     /// When chunk data is altered (block placed or removed) its meshed is recomputed
@@ -133,8 +699,7 @@ impl World {
                 direction,
                 BlockCoords(cc, bi)
                     .step(direction)
-                    .map(|position| self.get_block(position))
-                    .flatten()
+                    .and_then(|position| self.get_block(position))
                     .flatten(),
             )
         });
@@ -143,12 +708,13 @@ impl World {
                 // a block has been placed
                 if let Some(&block) = blocks.get(&bi) {
                     for (direction, neighbour) in neighbours {
-                        if neighbour.is_some() {
+                        if face_hidden(block, neighbour) {
                             if faces.remove(&(bi, direction)).is_some() {
                                 updated = true;
                             }
                         } else {
-                            if faces.insert((bi, direction), block).is_none() {
+                            // the block may be the same with another state
+                            if faces.insert((bi, direction), block) != Some(block) {
                                 updated = true;
                             }
                         }
@@ -167,144 +733,540 @@ impl World {
         updated
     }
 
-    pub fn remove_block(&self, bc: BlockCoords) {
-        // converts block coordinates to chunk coordinates and block index
+    /// Removes the block at the given coordinates and returns it
+    pub fn remove_block(&self, bc: BlockCoords) -> Result<Block, CmdError> {
+        self.edit().remove_block(bc)
+    }
+
+    /// Places a block at the given coordinates
+    pub fn place_block(&self, bc: BlockCoords, block: Block) -> Result<(), CmdError> {
+        self.edit().place_block(bc, block)
+    }
+
+    /// A torch stands on the floor or, if there is none, hangs on a wall
+    /// (preferably the one the player looks at)
+    fn attach_torch(&self, bc: BlockCoords, facing: Direction) -> Result<Block, CmdError> {
+        let supports = |direction| {
+            bc.step(direction)
+                .and_then(|bc| self.get_block(bc))
+                .flatten()
+                .is_some_and(|block| block.is_full() && block.is_solid())
+        };
+        if supports(Direction::Down) {
+            return Ok(Block::Torch(0));
+        }
+        [facing]
+            .into_iter()
+            .chain(Direction::CARDINAL)
+            .find(|&side| supports(side))
+            .map(|side| Block::Torch(state::WALL).with_facing(side))
+            .ok_or(CmdError::NoSupport(bc))
+    }
+
+    /// Puts a block in place of another one and returns the replaced one
+    ///
+    /// Unlike [`World::place_block`], the block is placed as is.
+    pub fn replace_block(
+        &self,
+        bc: BlockCoords,
+        block: Block,
+        who: Author,
+    ) -> Result<Block, CmdError> {
         let BlockCoords(cc, bi) = bc;
-        // at most 7 updated block (6 neighbour and the block itself)
-        // an ArrayVec is a dynamic array on the stack (max sized)
-        let mut updates = ArrayVec::<BlockCoords, 7>::new();
-        if let Some(mut chunk) = self.chunks.get_mut(&cc) {
-            if let ChunkState::Meshed(ref mut blocks, _) = *chunk {
-                if blocks.remove(&bi).is_some() {
-                    if !updates.contains(&bc) {
-                        // only add update if not yet present in list
-                        updates.push(bc);
-                    }
-                    for direction in Direction::ALL {
-                        // cascading effect on neighbours
-                        if let Some(neighbour) = bc.step(direction) {
-                            updates.push(neighbour);
-                        }
+        let replaced = {
+            let mut chunk = self
+                .chunks
+                .get_mut(&cc)
+                .ok_or(CmdError::ChunkNotLoaded(cc))?;
+            let ChunkState::Meshed(ref mut blocks, _) = *chunk else {
+                return Err(CmdError::ChunkNotLoaded(cc));
+            };
+            blocks.insert(bi, block).ok_or(CmdError::NoBlock(bc))?
+        };
+        self.record(bc, Some(replaced), Some(block), who);
+        self.dirty.insert(cc);
+        self.update_around(bc);
+        if replaced.is_transparent() != block.is_transparent()
+            || replaced.emission() != block.emission()
+        {
+            self.relight(bc);
+        }
+        Ok(replaced)
+    }
+
+    /// Opens or closes a door (both halves) or a trapdoor
+    ///
+    /// Returns whether it is open now.
+    pub fn toggle_open(&self, bc: BlockCoords) -> Result<bool, CmdError> {
+        let BlockCoords(cc, bi) = bc;
+        let (positions, open) = {
+            let mut chunk = self
+                .chunks
+                .get_mut(&cc)
+                .ok_or(CmdError::ChunkNotLoaded(cc))?;
+            let ChunkState::Meshed(ref mut blocks, _) = *chunk else {
+                return Err(CmdError::ChunkNotLoaded(cc));
+            };
+            let block = *blocks.get(&bi).ok_or(CmdError::NoBlock(bc))?;
+            let positions = [Some(bc), other_half(bc, block)];
+            let mut toggled = Vec::new();
+            for bc in positions.into_iter().flatten() {
+                let BlockCoords(_, bi) = bc;
+                if let Some(block) = blocks.get_mut(&bi) {
+                    let before = *block;
+                    *block = block.with_state(block.state() ^ state::OPEN);
+                    toggled.push((bc, before, *block));
+                }
+            }
+            for (bc, before, after) in toggled {
+                self.record(bc, Some(before), Some(after), Author::Player);
+            }
+            (positions, !block.is_open())
+        };
+        self.dirty.insert(cc);
+        for bc in positions.into_iter().flatten() {
+            self.update_around(bc);
+        }
+        Ok(open)
+    }
+
+    /// Turns dirt or grass into farmland, with nothing above it
+    pub fn till(&self, bc: BlockCoords) -> Result<(), CmdError> {
+        let BlockCoords(cc, _) = bc;
+        let block = self
+            .get_block(bc)
+            .ok_or(CmdError::ChunkNotLoaded(cc))?
+            .ok_or(CmdError::NoBlock(bc))?;
+        if !matches!(block, Block::Dirt | Block::Grass) {
+            return Err(CmdError::NotTillable(bc, block));
+        }
+        if let Some(above) = bc.step(Direction::Up) {
+            if let Some(Some(present)) = self.get_block(above) {
+                return Err(CmdError::Occupied(above, present));
+            }
+        }
+        self.replace_block(bc, Block::Farmland, Author::Player)?;
+        self.play(WorldSound::block(bc, Block::Farmland, true));
+        Ok(())
+    }
+
+    /// Whether the voxel is right above farmland, where crops grow
+    fn on_farmland(&self, bc: BlockCoords) -> bool {
+        bc.step(Direction::Down)
+            .and_then(|below| self.get_block(below))
+            .flatten()
+            == Some(Block::Farmland)
+    }
+
+    /// The cardinal direction the player looks at
+    fn player_facing(&self) -> Direction {
+        let [x, _, z, _] = self.pull_player().camera.matrix().vector_z();
+        match (x.abs() > z.abs(), x > 0.0, z > 0.0) {
+            (true, true, _) => Direction::East,
+            (true, false, _) => Direction::West,
+            (false, _, true) => Direction::South,
+            (false, _, false) => Direction::North,
+        }
+    }
+
+    /// Removes many blocks at once, the meshes are updated only once
+    ///
+    /// Returns the removed blocks (positions without block are skipped).
+    pub fn remove_blocks(
+        &self,
+        positions: &[BlockCoords],
+        who: Author,
+    ) -> Vec<(BlockCoords, Block)> {
+        let mut positions = positions.to_vec();
+        let mut removed = Vec::new();
+        let mut i = 0;
+        while let Some(&bc) = positions.get(i) {
+            i += 1;
+            let BlockCoords(cc, bi) = bc;
+            if let Some(mut chunk) = self.chunks.get_mut(&cc) {
+                if let ChunkState::Meshed(ref mut blocks, _) = *chunk {
+                    // explosions stop at the bottom of the world, and spare
+                    // the chests holding blocks
+                    let block = blocks.get(&bi).copied().filter(|b| {
+                        b.is_breakable() && !self.chests.contains_key(&<[i32; 3]>::from(bc))
+                    });
+                    if let Some(block) = block {
+                        blocks.remove(&bi);
+                        self.record(bc, Some(block), None, who);
+                        removed.push((bc, block));
+                        self.dirty.insert(cc);
+                        // the other half of a door goes too
+                        positions.extend(other_half(bc, block));
                     }
                 }
             }
         }
-        // which chunks where updated (theorical maximum is 3, but
-        // for some complicated reasons, it's better to put 7)
-        let mut updated = ArrayVec::<ChunkCoords, 7>::new();
-        for bc in updates {
-            if self.update_block_mesh(bc) {
-                let BlockCoords(cc, _) = bc;
-                if !updated.contains(&cc) {
-                    updated.push(cc);
+        let positions: Vec<_> = removed.iter().map(|&(bc, _)| bc).collect();
+        self.update_many(&positions);
+        for &(bc, _) in &removed {
+            self.relight(bc);
+        }
+        removed
+    }
+
+    /// Sets many blocks at once (`None` removing one), the meshes are
+    /// updated only once
+    ///
+    /// Either every block is set, or none when a chunk is not meshed.
+    fn set_blocks(
+        &self,
+        edits: &[(BlockCoords, Option<Block>)],
+        who: Author,
+    ) -> Result<(), CmdError> {
+        for &(BlockCoords(cc, _), _) in edits {
+            if !matches!(self.get_chunk_stage(cc), ChunkStage::Meshed) {
+                return Err(CmdError::ChunkNotLoaded(cc));
+            }
+        }
+        let mut changed = Vec::new();
+        for &(bc, block) in edits {
+            let BlockCoords(cc, bi) = bc;
+            let before = {
+                let Some(mut chunk) = self.chunks.get_mut(&cc) else {
+                    continue;
+                };
+                let ChunkState::Meshed(ref mut blocks, _) = *chunk else {
+                    continue;
+                };
+                match block {
+                    Some(block) => blocks.insert(bi, block),
+                    None => blocks.remove(&bi),
                 }
+            };
+            if before != block {
+                self.record(bc, before, block, who);
+                self.dirty.insert(cc);
+                changed.push(bc);
             }
         }
-        for chunk in updated {
-            self.aristide_cmd
-                .try_send(AristideCmd::RenderChunk(chunk, true))
-                .ok();
+        self.update_many(&changed);
+        for &bc in &changed {
+            self.relight(bc);
         }
+        Ok(())
     }
-    // similar to remove_block
-    pub fn place_block(&self, bc: BlockCoords, block: Block) {
-        let BlockCoords(cc, bi) = bc;
-        let mut updates = ArrayVec::<BlockCoords, 7>::new();
-        if let Some(mut chunk) = self.chunks.get_mut(&cc) {
-            if let ChunkState::Meshed(ref mut blocks, _) = *chunk {
-                if blocks.insert(bi, block).is_none() {
-                    if !updates.contains(&bc) {
-                        updates.push(bc);
-                    }
-                    for direction in Direction::ALL {
-                        if let Some(neighbour) = bc.step(direction) {
-                            updates.push(neighbour);
-                        }
-                    }
+
+    /// Updates the meshes after many blocks changed, each section is
+    /// rendered again only once
+    fn update_many(&self, positions: &[BlockCoords]) {
+        let mut updated = HashMap::<ChunkCoords, Sections>::new();
+        for &bc in positions {
+            self.update_block_mesh(bc);
+            for neighbour in Direction::ALL.into_iter().filter_map(|d| bc.step(d)) {
+                if self.update_block_mesh(neighbour) {
+                    let BlockCoords(cc, _) = neighbour;
+                    *updated.entry(cc).or_default() |= sections_around(neighbour);
                 }
             }
+            let BlockCoords(cc, _) = bc;
+            *updated.entry(cc).or_default() |= sections_around(bc);
+            for neighbour in across_border(bc) {
+                *updated.entry(neighbour).or_default() |= sections_around(bc);
+            }
+        }
+        for (chunk, sections) in updated {
+            self.render_sections(chunk, sections);
         }
-        let mut updated = ArrayVec::<ChunkCoords, 7>::new();
+    }
+
+    /// Updates the mesh of a changed block and its neighbours, then asks
+    /// Aristide to render again the affected chunks
+    fn update_around(&self, bc: BlockCoords) {
+        // at most 7 updated block (6 neighbour and the block itself)
+        // an ArrayVec is a dynamic array on the stack (max sized)
+        let mut updates = ArrayVec::<BlockCoords, 7>::new();
+        updates.push(bc);
+        for direction in Direction::ALL {
+            // cascading effect on neighbours
+            if let Some(neighbour) = bc.step(direction) {
+                updates.push(neighbour);
+            }
+        }
+        // which sections of which chunks where updated (theorical maximum
+        // is 3 chunks, but for some complicated reasons, it's better to put 7)
+        let mut updated = ArrayVec::<(ChunkCoords, Sections), 7>::new();
+        let mut update = |cc: ChunkCoords, sections: Sections| match updated
+            .iter_mut()
+            .find(|(chunk, _)| *chunk == cc)
+        {
+            Some((_, updated)) => *updated |= sections,
+            None => updated.push((cc, sections)),
+        };
         for bc in updates {
             if self.update_block_mesh(bc) {
                 let BlockCoords(cc, _) = bc;
-                if !updated.contains(&cc) {
-                    updated.push(cc);
-                }
+                update(cc, sections_around(bc));
             }
         }
-        for chunk in updated {
-            self.aristide_cmd
-                .try_send(AristideCmd::RenderChunk(chunk, true))
-                .ok();
+        // the faces of the chunks next to it may be the same, but not lit the
+        // same way
+        for neighbour in across_border(bc) {
+            update(neighbour, sections_around(bc));
+        }
+        for (chunk, sections) in updated {
+            self.render_sections(chunk, sections);
+        }
+    }
+
+    /// Asks Aristide to render again sections of a chunk, if it is meshed
+    fn render_sections(&self, cc: ChunkCoords, sections: Sections) {
+        if self.get_chunk_stage(cc) == ChunkStage::Meshed {
+            self.try_aristide_cmd(AristideCmd::RenderSections(cc, sections));
         }
     }
 
     pub fn get_chunk_stage(&self, cc: ChunkCoords) -> ChunkStage {
-        self.chunks
-            .get(&cc)
-            .map(|chunk| chunk.get_stage())
-            .unwrap_or(ChunkStage::None)
+        match self.chunks.get(&cc) {
+            Some(chunk) => chunk.get_stage(),
+            None if self.stored.contains_key(&cc) => ChunkStage::Stored,
+            None => ChunkStage::None,
+        }
     }
 
     pub fn get_block(&self, BlockCoords(cc, bi): BlockCoords) -> Option<Option<Block>> {
         self.chunks.get(&cc).map(|chunk| chunk.get_block(bi))
     }
 
-    /// Load the given chunk
-    pub fn chunk_stage_none_to_loaded(&self, cc: ChunkCoords) {
-        let mut chunk = BlocksChunk::new();
-        self.generator.gen_chunk(cc, &mut chunk);
+    /// Altitude of the highest block of a column (if its chunk is loaded)
+    pub fn surface(&self, x: i32, z: i32) -> Option<i32> {
+        let BlockCoords(cc, _) = BlockCoords::try_from([x, 0, z]).ok()?;
+        let chunk = self.chunks.get(&cc)?;
+        (0..256).rev().find(|&y| {
+            let BlockCoords(_, bi) = BlockCoords::try_from([x, y, z]).unwrap();
+            chunk.get_block(bi).is_some()
+        })
+    }
+
+    /// Highest block of every column of a chunk (if it is loaded)
+    pub fn surface_blocks(&self, cc: ChunkCoords) -> Option<SurfaceChunk> {
+        let chunk = self.chunks.get(&cc)?;
+        let mut columns: SurfaceChunk = [[None; 16]; 16];
+        match &*chunk {
+            ChunkState::Loaded(blocks) | ChunkState::Meshed(blocks, _) => {
+                // one pass over the blocks is faster than scanning every column
+                for (&bi, &block) in blocks {
+                    let [x, y, z]: [i32; 3] = bi.into();
+                    let column: &mut Option<(i32, Block)> = &mut columns[x as usize][z as usize];
+                    if column.is_none_or(|(top, _)| top < y) {
+                        *column = Some((y, block));
+                    }
+                }
+            }
+            ChunkState::Compressed(compressed) => {
+                for (x, column) in columns.iter_mut().enumerate() {
+                    for (z, top) in column.iter_mut().enumerate() {
+                        *top = (0..256).rev().find_map(|y| {
+                            let bi = BlockIndex::try_from([x as i32, y, z as i32]).unwrap();
+                            compressed.get(bi).map(|block| (y, block))
+                        });
+                    }
+                }
+            }
+        }
+        Some(columns)
+    }
+
+    /// Records the surface of a chunk seen by the player, and returns it
+    pub fn explore(&self, cc: ChunkCoords) -> Option<SurfaceChunk> {
+        let surface = self.surface_blocks(cc)?;
+        self.explored.insert(cc, surface);
+        Some(surface)
+    }
+
+    /// The surface of every chunk the player has seen
+    pub fn explored_chunks(&self) -> Vec<(ChunkCoords, SurfaceChunk)> {
+        self.explored
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect()
+    }
+
+    /// Reads the save of the given chunk, without blocking the thread
+    pub async fn chunk_stage_none_to_stored(&self, cc: ChunkCoords) {
+        let saved = self.save.load_chunk_async(cc).await;
+        self.stored.insert(cc, saved);
+    }
+
+    /// Load the given chunk (from the save if it was edited)
+    ///
+    /// If the save was not read yet, it is read now, blocking the thread.
+    pub fn chunk_stage_stored_to_loaded(&self, cc: ChunkCoords) {
+        let _span = tracing::debug_span!("generate", x = cc.x, z = cc.z).entered();
+        let saved = match self.stored.remove(&cc) {
+            Some((_, saved)) => saved,
+            None => self.save.load_chunk(cc),
+        };
+        let mut spill = Vec::new();
+        let chunk = saved.unwrap_or_else(|| {
+            let mut chunk = BlocksChunk::new();
+            spill = self.generator.gen_chunk(cc, &mut chunk);
+            chunk
+        });
         self.chunks.insert(cc, ChunkState::Loaded(chunk));
+        self.load_entities(cc);
+        self.light_chunk(cc);
+        self.place_pending(cc);
+        if !spill.is_empty() {
+            // saved, so its structures are never spilled twice
+            self.dirty.insert(cc);
+            self.spill_structures(spill);
+        }
     }
 
     /// Build mesh of given chunk
+    ///
+    /// The faces inside the chunk are found while it is locked, and it turns
+    /// meshed at once. The faces against its neighbours are found after: an
+    /// edit of a neighbour updates them once the chunk is meshed, and those
+    /// done before are seen when reading the neighbour.
     pub fn chunk_stage_loaded_to_meshed(&self, cc: ChunkCoords) {
-        let mut faces_chunk = FacesChunk::new();
-        // TODO: very inefficient to iterate over all possible indices
-        // should only iterate over stored block
-        for bi in BlockIndex::ALL {
-            let bc = BlockCoords(cc, bi);
-            if let Some(Some(block)) = self.get_block(bc) {
+        let _span = tracing::debug_span!("mesh", x = cc.x, z = cc.z).entered();
+        self.decompress_chunk(cc);
+        let mut border = Vec::new();
+        {
+            let Some(mut chunk) = self.chunks.get_mut(&cc) else {
+                return;
+            };
+            let ChunkState::Loaded(ref mut blocks) = *chunk else {
+                return;
+            };
+            let blocks = std::mem::take(blocks);
+            let mut faces_chunk = FacesChunk::new();
+            for (&bi, &block) in &blocks {
                 for direction in Direction::ALL {
-                    if let Some(Some(None)) = bc.step(direction).map(|bc| self.get_block(bc)) {
+                    // nothing is seen through the bottom or the top of the world
+                    let Some(BlockCoords(neighbour_cc, neighbour)) =
+                        BlockCoords(cc, bi).step(direction)
+                    else {
+                        continue;
+                    };
+                    if neighbour_cc != cc {
+                        border.push(bi);
+                    } else if !face_hidden(block, blocks.get(&neighbour).copied()) {
                         faces_chunk.insert((bi, direction), block);
                     }
                 }
             }
+            *chunk = ChunkState::Meshed(blocks, faces_chunk);
         }
-        // TODO: this is bad, between the time the chunk is removed then
-        // reinserted, the chunk loader could decide to load it again
-        // beleiving it is not.
-        if let Some((_, ChunkState::Loaded(chunk))) = self.chunks.remove(&cc) {
-            self.chunks
-                .insert(cc, ChunkState::Meshed(chunk, faces_chunk));
-        } else {
-            unreachable!()
+        // a block in a corner is there twice
+        border.dedup();
+        for bi in border {
+            self.update_block_mesh(BlockCoords(cc, bi));
         }
     }
 
+    /// Loaders keeping chunks loaded wherever the players are
+    pub fn named_loaders(&self) -> Vec<(String, ChunkLoader)> {
+        let loaders = self.loaders.read().unwrap();
+        loaders
+            .iter()
+            .map(|(name, &loader)| (name.clone(), loader))
+            .collect()
+    }
+
+    /// Adds a named loader, or moves the one with this name
+    pub fn set_loader(&self, name: String, loader: ChunkLoader) {
+        tracing::info!(name, ?loader, "chunk loader set");
+        self.loaders.write().unwrap().insert(name, loader);
+    }
+
+    /// Returns false if there was no loader with this name
+    pub fn remove_loader(&self, name: &str) -> bool {
+        self.loaders.write().unwrap().remove(name).is_some()
+    }
+
     // apply dependency of chunk stages to given chunk and its neighbours
-    pub fn request_chunk_stage(&self, cc: ChunkCoords, stage: ChunkStage) {
-        let chunk_stage = self.get_chunk_stage(cc);
-        if chunk_stage < stage {
-            let previous = stage.previous().unwrap();
-            self.request_chunk_stage(cc, previous);
-            for neighbour in cc.neighbors() {
-                self.request_chunk_stage(neighbour, previous);
+    /// Brings a chunk to the given stage, and its neighbours as far as needed
+    ///
+    /// The saves of the chunks to load are read first, asynchronously, so
+    /// the loader does not wait on the disk. Only the chunks that were never
+    /// saved are generated.
+    pub async fn request_chunk_stage(&self, cc: ChunkCoords, stage: ChunkStage) {
+        let mut to_load = Vec::new();
+        if stage >= ChunkStage::Loaded {
+            to_load.push(cc);
+        }
+        if stage >= ChunkStage::Meshed {
+            to_load.extend(cc.neighbors());
+        }
+        for cc in to_load {
+            if self.get_chunk_stage(cc) == ChunkStage::None {
+                self.chunk_stage_none_to_stored(cc).await;
             }
-            match previous {
-                ChunkStage::None => self.chunk_stage_none_to_loaded(cc),
-                ChunkStage::Loaded => self.chunk_stage_loaded_to_meshed(cc),
-                ChunkStage::Meshed => unreachable!(),
+        }
+        self.advance_chunk_stage(cc, stage);
+    }
+
+    /// Like [`World::request_chunk_stage`], the saves not read yet being
+    /// read while loading
+    pub fn advance_chunk_stage(&self, cc: ChunkCoords, stage: ChunkStage) {
+        if self.get_chunk_stage(cc) >= stage {
+            return;
+        }
+        match stage {
+            ChunkStage::None | ChunkStage::Stored => {}
+            ChunkStage::Loaded => self.chunk_stage_stored_to_loaded(cc),
+            // building the mesh requires to know the neighbours
+            ChunkStage::Meshed => {
+                self.advance_chunk_stage(cc, ChunkStage::Loaded);
+                for neighbour in cc.neighbors() {
+                    self.advance_chunk_stage(neighbour, ChunkStage::Loaded);
+                }
+                self.chunk_stage_loaded_to_meshed(cc);
             }
         }
     }
 
     pub async fn aristide_cmd(&self, cmd: AristideCmd) {
+        self.bump_mesh_generation(&cmd);
         self.aristide_cmd.send(cmd).await.unwrap()
     }
 
+    /// Sends a command to Aristide unless its queue is full
+    pub fn try_aristide_cmd(&self, cmd: AristideCmd) {
+        self.bump_mesh_generation(&cmd);
+        self.aristide_cmd.try_send(cmd).ok();
+    }
+
+    fn bump_mesh_generation(&self, cmd: &AristideCmd) {
+        let (cc, sections) = match *cmd {
+            AristideCmd::RenderChunk(cc, true) => (cc, ALL_SECTIONS),
+            AristideCmd::RenderSections(cc, sections) => (cc, sections),
+            AristideCmd::RenderChunk(_, false) | AristideCmd::Reload => return,
+        };
+        let mut generations = self.mesh_generations.entry(cc).or_default();
+        for (section, generation) in generations.iter_mut().enumerate() {
+            if sections >> section & 1 == 1 {
+                *generation += 1;
+            }
+        }
+    }
+
+    /// Makes every mesh out of date, so they are all built again
+    pub fn invalidate_meshes(&self) {
+        for mut generations in self.mesh_generations.iter_mut() {
+            for generation in generations.iter_mut() {
+                *generation += 1;
+            }
+        }
+    }
+
+    /// How many times the section of the chunk was requested to be rendered
+    ///
+    /// A mesh built after reading the generation is up to date with all the
+    /// requests of this generation (and the older ones).
+    pub fn mesh_generation(&self, cc: ChunkCoords, section: usize) -> u64 {
+        self.mesh_generations
+            .get(&cc)
+            .map_or(0, |generations| generations[section])
+    }
+
     // it workds, don't ask me to explain it XD
     fn find_collision_tranch<const X: usize, const Y: usize, const Z: usize>(
         &self,
@@ -312,8 +1274,8 @@ impl World {
         t: f32,
         boxel: Boxel,
         vector: [f32; 3],
+        e: f32,
     ) -> bool {
-        const E: f32 = def::constant::COLLISION_EPSILON;
         // COMPUTE TRANCH (move the hitbox to future position)
         let pos_min = boxel.pos.vector_add(vector.vector_scale(t));
         let pos_max = pos_min.vector_add(boxel.dimensions);
@@ -321,13 +1283,13 @@ impl World {
         // COVER DISCRET TRANCH (let X be the progression axis)
         // then find out the rectangle the hitbox is producing on Y and Z axis
 
-        let y_begin = (pos_min[Y] + E).floor() as i32;
-        let y_end = (pos_max[Y] - E).ceil() as i32;
+        let y_begin = (pos_min[Y] + e).floor() as i32;
+        let y_end = (pos_max[Y] - e).ceil() as i32;
         for y in y_begin..y_end {
             // iterate over all crossed integer values of Y axis
 
-            let z_begin = (pos_min[Z] + E).floor() as i32;
-            let z_end = (pos_max[Z] - E).ceil() as i32;
+            let z_begin = (pos_min[Z] + e).floor() as i32;
+            let z_end = (pos_max[Z] - e).ceil() as i32;
             for z in z_begin..z_end {
                 // iterate over all crossed integer values of Z axis
 
@@ -337,9 +1299,13 @@ impl World {
                 bc[Z] = z;
                 // if one of those values is the coordinate of solid block
                 if let Ok(bc) = BlockCoords::try_from(bc) {
-                    if let Some(Some(_)) = self.get_block(bc) {
-                        // then YES a collision occurs
-                        return true;
+                    match self.get_block(bc) {
+                        // then YES a collision occurs (if it is solid)
+                        Some(Some(block)) if block.is_solid() => return true,
+                        Some(_) => {}
+                        // an unloaded chunk holds the movement until it is
+                        // loaded, instead of letting the player fall through
+                        None => return true,
                     }
                 }
             }
@@ -368,7 +1334,7 @@ impl World {
         boxel: Boxel,
         vector: [f32; 3],
     ) -> f32 {
-        const E: f32 = def::constant::COLLISION_EPSILON;
+        let e = self.physics().collision_epsilon;
         let mut min_time = 1.0;
         let vx = vector[X];
 
@@ -378,9 +1344,9 @@ impl World {
             let x_end = x_begin + vx;
 
             // find min time
-            for x in (x_begin - E).ceil() as i32..=(x_end + E).floor() as i32 {
+            for x in (x_begin - e).ceil() as i32..=(x_end + e).floor() as i32 {
                 let time = (x as f32 - x_begin) / (x_end - x_begin);
-                if self.find_collision_tranch::<X, Y, Z>(x, time, boxel, vector) {
+                if self.find_collision_tranch::<X, Y, Z>(x, time, boxel, vector, e) {
                     min_time = time.min(min_time);
                 }
             }
@@ -392,9 +1358,9 @@ impl World {
             let x_end = x_begin + vx;
 
             // find min time
-            for x in (x_end - E).ceil() as i32..=(x_begin + E).floor() as i32 {
+            for x in (x_end - e).ceil() as i32..=(x_begin + e).floor() as i32 {
                 let time = (x as f32 - x_begin) / (x_end - x_begin);
-                if self.find_collision_tranch::<X, Y, Z>(x - 1, time, boxel, vector) {
+                if self.find_collision_tranch::<X, Y, Z>(x - 1, time, boxel, vector, e) {
                     min_time = time.min(min_time);
                 }
             }
@@ -403,3 +1369,114 @@ impl World {
         min_time
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+
+    #[test]
+    fn test_collision_unloaded() {
+        let harness = Harness::empty("collision-unloaded");
+        let world = &harness.world;
+        let boxel = Boxel::new([0.6, 1.8, 0.6], [0.3, 1.6, 0.3], [8.0, 100.0, 8.0]);
+        let fall = [0.0, -0.5, 0.0];
+        // nothing is loaded, the player is held
+        assert!(world.find_collision_y(boxel, fall) < 1.0);
+        // once loaded, there is air below
+        world.chunks.insert(
+            ChunkCoords { x: 0, z: 0 },
+            ChunkState::Loaded(BlocksChunk::new()),
+        );
+        assert_eq!(world.find_collision_y(boxel, fall), 1.0);
+        // walking into an unloaded chunk is held too
+        assert!(world.find_collision_x(boxel, [9.0, 0.0, 0.0]) < 1.0);
+    }
+
+    #[test]
+    fn test_surface_blocks() {
+        let harness = Harness::empty("surface-blocks");
+        let world = &harness.world;
+        let cc = ChunkCoords { x: 0, z: 0 };
+        assert_eq!(world.surface_blocks(cc), None);
+        let index = |p: [i32; 3]| BlockIndex::try_from(p).unwrap();
+        let blocks = BlocksChunk::from([
+            (index([3, 10, 5]), Block::Stone),
+            (index([3, 12, 5]), Block::Sand),
+            (index([0, 0, 0]), Block::Bedrock),
+        ]);
+        world.chunks.insert(cc, ChunkState::Loaded(blocks.clone()));
+        let columns = world.surface_blocks(cc).unwrap();
+        assert_eq!(columns[3][5], Some((12, Block::Sand)));
+        assert_eq!(columns[0][0], Some((0, Block::Bedrock)));
+        assert_eq!(columns[5][3], None);
+        // packed chunks have the same surface
+        world.chunks.insert(
+            cc,
+            ChunkState::Compressed(CompressedChunk::compress(&blocks)),
+        );
+        assert_eq!(world.surface_blocks(cc), Some(columns));
+    }
+
+    #[test]
+    fn test_mesh_generation() {
+        let mut harness = Harness::empty("mesh-generation");
+        let world = &harness.world;
+        let cc = ChunkCoords { x: 0, z: 0 };
+        world.chunks.insert(
+            cc,
+            ChunkState::Meshed(BlocksChunk::new(), FacesChunk::new()),
+        );
+        assert_eq!(world.mesh_generation(cc, 0), 0);
+        for x in 3..6 {
+            let bc = BlockCoords::try_from([x, 10, 3]).unwrap();
+            world.place_block(bc, Block::Stone).unwrap();
+        }
+        // each request to render the section bumped its generation, the
+        // sections above and below were not touched
+        let mut requests = 0;
+        for (requested, sections) in harness.render_requests() {
+            assert_eq!(sections, 1);
+            if requested == cc {
+                requests += 1;
+            }
+        }
+        let world = &harness.world;
+        assert!(requests >= 3);
+        assert_eq!(world.mesh_generation(cc, 0), requests);
+        assert_eq!(world.mesh_generation(cc, 1), 0);
+        assert_eq!(world.mesh_generation(ChunkCoords { x: 1, z: 0 }, 0), 0);
+        // a reload makes every mesh out of date
+        world.invalidate_meshes();
+        assert_eq!(world.mesh_generation(cc, 0), requests + 1);
+        assert_eq!(world.mesh_generation(cc, 1), 1);
+
+        // at the top of a section, the one above is rendered too
+        let bc = BlockCoords::try_from([3, 15, 3]).unwrap();
+        assert_eq!(sections_around(bc), 0b11);
+        let bc = BlockCoords::try_from([3, 255, 3]).unwrap();
+        assert_eq!(sections_around(bc), 1 << 15);
+    }
+
+    #[test]
+    fn test_seam() {
+        let mut harness = Harness::new("seam");
+        // two stones against each other, on both sides of the seam
+        let [west, east] = [[15, 10, 3], [16, 10, 3]];
+        harness.run("place 15 10 3 stone").unwrap();
+        assert_eq!(harness.faces(west).len(), 6);
+        harness.run("place 16 10 3 stone").unwrap();
+        assert!(!harness.faces(west).contains(&Direction::East));
+        assert_eq!(harness.faces(west).len(), 5);
+        assert!(!harness.faces(east).contains(&Direction::West));
+
+        // breaking one shows the side of the other, in the other chunk
+        harness.rendered();
+        harness.run("remove 16 10 3").unwrap();
+        assert!(harness.faces(west).contains(&Direction::East));
+        let [west, east] = [west, east].map(|coords| BlockCoords::try_from(coords).unwrap());
+        let rendered = harness.rendered();
+        assert!(rendered.contains_key(&west.0) && rendered.contains_key(&east.0));
+        assert_eq!(across_border(west).collect::<Vec<_>>(), [east.0]);
+    }
+}
@@ -0,0 +1,181 @@
+use std::{collections::BTreeSet, fmt, io};
+
+use def::Block;
+use mat::VectorTrait;
+
+use super::{Event, Player, World};
+
+/// Altitude the feet of the player must reach for [`Achievement::Heights`]
+pub const HEIGHTS: f32 = 100.0;
+
+/// Distance to travel for [`Achievement::Traveler`] (in blocks)
+pub const TRAVEL: f32 = 1000.0;
+
+/// A move longer than this in one tick is a teleport, which is not counted
+/// as traveling
+const MAX_STEP: f32 = 4.0;
+
+/// Goals given to the player, unlocked by what happens in the world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Achievement {
+    FirstBlock,
+    FirstTree,
+    Heights,
+    Traveler,
+}
+
+impl Achievement {
+    pub const ALL: [Self; 4] = [
+        Self::FirstBlock,
+        Self::FirstTree,
+        Self::Heights,
+        Self::Traveler,
+    ];
+}
+
+impl fmt::Display for Achievement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::FirstBlock => "first_block",
+            Self::FirstTree => "first_tree",
+            Self::Heights => "heights",
+            Self::Traveler => "traveler",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::str::FromStr for Achievement {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|achievement| achievement.to_string() == s)
+            .ok_or(())
+    }
+}
+
+/// The achievements of a world, and the progress toward the others
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Progress {
+    pub unlocked: BTreeSet<Achievement>,
+    /// Distance traveled by the player (in blocks)
+    pub traveled: f32,
+}
+
+impl Progress {
+    /// Takes an event into account, returns the achievements it unlocks
+    pub fn track(&mut self, event: &Event) -> Vec<Achievement> {
+        let mut reached = Vec::new();
+        match *event {
+            Event::BlockBroken { broken, .. } => {
+                reached.push(Achievement::FirstBlock);
+                if broken == Block::Trunk {
+                    reached.push(Achievement::FirstTree);
+                }
+            }
+            Event::PlayerMoved { from, to } => {
+                let step = to.vector_sub(from);
+                let distance = step.vector_dot(step).sqrt();
+                if distance < MAX_STEP {
+                    self.traveled += distance;
+                }
+                if to[1] - Player::EYES >= HEIGHTS {
+                    reached.push(Achievement::Heights);
+                }
+                if self.traveled >= TRAVEL {
+                    reached.push(Achievement::Traveler);
+                }
+            }
+            _ => {}
+        }
+        reached.retain(|&achievement| self.unlocked.insert(achievement));
+        reached
+    }
+}
+
+impl World {
+    /// Updates the achievements with an event received from the bus, and
+    /// tells the ones unlocked (with an [`Event::Achieved`])
+    pub fn track(&self, event: &Event) {
+        let reached = self.achievements.lock().unwrap().track(event);
+        for achievement in reached {
+            tracing::info!(%achievement, "achievement unlocked");
+            // nobody listening is not an error
+            self.events.send(Event::Achieved(achievement)).ok();
+        }
+    }
+
+    pub(super) fn save_achievements(&self) -> io::Result<()> {
+        let progress = self
+            .achievements
+            .try_lock()
+            .map_err(|_| io::Error::new(io::ErrorKind::WouldBlock, "achievements are locked"))?
+            .clone();
+        self.save.save_achievements(&progress)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+
+    #[test]
+    fn test_track() {
+        let mut progress = Progress::default();
+        let broken = |broken| Event::BlockBroken { broken };
+        assert_eq!(
+            progress.track(&broken(Block::Stone)),
+            [Achievement::FirstBlock]
+        );
+        // unlocked once
+        assert_eq!(
+            progress.track(&broken(Block::Trunk)),
+            [Achievement::FirstTree]
+        );
+        assert!(progress.track(&broken(Block::Trunk)).is_empty());
+
+        let moved = |from: [f32; 3], to: [f32; 3]| Event::PlayerMoved { from, to };
+        assert!(progress
+            .track(&moved([0.0, 70.0, 0.0], [1.5, 70.0, 2.0]))
+            .is_empty());
+        assert_eq!(progress.traveled, 2.5);
+        // a teleport is not traveling
+        progress.track(&moved([0.0, 70.0, 0.0], [500.0, 70.0, 0.0]));
+        assert_eq!(progress.traveled, 2.5);
+        assert_eq!(
+            progress.track(&moved([0.0, 101.0, 0.0], [0.0, 101.7, 0.0])),
+            [Achievement::Heights]
+        );
+        progress.traveled = TRAVEL - 1.0;
+        assert_eq!(
+            progress.track(&moved([0.0, 70.0, 0.0], [1.0, 70.0, 0.0])),
+            [Achievement::Traveler]
+        );
+        assert_eq!(progress.unlocked.len(), Achievement::ALL.len());
+        for achievement in Achievement::ALL {
+            assert_eq!(achievement.to_string().parse(), Ok(achievement));
+        }
+    }
+
+    #[test]
+    fn test_achieved() {
+        let harness = Harness::empty("achievements");
+        let world = &harness.world;
+        let mut events = world.subscribe();
+        // moving the player goes through the bus
+        let mut player = world.pull_player();
+        player.camera.pos = [0.5, 120.0, 0.5];
+        world.push_player(player);
+        let moved = events.try_recv().unwrap();
+        assert!(matches!(moved, Event::PlayerMoved { .. }));
+        world.track(&moved);
+        assert!(matches!(
+            events.try_recv(),
+            Ok(Event::Achieved(Achievement::Heights))
+        ));
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    hash::{Hash, Hasher},
+    sync::atomic::Ordering,
+};
+
+use def::ChunkCoords;
+
+use super::{ChunkState, World};
+
+/// FNV-1a, whose hashes are the same on every platform and with every
+/// version of Rust (unlike the hasher of the standard library), so they can
+/// be compared with the ones of another build
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+impl World {
+    /// Hash of what the simulation is made of: the clock, the blocks of the
+    /// loaded chunks, the entities, the random numbers and the weather
+    ///
+    /// Two runs from the same world doing the same things must have the
+    /// same hash at the same tick. What is derived from it (the meshes and
+    /// the light) or only read by it (the config) is left out.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = Fnv::default();
+        self.ticks.load(Ordering::Relaxed).hash(&mut hasher);
+        // sorted, as the maps are not in any given order
+        let mut chunks: Vec<ChunkCoords> = self.chunks.iter().map(|chunk| *chunk.key()).collect();
+        chunks.sort_by_key(|cc| (cc.x, cc.z));
+        for cc in chunks {
+            let mut blocks: Vec<_> = match self.chunks.get(&cc).as_deref() {
+                Some(ChunkState::Loaded(blocks) | ChunkState::Meshed(blocks, _)) => blocks
+                    .iter()
+                    .map(|(bi, &block)| (bi.index, block))
+                    .collect(),
+                Some(ChunkState::Compressed(compressed)) => compressed
+                    .decompress()
+                    .into_iter()
+                    .map(|(bi, block)| (bi.index, block))
+                    .collect(),
+                None => continue,
+            };
+            blocks.sort_by_key(|&(index, _)| index);
+            (cc.x, cc.z, blocks).hash(&mut hasher);
+        }
+        let mut entities: Vec<_> = self
+            .entities
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(&id, &entity)| (id, entity))
+            .collect();
+        entities.sort_by_key(|&(id, _)| id);
+        for (id, entity) in entities {
+            let floats = [entity.pos, entity.velocity].map(|v| v.map(f32::to_bits));
+            (
+                id,
+                entity.kind,
+                floats,
+                entity.age,
+                entity.health,
+                entity.hurt,
+            )
+                .hash(&mut hasher);
+        }
+        self.next_entity.load(Ordering::Relaxed).hash(&mut hasher);
+        self.rng.lock().unwrap().hash(&mut hasher);
+        self.weather.read().unwrap().hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Hashes of the world taken every few ticks, each one chained to the
+/// previous ones, so a run is told apart from another by a single line
+pub struct Audit {
+    every: u64,
+    rolling: u64,
+}
+
+impl Audit {
+    pub fn new(every: u64) -> Self {
+        Self {
+            every: every.max(1),
+            rolling: Fnv::default().finish(),
+        }
+    }
+
+    /// Takes the world into account after its tick, returns the tick and
+    /// the rolling hash when it is time to
+    pub fn tick(&mut self, world: &World) -> Option<(u64, u64)> {
+        let ticks = world.ticks();
+        if !ticks.is_multiple_of(self.every) {
+            return None;
+        }
+        let mut hasher = Fnv(self.rolling);
+        world.state_hash().hash(&mut hasher);
+        self.rolling = hasher.finish();
+        Some((ticks, self.rolling))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use def::{Block, BlockCoords};
+
+    use super::*;
+    use crate::{
+        harness::{Harness, Setup},
+        world::{Entity, DEFAULT_STAGES},
+    };
+
+    #[test]
+    fn test_audit() {
+        let runs = ["audit-a", "audit-b"].map(|name| {
+            let setup = Setup {
+                generation: DEFAULT_STAGES,
+                ..Setup::default()
+            };
+            let harness = Harness::with(name, setup);
+            let world = &harness.world;
+            world.spawn_entity(Entity::projectile([8.5, 120.0, 8.5], [0.3, 0.0, -0.2]));
+            let mut audit = Audit::new(20);
+            let hashes: Vec<_> = (0..100)
+                .filter_map(|_| {
+                    world.tick();
+                    audit.tick(world)
+                })
+                .collect();
+            (harness, hashes)
+        });
+        let [(a, a_hashes), (b, b_hashes)] = runs;
+        let (a, b) = (&a.world, &b.world);
+        assert_eq!(a_hashes.len(), 5);
+        assert!(a_hashes.iter().all(|&(tick, _)| tick % 20 == 0));
+        assert_eq!(a_hashes, b_hashes);
+        assert_eq!(a.state_hash(), b.state_hash());
+
+        // a single block apart
+        let bc = BlockCoords::try_from([3, 200, 3]).unwrap();
+        a.place_block(bc, Block::Glass).unwrap();
+        assert_ne!(a.state_hash(), b.state_hash());
+        b.place_block(bc, Block::Glass).unwrap();
+        assert_eq!(a.state_hash(), b.state_hash());
+        // the random numbers drawn too
+        a.rng.lock().unwrap().next();
+        assert_ne!(a.state_hash(), b.state_hash());
+    }
+}
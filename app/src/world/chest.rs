@@ -0,0 +1,169 @@
+use def::{Block, BlockCoords};
+
+use super::World;
+use crate::CmdError;
+
+/// Slots of a chest (3 rows of 9)
+pub const CHEST_SLOTS: usize = 27;
+
+/// Most blocks a slot holds
+pub const MAX_STACK: u8 = 64;
+
+/// Blocks of the same kind in a slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stack {
+    pub block: Block,
+    pub count: u8,
+}
+
+/// The content of a chest, slot by slot
+pub type Slots = [Option<Stack>; CHEST_SLOTS];
+
+impl World {
+    /// The content of the chest at the given coordinates (empty when there
+    /// is no chest)
+    pub fn chest(&self, bc: BlockCoords) -> Slots {
+        self.chests
+            .get(&<[i32; 3]>::from(bc))
+            .map(|slots| *slots)
+            .unwrap_or([None; CHEST_SLOTS])
+    }
+
+    /// The chests holding blocks, by coordinates (the empty ones are not
+    /// kept)
+    pub fn chests(&self) -> Vec<([i32; 3], Slots)> {
+        self.chests
+            .iter()
+            .map(|chest| (*chest.key(), *chest.value()))
+            .collect()
+    }
+
+    /// Puts one block in a slot of a chest, empty or holding the same block
+    ///
+    /// Returns how many the slot holds now.
+    pub fn chest_put(&self, bc: BlockCoords, slot: usize, block: Block) -> Result<u8, CmdError> {
+        self.check_chest(bc, slot)?;
+        let mut slots = self.chests.entry(bc.into()).or_insert([None; CHEST_SLOTS]);
+        match &mut slots[slot] {
+            Some(stack) if stack.block == block && stack.count < MAX_STACK => {
+                stack.count += 1;
+                Ok(stack.count)
+            }
+            // the chest was not empty, as the slot holds something
+            Some(stack) => Err(CmdError::SlotFull(slot, stack.block)),
+            empty => {
+                *empty = Some(Stack { block, count: 1 });
+                Ok(1)
+            }
+        }
+    }
+
+    /// Takes one block from a slot of a chest
+    ///
+    /// Returns the block and how many are left in the slot.
+    pub fn chest_take(&self, bc: BlockCoords, slot: usize) -> Result<(Block, u8), CmdError> {
+        self.check_chest(bc, slot)?;
+        let key = <[i32; 3]>::from(bc);
+        let mut slots = self.chests.get_mut(&key).ok_or(CmdError::SlotEmpty(slot))?;
+        let stack = slots[slot].as_mut().ok_or(CmdError::SlotEmpty(slot))?;
+        stack.count -= 1;
+        let taken = (stack.block, stack.count);
+        if stack.count == 0 {
+            slots[slot] = None;
+        }
+        drop(slots);
+        self.chests
+            .remove_if(&key, |_, slots| slots.iter().all(Option::is_none));
+        Ok(taken)
+    }
+
+    /// Whether there is a chest with this slot at the given coordinates
+    fn check_chest(&self, bc: BlockCoords, slot: usize) -> Result<(), CmdError> {
+        if self.get_block(bc) != Some(Some(Block::Chest)) {
+            return Err(CmdError::NotAChest(bc));
+        }
+        if slot >= CHEST_SLOTS {
+            return Err(CmdError::InvalidSlot(slot));
+        }
+        Ok(())
+    }
+
+    /// Forgets the content of a chest which is not there anymore
+    pub(super) fn forget_chest(&self, bc: BlockCoords) {
+        self.chests.remove(&<[i32; 3]>::from(bc));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use def::ChunkCoords;
+
+    use super::*;
+    use crate::{
+        harness::Harness,
+        world::{Author, BlocksChunk, ChunkState, FacesChunk},
+    };
+
+    #[test]
+    fn test_put_take() {
+        let harness = Harness::empty("chest");
+        let world = &harness.world;
+        let at = BlockCoords::try_from([1, 10, 1]).unwrap();
+        let chunk = BlocksChunk::from([(at.1, Block::Chest)]);
+        world.chunks.insert(
+            ChunkCoords { x: 0, z: 0 },
+            ChunkState::Meshed(chunk, FacesChunk::new()),
+        );
+
+        assert_eq!(world.chest_put(at, 3, Block::Sand), Ok(1));
+        assert_eq!(world.chest_put(at, 3, Block::Sand), Ok(2));
+        assert_eq!(
+            world.chest_put(at, 3, Block::Glass),
+            Err(CmdError::SlotFull(3, Block::Sand))
+        );
+        assert_eq!(
+            world.chest_put(at, CHEST_SLOTS, Block::Sand),
+            Err(CmdError::InvalidSlot(CHEST_SLOTS))
+        );
+        assert_eq!(world.chest(at)[3].map(|stack| stack.count), Some(2));
+        // a chest holding blocks can't be broken
+        assert_eq!(world.remove_block(at), Err(CmdError::ChestNotEmpty(at)));
+
+        assert_eq!(world.chest_take(at, 3), Ok((Block::Sand, 1)));
+        assert_eq!(world.chest_take(at, 3), Ok((Block::Sand, 0)));
+        assert_eq!(world.chest_take(at, 3), Err(CmdError::SlotEmpty(3)));
+        // the empty chests are not kept
+        assert!(world.chests().is_empty());
+        assert_eq!(world.remove_block(at), Ok(Block::Chest));
+        assert_eq!(
+            world.chest_put(at, 0, Block::Sand),
+            Err(CmdError::NotAChest(at))
+        );
+    }
+
+    #[test]
+    fn test_full_stack() {
+        let harness = Harness::empty("chest-full");
+        let world = &harness.world;
+        let at = BlockCoords::try_from([1, 10, 1]).unwrap();
+        let chunk = BlocksChunk::from([(at.1, Block::Chest)]);
+        world.chunks.insert(
+            ChunkCoords { x: 0, z: 0 },
+            ChunkState::Meshed(chunk, FacesChunk::new()),
+        );
+        for _ in 0..MAX_STACK {
+            world.chest_put(at, 0, Block::Brick).unwrap();
+        }
+        assert_eq!(
+            world.chest_put(at, 0, Block::Brick),
+            Err(CmdError::SlotFull(0, Block::Brick))
+        );
+        // an explosion spares it, and the content is forgotten once it is
+        // replaced
+        assert!(world.remove_blocks(&[at], Author::Explosion).is_empty());
+        world
+            .replace_block(at, Block::Stone, Author::Player)
+            .unwrap();
+        assert!(world.chests().is_empty());
+    }
+}
@@ -0,0 +1,190 @@
+use def::Boxel;
+use mat::VectorTrait;
+
+use super::{Entity, EntityId, EntityKind, Event, World, WorldSound};
+
+/// Ticks during which an entity just hit can't be hurt again
+const INVULNERABILITY: u32 = 10;
+
+/// Health lost by an entity at each hit
+const DAMAGE: u8 = 2;
+
+/// Speed given to an entity hit, away from the player and a bit upward
+const KNOCKBACK: f32 = 0.3;
+const KNOCKBACK_UP: f32 = 0.12;
+
+/// How far an explosion hurts the player, in radiuses of the explosion
+const BLAST_REACH: f32 = 2.0;
+
+impl Entity {
+    /// The box an attack (or a right click) must point at
+    pub fn hit_box(&self) -> Boxel {
+        match self.kind {
+            EntityKind::Boat => {
+                let [w, h, d] = Self::BOAT_SIZE;
+                Boxel::new([w, h, d], [w / 2.0, 0.0, d / 2.0], self.pos)
+            }
+            EntityKind::Mob => Boxel::new([Self::MOB_SIZE; 3], [Self::MOB_SIZE / 2.0; 3], self.pos),
+            EntityKind::Item(_) => {
+                Boxel::new([Self::ITEM_SIZE; 3], [Self::ITEM_SIZE / 2.0; 3], self.pos)
+            }
+            EntityKind::Projectile | EntityKind::PrimedTnt => {
+                Boxel::new([1.0; 3], [0.5; 3], self.pos)
+            }
+        }
+    }
+
+    pub fn is_hurt(&self) -> bool {
+        self.hurt > 0
+    }
+}
+
+impl World {
+    /// Hurts the player caught in an explosion, telling where it came from
+    /// (with a [`Event::PlayerHurt`])
+    pub(super) fn blast_player(&self, pos: [f32; 3], radius: f32) {
+        let eyes = self.player.read().unwrap().camera.pos;
+        let offset = eyes.vector_sub(pos);
+        let reach = radius * BLAST_REACH;
+        if offset.vector_dot(offset) <= reach * reach {
+            self.play(WorldSound::Hurt { pos: eyes });
+            self.events.send(Event::PlayerHurt { from: pos }).ok();
+        }
+    }
+
+    /// The player hits an entity, looking toward `direction`
+    ///
+    /// It is pushed away and loses some health, unless it was hurt too
+    /// recently. It is removed when it has no health left. Returns the
+    /// health it lost, if it was hurt.
+    pub fn attack(&self, id: EntityId, direction: [f32; 3]) -> Option<u8> {
+        let mut entities = self.entities.lock().unwrap();
+        let entity = entities.get_mut(&id)?;
+        if entity.kind.max_health() == 0 || entity.is_hurt() {
+            return None;
+        }
+        let damage = DAMAGE.min(entity.health);
+        entity.health -= damage;
+        entity.hurt = INVULNERABILITY;
+        let [x, _, z] = direction;
+        let length = x.hypot(z);
+        if length > 0.0 {
+            entity.velocity[0] += x / length * KNOCKBACK;
+            entity.velocity[2] += z / length * KNOCKBACK;
+        }
+        entity.velocity[1] = KNOCKBACK_UP;
+        if entity.health == 0 {
+            tracing::debug!(kind = ?entity.kind, "entity killed");
+            entities.remove(&id);
+        }
+        Some(damage)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use def::{Block, BlockCoords, ChunkCoords};
+
+    use super::*;
+    use crate::{
+        config::Config,
+        harness::{Harness, Setup},
+        world::{BlocksChunk, ChunkState, FacesChunk, GameMode, RayHit, REACH},
+    };
+
+    #[test]
+    fn test_attack() {
+        // no other mob spawns around
+        let config = Config {
+            spawning: false,
+            ..Config::default()
+        };
+        let harness = Harness::with(
+            "combat",
+            Setup {
+                config,
+                mode: GameMode::Survival,
+                radius: None,
+                ..Setup::default()
+            },
+        );
+        let world = &harness.world;
+        // a stone floor, with a pillar at x = 10
+        let floor = (0..16).flat_map(|x| {
+            (0..16).flat_map(move |z| {
+                let top = if x == 10 { 13 } else { 9 };
+                (9..=top).map(move |y| (BlockCoords::try_from([x, y, z]).unwrap().1, Block::Stone))
+            })
+        });
+        world.chunks.insert(
+            ChunkCoords { x: 0, z: 0 },
+            ChunkState::Meshed(BlocksChunk::from_iter(floor), FacesChunk::new()),
+        );
+        let id = world.spawn_entity(Entity::mob(BlockCoords::try_from([5, 10, 5]).unwrap()));
+        for _ in 0..10 {
+            world.tick();
+        }
+        // the player stands at x = 2, looking toward the mob
+        let eyes = [2.5, 11.6, 5.5];
+        let toward = [1.0, -0.3, 0.0];
+        let hit = world.raycast(eyes, toward, REACH);
+        assert!(matches!(hit, Some(RayHit::Entity { id: hit, .. }) if hit == id));
+        // nothing is hit through the pillar
+        let hit = world.raycast([12.5, 11.6, 5.5], [-1.0, -0.2, 0.0], REACH);
+        assert!(matches!(hit, Some(RayHit::Block { .. })), "{hit:?}");
+
+        assert_eq!(world.attack(id, toward), Some(DAMAGE));
+        let mob = world.entity(id).unwrap();
+        assert_eq!(mob.health, EntityKind::Mob.max_health() - DAMAGE);
+        assert!(mob.is_hurt() && mob.velocity[0] > 0.0);
+        // invulnerable for a while
+        assert_eq!(world.attack(id, toward), None);
+        for _ in 0..INVULNERABILITY {
+            world.tick();
+        }
+        assert!(!world.entity(id).unwrap().is_hurt());
+        for _ in 0..30 {
+            world.tick();
+        }
+        // knocked back, and on the ground again
+        let mob = world.entity(id).unwrap();
+        assert!(mob.pos[0] > 6.0, "{:?}", mob.pos);
+        assert_eq!(mob.pos[1], 10.0 + Entity::MOB_SIZE / 2.0);
+
+        while world.attack(id, toward).is_some() {
+            for _ in 0..INVULNERABILITY {
+                world.tick();
+            }
+        }
+        assert!(world.entity(id).is_none());
+    }
+
+    #[test]
+    fn test_blast() {
+        let setup = Setup {
+            mode: GameMode::Survival,
+            radius: None,
+            ..Setup::default()
+        };
+        let mut harness = Harness::with("blast", setup);
+        harness.teleport([0.0, 20.0, 0.0]);
+        harness.events();
+        let mut sounds = harness.world.listen();
+        harness.world.blast_player([10.0, 20.0, 0.0], 4.0);
+        assert!(harness.events().is_empty());
+
+        harness.world.blast_player([5.0, 20.0, 0.0], 4.0);
+        assert!(matches!(
+            harness.events()[..],
+            [Event::PlayerHurt {
+                from: [5.0, 20.0, 0.0]
+            }]
+        ));
+        assert_eq!(
+            sounds.try_recv(),
+            Ok(WorldSound::Hurt {
+                pos: [0.0, 20.0, 0.0]
+            })
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use def::{Block, BlockIndex, ChunkCoords};
+
+use super::{BlocksChunk, ChunkLoader, ChunkState, World};
+
+/// A chunk packed as runs of identical blocks (or of air)
+///
+/// Blocks are taken in the order of their index, so a run is often a whole
+/// layer of stone or of air: a chunk of terrain is a few hundred runs of
+/// 4 bytes instead of thousands of map entries. Reading a block is a binary
+/// search over the runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressedChunk {
+    /// Index of the first block of each run, and its block
+    runs: Vec<(u16, Option<Block>)>,
+}
+
+impl CompressedChunk {
+    pub fn compress(blocks: &BlocksChunk) -> Self {
+        let mut runs: Vec<(u16, Option<Block>)> = Vec::new();
+        for bi in BlockIndex::ALL {
+            let block = blocks.get(&bi).copied();
+            if runs.last().map(|&(_, last)| last) != Some(block) {
+                runs.push((bi.index, block));
+            }
+        }
+        runs.shrink_to_fit();
+        Self { runs }
+    }
+
+    pub fn decompress(&self) -> BlocksChunk {
+        let mut blocks = BlocksChunk::new();
+        for (i, &(start, block)) in self.runs.iter().enumerate() {
+            let Some(block) = block else {
+                continue;
+            };
+            let end = self
+                .runs
+                .get(i + 1)
+                .map_or(1 << 16, |&(next, _)| next as u32);
+            for index in start as u32..end {
+                blocks.insert(
+                    BlockIndex {
+                        index: index as u16,
+                    },
+                    block,
+                );
+            }
+        }
+        blocks
+    }
+
+    /// Bytes taken by the runs
+    pub(super) fn bytes(&self) -> usize {
+        self.runs.capacity() * std::mem::size_of::<(u16, Option<Block>)>()
+    }
+
+    pub fn get(&self, BlockIndex { index }: BlockIndex) -> Option<Block> {
+        // the first run always starts at index 0
+        let run = self.runs.partition_point(|&(start, _)| start <= index) - 1;
+        self.runs[run].1
+    }
+}
+
+impl World {
+    /// Packs the chunks kept by none of the loaders
+    ///
+    /// Far meshed chunks go back to the loaded stage (they are not rendered
+    /// anymore), and are meshed again when a loader comes back. Their
+    /// entities stop moving until then. The saves read for far chunks not
+    /// loaded yet are dropped. Returns the number of chunks compressed.
+    pub fn compress_far_chunks(&self, loaders: &[ChunkLoader]) -> usize {
+        let kept = |cc: ChunkCoords| loaders.iter().any(|loader| loader.keeps(cc));
+        // collected first, as updating while iterating would deadlock
+        let far: Vec<ChunkCoords> = self
+            .chunks
+            .iter()
+            .filter(|chunk| !kept(*chunk.key()))
+            .filter(|chunk| !matches!(chunk.value(), ChunkState::Compressed(_)))
+            .map(|chunk| *chunk.key())
+            .collect();
+        let mut count = 0;
+        for cc in far {
+            if let Some(mut chunk) = self.chunks.get_mut(&cc) {
+                let compressed = match &*chunk {
+                    ChunkState::Loaded(blocks) | ChunkState::Meshed(blocks, _) => {
+                        CompressedChunk::compress(blocks)
+                    }
+                    ChunkState::Compressed(_) => continue,
+                };
+                *chunk = ChunkState::Compressed(compressed);
+                count += 1;
+            }
+        }
+        if count > 0 {
+            tracing::debug!(count, "far chunks compressed");
+        }
+        self.park_entities(|cc| !kept(cc));
+        // their save is read again when the player comes back
+        self.stored.retain(|&cc, _| kept(cc));
+        count
+    }
+
+    /// Unpacks a chunk, before it is meshed, and wakes its entities up
+    pub(super) fn decompress_chunk(&self, cc: ChunkCoords) {
+        if let Some(mut chunk) = self.chunks.get_mut(&cc) {
+            if let ChunkState::Compressed(compressed) = &*chunk {
+                let blocks = compressed.decompress();
+                *chunk = ChunkState::Loaded(blocks);
+            }
+        }
+        self.wake_entities(cc);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_compress_chunk() {
+        let mut blocks = BlocksChunk::new();
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..40 {
+                    let block = if y < 30 { Block::Stone } else { Block::Dirt };
+                    blocks.insert([x, y, z].try_into().unwrap(), block);
+                }
+            }
+        }
+        blocks.insert([3, 40, 7].try_into().unwrap(), Block::Door(0b1011));
+        blocks.insert([15, 255, 15].try_into().unwrap(), Block::Glass);
+
+        let compressed = CompressedChunk::compress(&blocks);
+        // stone, dirt, air, door, air, glass
+        assert_eq!(compressed.runs.len(), 6);
+        assert_eq!(compressed.decompress(), blocks);
+        for bi in BlockIndex::ALL {
+            assert_eq!(compressed.get(bi), blocks.get(&bi).copied());
+        }
+    }
+}
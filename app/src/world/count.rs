@@ -0,0 +1,110 @@
+use def::{Block, BlockCoords, ChunkCoords};
+
+use super::{BlocksChunk, ChunkState, World};
+
+/// Outcome of [`World::count_blocks_in_region`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegionCount {
+    /// Blocks matching the filter
+    pub blocks: usize,
+    /// Chunks of the region that could not be counted (not loaded)
+    pub missing_chunks: usize,
+}
+
+impl World {
+    /// Counts the blocks of a region (a box, both corners included)
+    /// accepted by the filter
+    ///
+    /// The loaded chunks are counted, and with `stored` the chunks whose
+    /// save was read but which are not loaded yet. The other chunks are
+    /// reported as missing: they would have to be generated to be counted.
+    pub fn count_blocks_in_region(
+        &self,
+        from: BlockCoords,
+        to: BlockCoords,
+        filter: impl Fn(Block) -> bool,
+        stored: bool,
+    ) -> RegionCount {
+        let [from, to]: [[i32; 3]; 2] = [from.into(), to.into()];
+        let min: [i32; 3] = std::array::from_fn(|i| from[i].min(to[i]));
+        let max: [i32; 3] = std::array::from_fn(|i| from[i].max(to[i]));
+        let inside = |bc: BlockCoords, block: Block| {
+            let p: [i32; 3] = bc.into();
+            (0..3).all(|i| (min[i]..=max[i]).contains(&p[i])) && filter(block)
+        };
+        let in_chunk = |cc, blocks: &BlocksChunk| {
+            blocks
+                .iter()
+                .filter(|&(&bi, &block)| inside(BlockCoords(cc, bi), block))
+                .count()
+        };
+        let mut count = RegionCount::default();
+        for x in (min[0] >> 4)..=(max[0] >> 4) {
+            for z in (min[2] >> 4)..=(max[2] >> 4) {
+                let cc = ChunkCoords { x, z };
+                count.blocks += match self.chunks.get(&cc).as_deref() {
+                    Some(ChunkState::Loaded(blocks) | ChunkState::Meshed(blocks, _)) => {
+                        in_chunk(cc, blocks)
+                    }
+                    Some(ChunkState::Compressed(compressed)) => {
+                        in_chunk(cc, &compressed.decompress())
+                    }
+                    None => match self.stored.get(&cc).as_deref() {
+                        Some(Some(blocks)) if stored => in_chunk(cc, blocks),
+                        _ => {
+                            count.missing_chunks += 1;
+                            0
+                        }
+                    },
+                };
+            }
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use def::BlockIndex;
+
+    use super::*;
+    use crate::harness::Harness;
+
+    #[test]
+    fn test_count_blocks() {
+        let harness = Harness::empty("count");
+        let world = &harness.world;
+        let at = |x, y, z| BlockCoords::try_from([x, y, z]).unwrap();
+        let index = |p| BlockIndex::try_from(p).unwrap();
+        let loaded = BlocksChunk::from([
+            (index([1, 10, 1]), Block::Sand),
+            (index([2, 10, 1]), Block::Sand),
+            (index([3, 10, 1]), Block::Stone),
+            (index([1, 50, 1]), Block::Sand),
+        ]);
+        world
+            .chunks
+            .insert(ChunkCoords { x: 0, z: 0 }, ChunkState::Loaded(loaded));
+        let saved = BlocksChunk::from([(index([0, 10, 0]), Block::Sand)]);
+        world.stored.insert(ChunkCoords { x: 1, z: 0 }, Some(saved));
+
+        let sand = |block| block == Block::Sand;
+        // the corners can be given in any order
+        let count = world.count_blocks_in_region(at(20, 20, 0), at(0, 0, 15), sand, false);
+        assert_eq!(
+            count,
+            RegionCount {
+                blocks: 2,
+                missing_chunks: 1
+            }
+        );
+        let count = world.count_blocks_in_region(at(0, 0, 0), at(20, 20, 15), sand, true);
+        assert_eq!(
+            count,
+            RegionCount {
+                blocks: 3,
+                missing_chunks: 0
+            }
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use def::{state, Block, BlockCoords, Direction};
+use mat::VectorTrait;
+
+use super::{other_half, Author, ChunkState, Event, GameMode, World, WorldSound};
+use crate::CmdError;
+
+/// Blocks removed and placed by the player, whose meshes are updated once
+/// the edit is dropped
+///
+/// A script building thousands of blocks would otherwise render the same
+/// sections again for every block, and flood Aristide with messages. The
+/// blocks change right away, only the meshes wait.
+pub struct Edit<'a> {
+    world: &'a World,
+    /// The voxels whose mesh changed
+    changed: Vec<BlockCoords>,
+}
+
+impl World {
+    /// Starts an edit, see [`Edit`]
+    pub fn edit(&self) -> Edit<'_> {
+        Edit {
+            world: self,
+            changed: Vec::new(),
+        }
+    }
+}
+
+impl Edit<'_> {
+    /// Removes the block at the given coordinates and returns it
+    pub fn remove_block(&mut self, bc: BlockCoords) -> Result<Block, CmdError> {
+        let world = self.world;
+        // converts block coordinates to chunk coordinates and block index
+        let BlockCoords(cc, bi) = bc;
+        let block = {
+            // the chunk is only borrowed in this scope, because updating
+            // the light will borrow it (and its neighbours) again
+            let mut chunk = world
+                .chunks
+                .get_mut(&cc)
+                .ok_or(CmdError::ChunkNotLoaded(cc))?;
+            let ChunkState::Meshed(ref mut blocks, _) = *chunk else {
+                return Err(CmdError::ChunkNotLoaded(cc));
+            };
+            let block = *blocks.get(&bi).ok_or(CmdError::NoBlock(bc))?;
+            if !block.is_breakable() {
+                return Err(CmdError::Unbreakable(bc, block));
+            }
+            if world.chests.contains_key(&<[i32; 3]>::from(bc)) {
+                return Err(CmdError::ChestNotEmpty(bc));
+            }
+            blocks.remove(&bi);
+            block
+        };
+        world.record(bc, Some(block), None, Author::Player);
+        world.events.send(Event::BlockBroken { broken: block }).ok();
+        world.play(WorldSound::block(bc, block, false));
+        if world.mode == GameMode::Survival {
+            world.drop_items(bc, block);
+        }
+        world.dirty.insert(cc);
+        self.changed.push(bc);
+        world.relight(bc);
+        // a door does not stay cut in half
+        if let Some(other) = other_half(bc, block) {
+            world.remove_blocks(&[other], Author::Player);
+        }
+        Ok(block)
+    }
+
+    // similar to remove_block
+    //
+    // doors and trapdoors are attached to the side the player looks at,
+    // and a door is placed with its upper half above
+    pub fn place_block(&mut self, bc: BlockCoords, block: Block) -> Result<(), CmdError> {
+        let world = self.world;
+        let BlockCoords(cc, bi) = bc;
+        let facing = world.player_facing();
+        let block = match block {
+            Block::Torch(_) => world.attach_torch(bc, facing)?,
+            Block::Crop(_) if !world.on_farmland(bc) => return Err(CmdError::NoSupport(bc)),
+            Block::Door(_) | Block::Trapdoor(_) | Block::Piston(_) => block.with_facing(facing),
+            block => block,
+        };
+        let upper = match block {
+            Block::Door(state) => {
+                let above = bc.step(Direction::Up).ok_or_else(|| {
+                    CmdError::OutOfWorld(<[i32; 3]>::from(bc).vector_add([0, 1, 0]))
+                })?;
+                Some((above, Block::Door(state | state::UPPER)))
+            }
+            _ => None,
+        };
+        {
+            let mut chunk = world
+                .chunks
+                .get_mut(&cc)
+                .ok_or(CmdError::ChunkNotLoaded(cc))?;
+            let ChunkState::Meshed(ref mut blocks, _) = *chunk else {
+                return Err(CmdError::ChunkNotLoaded(cc));
+            };
+            // the upper half is in the same chunk, as chunks are whole columns
+            for (bc, _) in [(bc, block)].into_iter().chain(upper) {
+                let BlockCoords(_, bi) = bc;
+                if let Some(&present) = blocks.get(&bi) {
+                    return Err(CmdError::Occupied(bc, present));
+                }
+            }
+            blocks.insert(bi, block);
+            if let Some((BlockCoords(_, bi), upper)) = upper {
+                blocks.insert(bi, upper);
+            }
+        }
+        for (bc, block) in [(bc, block)].into_iter().chain(upper) {
+            world.record(bc, None, Some(block), Author::Player);
+            self.changed.push(bc);
+            world.relight(bc);
+        }
+        world.play(WorldSound::block(bc, block, true));
+        world.dirty.insert(cc);
+        Ok(())
+    }
+}
+
+impl Drop for Edit<'_> {
+    fn drop(&mut self) {
+        self.world.update_many(&self.changed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use def::ChunkCoords;
+
+    use super::*;
+    use crate::{harness::Harness, world::FacesChunk};
+
+    #[test]
+    fn test_edit() {
+        let mut harness = Harness::empty("edit");
+        let cc = ChunkCoords { x: 0, z: 0 };
+        harness.world.chunks.insert(
+            cc,
+            ChunkState::Meshed(Default::default(), FacesChunk::new()),
+        );
+        let at = |x| BlockCoords::try_from([x, 10, 3]).unwrap();
+        // the edit holds the world while the harness reads what is rendered
+        let world = harness.world.clone();
+        let mut edit = world.edit();
+        for x in 1..15 {
+            edit.place_block(at(x), Block::Stone).unwrap();
+        }
+        edit.remove_block(at(7)).unwrap();
+        assert_eq!(
+            edit.place_block(at(8), Block::Dirt),
+            Err(CmdError::Occupied(at(8), Block::Stone))
+        );
+        // the blocks are there, but not their mesh yet
+        assert_eq!(world.get_block(at(3)), Some(Some(Block::Stone)));
+        assert!(harness.render_requests().is_empty());
+        drop(edit);
+        // the chunk is rendered again once
+        let rendered: Vec<_> = harness
+            .render_requests()
+            .into_iter()
+            .map(|(cc, _)| cc)
+            .collect();
+        assert_eq!(rendered, [cc]);
+        let faces = match world.chunks.get(&cc).as_deref() {
+            Some(ChunkState::Meshed(_, faces)) => faces.len(),
+            _ => panic!("not meshed"),
+        };
+        // two rows of stone, with the faces along them and at their ends
+        assert_eq!(faces, 13 * 4 + 4);
+    }
+}
@@ -0,0 +1,534 @@
+use std::{collections::HashMap, io};
+
+use def::{Block, BlockCoords, ChunkCoords, Direction, RayTravel};
+use mat::VectorTrait;
+
+use super::{Achievement, World};
+
+pub type EntityId = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntityKind {
+    /// Thrown by the player, it breaks on the first block hit
+    Projectile,
+    /// Ignited TNT, it explodes when its fuse is burnt
+    PrimedTnt,
+    /// A hostile creature, spawned in the dark (see `spawn_mobs`)
+    Mob,
+    /// A block dropped when the player broke one
+    Item(Block),
+    /// Floats on water, the player can ride it (see `World::mount`)
+    Boat,
+}
+
+/// Anything moving in the world which is not a block (nor the player)
+#[derive(Debug, Clone, Copy)]
+pub struct Entity {
+    pub kind: EntityKind,
+    pub pos: [f32; 3],
+    /// Distance travelled in one tick
+    pub velocity: [f32; 3],
+    /// Number of ticks since the entity was spawned
+    pub age: u32,
+    /// Hits it can still take (see `World::attack`)
+    pub health: u8,
+    /// Ticks left before it can be hurt again, it flashes red meanwhile
+    pub hurt: u32,
+}
+
+/// Something that happened in the world, for whoever is interested
+#[derive(Debug, Clone, Copy)]
+pub enum Event {
+    /// An entity hit a block (through the given face)
+    Impact {
+        kind: EntityKind,
+        block: BlockCoords,
+        face: Direction,
+    },
+    /// Every block in the sphere is destroyed
+    Explosion {
+        pos: [f32; 3],
+        radius: f32,
+    },
+    /// The player broke a block
+    BlockBroken {
+        broken: Block,
+    },
+    /// The eyes of the player moved (walking, falling, riding...)
+    PlayerMoved {
+        from: [f32; 3],
+        to: [f32; 3],
+    },
+    Achieved(Achievement),
+    /// The player opened a chest
+    ChestOpened(BlockCoords),
+    /// The player was hurt by something at the given position
+    PlayerHurt {
+        from: [f32; 3],
+    },
+}
+
+/// What happened to an entity during a tick
+pub enum Step {
+    Moved,
+    /// The entity is removed after causing the event
+    Done(Event),
+    /// Too old or out of the world
+    Vanished,
+}
+
+impl EntityKind {
+    /// Hits taken before dying (0 for the entities which can't be hit)
+    pub fn max_health(self) -> u8 {
+        match self {
+            Self::Mob => 6,
+            Self::Boat => 4,
+            _ => 0,
+        }
+    }
+}
+
+impl Entity {
+    /// Speed of a thrown projectile (in blocks per tick)
+    const THROW_SPEED: f32 = 0.8;
+    /// A projectile which never hit anything vanishes after 10 seconds
+    const MAX_AGE: u32 = 600;
+    /// Ticks between ignition and explosion of TNT
+    const FUSE: u32 = 80;
+    const EXPLOSION_RADIUS: f32 = 3.5;
+    /// A dropped item vanishes after 5 minutes
+    const ITEM_AGE: u32 = 18_000;
+    /// Side of the cube of a mob, its position being its middle
+    pub const MOB_SIZE: f32 = 0.9;
+    /// Part of its horizontal speed a mob keeps from a tick to the next
+    const MOB_DRAG: f32 = 0.7;
+    /// Side of the cube of a dropped item
+    pub const ITEM_SIZE: f32 = 0.25;
+    /// Size of the hull of a boat, its position being the middle of its bottom
+    pub const BOAT_SIZE: [f32; 3] = [1.2, 0.4, 1.2];
+    /// Depth of the bottom of a floating boat under the surface
+    const BOAT_DRAFT: f32 = 0.15;
+    /// Part of its speed a boat keeps from a tick to the next
+    const BOAT_DRAG: f32 = 0.9;
+    /// Vertical speed of a boat rising from under the water
+    const BOAT_RISE: f32 = 0.05;
+
+    /// A projectile thrown from `pos` toward `direction`
+    pub fn projectile(pos: [f32; 3], direction: [f32; 3]) -> Self {
+        let length = direction.vector_dot(direction).sqrt();
+        Self {
+            kind: EntityKind::Projectile,
+            pos,
+            velocity: direction.vector_scale(Self::THROW_SPEED / length),
+            age: 0,
+            health: EntityKind::Projectile.max_health(),
+            hurt: 0,
+        }
+    }
+
+    /// Ignited TNT, in place of the TNT block
+    pub fn primed_tnt(bc: BlockCoords) -> Self {
+        Self {
+            kind: EntityKind::PrimedTnt,
+            pos: <[f32; 3]>::from(bc).vector_add([0.5; 3]),
+            velocity: [0.0; 3],
+            age: 0,
+            health: EntityKind::PrimedTnt.max_health(),
+            hurt: 0,
+        }
+    }
+
+    /// A mob standing in the voxel
+    pub fn mob(bc: BlockCoords) -> Self {
+        Self {
+            kind: EntityKind::Mob,
+            pos: <[f32; 3]>::from(bc).vector_add([0.5; 3]),
+            velocity: [0.0; 3],
+            age: 0,
+            health: EntityKind::Mob.max_health(),
+            hurt: 0,
+        }
+    }
+
+    /// A dropped block, in the middle of the voxel it was broken from
+    pub fn item(bc: BlockCoords, block: Block) -> Self {
+        Self {
+            kind: EntityKind::Item(block),
+            pos: <[f32; 3]>::from(bc).vector_add([0.5; 3]),
+            velocity: [0.0; 3],
+            age: 0,
+            health: EntityKind::Item(block).max_health(),
+            hurt: 0,
+        }
+    }
+
+    /// A boat with its bottom at `pos`
+    pub fn boat(pos: [f32; 3]) -> Self {
+        Self {
+            kind: EntityKind::Boat,
+            pos,
+            velocity: [0.0; 3],
+            age: 0,
+            health: EntityKind::Boat.max_health(),
+            hurt: 0,
+        }
+    }
+
+    /// Moves the entity by one tick
+    ///
+    /// The voxels crossed during the tick are checked one by one, so a fast
+    /// entity can't go through a thin wall.
+    pub fn step(
+        &mut self,
+        gravity: f32,
+        get_block: impl Fn(BlockCoords) -> Option<Option<Block>>,
+    ) -> Step {
+        self.age += 1;
+        self.hurt = self.hurt.saturating_sub(1);
+        if self.kind == EntityKind::PrimedTnt {
+            return if self.age < Self::FUSE {
+                Step::Moved
+            } else {
+                Step::Done(Event::Explosion {
+                    pos: self.pos,
+                    radius: Self::EXPLOSION_RADIUS,
+                })
+            };
+        }
+        self.velocity[1] += gravity;
+        match self.kind {
+            EntityKind::Mob => {
+                // knocked back on the ground, it soon stops
+                self.slide(Self::MOB_SIZE / 2.0, 0.0, Self::MOB_DRAG, &get_block);
+                return self.fall(Self::MOB_SIZE / 2.0, get_block);
+            }
+            EntityKind::Item(_) if self.age > Self::ITEM_AGE => return Step::Vanished,
+            EntityKind::Item(_) => return self.fall(Self::ITEM_SIZE / 2.0, get_block),
+            EntityKind::Boat => return self.sail(get_block),
+            _ => {}
+        }
+        for (block, face) in RayTravel::new(self.pos, self.velocity, 1.0).flatten() {
+            if let Some(Some(hit)) = get_block(block) {
+                if !hit.is_solid() {
+                    continue;
+                }
+                return Step::Done(Event::Impact {
+                    kind: self.kind,
+                    block,
+                    face,
+                });
+            }
+        }
+        self.pos.vector_add_assign(self.velocity);
+        if self.age > Self::MAX_AGE || self.pos[1] < 0.0 {
+            Step::Vanished
+        } else {
+            Step::Moved
+        }
+    }
+}
+
+impl Entity {
+    /// Moves horizontally, unless the front (`radius` away from the position,
+    /// `height` above it) would enter a solid block or an unloaded chunk,
+    /// then keeps the `drag` part of the speed
+    fn slide(
+        &mut self,
+        radius: f32,
+        height: f32,
+        drag: f32,
+        get_block: impl Fn(BlockCoords) -> Option<Option<Block>>,
+    ) {
+        let [vx, _, vz] = self.velocity;
+        let speed = vx.hypot(vz);
+        if speed == 0.0 {
+            return;
+        }
+        let front = [vx, 0.0, vz].vector_scale(radius / speed);
+        let ahead = self.pos.vector_add([vx, height, vz]).vector_add(front);
+        let blocked = match BlockCoords::try_from(ahead).ok().map(get_block) {
+            Some(Some(Some(block))) => block.is_solid() && block.fluid_height().is_none(),
+            Some(None) => true,
+            _ => false,
+        };
+        if blocked {
+            self.velocity[0] = 0.0;
+            self.velocity[2] = 0.0;
+        } else {
+            self.pos.vector_add_assign([vx, 0.0, vz]);
+        }
+        self.velocity[0] *= drag;
+        self.velocity[2] *= drag;
+    }
+
+    /// Falls until standing on a solid block, the position being `height`
+    /// above the bottom of the entity
+    fn fall(
+        &mut self,
+        height: f32,
+        get_block: impl Fn(BlockCoords) -> Option<Option<Block>>,
+    ) -> Step {
+        let feet = self.pos.vector_add([0.0, self.velocity[1] - height, 0.0]);
+        let ground = BlockCoords::try_from(feet)
+            .ok()
+            .and_then(get_block)
+            .flatten()
+            .filter(|block| block.is_solid());
+        if ground.is_some() {
+            // it lands on top of the block
+            self.pos[1] = feet[1].floor() + 1.0 + height;
+            self.velocity[1] = 0.0;
+        } else {
+            self.pos[1] += self.velocity[1];
+        }
+        if self.pos[1] < 0.0 {
+            Step::Vanished
+        } else {
+            Step::Moved
+        }
+    }
+
+    /// Goes where it was steered until a solid block stops it, floating on
+    /// water (or falling like the other entities out of it)
+    fn sail(&mut self, get_block: impl Fn(BlockCoords) -> Option<Option<Block>>) -> Step {
+        let block = |pos: [f32; 3]| BlockCoords::try_from(pos).ok().map(&get_block);
+        // the hull is blocked above the water line
+        self.slide(
+            Self::BOAT_SIZE[0] / 2.0,
+            Self::BOAT_DRAFT,
+            Self::BOAT_DRAG,
+            &get_block,
+        );
+        let fluid = |pos| block(pos).flatten().flatten().and_then(Block::fluid_height);
+        if fluid(self.pos.vector_add([0.0, 1.0, 0.0])).is_some() {
+            // sunk under the surface, it rises
+            self.velocity[1] = 0.0;
+            self.pos[1] += Self::BOAT_RISE;
+        } else if let Some(height) = fluid(self.pos) {
+            self.velocity[1] = 0.0;
+            self.pos[1] = self.pos[1].floor() + (height - Self::BOAT_DRAFT).max(0.0);
+        } else if fluid(self.pos.vector_add([0.0, self.velocity[1], 0.0])).is_some() {
+            // falling in the water (which stops the other entities)
+            self.pos[1] += self.velocity[1];
+        } else {
+            return self.fall(0.0, get_block);
+        }
+        Step::Moved
+    }
+}
+
+impl World {
+    /// Drops what the player gets from breaking a block (see
+    /// [`Block::drops`]), where the block was
+    pub(super) fn drop_items(&self, bc: BlockCoords, block: Block) {
+        let dropped: Vec<Block> = {
+            let mut rng = self.rng.lock().unwrap();
+            block
+                .drops()
+                .iter()
+                .filter(|(_, chance)| (rng.below(1000) as f32) < chance * 1000.0)
+                .map(|&(item, _)| item)
+                .collect()
+        };
+        for item in dropped {
+            self.spawn_entity(Entity::item(bc, item));
+        }
+    }
+
+    /// Spawns the saved entities of a chunk being loaded
+    pub(super) fn load_entities(&self, cc: ChunkCoords) {
+        let saved = self.save.load_entities(cc);
+        if !saved.is_empty() {
+            self.entity_chunks.insert(cc);
+        }
+        for entity in saved {
+            self.spawn_entity(entity);
+        }
+        self.wake_entities(cc);
+    }
+
+    /// Freezes the entities in the chunks matching the filter
+    pub(super) fn park_entities(&self, far: impl Fn(ChunkCoords) -> bool) {
+        self.entities.lock().unwrap().retain(|_, entity| {
+            let cc = ChunkCoords::from_position(entity.pos);
+            if far(cc) {
+                self.parked.entry(cc).or_default().push(*entity);
+            }
+            !far(cc)
+        });
+    }
+
+    /// Lets the frozen entities of a chunk move again
+    pub(super) fn wake_entities(&self, cc: ChunkCoords) {
+        if let Some((_, parked)) = self.parked.remove(&cc) {
+            for entity in parked {
+                self.spawn_entity(entity);
+            }
+        }
+    }
+
+    /// Writes the entities of the loaded chunks, moving or frozen
+    ///
+    /// An entity out of the loaded chunks is not saved, as it would replace
+    /// the saved entities of its chunk, which were never read.
+    pub(super) fn save_entities(&self) -> io::Result<()> {
+        let mut by_chunk: HashMap<ChunkCoords, Vec<Entity>> = HashMap::new();
+        let entities = self
+            .entities
+            .try_lock()
+            .map_err(|_| io::Error::new(io::ErrorKind::WouldBlock, "entities are locked"))?;
+        for entity in entities.values() {
+            let cc = ChunkCoords::from_position(entity.pos);
+            by_chunk.entry(cc).or_default().push(*entity);
+        }
+        drop(entities);
+        for parked in self.parked.iter() {
+            by_chunk
+                .entry(*parked.key())
+                .or_default()
+                .extend(parked.value());
+        }
+        by_chunk.retain(|cc, _| self.chunks.contains_key(cc));
+        // the chunks whose entities are all gone are saved empty
+        for cc in self.entity_chunks.iter().map(|cc| *cc).collect::<Vec<_>>() {
+            if !by_chunk.contains_key(&cc) {
+                self.save.save_entities(cc, &[])?;
+                self.entity_chunks.remove(&cc);
+            }
+        }
+        for (cc, entities) in by_chunk {
+            self.save.save_entities(cc, &entities)?;
+            self.entity_chunks.insert(cc);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        harness::{Harness, Setup},
+        world::{BlocksChunk, ChunkState, FacesChunk, GameMode, Physics},
+    };
+
+    #[test]
+    fn test_projectile_hits_wall() {
+        let gravity = Physics::default().gravity;
+        // a wall at x = 5, thrown from x = 0.5 toward positive x
+        let wall = |bc: BlockCoords| {
+            let [x, _, _]: [i32; 3] = bc.into();
+            Some((x == 5).then_some(Block::Stone))
+        };
+        let mut projectile = Entity::projectile([0.5, 10.5, 0.5], [1.0, 0.0, 0.0]);
+        let event = loop {
+            match projectile.step(gravity, wall) {
+                Step::Moved => assert!(projectile.age < 20),
+                Step::Done(event) => break event,
+                Step::Vanished => panic!("went through the wall"),
+            }
+        };
+        let Event::Impact { block, face, .. } = event else {
+            panic!("expected an impact");
+        };
+        assert_eq!(<[i32; 3]>::from(block)[0], 5);
+        assert_eq!(face, Direction::West);
+        // it falls while flying
+        assert!(projectile.pos[1] < 10.5);
+    }
+
+    #[test]
+    fn test_tnt_fuse() {
+        let gravity = Physics::default().gravity;
+        let mut tnt = Entity::primed_tnt(BlockCoords::try_from([0, 10, 0]).unwrap());
+        let mut ticks = 1;
+        while let Step::Moved = tnt.step(gravity, |_| Some(None)) {
+            ticks += 1;
+        }
+        assert_eq!(ticks, Entity::FUSE);
+        // it stays where the block was
+        assert_eq!(tnt.pos, [0.5, 10.5, 0.5]);
+    }
+
+    #[test]
+    fn test_drops() {
+        let harness = |name, mode| {
+            let setup = Setup {
+                mode,
+                radius: None,
+                ..Setup::default()
+            };
+            let harness = Harness::with(name, setup);
+            let BlockCoords(cc, bi) = BlockCoords::try_from([0, 10, 0]).unwrap();
+            let chunk = BlocksChunk::from([(bi, Block::Grass)]);
+            harness
+                .world
+                .chunks
+                .insert(cc, ChunkState::Meshed(chunk, FacesChunk::new()));
+            harness
+        };
+        let bc = BlockCoords::try_from([0, 10, 0]).unwrap();
+        let survival = harness("drops-survival", GameMode::Survival);
+        survival.world.remove_block(bc).unwrap();
+        let items = survival.world.pull_entities();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].kind, EntityKind::Item(Block::Dirt));
+        // it falls to the ground, nothing being below
+        let mut item = items[0];
+        let gravity = Physics::default().gravity;
+        let floor = |bc: BlockCoords| Some((<[i32; 3]>::from(bc)[1] == 5).then_some(Block::Stone));
+        for _ in 0..100 {
+            assert!(matches!(item.step(gravity, floor), Step::Moved));
+        }
+        assert_eq!(item.pos[1], 6.0 + Entity::ITEM_SIZE / 2.0);
+
+        let creative = harness("drops-creative", GameMode::Creative);
+        creative.world.remove_block(bc).unwrap();
+        assert!(creative.world.pull_entities().is_empty());
+    }
+
+    #[test]
+    fn test_entity_persistence() {
+        let mut harness = Harness::empty("entities");
+        let world = &harness.world;
+        let near = ChunkCoords { x: 0, z: 0 };
+        let far = ChunkCoords { x: 9, z: 0 };
+        for cc in [near, far] {
+            world
+                .chunks
+                .insert(cc, ChunkState::Loaded(BlocksChunk::new()));
+        }
+        let tnt = Entity::primed_tnt(BlockCoords::try_from([1, 10, 1]).unwrap());
+        world.spawn_entity(tnt);
+        world.spawn_entity(Entity::projectile([148.0, 20.0, 3.0], [0.0, 1.0, 0.0]));
+
+        // far from every loader, the projectile is frozen
+        let loaders = [crate::world::ChunkLoader {
+            center: near,
+            range: 2,
+        }];
+        world.compress_far_chunks(&loaders);
+        assert_eq!(world.pull_entities().len(), 1);
+        world.tick();
+        assert_eq!(world.parked.get(&far).unwrap()[0].age, 0);
+        world.save_entities().unwrap();
+        world.decompress_chunk(far);
+        assert_eq!(world.pull_entities().len(), 2);
+
+        // the entities come back with their chunk
+        harness.reopen();
+        let world = &harness.world;
+        assert!(world.pull_entities().is_empty());
+        world.chunk_stage_stored_to_loaded(near);
+        let entities = world.pull_entities();
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].kind, EntityKind::PrimedTnt);
+        assert_eq!((entities[0].pos, entities[0].age), (tnt.pos, 1));
+
+        // once gone, they are not saved anymore
+        world.entities.lock().unwrap().clear();
+        world.save_entities().unwrap();
+        assert!(world.save.load_entities(near).is_empty());
+        assert_eq!(world.save.load_entities(far).len(), 1);
+    }
+}
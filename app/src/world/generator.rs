@@ -1,23 +1,52 @@
 use std::collections::HashMap;
 
-use def::{Block, BlockIndex, ChunkCoords};
-use noise::{Fbm, NoiseFn, Perlin};
+use def::{Block, BlockCoords, BlockIndex, ChunkCoords};
+use noise::{Fbm, NoiseFn, Perlin, Seedable};
 
-pub struct Generator {
+use super::BlocksChunk;
+
+mod stages;
+pub use stages::{
+    AmplifiedStage, ArchipelagoStage, BedrockStage, CarverStage, DecorationStage, DensityStage,
+    FlatStage, FloatingIslandsStage, StructureStage, SurfaceStage,
+};
+
+/// Large area sharing the same terrain features
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Biome {
+    Beach,
+    Plains,
+    Mountains,
+}
+
+impl Biome {
+    fn from_altitude(altitude: i32) -> Self {
+        match altitude {
+            ..=10 => Self::Beach,
+            11..=35 => Self::Plains,
+            _ => Self::Mountains,
+        }
+    }
+}
+
+/// Noise functions shared by the stages, all derived from the seed
+pub struct Terrain {
+    seed: u32,
     fbm: Fbm,
     perlin: Perlin,
 }
 
-impl Generator {
-    pub fn new() -> Self {
+impl Terrain {
+    fn new(seed: u32) -> Self {
         Self {
-            fbm: Fbm::new(),
-            perlin: Perlin::new(),
+            seed,
+            fbm: Fbm::new().set_seed(seed),
+            perlin: Perlin::new().set_seed(seed),
         }
     }
 
     // determines the altitude at given position
-    fn altitude(&self, x: i32, z: i32) -> i32 {
+    pub fn altitude(&self, x: i32, z: i32) -> i32 {
         let v1 = self.fbm.get([x as f64 / 100.0, z as f64 / 100.0]);
         let v1 = (v1 + 1.0) / 2.0;
         let v2 = self.perlin.get([x as f64 / 500.0, z as f64 / 500.0]);
@@ -26,35 +55,424 @@ impl Generator {
         v as i32
     }
 
+    /// Smooth 2D noise, with details, between -1.0 and 1.0
+    pub fn noise2(&self, position: [f64; 2]) -> f64 {
+        self.fbm.get(position)
+    }
+
+    /// Smooth 3D noise between -1.0 and 1.0
+    pub fn noise3(&self, position: [f64; 3]) -> f64 {
+        self.perlin.get(position)
+    }
+
+    /// A number that looks random but is always the same for a column
+    pub fn hash(&self, x: i32, z: i32) -> u32 {
+        let mut h =
+            self.seed ^ (x as u32).wrapping_mul(0x27d4_eb2d) ^ (z as u32).wrapping_mul(0x1656_67b1);
+        h ^= h >> 15;
+        h = h.wrapping_mul(0x2c1b_3c6d);
+        h ^= h >> 12;
+        h
+    }
+}
+
+/// A chunk being generated, passed from stage to stage
+pub struct ChunkGen<'a> {
+    pub cc: ChunkCoords,
+    pub blocks: &'a mut BlocksChunk,
+    /// Altitude of the top block of each column (by x then z), set by the
+    /// stage shaping the terrain (-1 for an empty column)
+    pub surface: [[i32; 16]; 16],
+    /// Blocks of structures over other chunks, placed by the world
+    pub spill: Vec<(BlockCoords, Block)>,
+}
+
+impl ChunkGen<'_> {
+    /// World coordinates of a column of the chunk
+    pub fn column(&self, x: i32, z: i32) -> [i32; 2] {
+        [self.cc.x * 16 + x, self.cc.z * 16 + z]
+    }
+
+    pub fn get(&self, [x, y, z]: [i32; 3]) -> Option<Block> {
+        let bi = [x, y, z].try_into().ok()?;
+        self.blocks.get(&bi).copied()
+    }
+
+    /// Places a block of a structure, only in the air
+    ///
+    /// Positions out of the chunk are kept aside in the spill.
+    pub fn place(&mut self, [x, y, z]: [i32; 3], block: Block) {
+        if let Ok(bi) = BlockIndex::try_from([x, y, z]) {
+            self.blocks.entry(bi).or_insert(block);
+        } else if let Ok(bc) = BlockCoords::try_from([self.cc.x * 16 + x, y, self.cc.z * 16 + z]) {
+            self.spill.push((bc, block));
+        }
+    }
+
+    pub fn set(&mut self, [x, y, z]: [i32; 3], block: Option<Block>) {
+        if let Ok(bi) = [x, y, z].try_into() {
+            match block {
+                Some(block) => self.blocks.insert(bi, block),
+                None => self.blocks.remove(&bi),
+            };
+        }
+    }
+}
+
+/// A step of the generation of a chunk
+///
+/// Stages run one after the other on the same chunk, each one changing
+/// what the previous ones made. They must only depend on the seed and the
+/// chunk coordinates, so a chunk is always generated the same way.
+pub trait GenStage: Send + Sync {
+    /// Altitude of the terrain made by the stage, for the stages shaping it
+    fn altitude(&self, _terrain: &Terrain, _x: i32, _z: i32) -> Option<i32> {
+        None
+    }
+
+    fn generate(&self, terrain: &Terrain, chunk: &mut ChunkGen);
+}
+
+/// Stages used when none are specified, the original terrain
+pub const DEFAULT_STAGES: &str = "density, surface, bedrock";
+
+/// Layers of a superflat world when none are given, from the bottom
+pub const SUPERFLAT_LAYERS: &str = "bedrock, stone, dirt*3, grass";
+
+/// Presets made of stages, by name
+const PRESETS: [(&str, &str); 4] = [
+    ("default", DEFAULT_STAGES),
+    ("amplified", "amplified, surface, trees, bedrock"),
+    ("archipelago", "archipelago, surface, trees, bedrock"),
+    ("floating_islands", "floating, trees"),
+];
+
+pub struct Generator {
+    terrain: Terrain,
+    stages: Vec<Box<dyn GenStage>>,
+}
+
+impl Generator {
+    pub fn new(seed: u32) -> Self {
+        Self::from_spec(seed, DEFAULT_STAGES).unwrap()
+    }
+
+    pub fn with_stages(seed: u32, stages: Vec<Box<dyn GenStage>>) -> Self {
+        Self {
+            terrain: Terrain::new(seed),
+            stages,
+        }
+    }
+
+    /// Builds the pipeline from a preset or a list of stage names
+    ///
+    /// The presets are `default` (the [`DEFAULT_STAGES`]), `amplified`,
+    /// `archipelago`, `floating_islands`, `void` (not a single block) and
+    /// `superflat`, optionally followed by its layers from the bottom, like
+    /// `superflat: stone, dirt*3, grass`. Otherwise the stages are separated
+    /// by commas: a stage shaping the terrain (`density`, `amplified`,
+    /// `archipelago` or `floating`) then `surface`, `caves`, `trees`,
+    /// `structures` and `bedrock`.
+    /// Returns the invalid part on error.
+    pub fn from_spec(seed: u32, spec: &str) -> Result<Self, String> {
+        if let Some((_, stages)) = PRESETS.iter().find(|(name, _)| *name == spec.trim()) {
+            return Self::from_spec(seed, stages);
+        }
+        let (preset, layers) = match spec.split_once(':') {
+            Some((preset, layers)) => (preset.trim(), Some(layers)),
+            None => (spec.trim(), None),
+        };
+        match (preset, layers) {
+            ("void", None) => return Ok(Self::with_stages(seed, Vec::new())),
+            ("superflat", layers) => {
+                let layers = parse_layers(layers.unwrap_or(SUPERFLAT_LAYERS))?;
+                return Ok(Self::with_stages(
+                    seed,
+                    vec![Box::new(FlatStage { layers })],
+                ));
+            }
+            (_, Some(_)) => return Err(preset.to_string()),
+            _ => {}
+        }
+        let stages = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| -> Result<Box<dyn GenStage>, String> {
+                match name {
+                    "density" => Ok(Box::new(DensityStage)),
+                    "amplified" => Ok(Box::new(AmplifiedStage)),
+                    "archipelago" => Ok(Box::new(ArchipelagoStage)),
+                    "floating" => Ok(Box::new(FloatingIslandsStage)),
+                    "surface" => Ok(Box::new(SurfaceStage)),
+                    "caves" => Ok(Box::new(CarverStage)),
+                    "trees" => Ok(Box::new(DecorationStage)),
+                    "structures" => Ok(Box::new(StructureStage)),
+                    "bedrock" => Ok(Box::new(BedrockStage)),
+                    _ => Err(name.to_string()),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self::with_stages(seed, stages))
+    }
+
+    /// Altitude of the generated terrain (-1 where there is none)
+    pub fn altitude(&self, x: i32, z: i32) -> i32 {
+        // the last stage shaping the terrain wins
+        self.stages
+            .iter()
+            .rev()
+            .find_map(|stage| stage.altitude(&self.terrain, x, z))
+            .unwrap_or(-1)
+    }
+
+    pub fn biome(&self, x: i32, z: i32) -> Biome {
+        Biome::from_altitude(self.altitude(x, z))
+    }
+
+    /// Looks for a column near the origin where a player can stand
+    ///
+    /// The column must be flat (not the side of a mountain) with solid
+    /// ground (not water nor the void) and room for the player above.
+    /// Returns the position of the feet, or `None` if no column fits.
+    pub fn find_spawn(&self) -> Option<[i32; 3]> {
+        // a column every few blocks, in squares of growing size
+        const STEP: i32 = 4;
+        const RINGS: i32 = 64;
+        let mut chunks: HashMap<ChunkCoords, BlocksChunk> = HashMap::new();
+        for ring in 0..=RINGS {
+            let columns =
+                (-ring..=ring).flat_map(|i| [[i, -ring], [i, ring], [-ring, i], [ring, i]]);
+            for [x, z] in columns.map(|column| column.map(|v| v * STEP)) {
+                let altitude = self.altitude(x, z);
+                let flat = [[1, 0], [-1, 0], [0, 1], [0, -1]]
+                    .iter()
+                    .all(|[dx, dz]| (self.altitude(x + dx, z + dz) - altitude).abs() <= 1);
+                let Ok(BlockCoords(cc, _)) = BlockCoords::try_from([x, altitude, z]) else {
+                    continue;
+                };
+                if !flat {
+                    continue;
+                }
+                // the trees and the structures are only known once generated
+                let blocks = chunks.entry(cc).or_insert_with(|| {
+                    let mut blocks = BlocksChunk::new();
+                    self.gen_chunk(cc, &mut blocks);
+                    blocks
+                });
+                let get = |y| {
+                    let bi = BlockIndex::try_from([x.rem_euclid(16), y, z.rem_euclid(16)]).ok()?;
+                    blocks.get(&bi).copied()
+                };
+                let ground = get(altitude).is_some_and(|block| block.is_full() && block.is_solid());
+                if ground && get(altitude + 1).is_none() && get(altitude + 2).is_none() {
+                    return Some([x, altitude + 1, z]);
+                }
+            }
+        }
+        None
+    }
+
+    /// Generates a chunk, returning the blocks of its structures that are
+    /// over other chunks
     pub fn gen_chunk(
         &self,
-        ChunkCoords { x: cx, z: cz }: ChunkCoords,
-        blocks: &mut HashMap<BlockIndex, Block>,
-    ) {
-        for bx in 0..16 {
-            for bz in 0..16 {
-                let altitude = self.altitude(cx * 16 + bx, cz * 16 + bz);
-                for y in 0..=altitude {
-                    blocks.insert([bx, y, bz].try_into().unwrap(), {
-                        let deep = (altitude - y) * altitude;
-                        match altitude {
-                            0..=10 => match deep {
-                                0..=30 => Block::Sand,
-                                _ => Block::Stone,
-                            },
-                            11..=35 => match deep {
-                                0 => Block::Grass,
-                                1..=30 => Block::Dirt,
-                                _ => Block::Stone,
-                            },
-                            _ => match deep {
-                                0..=40 => Block::Stone,
-                                _ => Block::Brick,
-                            },
-                        }
-                    });
-                }
+        cc: ChunkCoords,
+        blocks: &mut BlocksChunk,
+    ) -> Vec<(BlockCoords, Block)> {
+        let mut chunk = ChunkGen {
+            cc,
+            blocks,
+            surface: [[-1; 16]; 16],
+            spill: Vec::new(),
+        };
+        for stage in &self.stages {
+            stage.generate(&self.terrain, &mut chunk);
+        }
+        chunk.spill
+    }
+}
+
+/// Parses the layers of a superflat world, like `stone, dirt*3, grass`
+fn parse_layers(layers: &str) -> Result<Vec<Block>, String> {
+    let mut blocks = Vec::new();
+    for layer in layers.split(',').map(str::trim).filter(|l| !l.is_empty()) {
+        let (name, count) = match layer.split_once('*') {
+            Some((name, count)) => (name.trim(), count.trim().parse().ok()),
+            None => (layer, Some(1)),
+        };
+        let block = match name {
+            "stone" => Block::Stone,
+            "dirt" => Block::Dirt,
+            "grass" => Block::Grass,
+            "sand" => Block::Sand,
+            "water" => Block::Water(0),
+            "glass" => Block::Glass,
+            "brick" => Block::Brick,
+            "trunk" => Block::Trunk,
+            "leaves" => Block::Leaves,
+            "tnt" => Block::Tnt,
+            "bedrock" => Block::Bedrock,
+            "ice" => Block::Ice,
+            "slime" => Block::Slime,
+            _ => def::custom::find(name).ok_or_else(|| layer.to_string())?,
+        };
+        let count: usize = count.ok_or_else(|| layer.to_string())?;
+        blocks.extend(std::iter::repeat_n(block, count));
+    }
+    // the world is 256 blocks high
+    if blocks.len() > 256 {
+        return Err(layers.trim().to_string());
+    }
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pipeline() {
+        let cc = ChunkCoords { x: 3, z: -2 };
+        let generate = |spec| {
+            let mut blocks = BlocksChunk::new();
+            Generator::from_spec(42, spec)
+                .unwrap()
+                .gen_chunk(cc, &mut blocks);
+            blocks
+        };
+        // nobody falls through the bottom of the world
+        let blocks = generate(DEFAULT_STAGES);
+        assert!(BlockIndex::ALL
+            .filter(|&bi| <[i32; 3]>::from(bi)[1] == 0)
+            .all(|bi| blocks.get(&bi) == Some(&Block::Bedrock)));
+        // stages are deterministic
+        assert_eq!(generate(DEFAULT_STAGES), generate(DEFAULT_STAGES));
+        // the density stage only makes stone, the surface covers it
+        assert!(generate("density").values().all(|&b| b == Block::Stone));
+        assert!(generate(DEFAULT_STAGES)
+            .values()
+            .any(|&b| b != Block::Stone));
+        // caves only remove blocks
+        assert!(generate("density, surface, caves").len() <= generate(DEFAULT_STAGES).len());
+        assert!(generate("").is_empty());
+        assert_eq!(
+            Generator::from_spec(0, "density, bogus").err(),
+            Some("bogus".into())
+        );
+    }
+
+    #[test]
+    fn test_presets() {
+        let cc = ChunkCoords { x: 0, z: 0 };
+        let generate = |spec| {
+            let mut blocks = BlocksChunk::new();
+            Generator::from_spec(42, spec)
+                .unwrap()
+                .gen_chunk(cc, &mut blocks);
+            blocks
+        };
+        assert_eq!(generate("default"), generate(DEFAULT_STAGES));
+        assert!(generate("void").is_empty());
+        assert_eq!(Generator::from_spec(0, "void").unwrap().altitude(5, 5), -1);
+
+        let flat = generate("superflat");
+        assert_eq!(flat.len(), 16 * 16 * 6);
+        let layer = |y| flat.get(&[3, y, 3].try_into().unwrap()).copied();
+        assert_eq!(layer(0), Some(Block::Bedrock));
+        assert_eq!(layer(1), Some(Block::Stone));
+        assert_eq!(layer(4), Some(Block::Dirt));
+        assert_eq!(layer(5), Some(Block::Grass));
+        let flat_generator = Generator::from_spec(0, "superflat").unwrap();
+        assert_eq!(flat_generator.altitude(-40, 7), 5);
+
+        let flat = generate("superflat: brick*2, sand");
+        assert_eq!(flat.len(), 16 * 16 * 3);
+        assert_eq!(flat.get(&[0, 2, 0].try_into().unwrap()), Some(&Block::Sand));
+
+        let error = |spec| Generator::from_spec(0, spec).err();
+        assert_eq!(error("superflat: stone, gold"), Some("gold".into()));
+        assert_eq!(error("superflat: dirt*x"), Some("dirt*x".into()));
+        assert_eq!(error("superflat: stone*300"), Some("stone*300".into()));
+        assert_eq!(error("hilly: stone"), Some("hilly".into()));
+    }
+
+    #[test]
+    fn test_terrain_presets() {
+        let default = Generator::from_spec(7, "default").unwrap();
+        let amplified = Generator::from_spec(7, "amplified").unwrap();
+        let archipelago = Generator::from_spec(7, "archipelago").unwrap();
+        let columns = || {
+            (-200..200)
+                .step_by(13)
+                .flat_map(|x| (-200..200).step_by(17).map(move |z| (x, z)))
+        };
+        assert!(columns().all(|(x, z)| amplified.altitude(x, z) >= default.altitude(x, z)));
+        // islands, and sea around them
+        assert!(columns().any(|(x, z)| archipelago.altitude(x, z) > 10));
+        assert!(columns().any(|(x, z)| archipelago.altitude(x, z) < 8));
+
+        let mut water = 0;
+        let mut floating = 0;
+        for x in -2..2 {
+            for z in -2..2 {
+                let cc = ChunkCoords { x, z };
+                let mut blocks = BlocksChunk::new();
+                archipelago.gen_chunk(cc, &mut blocks);
+                water += blocks
+                    .values()
+                    .filter(|b| matches!(b, Block::Water(_)))
+                    .count();
+                let mut blocks = BlocksChunk::new();
+                Generator::from_spec(7, "floating_islands")
+                    .unwrap()
+                    .gen_chunk(cc, &mut blocks);
+                floating += blocks.len();
+                // nothing under the islands
+                assert!(blocks.keys().all(|&bi| <[i32; 3]>::from(bi)[1] >= 60));
+            }
+        }
+        assert!(water > 0 && floating > 0);
+    }
+
+    #[test]
+    fn test_spawn() {
+        for seed in [0, 1, 2] {
+            let generator = Generator::new(seed);
+            let [x, y, z] = generator.find_spawn().unwrap();
+            assert_eq!(generator.altitude(x, z), y - 1);
+            let mut blocks = BlocksChunk::new();
+            let BlockCoords(cc, bi) = BlockCoords::try_from([x, y, z]).unwrap();
+            generator.gen_chunk(cc, &mut blocks);
+            assert_eq!(blocks.get(&bi), None);
+        }
+        let superflat = Generator::from_spec(0, "superflat").unwrap();
+        assert_eq!(superflat.find_spawn(), Some([0, 6, 0]));
+        let water = Generator::from_spec(0, "superflat: stone, water").unwrap();
+        assert_eq!(water.find_spawn(), None);
+        assert_eq!(Generator::from_spec(0, "void").unwrap().find_spawn(), None);
+    }
+
+    #[test]
+    fn test_structures() {
+        let generator = Generator::from_spec(5, "density, surface, bedrock, structures").unwrap();
+        let plain = Generator::new(5);
+        let mut spilled = 0;
+        for x in -4..4 {
+            for z in -4..4 {
+                let cc = ChunkCoords { x, z };
+                let mut blocks = BlocksChunk::new();
+                let spill = generator.gen_chunk(cc, &mut blocks);
+                let mut terrain = BlocksChunk::new();
+                plain.gen_chunk(cc, &mut terrain);
+                // structures only take the place of air
+                assert!(terrain
+                    .iter()
+                    .all(|(bi, block)| blocks.get(bi) == Some(block)));
+                assert!(spill.iter().all(|(BlockCoords(other, _), _)| *other != cc));
+                spilled += spill.len();
             }
         }
+        assert!(spilled > 0);
     }
 }
@@ -0,0 +1,348 @@
+use def::Block;
+
+use super::{Biome, ChunkGen, GenStage, Terrain};
+use crate::world::Structure;
+
+/// Shapes the terrain: stone from the bottom of the world up to the altitude
+pub struct DensityStage;
+
+impl GenStage for DensityStage {
+    fn altitude(&self, terrain: &Terrain, x: i32, z: i32) -> Option<i32> {
+        Some(terrain.altitude(x, z))
+    }
+
+    fn generate(&self, terrain: &Terrain, chunk: &mut ChunkGen) {
+        fill(chunk, |x, z| terrain.altitude(x, z));
+    }
+}
+
+/// Fills the columns of the chunk with stone, up to the given altitude
+fn fill(chunk: &mut ChunkGen, altitude: impl Fn(i32, i32) -> i32) {
+    for x in 0..16 {
+        for z in 0..16 {
+            let [wx, wz] = chunk.column(x, z);
+            let altitude = altitude(wx, wz);
+            for y in 0..=altitude {
+                chunk.set([x, y, z], Some(Block::Stone));
+            }
+            chunk.surface[x as usize][z as usize] = altitude;
+        }
+    }
+}
+
+/// Like the density stage, with much higher and steeper mountains
+pub struct AmplifiedStage;
+
+impl AmplifiedStage {
+    fn amplify(altitude: i32) -> i32 {
+        // the plains stay low, the mountains get up to the top of the world
+        (altitude + altitude * altitude / 40).min(250)
+    }
+}
+
+impl GenStage for AmplifiedStage {
+    fn altitude(&self, terrain: &Terrain, x: i32, z: i32) -> Option<i32> {
+        Some(Self::amplify(terrain.altitude(x, z)))
+    }
+
+    fn generate(&self, terrain: &Terrain, chunk: &mut ChunkGen) {
+        fill(chunk, |x, z| Self::amplify(terrain.altitude(x, z)));
+    }
+}
+
+/// Islands scattered in a sea
+///
+/// Each cell of a grid has an island around a random center, its height
+/// falling with the distance to the center. The noise makes the coasts
+/// irregular.
+pub struct ArchipelagoStage;
+
+impl ArchipelagoStage {
+    /// Size of the cells of the grid, with one island each
+    const CELL: i32 = 96;
+    /// Altitude of the top of the sea
+    const SEA_LEVEL: i32 = 8;
+
+    fn island_altitude(terrain: &Terrain, x: i32, z: i32) -> i32 {
+        let [cell_x, cell_z] = [x, z].map(|v| v.div_euclid(Self::CELL));
+        // the island of a neighbour cell can reach this one
+        let mut height: f64 = 0.0;
+        for [cx, cz] in [-1, 0, 1]
+            .map(|dx| [-1, 0, 1].map(|dz| [cell_x + dx, cell_z + dz]))
+            .concat()
+        {
+            let hash = terrain.hash(cx, cz);
+            let center_x = cx * Self::CELL + (hash % Self::CELL as u32) as i32;
+            let center_z = cz * Self::CELL + ((hash >> 8) % Self::CELL as u32) as i32;
+            let radius = 20.0 + ((hash >> 16) % 24) as f64;
+            let distance = ((x - center_x) as f64).hypot((z - center_z) as f64);
+            height = height.max(1.0 - distance / radius);
+        }
+        let noise = terrain.noise2([x as f64 / 40.0, z as f64 / 40.0]);
+        let height = height + noise * 0.25;
+        ((Self::SEA_LEVEL - 5) as f64 + height * 28.0).max(1.0) as i32
+    }
+}
+
+impl GenStage for ArchipelagoStage {
+    fn altitude(&self, terrain: &Terrain, x: i32, z: i32) -> Option<i32> {
+        Some(Self::island_altitude(terrain, x, z))
+    }
+
+    fn generate(&self, terrain: &Terrain, chunk: &mut ChunkGen) {
+        fill(chunk, |x, z| Self::island_altitude(terrain, x, z));
+        for x in 0..16 {
+            for z in 0..16 {
+                let altitude = chunk.surface[x as usize][z as usize];
+                for y in altitude + 1..=Self::SEA_LEVEL {
+                    chunk.set([x, y, z], Some(Block::Water(0)));
+                }
+            }
+        }
+    }
+}
+
+/// Islands floating in the sky, above the void
+///
+/// The islands are where the 3D noise is high, and it gets harder to be
+/// high far from the middle of the band, so they do not touch its ends.
+/// The stage covers them with grass and dirt itself, as the altitude does
+/// not say much about them.
+pub struct FloatingIslandsStage;
+
+impl FloatingIslandsStage {
+    /// Band of altitudes where the islands float
+    const BOTTOM: i32 = 60;
+    const TOP: i32 = 140;
+    const THRESHOLD: f64 = 0.2;
+
+    fn solid(terrain: &Terrain, x: i32, y: i32, z: i32) -> bool {
+        let middle = (Self::BOTTOM + Self::TOP) as f64 / 2.0;
+        let half = (Self::TOP - Self::BOTTOM) as f64 / 2.0;
+        let falloff = ((y as f64 - middle) / half).powi(2);
+        let position = [x as f64 / 32.0, y as f64 / 16.0, z as f64 / 32.0];
+        terrain.noise3(position) - falloff * 0.6 > Self::THRESHOLD
+    }
+}
+
+impl GenStage for FloatingIslandsStage {
+    fn altitude(&self, terrain: &Terrain, x: i32, z: i32) -> Option<i32> {
+        (Self::BOTTOM..Self::TOP)
+            .rev()
+            .find(|&y| Self::solid(terrain, x, y, z))
+    }
+
+    fn generate(&self, terrain: &Terrain, chunk: &mut ChunkGen) {
+        for x in 0..16 {
+            for z in 0..16 {
+                let [wx, wz] = chunk.column(x, z);
+                // blocks under the last air block met, going down
+                let mut depth = None;
+                for y in (Self::BOTTOM..Self::TOP).rev() {
+                    if !Self::solid(terrain, wx, y, wz) {
+                        depth = Some(0);
+                        continue;
+                    }
+                    let block = match depth {
+                        Some(0) => Block::Grass,
+                        Some(1..=2) => Block::Dirt,
+                        _ => Block::Stone,
+                    };
+                    chunk.set([x, y, z], Some(block));
+                    depth = depth.map(|depth| depth + 1);
+                    if chunk.surface[x as usize][z as usize] < 0 {
+                        chunk.surface[x as usize][z as usize] = y;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The same layers of blocks everywhere, the whole terrain of a superflat world
+pub struct FlatStage {
+    /// One block per layer, from the bottom of the world
+    pub layers: Vec<Block>,
+}
+
+impl GenStage for FlatStage {
+    fn altitude(&self, _: &Terrain, _: i32, _: i32) -> Option<i32> {
+        Some(self.layers.len() as i32 - 1)
+    }
+
+    fn generate(&self, _: &Terrain, chunk: &mut ChunkGen) {
+        for x in 0..16 {
+            for z in 0..16 {
+                for (y, &block) in self.layers.iter().enumerate() {
+                    chunk.set([x, y as i32, z], Some(block));
+                }
+                chunk.surface[x as usize][z as usize] = self.layers.len() as i32 - 1;
+            }
+        }
+    }
+}
+
+/// Makes the bottom layer of the terrain unbreakable, so nobody falls out
+/// of the world
+pub struct BedrockStage;
+
+impl GenStage for BedrockStage {
+    fn generate(&self, _: &Terrain, chunk: &mut ChunkGen) {
+        for x in 0..16 {
+            for z in 0..16 {
+                // only under the terrain, the void stays empty
+                if chunk.get([x, 0, z]).is_some() {
+                    chunk.set([x, 0, z], Some(Block::Bedrock));
+                }
+            }
+        }
+    }
+}
+
+/// Covers the terrain with the blocks of its biome (sand, grass, dirt...)
+pub struct SurfaceStage;
+
+impl GenStage for SurfaceStage {
+    fn generate(&self, _: &Terrain, chunk: &mut ChunkGen) {
+        for x in 0..16 {
+            for z in 0..16 {
+                let altitude = chunk.surface[x as usize][z as usize];
+                for y in 0..=altitude {
+                    // caves and gaps are kept
+                    if chunk.get([x, y, z]).is_none() {
+                        continue;
+                    }
+                    let deep = (altitude - y) * altitude;
+                    let block = match Biome::from_altitude(altitude) {
+                        Biome::Beach => match deep {
+                            0..=30 => Block::Sand,
+                            _ => Block::Stone,
+                        },
+                        Biome::Plains => match deep {
+                            0 => Block::Grass,
+                            1..=30 => Block::Dirt,
+                            _ => Block::Stone,
+                        },
+                        Biome::Mountains => match deep {
+                            0..=40 => Block::Stone,
+                            _ => Block::Brick,
+                        },
+                    };
+                    chunk.set([x, y, z], Some(block));
+                }
+            }
+        }
+    }
+}
+
+/// Digs caves where the 3D noise is high, under the surface
+pub struct CarverStage;
+
+impl CarverStage {
+    /// Caves never open closer to the surface than this
+    const CEILING: i32 = 4;
+    const THRESHOLD: f64 = 0.45;
+}
+
+impl GenStage for CarverStage {
+    fn generate(&self, terrain: &Terrain, chunk: &mut ChunkGen) {
+        for x in 0..16 {
+            for z in 0..16 {
+                let [wx, wz] = chunk.column(x, z);
+                let top = chunk.surface[x as usize][z as usize] - Self::CEILING;
+                // the bottom layer is kept, so there is always a floor
+                for y in 1..top {
+                    let position = [wx as f64 / 24.0, y as f64 / 12.0, wz as f64 / 24.0];
+                    if terrain.noise3(position) > Self::THRESHOLD {
+                        chunk.set([x, y, z], None);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Scatters huts and ruins over the terrain
+///
+/// The world is split in cells of a few chunks, with at most one structure
+/// each, placed from the seed. It is built by the chunk holding its origin:
+/// its parts over other chunks are left to the world.
+pub struct StructureStage;
+
+impl StructureStage {
+    /// Size of the cells, in chunks
+    const CELL: i32 = 4;
+    /// Chance (per hundred) for a cell to have a structure
+    const CHANCE: u32 = 40;
+}
+
+impl GenStage for StructureStage {
+    fn generate(&self, terrain: &Terrain, chunk: &mut ChunkGen) {
+        let [cell_x, cell_z] = [chunk.cc.x, chunk.cc.z].map(|v| v.div_euclid(Self::CELL));
+        // moved away, so the cells do not have the hash of the columns
+        let hash = terrain.hash(cell_x, cell_z.wrapping_add(1 << 30));
+        if hash % 100 >= Self::CHANCE {
+            return;
+        }
+        let size = (Self::CELL * 16) as u32;
+        let [origin_x, origin_z] = [
+            cell_x * size as i32 + ((hash >> 8) % size) as i32,
+            cell_z * size as i32 + ((hash >> 16) % size) as i32,
+        ];
+        let [x, z] = [origin_x - chunk.cc.x * 16, origin_z - chunk.cc.z * 16];
+        if !(0..16).contains(&x) || !(0..16).contains(&z) {
+            return;
+        }
+        // on solid ground, not in the water nor in the void
+        let ground = chunk.surface[x as usize][z as usize];
+        if !chunk.get([x, ground, z]).is_some_and(Block::is_full) {
+            return;
+        }
+        let structure = match hash >> 24 & 1 {
+            0 => Structure::hut(),
+            _ => Structure::ruin(hash),
+        };
+        for ([dx, dy, dz], block) in structure.blocks {
+            chunk.place([x + dx, ground + 1 + dy, z + dz], block);
+        }
+    }
+}
+
+/// Grows trees on the grass of the plains
+///
+/// Trees are kept whole inside the chunk, so they never need the
+/// neighbour chunks.
+pub struct DecorationStage;
+
+impl DecorationStage {
+    /// Chance (per thousand) for a column of grass to have a tree
+    const TREES: u32 = 8;
+}
+
+impl GenStage for DecorationStage {
+    fn generate(&self, terrain: &Terrain, chunk: &mut ChunkGen) {
+        for x in 2..14 {
+            for z in 2..14 {
+                let [wx, wz] = chunk.column(x, z);
+                let hash = terrain.hash(wx, wz);
+                let y = chunk.surface[x as usize][z as usize];
+                let Ok(bi) = [x, y, z].try_into() else {
+                    continue;
+                };
+                if hash % 1000 >= Self::TREES || chunk.blocks.get(&bi) != Some(&Block::Grass) {
+                    continue;
+                }
+                let tree = Structure::tree(4 + (hash >> 16) as i32 % 3);
+                for ([dx, dy, dz], block) in tree.blocks {
+                    let position = [x + dx, y + 1 + dy, z + dz];
+                    let free = position
+                        .try_into()
+                        .is_ok_and(|bi| !chunk.blocks.contains_key(&bi));
+                    if free {
+                        chunk.set(position, Some(block));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use def::Block;
+
+use super::Player;
+use crate::camera::Camera;
+
+/// Falling speed from which a player holding a glider opens it (about a
+/// fall of three blocks, more than a jump)
+const OPENING_SPEED: f32 = 0.25;
+
+/// Part of the gravity the wings hold when flat
+const LIFT: f32 = 0.75;
+
+/// Part of the fall turned into forward speed at each tick
+const GLIDE: f32 = 0.1;
+
+/// Forward speed traded for height when looking up
+const CLIMB: f32 = 0.04;
+
+/// Height gained for the forward speed traded
+const CLIMB_HEIGHT: f32 = 3.2;
+
+/// How quickly the movement turns toward where the player looks
+const TURN: f32 = 0.1;
+
+/// Part of the speed kept at each tick, horizontally and vertically
+const DRAG: [f32; 2] = [0.99, 0.98];
+
+impl Player {
+    /// Opens the glider when falling fast enough while holding it, and
+    /// closes it once on the ground (or flying, or in a boat)
+    pub fn update_glide(&mut self) {
+        self.gliding = self.block_placing == Block::Glider
+            && !self.on_ground
+            && !self.fly
+            && self.riding.is_none()
+            && (self.gliding || self.gravity < -OPENING_SPEED);
+    }
+}
+
+/// The velocity of a gliding player after a tick
+///
+/// Looking down, the player dives and its fall speeds it up; looking up,
+/// it climbs and slows down. The movement follows where it looks.
+pub fn glide(velocity: [f32; 3], camera: &Camera, gravity: f32) -> [f32; 3] {
+    let [mut x, mut y, mut z] = velocity;
+    let (sin_h, cos_h) = camera.h_angle.sin_cos();
+    // where the player looks, horizontally (a unit vector)
+    let [look_x, look_z] = [-sin_h, cos_h];
+    let (sin_v, cos_v) = camera.v_angle.sin_cos();
+    let speed = x.hypot(z);
+    // the wings hold the most when flat
+    let flat = cos_v * cos_v;
+    y += gravity * (1.0 - LIFT * flat);
+    if y < 0.0 {
+        let glide = -y * GLIDE * flat;
+        y += glide;
+        x += look_x * glide;
+        z += look_z * glide;
+    }
+    if sin_v > 0.0 {
+        let climb = speed * sin_v * CLIMB;
+        y += climb * CLIMB_HEIGHT;
+        x -= look_x * climb;
+        z -= look_z * climb;
+    }
+    x += (look_x * speed - x) * TURN;
+    z += (look_z * speed - z) * TURN;
+    [x * DRAG[0], y * DRAG[1], z * DRAG[0]]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::{Physics, MAX_STAMINA};
+
+    fn camera(h_angle: f32, v_angle: f32) -> Camera {
+        Camera {
+            pos: [0.0; 3],
+            h_angle,
+            v_angle,
+        }
+    }
+
+    /// The velocity after some ticks of gliding
+    fn flight(camera: &Camera, ticks: usize) -> [f32; 3] {
+        let gravity = Physics::default().gravity;
+        let mut velocity = [0.0, 0.0, 0.1];
+        for _ in 0..ticks {
+            velocity = glide(velocity, camera, gravity);
+        }
+        velocity
+    }
+
+    #[test]
+    fn test_glide() {
+        let [_, level_y, level_z] = flight(&camera(0.0, 0.0), 200);
+        // falling without a glider, 2 blocks per tick after 200 ticks
+        assert!(level_y < 0.0 && level_y > -0.1, "{level_y}");
+        assert!(level_z > 0.1, "{level_z}");
+
+        // diving goes faster, climbing slows down
+        let [_, dive_y, dive_z] = flight(&camera(0.0, -0.6), 200);
+        assert!(dive_y < level_y && dive_z > level_z);
+        let [_, climb_y, climb_z] = flight(&camera(0.0, 0.6), 20);
+        assert!(climb_y > 0.0 && climb_z < 0.1);
+
+        // the movement turns toward where the player looks
+        let [x, _, z] = flight(&camera(std::f32::consts::FRAC_PI_2, 0.0), 100);
+        assert!(x < 0.0 && x.abs() > 10.0 * z.abs(), "{x} {z}");
+    }
+
+    #[test]
+    fn test_opening() {
+        let mut player = Player {
+            camera: camera(0.0, 0.0),
+            fly: false,
+            gravity: -0.1,
+            on_ground: false,
+            block_placing: Block::Glider,
+            stamina: MAX_STAMINA,
+            riding: None,
+            gliding: false,
+        };
+        // not after a jump
+        player.update_glide();
+        assert!(!player.gliding);
+        player.gravity = -0.3;
+        player.update_glide();
+        assert!(player.gliding);
+        // kept open when the glider slows the fall
+        player.gravity = -0.02;
+        player.update_glide();
+        assert!(player.gliding);
+        player.on_ground = true;
+        player.update_glide();
+        assert!(!player.gliding);
+
+        player.on_ground = false;
+        player.gravity = -0.3;
+        player.block_placing = Block::Stone;
+        player.update_glide();
+        assert!(!player.gliding);
+    }
+}
@@ -0,0 +1,177 @@
+use def::{Block, BlockCoords, BlockIndex, ChunkCoords};
+
+use super::{now, ChunkState, World};
+
+/// Edits older than this are forgotten (in seconds)
+pub const HISTORY: u64 = 60 * 60;
+
+/// Who made an edit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Author {
+    /// The player, by clicking or with a command
+    Player,
+    Explosion,
+    /// Grass spreading, trees growing...
+    Nature,
+}
+
+/// An edit of a block of a chunk, in the log of the chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkDiff {
+    /// When (seconds since the unix epoch)
+    pub when: u64,
+    pub who: Author,
+    pub index: BlockIndex,
+    pub before: Option<Block>,
+    pub after: Option<Block>,
+}
+
+impl World {
+    /// Adds an edit to the log of its chunk, forgetting the old ones
+    pub(super) fn record(
+        &self,
+        BlockCoords(cc, index): BlockCoords,
+        before: Option<Block>,
+        after: Option<Block>,
+        who: Author,
+    ) {
+        // whatever replaced a chest, its content is gone with it
+        if before == Some(Block::Chest) && after != Some(Block::Chest) {
+            self.forget_chest(BlockCoords(cc, index));
+        }
+        // the blocks around react at the next tick (see `signal`)
+        self.schedule_update(BlockCoords(cc, index));
+        let when = now().as_secs();
+        let mut log = self.edits.entry(cc).or_default();
+        log.retain(|diff| diff.when + HISTORY > when);
+        log.push(ChunkDiff {
+            when,
+            who,
+            index,
+            before,
+            after,
+        });
+    }
+
+    /// Reverts the edits made since the given time (seconds since the unix
+    /// epoch), in the region between two corners or everywhere
+    ///
+    /// The edits are undone from the most recent one. The chunks not loaded
+    /// keep their edits. Returns the number of edits reverted.
+    pub fn rollback(&self, since: u64, region: Option<(BlockCoords, BlockCoords)>) -> usize {
+        let bounds = region.map(|(from, to)| {
+            let [from, to]: [[i32; 3]; 2] = [from.into(), to.into()];
+            let min: [i32; 3] = std::array::from_fn(|i| from[i].min(to[i]));
+            let max: [i32; 3] = std::array::from_fn(|i| from[i].max(to[i]));
+            (min, max)
+        });
+        let inside = |bc: BlockCoords| {
+            let p: [i32; 3] = bc.into();
+            bounds.is_none_or(|(min, max)| (0..3).all(|i| (min[i]..=max[i]).contains(&p[i])))
+        };
+        // collected first, as editing the chunks while iterating would deadlock
+        let logged: Vec<ChunkCoords> = self.edits.iter().map(|log| *log.key()).collect();
+        let mut reverted = Vec::new();
+        for cc in logged {
+            self.decompress_chunk(cc);
+            let Some(mut chunk) = self.chunks.get_mut(&cc) else {
+                continue;
+            };
+            let (ChunkState::Loaded(blocks) | ChunkState::Meshed(blocks, _)) = &mut *chunk else {
+                continue;
+            };
+            let Some(mut log) = self.edits.get_mut(&cc) else {
+                continue;
+            };
+            let mut kept = Vec::new();
+            let count = reverted.len();
+            while let Some(diff) = log.pop() {
+                let bc = BlockCoords(cc, diff.index);
+                if diff.when < since || !inside(bc) {
+                    kept.push(diff);
+                    continue;
+                }
+                let replaced = match diff.before {
+                    Some(block) => blocks.insert(diff.index, block),
+                    None => blocks.remove(&diff.index),
+                };
+                // a chest taken away takes its content with it (see `record`)
+                if replaced == Some(Block::Chest) && diff.before != Some(Block::Chest) {
+                    self.forget_chest(bc);
+                }
+                reverted.push(bc);
+            }
+            kept.reverse();
+            *log = kept;
+            if reverted.len() > count {
+                self.dirty.insert(cc);
+            }
+        }
+        self.edits.retain(|_, log| !log.is_empty());
+        self.update_many(&reverted);
+        for &bc in &reverted {
+            self.relight(bc);
+        }
+        tracing::info!(since, reverted = reverted.len(), "edits rolled back");
+        reverted.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        harness::Harness,
+        world::{BlocksChunk, FacesChunk, CHEST_SLOTS},
+    };
+
+    #[test]
+    fn test_rollback() {
+        let harness = Harness::empty("rollback");
+        let world = &harness.world;
+        let at = |x, y, z| BlockCoords::try_from([x, y, z]).unwrap();
+        let cc = ChunkCoords { x: 0, z: 0 };
+        let chunk = BlocksChunk::from([(at(1, 10, 1).1, Block::Stone)]);
+        world
+            .chunks
+            .insert(cc, ChunkState::Meshed(chunk, FacesChunk::new()));
+
+        world.remove_block(at(1, 10, 1)).unwrap();
+        world.place_block(at(1, 10, 1), Block::Sand).unwrap();
+        world.place_block(at(5, 10, 5), Block::Glass).unwrap();
+        let log = world.edits.get(&cc).unwrap().clone();
+        assert_eq!(log.len(), 3);
+        assert_eq!(log[0].before, Some(Block::Stone));
+        assert_eq!(log[0].who, Author::Player);
+
+        // only the region is rolled back, from the last edit
+        assert_eq!(world.rollback(0, Some((at(0, 0, 0), at(2, 20, 2)))), 2);
+        assert_eq!(world.get_block(at(1, 10, 1)), Some(Some(Block::Stone)));
+        assert_eq!(world.get_block(at(5, 10, 5)), Some(Some(Block::Glass)));
+        // the future is never reached
+        assert_eq!(world.rollback(u64::MAX, None), 0);
+        assert_eq!(world.rollback(0, None), 1);
+        assert_eq!(world.get_block(at(5, 10, 5)), Some(None));
+        assert!(world.edits.is_empty());
+    }
+
+    #[test]
+    fn test_rollback_chest() {
+        let harness = Harness::empty("rollback-chest");
+        let world = &harness.world;
+        let bc = BlockCoords::try_from([1, 10, 1]).unwrap();
+        world.chunks.insert(
+            bc.0,
+            ChunkState::Meshed(BlocksChunk::new(), FacesChunk::new()),
+        );
+        world.place_block(bc, Block::Chest).unwrap();
+        world.chest_put(bc, 0, Block::Stone).unwrap();
+
+        // undoing the chest empties it
+        assert_eq!(world.rollback(0, None), 1);
+        assert_eq!(world.get_block(bc), Some(None));
+        assert!(world.chests().is_empty());
+        world.place_block(bc, Block::Chest).unwrap();
+        assert_eq!(world.chest(bc), [None; CHEST_SLOTS]);
+    }
+}
@@ -0,0 +1,56 @@
+use def::{state, Block, BlockCoords, Direction};
+
+use super::{Author, Entity, Event, World};
+use crate::{CmdError, CmdOutput};
+
+/// What right-clicking a block does
+///
+/// An interactive block takes priority over placing a block against it.
+pub type Interact = fn(&World, BlockCoords) -> Result<CmdOutput, CmdError>;
+
+/// The interaction of a block, if it has one
+pub fn on_interact(block: Block) -> Option<Interact> {
+    match block {
+        Block::Tnt => Some(ignite),
+        Block::Door(_) | Block::Trapdoor(_) => Some(toggle),
+        Block::Chest => Some(open_chest),
+        Block::Lever(_) => Some(switch),
+        _ => None,
+    }
+}
+
+/// Whether a right click tills the block rather than placing the held
+/// one: the top of dirt or grass, crops in hand
+pub fn tills(held: Block, block: Block, face: Direction) -> bool {
+    matches!(held, Block::Crop(_))
+        && matches!(block, Block::Dirt | Block::Grass)
+        && face == Direction::Up
+}
+
+fn toggle(world: &World, bc: BlockCoords) -> Result<CmdOutput, CmdError> {
+    let open = world.toggle_open(bc)?;
+    Ok(CmdOutput::Opened(bc, open))
+}
+
+/// The wires and lamps around follow at the next ticks (see `signal`)
+fn switch(world: &World, bc: BlockCoords) -> Result<CmdOutput, CmdError> {
+    let Some(Some(lever)) = world.get_block(bc) else {
+        return Err(CmdError::NoBlock(bc));
+    };
+    let state = lever.state() ^ state::ON;
+    world.replace_block(bc, lever.with_state(state), Author::Player)?;
+    Ok(CmdOutput::Switched(bc, state & state::ON != 0))
+}
+
+/// The chest is shown to whoever listens to the events (the renderer)
+fn open_chest(world: &World, bc: BlockCoords) -> Result<CmdOutput, CmdError> {
+    world.events.send(Event::ChestOpened(bc)).ok();
+    Ok(CmdOutput::Opened(bc, true))
+}
+
+/// The TNT becomes an entity which explodes when its fuse is burnt
+fn ignite(world: &World, bc: BlockCoords) -> Result<CmdOutput, CmdError> {
+    world.remove_block(bc)?;
+    world.spawn_entity(Entity::primed_tnt(bc));
+    Ok(CmdOutput::Ignited(bc))
+}
@@ -0,0 +1,315 @@
+use std::collections::{HashMap, VecDeque};
+
+use def::{constant::MAX_LIGHT, BlockCoords, BlockIndex, ChunkCoords, Direction};
+
+use super::{across_border, sections_around, ChunkState, Sections, World};
+use crate::AristideCmd;
+
+/// Block light levels of a chunk (only the lit voxels are stored)
+///
+/// Light is emitted by blocks like torches and spreads from voxel to voxel,
+/// losing a level at each step, as long as the voxels let it through.
+pub type LightChunk = HashMap<BlockIndex, u8>;
+
+/// Sections of chunks whose light changed, to be rendered again
+type Changed = HashMap<ChunkCoords, Sections>;
+
+/// Voxels propagated per tick, the rest waits for the next ones
+pub(super) const LIGHT_BUDGET: usize = 4096;
+
+/// Light changes waiting to be propagated
+///
+/// Darkening goes first: the light spreads again once every voxel lit by
+/// a removed source is dark.
+#[derive(Debug, Default)]
+pub(super) struct LightQueue {
+    /// Voxels which lost their light, with the level they had
+    darken: VecDeque<(BlockCoords, u8)>,
+    /// Voxels whose light spreads to their neighbours
+    spread: VecDeque<BlockCoords>,
+    /// Voxels queued in each chunk
+    queued: HashMap<ChunkCoords, usize>,
+    /// Rendered again once the light settled around them
+    changed: Changed,
+}
+
+impl LightQueue {
+    fn push_darken(&mut self, bc: BlockCoords, level: u8) {
+        *self.queued.entry(bc.0).or_default() += 1;
+        self.darken.push_back((bc, level));
+    }
+
+    fn push_spread(&mut self, bc: BlockCoords) {
+        *self.queued.entry(bc.0).or_default() += 1;
+        self.spread.push_back(bc);
+    }
+
+    fn popped(&mut self, BlockCoords(cc, _): BlockCoords) {
+        if let Some(count) = self.queued.get_mut(&cc) {
+            *count -= 1;
+            if *count == 0 {
+                self.queued.remove(&cc);
+            }
+        }
+    }
+
+    /// Whether no light moves in the chunk nor at its borders
+    fn settled(&self, cc: ChunkCoords) -> bool {
+        !self.queued.contains_key(&cc)
+            && cc
+                .neighbors()
+                .into_iter()
+                .all(|neighbour| !self.queued.contains_key(&neighbour))
+    }
+}
+
+impl World {
+    /// Block light level of a voxel (0 in the dark)
+    pub fn get_light(&self, BlockCoords(cc, bi): BlockCoords) -> u8 {
+        self.lights
+            .get(&cc)
+            .and_then(|lights| lights.get(&bi).copied())
+            .unwrap_or(0)
+    }
+
+    fn set_light(&self, bc: BlockCoords, level: u8, changed: &mut Changed) {
+        let BlockCoords(cc, bi) = bc;
+        let mut lights = self.lights.entry(cc).or_default();
+        let previous = if level == 0 {
+            lights.remove(&bi)
+        } else {
+            lights.insert(bi, level.min(MAX_LIGHT))
+        };
+        if previous.unwrap_or(0) != level {
+            // the faces of the next chunk are lit by the voxel too
+            for cc in [cc].into_iter().chain(across_border(bc)) {
+                *changed.entry(cc).or_default() |= sections_around(bc);
+            }
+        }
+    }
+
+    /// Whether light goes through the voxel (not through unloaded chunks)
+    fn lets_light(&self, bc: BlockCoords) -> bool {
+        match self.get_block(bc) {
+            Some(Some(block)) => block.is_transparent(),
+            Some(None) => true,
+            None => false,
+        }
+    }
+
+    fn emission(&self, bc: BlockCoords) -> u8 {
+        self.get_block(bc)
+            .flatten()
+            .map_or(0, |block| block.emission())
+    }
+
+    /// Queues the light to compute again after the block at `bc` was placed
+    /// or removed (see [`World::propagate_light`])
+    ///
+    /// The light coming from the voxel is removed first (darkening every voxel
+    /// it reached), then the light around flows back in, along with the light
+    /// of the new block.
+    pub(super) fn relight(&self, bc: BlockCoords) {
+        let mut queue = self.light_queue.lock().unwrap();
+        let queue = &mut *queue;
+        queue.push_darken(bc, self.get_light(bc));
+        self.set_light(bc, self.emission(bc), &mut queue.changed);
+        // a removed block lets the light of its neighbours in
+        queue.push_spread(bc);
+        for neighbour in Direction::ALL.into_iter().filter_map(|d| bc.step(d)) {
+            queue.push_spread(neighbour);
+        }
+    }
+
+    /// Propagates the queued light changes, at most `budget` voxels, so a
+    /// torch or a cave opened does not hold the frame
+    ///
+    /// The sections are rendered again once the light settled around their
+    /// chunk, rather than at each step. Returns the number of voxels
+    /// propagated.
+    pub(super) fn propagate_light(&self, budget: usize) -> usize {
+        let mut queue = self.light_queue.lock().unwrap();
+        let count = self.propagate(&mut queue, budget);
+        let settled: Vec<ChunkCoords> = queue
+            .changed
+            .keys()
+            .copied()
+            .filter(|&cc| queue.settled(cc))
+            .collect();
+        let changed = settled
+            .into_iter()
+            .filter_map(|cc| queue.changed.remove_entry(&cc))
+            .collect();
+        drop(queue);
+        self.render_lit(changed);
+        count
+    }
+
+    /// Lights a freshly loaded chunk
+    ///
+    /// Its own sources shine, and the light of the loaded neighbours comes
+    /// in through the borders. It is done at once, as the chunk is loaded
+    /// out of the frame.
+    pub(super) fn light_chunk(&self, cc: ChunkCoords) {
+        let mut queue = LightQueue::default();
+        let sources: Vec<_> = match self.chunks.get(&cc).as_deref() {
+            Some(ChunkState::Loaded(blocks) | ChunkState::Meshed(blocks, _)) => blocks
+                .iter()
+                .filter(|(_, block)| block.emission() > 0)
+                .map(|(&bi, block)| (BlockCoords(cc, bi), block.emission()))
+                .collect(),
+            Some(ChunkState::Compressed(_)) | None => return,
+        };
+        for (bc, emission) in sources {
+            self.set_light(bc, emission, &mut queue.changed);
+            queue.push_spread(bc);
+        }
+        for neighbour in cc.neighbors() {
+            if let Some(lights) = self.lights.get(&neighbour) {
+                for &bi in lights.keys() {
+                    queue.push_spread(BlockCoords(neighbour, bi));
+                }
+            }
+        }
+        self.propagate(&mut queue, usize::MAX);
+        // the chunk itself is not rendered yet
+        queue.changed.remove(&cc);
+        self.render_lit(queue.changed);
+    }
+
+    /// Takes up to `budget` voxels from the queue, returns how many
+    fn propagate(&self, queue: &mut LightQueue, budget: usize) -> usize {
+        let mut count = 0;
+        while count < budget {
+            if let Some((bc, level)) = queue.darken.pop_front() {
+                queue.popped(bc);
+                self.darken_step(bc, level, queue);
+            } else if let Some(bc) = queue.spread.pop_front() {
+                queue.popped(bc);
+                self.spread_step(bc, queue);
+            } else {
+                break;
+            }
+            count += 1;
+        }
+        count
+    }
+
+    fn darken_step(&self, bc: BlockCoords, level: u8, queue: &mut LightQueue) {
+        for neighbour in Direction::ALL.into_iter().filter_map(|d| bc.step(d)) {
+            let neighbour_level = self.get_light(neighbour);
+            if neighbour_level != 0 && neighbour_level < level {
+                // it was lit by the removed light, sources are lit again
+                self.set_light(neighbour, 0, &mut queue.changed);
+                queue.push_darken(neighbour, neighbour_level);
+                let emission = self.emission(neighbour);
+                if emission > 0 {
+                    self.set_light(neighbour, emission, &mut queue.changed);
+                    queue.push_spread(neighbour);
+                }
+            } else if neighbour_level >= level {
+                // lit by another source
+                queue.push_spread(neighbour);
+            }
+        }
+    }
+
+    fn spread_step(&self, bc: BlockCoords, queue: &mut LightQueue) {
+        let level = self.get_light(bc);
+        if level <= 1 {
+            return;
+        }
+        for neighbour in Direction::ALL.into_iter().filter_map(|d| bc.step(d)) {
+            if self.get_light(neighbour) + 1 < level && self.lets_light(neighbour) {
+                self.set_light(neighbour, level - 1, &mut queue.changed);
+                queue.push_spread(neighbour);
+            }
+        }
+    }
+
+    fn render_lit(&self, changed: Changed) {
+        for (cc, sections) in changed {
+            if let Some(ChunkState::Meshed(..)) = self.chunks.get(&cc).as_deref() {
+                self.try_aristide_cmd(AristideCmd::RenderSections(cc, sections));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use def::Block;
+
+    use super::*;
+    use crate::{
+        harness::Harness,
+        world::{BlocksChunk, FacesChunk},
+    };
+
+    fn at(x: i32, y: i32, z: i32) -> BlockCoords {
+        [x, y, z].try_into().unwrap()
+    }
+
+    fn set_block(world: &World, bc: BlockCoords, block: Option<Block>) {
+        let BlockCoords(cc, bi) = bc;
+        if let ChunkState::Loaded(blocks) = &mut *world.chunks.get_mut(&cc).unwrap() {
+            match block {
+                Some(block) => blocks.insert(bi, block),
+                None => blocks.remove(&bi),
+            };
+        }
+        world.relight(bc);
+        world.propagate_light(usize::MAX);
+    }
+
+    #[test]
+    fn test_torch_light() {
+        let harness = Harness::empty("light");
+        let world = &harness.world;
+        let cc = ChunkCoords { x: 0, z: 0 };
+        let BlockCoords(_, torch) = at(8, 10, 8);
+        let chunk = BlocksChunk::from([(torch, Block::Torch(0))]);
+        world.chunks.insert(cc, ChunkState::Loaded(chunk));
+        world.light_chunk(cc);
+        assert_eq!(world.get_light(at(8, 10, 8)), 14);
+        assert_eq!(world.get_light(at(8, 10, 11)), 11);
+        // light does not go into unloaded chunks
+        assert_eq!(world.get_light(at(16, 10, 8)), 0);
+
+        // a block in the way, the light goes around it
+        set_block(world, at(8, 10, 9), Some(Block::Stone));
+        assert_eq!(world.get_light(at(8, 10, 9)), 0);
+        assert_eq!(world.get_light(at(8, 10, 10)), 10);
+        set_block(world, at(8, 10, 9), None);
+        assert_eq!(world.get_light(at(8, 10, 10)), 12);
+
+        // without the torch, everything goes dark
+        set_block(world, at(8, 10, 8), None);
+        assert!(world.lights.get(&cc).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_light_budget() {
+        let mut harness = Harness::empty("light-budget");
+        let cc = ChunkCoords { x: 0, z: 0 };
+        let BlockCoords(_, torch) = at(8, 10, 8);
+        let chunk = BlocksChunk::from([(torch, Block::Torch(0))]);
+        harness
+            .world
+            .chunks
+            .insert(cc, ChunkState::Meshed(chunk, FacesChunk::new()));
+        harness.world.relight(at(8, 10, 8));
+        assert_eq!(harness.world.get_light(at(8, 10, 8)), 14);
+
+        // a few voxels at a time, and nothing rendered while the light moves
+        assert_eq!(harness.world.propagate_light(10), 10);
+        assert_eq!(harness.world.get_light(at(8, 10, 14)), 0);
+        assert!(harness.render_requests().is_empty());
+        // until the last voxels, which leave the light settled
+        while harness.world.propagate_light(10) == 10 {
+            assert!(harness.render_requests().is_empty());
+        }
+        assert_eq!(harness.world.get_light(at(8, 10, 14)), 8);
+        assert_eq!(harness.render_requests()[0].0, cc);
+    }
+}
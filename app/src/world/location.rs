@@ -0,0 +1,229 @@
+use std::fmt;
+
+use def::BlockCoords;
+use mat::VectorTrait;
+
+use super::{GameMode, Player, World};
+use crate::{camera::Camera, CmdError};
+
+/// The main direction the player looks toward, north being negative z
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Facing {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Facing {
+    pub fn from_angle(h_angle: f32) -> Self {
+        // the camera looks toward positive z, turning toward negative x
+        let [x, z] = [-h_angle.sin(), h_angle.cos()];
+        match (x.abs() > z.abs(), x > 0.0, z > 0.0) {
+            (true, true, _) => Self::East,
+            (true, false, _) => Self::West,
+            (false, _, true) => Self::South,
+            (false, _, false) => Self::North,
+        }
+    }
+}
+
+impl fmt::Display for Facing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::North => "north",
+            Self::East => "east",
+            Self::South => "south",
+            Self::West => "west",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// The `tp` command bringing back to where the player stands (its feet),
+/// looking the same way
+pub fn tp_command(camera: &Camera) -> String {
+    let [x, y, z] = camera.pos;
+    format!(
+        "tp {x:.2} {:.2} {z:.2} {:.1} {:.1}",
+        y - Player::EYES,
+        camera.h_angle.to_degrees(),
+        camera.v_angle.to_degrees()
+    )
+}
+
+/// A coordinate of a position given in a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coord {
+    Absolute(i32),
+    /// From the block at the feet of the player (`~` or `~-2`)
+    Relative(i32),
+    /// Along the left, up or forward axis of the view, from the feet of the
+    /// player (`^` or `^3`)
+    Local(i32),
+}
+
+/// The block given by the coordinates of a command, from where the player
+/// stands and looks
+///
+/// Local coordinates go left, up and forward (in that order, the look
+/// going up or down tilts them), they can't be mixed with the others.
+pub fn resolve(camera: &Camera, coords: [Coord; 3]) -> Result<BlockCoords, CmdError> {
+    let [x, y, z] = camera.pos;
+    let feet = [x, y - Player::EYES, z];
+    let local = coords.map(|coord| match coord {
+        Coord::Local(offset) => Some(offset as f32),
+        _ => None,
+    });
+    let block = match local {
+        [Some(left), Some(up), Some(forward)] => {
+            let matrix = camera.matrix();
+            let axis = |[x, y, z, _]: [f32; 4], length: f32| [x, y, z].vector_scale(length);
+            // from the middle of the block, so a small tilt does not move
+            // the position to the next block
+            let center = feet.map(|v| v.floor() + 0.5);
+            center
+                .vector_add(axis(matrix.vector_x(), left))
+                .vector_add(axis(matrix.vector_y(), up))
+                .vector_add(axis(matrix.vector_z(), forward))
+                .map(|v| v.floor() as i32)
+        }
+        // (none of them is local)
+        [None, None, None] => std::array::from_fn(|i| match coords[i] {
+            Coord::Absolute(v) => v,
+            Coord::Relative(offset) | Coord::Local(offset) => {
+                (feet[i].floor() as i32).saturating_add(offset)
+            }
+        }),
+        _ => return Err(CmdError::MixedCoordinates),
+    };
+    BlockCoords::try_from(block).map_err(|()| CmdError::OutOfWorld(block))
+}
+
+impl World {
+    /// Moves the feet of the player to `pos`, and turns it toward the
+    /// horizontal and vertical angles (in degrees) if given
+    ///
+    /// A ridden boat is left behind.
+    pub fn teleport(&self, pos: [f32; 3], look: Option<[f32; 2]>) -> Result<(), CmdError> {
+        if self.mode != GameMode::Creative {
+            return Err(CmdError::CreativeOnly);
+        }
+        let block = pos.map(|v| v.floor() as i32);
+        BlockCoords::try_from(block).map_err(|()| CmdError::OutOfWorld(block))?;
+        let mut player = self.player.write().unwrap();
+        player.dismount(None);
+        let [x, y, z] = pos;
+        player.camera.pos = [x, y + Player::EYES, z];
+        if let Some([h, v]) = look {
+            player.camera.h_angle = 0.0;
+            player.camera.delta_angle_h(h.to_radians());
+            player.camera.v_angle = 0.0;
+            player.camera.delta_angle_v(v.to_radians());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::PI;
+
+    use lalrpop_util::ParseError;
+
+    use super::*;
+    use crate::{grammar::CmdParser, harness::Harness, Cmd};
+
+    #[test]
+    fn test_facing() {
+        assert_eq!(Facing::from_angle(0.0), Facing::South);
+        assert_eq!(Facing::from_angle(PI / 2.0), Facing::West);
+        assert_eq!(Facing::from_angle(PI), Facing::North);
+        assert_eq!(Facing::from_angle(3.0 * PI / 2.0), Facing::East);
+        assert_eq!(Facing::from_angle(2.0 * PI - 0.1), Facing::South);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let parser = CmdParser::new();
+        let at = |x, y, z| BlockCoords::try_from([x, y, z]).unwrap();
+        // feet at [4, 70, -2], looking toward positive z
+        let camera = Camera {
+            pos: [4.5, 71.6, -1.5],
+            ..Camera::default()
+        };
+        let Ok(Cmd::Fill(from, to, _)) = parser.parse(camera, "fill ~ ~ ~ ~10 ~3 ~-1 brick") else {
+            panic!();
+        };
+        assert_eq!((from, to), (at(4, 70, -2), at(14, 73, -3)));
+        let resolve = |camera, coords| resolve(&camera, coords).ok();
+        let local = |l, u, f| [Coord::Local(l), Coord::Local(u), Coord::Local(f)];
+        // left is positive x when looking toward positive z
+        assert_eq!(resolve(camera, local(2, 1, 3)), Some(at(6, 71, 1)));
+        // turned a quarter toward negative x (west), left becomes positive z
+        let turned = Camera {
+            h_angle: PI / 2.0,
+            ..camera
+        };
+        assert_eq!(resolve(turned, local(2, 0, 3)), Some(at(1, 70, 0)));
+        // looking straight down, forward is below
+        let down = Camera {
+            v_angle: -PI / 2.0,
+            ..camera
+        };
+        assert_eq!(resolve(down, local(0, 0, 2)), Some(at(4, 68, -2)));
+
+        assert_eq!(
+            super::resolve(
+                &camera,
+                [Coord::Local(1), Coord::Relative(0), Coord::Absolute(3)]
+            ),
+            Err(CmdError::MixedCoordinates)
+        );
+        assert!(matches!(
+            parser.parse(camera, "remove ~ ~-71 ~"),
+            Err(ParseError::User {
+                error: CmdError::OutOfWorld([4, -1, -2])
+            })
+        ));
+        // far away, without overflowing
+        assert_eq!(
+            resolve(camera, [0, 0, i32::MIN].map(Coord::Relative)),
+            Some(at(4, 70, i32::MIN))
+        );
+    }
+
+    #[test]
+    fn test_tp_command() {
+        let harness = Harness::empty("location");
+        let world = &harness.world;
+        let camera = Camera {
+            pos: [-12.25, 71.6, 3.5],
+            h_angle: 1.0,
+            v_angle: -0.5,
+        };
+        // the command given is accepted by the parser, and brings back there
+        let command = tp_command(&camera);
+        let Ok(Cmd::Tp(pos, look)) = CmdParser::new().parse(Camera::default(), &command) else {
+            panic!("{command}");
+        };
+        world.teleport(pos, look).unwrap();
+        let player = world.pull_player();
+        for i in 0..3 {
+            assert!((player.camera.pos[i] - camera.pos[i]).abs() < 0.01);
+        }
+        assert!((player.camera.h_angle - camera.h_angle).abs() < 0.01);
+        assert!((player.camera.v_angle - camera.v_angle).abs() < 0.01);
+
+        // a negative angle is turned into the usual range
+        world
+            .teleport([0.5, 80.0, 0.5], Some([-90.0, 0.0]))
+            .unwrap();
+        let h_angle = world.pull_player().camera.h_angle;
+        assert!((h_angle - 1.5 * PI).abs() < 1e-4);
+        assert_eq!(
+            world.teleport([0.5, -5.0, 0.5], None),
+            Err(CmdError::OutOfWorld([0, -5, 0]))
+        );
+    }
+}
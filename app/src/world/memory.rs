@@ -0,0 +1,223 @@
+use std::{collections::HashMap, mem::size_of};
+
+use def::ChunkCoords;
+
+use super::{ChunkLoader, ChunkState, Entity, World};
+
+/// Memory taken by the world and its rendering, in bytes
+///
+/// The sizes are estimated from what is stored (the allocator and the
+/// driver add their own overhead), which is enough to compare with the
+/// budget.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Blocks, faces and light of the chunks, with their parked entities
+    pub chunks: usize,
+    /// Vertices and indices of the chunks uploaded by Aristide
+    pub meshes: usize,
+    pub textures: usize,
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.chunks + self.meshes + self.textures
+    }
+}
+
+/// A map takes its capacity in entries, plus a control byte for each
+fn map_bytes<K, V>(map: &HashMap<K, V>) -> usize {
+    map.capacity() * (size_of::<(K, V)>() + 1)
+}
+
+impl ChunkState {
+    fn bytes(&self) -> usize {
+        match self {
+            Self::Loaded(blocks) => map_bytes(blocks),
+            Self::Meshed(blocks, faces) => map_bytes(blocks) + map_bytes(faces),
+            Self::Compressed(compressed) => compressed.bytes(),
+        }
+    }
+}
+
+impl World {
+    /// What is known of the memory taken, as last measured
+    pub fn memory_usage(&self) -> MemoryUsage {
+        *self.memory.lock().unwrap()
+    }
+
+    /// Whether the memory taken is past the budget of the config
+    pub fn over_budget(&self) -> bool {
+        let budget = self.config.read().unwrap().memory_budget;
+        self.memory_usage().total() > budget << 20
+    }
+
+    /// Records what Aristide keeps on the GPU
+    pub fn set_gpu_memory(&self, meshes: usize, textures: usize) {
+        let mut memory = self.memory.lock().unwrap();
+        memory.meshes = meshes;
+        memory.textures = textures;
+    }
+
+    /// Bytes taken by a chunk in memory, with its light and parked entities
+    fn chunk_bytes(&self, cc: ChunkCoords) -> usize {
+        let blocks = self.chunks.get(&cc).map_or(0, |chunk| chunk.bytes());
+        let lights = self.lights.get(&cc).map_or(0, |lights| map_bytes(&lights));
+        let parked = self
+            .parked
+            .get(&cc)
+            .map_or(0, |parked| parked.capacity() * size_of::<Entity>());
+        blocks + lights + parked
+    }
+
+    /// Measures the memory taken by the chunks again
+    pub fn measure_chunks(&self) -> usize {
+        let chunks: Vec<ChunkCoords> = self.chunks.iter().map(|chunk| *chunk.key()).collect();
+        let bytes = chunks.into_iter().map(|cc| self.chunk_bytes(cc)).sum();
+        self.memory.lock().unwrap().chunks = bytes;
+        bytes
+    }
+
+    /// Lets go of the far chunks, the farthest from the loaders first, until
+    /// the memory taken is back under the budget
+    ///
+    /// This is the last resort: the far chunks are compressed first, then
+    /// the meshes beyond the render distance are dropped. An edited chunk
+    /// is saved before being unloaded, along with its parked entities, and
+    /// it is read from the save when a loader comes back. Returns the number
+    /// of chunks unloaded.
+    pub fn unload_far_chunks(&self, loaders: &[ChunkLoader]) -> usize {
+        let distance = |cc: ChunkCoords| {
+            loaders
+                .iter()
+                .map(|loader| (cc.x - loader.center.x).pow(2) + (cc.z - loader.center.z).pow(2))
+                .min()
+                .unwrap_or(0)
+        };
+        let mut far: Vec<ChunkCoords> = self
+            .chunks
+            .iter()
+            .filter(|chunk| !loaders.iter().any(|loader| loader.keeps(*chunk.key())))
+            .map(|chunk| *chunk.key())
+            .collect();
+        far.sort_by_key(|&cc| std::cmp::Reverse(distance(cc)));
+        let mut count = 0;
+        for cc in far {
+            if !self.over_budget() {
+                break;
+            }
+            let bytes = self.chunk_bytes(cc);
+            if let Err(error) = self.save_far_chunk(cc) {
+                tracing::warn!(%error, x = cc.x, z = cc.z, "far chunk kept, its save failed");
+                break;
+            }
+            self.chunks.remove(&cc);
+            self.lights.remove(&cc);
+            self.mesh_generations.remove(&cc);
+            let mut memory = self.memory.lock().unwrap();
+            memory.chunks = memory.chunks.saturating_sub(bytes);
+            count += 1;
+        }
+        if count > 0 {
+            tracing::info!(count, "far chunks unloaded to hold the memory budget");
+        }
+        count
+    }
+
+    /// Writes a chunk about to be unloaded if it was edited, and its
+    /// parked entities
+    fn save_far_chunk(&self, cc: ChunkCoords) -> std::io::Result<()> {
+        if self.dirty.contains(&cc) {
+            let blocks = match self.chunks.get(&cc).as_deref() {
+                Some(ChunkState::Loaded(blocks) | ChunkState::Meshed(blocks, _)) => blocks.clone(),
+                Some(ChunkState::Compressed(compressed)) => compressed.decompress(),
+                None => return Ok(()),
+            };
+            self.save.save_chunk(cc, &blocks)?;
+            self.dirty.remove(&cc);
+        }
+        let parked = self.parked.get(&cc).map(|parked| parked.clone());
+        // the chunks whose entities are all gone are saved empty
+        if parked.is_some() || self.entity_chunks.contains(&cc) {
+            self.save
+                .save_entities(cc, parked.as_deref().unwrap_or_default())?;
+        }
+        self.parked.remove(&cc);
+        // the save holds them now, they are read again with the chunk
+        self.entity_chunks.remove(&cc);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use def::Block;
+
+    use super::*;
+    use crate::harness::Harness;
+
+    #[test]
+    fn test_unload_far_chunks() {
+        let harness = Harness::empty("memory");
+        let world = &harness.world;
+        let loader = ChunkLoader {
+            center: ChunkCoords { x: 0, z: 0 },
+            range: 2,
+        };
+        let near = ChunkCoords { x: 0, z: 0 };
+        let far = ChunkCoords { x: 10, z: 0 };
+        let farther = ChunkCoords { x: -20, z: 0 };
+        for cc in [near, far, farther] {
+            world.advance_chunk_stage(cc, crate::world::ChunkStage::Loaded);
+        }
+        // an edit of the far chunk, not saved yet
+        let coords = def::BlockCoords(far, [1, 250, 1].try_into().unwrap());
+        if let Some(ChunkState::Loaded(blocks)) = world.chunks.get_mut(&far).as_deref_mut() {
+            blocks.insert(coords.1, Block::Brick);
+        }
+        world.dirty.insert(far);
+        world.spawn_entity(Entity::projectile([160.5, 90.0, 0.5], [0.0; 3]));
+        world.compress_far_chunks(&[loader]);
+
+        // within the budget, nothing goes
+        world.config.write().unwrap().memory_budget = 1;
+        assert!(world.measure_chunks() > 0);
+        assert!(world.measure_chunks() < 1 << 20);
+        assert_eq!(world.unload_far_chunks(&[loader]), 0);
+
+        // past it, the far chunks go but not the ones kept
+        world.set_gpu_memory(1 << 20, 0);
+        assert_eq!(world.unload_far_chunks(&[loader]), 2);
+        assert!(world.chunks.contains_key(&near));
+        assert!(!world.chunks.contains_key(&far));
+        assert!(world.parked.is_empty());
+        assert_eq!(world.memory_usage().chunks, world.measure_chunks());
+
+        // and they come back as they were
+        world.advance_chunk_stage(far, crate::world::ChunkStage::Loaded);
+        assert_eq!(world.get_block(coords), Some(Some(Block::Brick)));
+        world.compress_far_chunks(&[]);
+        assert_eq!(world.parked.get(&far).map(|parked| parked.len()), Some(1));
+    }
+
+    #[test]
+    fn test_build_unloaded_sections() {
+        let harness = Harness::empty("memory-sections");
+        let world = &harness.world;
+        let far = ChunkCoords { x: 10, z: 0 };
+        world.advance_chunk_stage(far, crate::world::ChunkStage::Meshed);
+        let mut builder = crate::aristide::chunk_loader::ChunkLoader::new();
+        assert!(builder
+            .fill_sections(far, crate::world::ALL_SECTIONS, world)
+            .is_some());
+
+        world.measure_chunks();
+        world.config.write().unwrap().memory_budget = 1;
+        world.set_gpu_memory(1 << 20, 0);
+        world.unload_far_chunks(&[]);
+        assert!(!world.chunks.contains_key(&far));
+        // a render request sent before the unloading finds nothing to build
+        assert!(builder
+            .fill_sections(far, crate::world::ALL_SECTIONS, world)
+            .is_none());
+    }
+}
@@ -0,0 +1,43 @@
+use super::World;
+
+/// Constants of the movements, tunable in the config file
+///
+/// Speeds are in blocks per tick, accelerations in blocks per tick squared.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Physics {
+    /// Vertical acceleration of the player and the entities (negative, down)
+    pub gravity: f32,
+    /// Vertical speed given by a jump
+    pub jump: f32,
+    /// Margin kept between a hit box and the blocks around, so a player
+    /// against a wall does not collide with the floor along it
+    pub collision_epsilon: f32,
+    /// Horizontal speed of a walking player, along one axis
+    pub walk: f32,
+    /// Horizontal speed of a sprinting player, along one axis
+    pub sprint: f32,
+    /// Speed of a flying player, along one axis
+    pub fly: f32,
+    /// Fastest fall, reached when gravity has accumulated for long enough
+    pub max_fall: f32,
+}
+
+impl Default for Physics {
+    fn default() -> Self {
+        Self {
+            gravity: -0.01,
+            jump: 0.15,
+            collision_epsilon: 0.001,
+            walk: 0.075,
+            sprint: 0.15,
+            fly: 1.0,
+            max_fall: 2.0,
+        }
+    }
+}
+
+impl World {
+    pub fn physics(&self) -> Physics {
+        self.config.read().unwrap().physics
+    }
+}
@@ -0,0 +1,176 @@
+use def::{state, Block, BlockCoords, Direction};
+use mat::VectorTrait;
+
+use super::{Author, Player, World};
+
+/// Most blocks a piston pushes at once
+pub const MAX_PUSH: usize = 12;
+
+impl World {
+    /// Pushes the blocks in front of a piston by one voxel, its head taking
+    /// the freed voxel
+    ///
+    /// Nothing moves when an immovable block is in the way, when the line
+    /// is longer than [`MAX_PUSH`] or goes out of the loaded world. Returns
+    /// whether the piston extended.
+    pub(super) fn extend_piston(&self, bc: BlockCoords, piston: Block) -> bool {
+        let facing = piston.facing();
+        let Some(line) = self.pushed_line(bc, facing) else {
+            return false;
+        };
+        let head = Block::PistonHead(0).with_facing(facing);
+        let mut edits = vec![(bc, Some(piston.with_state(piston.state() | state::ON)))];
+        let mut filled = Vec::new();
+        // the line ended on air, so every voxel ahead of a block exists
+        for (at, block) in [(bc, head)].into_iter().chain(line.iter().copied()) {
+            if let Some(ahead) = at.step(facing) {
+                edits.push((ahead, Some(block)));
+                filled.push(ahead);
+            }
+        }
+        if self.set_blocks(&edits, Author::Nature).is_err() {
+            return false;
+        }
+        let carrying: Vec<_> = line
+            .iter()
+            .filter_map(|&(at, _)| at.step(Direction::Up))
+            .collect();
+        self.carry(&filled, &carrying, facing);
+        true
+    }
+
+    /// Takes the head of a piston back, the pushed blocks stay where they
+    /// are
+    pub(super) fn retract_piston(&self, bc: BlockCoords, piston: Block) {
+        let facing = piston.facing();
+        let mut edits = vec![(bc, Some(piston.with_state(piston.state() & !state::ON)))];
+        if let Some(ahead) = bc.step(facing) {
+            if let Some(Some(Block::PistonHead(_))) = self.get_block(ahead) {
+                edits.push((ahead, None));
+            }
+        }
+        self.set_blocks(&edits, Author::Nature).ok();
+    }
+
+    /// The blocks a piston would push, from the nearest one (`None` when
+    /// it can't push them)
+    fn pushed_line(&self, bc: BlockCoords, facing: Direction) -> Option<Vec<(BlockCoords, Block)>> {
+        let mut line = Vec::new();
+        let mut at = bc.step(facing)?;
+        while let Some(block) = self.get_block(at)? {
+            if !block.is_movable() || line.len() == MAX_PUSH {
+                return None;
+            }
+            line.push((at, block));
+            at = at.step(facing)?;
+        }
+        Some(line)
+    }
+
+    /// Moves by one voxel the entities (and the player) in the voxels now
+    /// filled, or standing on the pushed blocks
+    fn carry(&self, filled: &[BlockCoords], carrying: &[BlockCoords], facing: Direction) {
+        let offset = match facing {
+            Direction::North => [0.0, 0.0, -1.0],
+            Direction::South => [0.0, 0.0, 1.0],
+            Direction::East => [1.0, 0.0, 0.0],
+            Direction::West => [-1.0, 0.0, 0.0],
+            Direction::Up => [0.0, 1.0, 0.0],
+            Direction::Down => [0.0, -1.0, 0.0],
+        };
+        let carried = |pos: [f32; 3]| {
+            BlockCoords::try_from(pos)
+                .is_ok_and(|bc| filled.contains(&bc) || carrying.contains(&bc))
+        };
+        for entity in self.entities.lock().unwrap().values_mut() {
+            if carried(entity.pos) {
+                entity.pos.vector_add_assign(offset);
+            }
+        }
+        let mut player = self.player.write().unwrap();
+        let [x, y, z] = player.camera.pos;
+        if carried([x, y - Player::EYES, z]) {
+            player.camera.pos.vector_add_assign(offset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use def::ChunkCoords;
+
+    use super::*;
+    use crate::{
+        harness::Harness,
+        world::{BlocksChunk, ChunkState, Entity, FacesChunk},
+    };
+
+    fn at(x: i32, y: i32, z: i32) -> BlockCoords {
+        [x, y, z].try_into().unwrap()
+    }
+
+    fn harness(name: &str, blocks: &[([i32; 3], Block)]) -> Harness {
+        let harness = Harness::empty(name);
+        let chunk: BlocksChunk = blocks
+            .iter()
+            .map(|&([x, y, z], block)| (at(x, y, z).1, block))
+            .collect();
+        harness.world.chunks.insert(
+            ChunkCoords { x: 0, z: 0 },
+            ChunkState::Meshed(chunk, FacesChunk::new()),
+        );
+        harness
+    }
+
+    #[test]
+    fn test_push() {
+        let piston = Block::Piston(0).with_facing(Direction::East);
+        let harness = harness(
+            "piston",
+            &[
+                ([0, 10, 0], piston),
+                ([1, 10, 0], Block::Sand),
+                ([2, 10, 0], Block::Brick),
+            ],
+        );
+        let world = &harness.world;
+        let item = world.spawn_entity(Entity::item(at(1, 11, 0), Block::Dirt));
+        assert!(world.extend_piston(at(0, 10, 0), piston));
+        let block = |x| world.get_block(at(x, 10, 0)).flatten();
+        assert_eq!(block(0).map(Block::state), Some(piston.state() | state::ON));
+        assert_eq!(
+            block(1),
+            Some(Block::PistonHead(0).with_facing(Direction::East))
+        );
+        assert_eq!(
+            (block(2), block(3)),
+            (Some(Block::Sand), Some(Block::Brick))
+        );
+        // the item lying on the sand went with it
+        let item = world.entities.lock().unwrap()[&item];
+        assert_eq!(BlockCoords::try_from(item.pos), Ok(at(2, 11, 0)));
+
+        world.retract_piston(at(0, 10, 0), block(0).unwrap());
+        assert_eq!((block(0), block(1)), (Some(piston), None));
+        assert_eq!(block(2), Some(Block::Sand));
+    }
+
+    #[test]
+    fn test_stuck() {
+        let piston = Block::Piston(0).with_facing(Direction::East);
+        let mut blocks = vec![([0, 10, 0], piston), ([1, 10, 0], Block::Sand)];
+        blocks.push(([2, 10, 0], Block::Bedrock));
+        let stuck = harness("piston-stuck", &blocks);
+        let world = &stuck.world;
+        assert!(!world.extend_piston(at(0, 10, 0), piston));
+        assert_eq!(world.get_block(at(1, 10, 0)), Some(Some(Block::Sand)));
+
+        // too many blocks in a row
+        let mut blocks = vec![([0, 10, 0], piston)];
+        blocks.extend((1..=MAX_PUSH as i32 + 1).map(|x| ([x, 10, 0], Block::Dirt)));
+        let long = harness("piston-long", &blocks);
+        let world = &long.world;
+        assert!(!world.extend_piston(at(0, 10, 0), piston));
+        assert_eq!(world.get_block(at(0, 10, 0)), Some(Some(piston)));
+    }
+}
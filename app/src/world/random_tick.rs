@@ -0,0 +1,289 @@
+use def::{state, Block, BlockCoords, BlockIndex, ChunkCoords, Direction};
+use mat::VectorTrait;
+
+use super::{Author, ChunkState, Structure, World};
+
+/// Voxels picked at random in every meshed chunk, at each tick
+///
+/// Most of them are air, so slow processes (like grass spreading) happen
+/// here and there, at an even pace all over the loaded terrain.
+const RANDOM_TICKS: u32 = 16;
+
+/// Light level needed by plants to grow (when they do not see the sky)
+const PLANT_LIGHT: u8 = 9;
+
+/// Growth stages of a sapling before it becomes a tree
+const SAPLING_STAGES: u8 = 4;
+
+/// How far from water (horizontally) farmland is watered
+const WATER_REACH: i32 = 4;
+
+/// What a block does when it is picked by a random tick
+pub type RandomTick = fn(&World, BlockCoords, Block, &mut Rng);
+
+/// The random tick behaviour of a block, if it has one
+pub fn on_random_tick(block: Block) -> Option<RandomTick> {
+    match block {
+        Block::Grass => Some(grass),
+        Block::Sapling(_) => Some(sapling),
+        Block::Crop(_) => Some(crop),
+        _ => None,
+    }
+}
+
+/// Small and fast pseudo random generator (xorshift)
+///
+/// It does not need to be good, only to spread the ticks evenly.
+#[derive(Debug, Clone, Copy, Hash)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // the state must never be zero
+        Self(seed | 1)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `0..n`
+    pub fn below(&mut self, n: u32) -> u32 {
+        (self.next() % n as u64) as u32
+    }
+}
+
+impl World {
+    /// Gives a random tick to a few voxels of every meshed chunk
+    pub(super) fn random_tick(&self) {
+        let mut chunks: Vec<ChunkCoords> = self
+            .chunks
+            .iter()
+            .filter(|chunk| matches!(chunk.value(), ChunkState::Meshed(..)))
+            .map(|chunk| *chunk.key())
+            .collect();
+        // the numbers drawn go to the same chunks at every run
+        chunks.sort_by_key(|cc| (cc.x, cc.z));
+        let mut rng = self.rng.lock().unwrap();
+        for cc in chunks {
+            for _ in 0..RANDOM_TICKS {
+                let bc = BlockCoords(
+                    cc,
+                    BlockIndex {
+                        index: rng.next() as u16,
+                    },
+                );
+                if let Some(Some(block)) = self.get_block(bc) {
+                    if let Some(random_tick) = on_random_tick(block) {
+                        random_tick(self, bc, block, &mut rng);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether nothing opaque is above the voxel, up to the top of the world
+    pub fn sees_sky(&self, BlockCoords(cc, bi): BlockCoords) -> bool {
+        let Some(chunk) = self.chunks.get(&cc) else {
+            return false;
+        };
+        let [x, y, z]: [i32; 3] = bi.into();
+        ((y + 1)..256).all(|y| {
+            let bi = BlockIndex::try_from([x, y, z]).unwrap();
+            chunk.get_block(bi).is_none_or(Block::is_transparent)
+        })
+    }
+}
+
+/// Grass dies under an opaque block, and spreads to nearby dirt with
+/// enough light (seeing the sky or lit by torches)
+fn grass(world: &World, bc: BlockCoords, _: Block, rng: &mut Rng) {
+    let covered = |bc: BlockCoords| {
+        bc.step(Direction::Up)
+            .and_then(|above| world.get_block(above))
+            .flatten()
+            .is_some_and(|block| !block.is_transparent())
+    };
+    if covered(bc) {
+        world.replace_block(bc, Block::Dirt, Author::Nature).ok();
+        return;
+    }
+    // one block around, from three below to one above
+    let offset = [
+        rng.below(3) as i32 - 1,
+        rng.below(5) as i32 - 3,
+        rng.below(3) as i32 - 1,
+    ];
+    let Ok(target) = BlockCoords::try_from(<[i32; 3]>::from(bc).vector_add(offset)) else {
+        return;
+    };
+    let above = target.step(Direction::Up);
+    if world.get_block(target) == Some(Some(Block::Dirt))
+        && !covered(target)
+        && above.is_some_and(|above| lit(world, above))
+    {
+        world
+            .replace_block(target, Block::Grass, Author::Nature)
+            .ok();
+    }
+}
+
+/// A sapling grows slowly when lit, then becomes a tree if there is room
+fn sapling(world: &World, bc: BlockCoords, block: Block, rng: &mut Rng) {
+    // half the ticks are skipped, so trees take more or less time to grow
+    if !lit(world, bc) || rng.below(2) == 0 {
+        return;
+    }
+    let stage = block.state();
+    if stage + 1 < SAPLING_STAGES {
+        world
+            .replace_block(bc, Block::Sapling(stage + 1), Author::Nature)
+            .ok();
+        return;
+    }
+    let tree = Structure::tree(4 + rng.below(3) as i32);
+    if world.fits(bc, &tree) {
+        world.paste(bc, &tree, Author::Nature).ok();
+    }
+}
+
+/// A crop grows a stage at a time when lit, on farmland watered nearby
+fn crop(world: &World, bc: BlockCoords, block: Block, rng: &mut Rng) {
+    let stage = block.state();
+    if stage >= state::RIPE || !lit(world, bc) || rng.below(2) == 0 {
+        return;
+    }
+    let watered = bc
+        .step(Direction::Down)
+        .is_some_and(|below| world.on_farmland(bc) && near_water(world, below));
+    if watered {
+        world
+            .replace_block(bc, Block::Crop(stage + 1), Author::Nature)
+            .ok();
+    }
+}
+
+/// Whether there is water at the height of the voxel, up to
+/// [`WATER_REACH`] blocks away
+fn near_water(world: &World, bc: BlockCoords) -> bool {
+    let [x, y, z]: [i32; 3] = bc.into();
+    (-WATER_REACH..=WATER_REACH).any(|dx| {
+        (-WATER_REACH..=WATER_REACH).any(|dz| {
+            BlockCoords::try_from([x + dx, y, z + dz])
+                .ok()
+                .and_then(|bc| world.get_block(bc))
+                .flatten()
+                .is_some_and(|block| matches!(block, Block::Water(_)))
+        })
+    })
+}
+
+/// Whether a plant in the voxel gets enough light to grow
+fn lit(world: &World, bc: BlockCoords) -> bool {
+    world.sees_sky(bc) || world.get_light(bc) >= PLANT_LIGHT
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        harness::Harness,
+        world::{BlocksChunk, FacesChunk},
+        CmdError,
+    };
+
+    #[test]
+    fn test_grass() {
+        let harness = Harness::empty("grass");
+        let world = &harness.world;
+        let at = |x, y, z| BlockCoords::try_from([x, y, z]).unwrap();
+        let index = |bc: BlockCoords| bc.1;
+        // grass next to dirt in the open, and grass under stone
+        let chunk = BlocksChunk::from([
+            (index(at(4, 10, 4)), Block::Grass),
+            (index(at(5, 10, 4)), Block::Dirt),
+            (index(at(10, 10, 10)), Block::Grass),
+            (index(at(10, 11, 10)), Block::Stone),
+        ]);
+        let cc = ChunkCoords { x: 0, z: 0 };
+        world
+            .chunks
+            .insert(cc, ChunkState::Meshed(chunk, FacesChunk::new()));
+        assert!(world.sees_sky(at(5, 11, 4)));
+        assert!(!world.sees_sky(at(10, 10, 10)));
+
+        let mut rng = Rng::new(7);
+        grass(world, at(10, 10, 10), Block::Grass, &mut rng);
+        assert_eq!(world.get_block(at(10, 10, 10)), Some(Some(Block::Dirt)));
+        for _ in 0..100 {
+            grass(world, at(4, 10, 4), Block::Grass, &mut rng);
+        }
+        assert_eq!(world.get_block(at(5, 10, 4)), Some(Some(Block::Grass)));
+
+        // a sapling in the open becomes a tree
+        world
+            .replace_block(at(4, 10, 4), Block::Dirt, Author::Nature)
+            .unwrap();
+        let BlockCoords(_, bi) = at(4, 11, 4);
+        if let ChunkState::Meshed(blocks, _) = &mut *world.chunks.get_mut(&cc).unwrap() {
+            blocks.insert(bi, Block::Sapling(0));
+        }
+        for _ in 0..100 {
+            if let Some(Some(block @ Block::Sapling(_))) = world.get_block(at(4, 11, 4)) {
+                sapling(world, at(4, 11, 4), block, &mut rng);
+            }
+        }
+        assert_eq!(world.get_block(at(4, 11, 4)), Some(Some(Block::Trunk)));
+    }
+
+    #[test]
+    fn test_crop() {
+        let harness = Harness::empty("crop");
+        let world = &harness.world;
+        let at = |x, y, z| BlockCoords::try_from([x, y, z]).unwrap();
+        let index = |bc: BlockCoords| bc.1;
+        // dirt next to water, and dirt far from it
+        let chunk = BlocksChunk::from([
+            (index(at(4, 10, 4)), Block::Dirt),
+            (index(at(7, 10, 4)), Block::Water(0)),
+            (index(at(14, 10, 14)), Block::Grass),
+            (index(at(1, 10, 12)), Block::Stone),
+        ]);
+        world.chunks.insert(
+            ChunkCoords { x: 0, z: 0 },
+            ChunkState::Meshed(chunk, FacesChunk::new()),
+        );
+        // crops only grow on farmland
+        assert_eq!(
+            world.place_block(at(4, 11, 4), Block::Crop(0)),
+            Err(CmdError::NoSupport(at(4, 11, 4)))
+        );
+        assert_eq!(
+            world.till(at(1, 10, 12)),
+            Err(CmdError::NotTillable(at(1, 10, 12), Block::Stone))
+        );
+        for bc in [at(4, 10, 4), at(14, 10, 14)] {
+            world.till(bc).unwrap();
+            let above = bc.step(Direction::Up).unwrap();
+            world.place_block(above, Block::Crop(0)).unwrap();
+        }
+
+        let mut rng = Rng::new(5);
+        for _ in 0..100 {
+            for bc in [at(4, 11, 4), at(14, 11, 14)] {
+                if let Some(Some(block)) = world.get_block(bc) {
+                    crop(world, bc, block, &mut rng);
+                }
+            }
+        }
+        assert_eq!(
+            world.get_block(at(4, 11, 4)),
+            Some(Some(Block::Crop(state::RIPE)))
+        );
+        // the dry one did not grow
+        assert_eq!(world.get_block(at(14, 11, 14)), Some(Some(Block::Crop(0))));
+    }
+}
@@ -0,0 +1,149 @@
+use def::{BlockCoords, Boxel, Direction, RayTravel};
+use mat::VectorTrait;
+
+use super::{EntityId, World};
+
+/// What a ray cast from the eyes of the player hits first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RayHit {
+    /// A block, `face` being the side the ray came in by
+    Block {
+        coords: BlockCoords,
+        face: Direction,
+        point: [f32; 3],
+    },
+    /// An entity that can be hit (see [`super::EntityKind::max_health`])
+    Entity { id: EntityId, point: [f32; 3] },
+}
+
+/// Distance along the ray where it enters the box (0.0 when it starts
+/// inside), if it does
+fn enters(origin: [f32; 3], direction: [f32; 3], hit_box: Boxel) -> Option<f32> {
+    let mut near = 0.0f32;
+    let mut far = f32::INFINITY;
+    for axis in 0..3 {
+        let min = hit_box.pos[axis];
+        let max = min + hit_box.dimensions[axis];
+        if direction[axis] == 0.0 {
+            if !(min..max).contains(&origin[axis]) {
+                return None;
+            }
+            continue;
+        }
+        let [a, b] = [min, max].map(|bound| (bound - origin[axis]) / direction[axis]);
+        near = near.max(a.min(b));
+        far = far.min(a.max(b));
+    }
+    (near <= far).then_some(near)
+}
+
+impl World {
+    /// The first block or entity met by the ray from `origin` toward
+    /// `direction`, up to `max` blocks away
+    ///
+    /// The entities are seen through fluids (a boat is pointed at even
+    /// though the water surface is in front of it), and the boat the player
+    /// sits in is ignored.
+    pub fn raycast(&self, origin: [f32; 3], direction: [f32; 3], max: f32) -> Option<RayHit> {
+        let direction = direction.vector_scale(1.0 / direction.vector_dot(direction).sqrt());
+        let voxel = |bc: BlockCoords| Boxel::new([1.0; 3], [0.0; 3], bc.into());
+        // the first block, and the first one the entities can't be seen through
+        let mut block = None;
+        let mut wall = max;
+        for (coords, face) in RayTravel::new(origin, direction, max).flatten() {
+            let Some(Some(found)) = self.get_block(coords) else {
+                continue;
+            };
+            let distance = enters(origin, direction, voxel(coords)).unwrap_or(0.0);
+            if distance > max {
+                break;
+            }
+            block.get_or_insert((coords, face, distance));
+            if found.is_solid() && found.fluid_height().is_none() {
+                wall = distance;
+                break;
+            }
+        }
+        let riding = self.player.read().unwrap().riding;
+        let entity = self
+            .entities
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|&(&id, entity)| entity.kind.max_health() > 0 && riding != Some(id))
+            .filter_map(|(&id, entity)| Some((id, enters(origin, direction, entity.hit_box())?)))
+            .filter(|&(_, distance)| distance <= wall)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+        let point = |distance| origin.vector_add(direction.vector_scale(distance));
+        match (entity, block) {
+            (Some((id, distance)), _) => Some(RayHit::Entity {
+                id,
+                point: point(distance),
+            }),
+            (None, Some((coords, face, distance))) => Some(RayHit::Block {
+                coords,
+                face,
+                point: point(distance),
+            }),
+            (None, None) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use def::{Block, ChunkCoords};
+
+    use super::*;
+    use crate::{
+        harness::Harness,
+        world::{BlocksChunk, ChunkState, Entity, FacesChunk},
+    };
+
+    #[test]
+    fn test_raycast() {
+        let harness = Harness::empty("raycast-entities");
+        let world = &harness.world;
+        // a stone wall at x = 8, with water in front of it at x = 6
+        let blocks = (0..16).flat_map(|y| {
+            (0..16).flat_map(move |z| {
+                [(8, Block::Stone), (6, Block::Water(0))]
+                    .map(|(x, block)| (BlockCoords::try_from([x, y, z]).unwrap().1, block))
+            })
+        });
+        world.chunks.insert(
+            ChunkCoords { x: 0, z: 0 },
+            ChunkState::Meshed(BlocksChunk::from_iter(blocks), FacesChunk::new()),
+        );
+        let origin = [2.5, 10.5, 4.5];
+        let hit = world.raycast(origin, [1.0, 0.0, 0.0], 10.0);
+        assert_eq!(
+            hit,
+            Some(RayHit::Block {
+                coords: BlockCoords::try_from([6, 10, 4]).unwrap(),
+                face: Direction::West,
+                point: [6.0, 10.5, 4.5],
+            })
+        );
+        // too far
+        assert_eq!(world.raycast(origin, [1.0, 0.0, 0.0], 3.0), None);
+
+        // a mob in the water is pointed at, not one behind the wall
+        let id = world.spawn_entity(Entity::mob(BlockCoords::try_from([7, 10, 4]).unwrap()));
+        world.spawn_entity(Entity::mob(BlockCoords::try_from([10, 10, 5]).unwrap()));
+        let Some(RayHit::Entity { id: hit, point }) = world.raycast(origin, [1.0, 0.0, 0.0], 10.0)
+        else {
+            panic!();
+        };
+        assert_eq!(hit, id);
+        assert!((point[0] - (7.5 - Entity::MOB_SIZE / 2.0)).abs() < 1e-4);
+        assert!(matches!(
+            world.raycast([9.5, 10.5, 5.5], [1.0, 0.0, 0.0], 10.0),
+            Some(RayHit::Entity { .. })
+        ));
+        assert!(matches!(
+            world.raycast(origin, [1.0, 0.0, 0.2], 10.0),
+            Some(RayHit::Block { .. })
+        ));
+    }
+}
@@ -0,0 +1,129 @@
+use def::BlockCoords;
+use mat::VectorTrait;
+
+use super::{Entity, EntityId, EntityKind, Player, World};
+
+/// Height of the eyes of a rider above the bottom of its boat
+const SEAT: f32 = 0.9;
+
+impl World {
+    pub fn entity(&self, id: EntityId) -> Option<Entity> {
+        self.entities.lock().unwrap().get(&id).copied()
+    }
+
+    /// The player gets in the boat, until it dismounts (see
+    /// [`Player::dismount`])
+    pub fn mount(&self, id: EntityId) -> bool {
+        if self.entity(id).map(|entity| entity.kind) != Some(EntityKind::Boat) {
+            return false;
+        }
+        let mut player = self.player.write().unwrap();
+        player.riding = Some(id);
+        player.fly = false;
+        player.gravity = 0.0;
+        player.on_ground = false;
+        true
+    }
+
+    /// Sets the horizontal speed of a boat (its drag slows it down after)
+    pub fn steer(&self, id: EntityId, [x, z]: [f32; 2]) {
+        if let Some(boat) = self.entities.lock().unwrap().get_mut(&id) {
+            boat.velocity[0] = x;
+            boat.velocity[2] = z;
+        }
+    }
+
+    /// Places a boat on the water (or on the ground) at the voxel
+    pub fn spawn_boat(&self, bc: BlockCoords) -> EntityId {
+        let pos = <[f32; 3]>::from(bc).vector_add([0.5, 0.0, 0.5]);
+        self.spawn_entity(Entity::boat(pos))
+    }
+}
+
+impl Player {
+    /// The eyes of a player sitting in the boat
+    pub fn seat(boat: &Entity) -> [f32; 3] {
+        boat.pos.vector_add([0.0, SEAT, 0.0])
+    }
+
+    /// Gets out of the boat, standing on top of it
+    pub fn dismount(&mut self, boat: Option<&Entity>) {
+        self.riding = None;
+        if let Some(boat) = boat {
+            self.camera.pos = boat
+                .pos
+                .vector_add([0.0, Entity::BOAT_SIZE[1] + Player::EYES, 0.0]);
+        }
+        self.gravity = 0.0;
+        self.on_ground = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use def::{Block, ChunkCoords};
+
+    use super::*;
+    use crate::{
+        harness::Harness,
+        world::{BlocksChunk, ChunkState, FacesChunk, RayHit, REACH},
+    };
+
+    #[test]
+    fn test_boat() {
+        let harness = Harness::empty("riding");
+        let world = &harness.world;
+        // a pool of water two blocks deep, closed by a wall at x = 8
+        let pool = (0..16).flat_map(|x| {
+            (0..16).flat_map(move |z| {
+                (9..13).filter_map(move |y| {
+                    let block = match y {
+                        _ if x == 8 => Block::Stone,
+                        9 => Block::Stone,
+                        12 => return None,
+                        _ => Block::Water(0),
+                    };
+                    Some((BlockCoords::try_from([x, y, z]).unwrap().1, block))
+                })
+            })
+        });
+        world.chunks.insert(
+            ChunkCoords { x: 0, z: 0 },
+            ChunkState::Meshed(BlocksChunk::from_iter(pool), FacesChunk::new()),
+        );
+        // dropped from above, it comes to float on the surface
+        let id = world.spawn_boat(BlockCoords::try_from([2, 14, 2]).unwrap());
+        for _ in 0..100 {
+            world.tick();
+        }
+        let boat = world.entity(id).unwrap();
+        assert_eq!([boat.pos[0], boat.pos[2]], [2.5, 2.5]);
+        assert!(
+            (boat.pos[1] - (11.0 + 14.0 / 16.0 - 0.15)).abs() < 1e-4,
+            "{:?}",
+            boat.pos
+        );
+
+        // pointed at from the shore
+        let origin = [5.5, 13.0, 2.5];
+        let hit = world.raycast(origin, [-3.0, -1.3, 0.0], REACH);
+        assert!(matches!(hit, Some(RayHit::Entity { id: hit, .. }) if hit == id));
+        assert_eq!(world.raycast(origin, [-3.0, 1.0, 0.0], REACH), None);
+
+        assert!(world.mount(id));
+        assert_eq!(world.pull_player().riding, Some(id));
+        // steered toward the wall, it stops against it
+        for _ in 0..100 {
+            world.steer(id, [0.1, 0.0]);
+            world.tick();
+        }
+        let boat = world.entity(id).unwrap();
+        assert!((7.0..8.0).contains(&boat.pos[0]), "{:?}", boat.pos);
+        assert_eq!(boat.velocity, [0.0; 3]);
+
+        let mut player = world.pull_player();
+        player.dismount(Some(&boat));
+        assert_eq!(player.riding, None);
+        assert!(player.camera.pos[1] > Player::seat(&boat)[1]);
+    }
+}
@@ -0,0 +1,692 @@
+use std::{
+    collections::HashMap,
+    fs, io, mem,
+    path::{Path, PathBuf},
+};
+
+use def::{Block, BlockIndex, ChunkCoords};
+
+use super::{
+    generator::DEFAULT_STAGES, BlocksChunk, ChunkLoader, Entity, EntityKind, GameMode, Player,
+    Progress, Slots, Stack, Structure, SurfaceChunk, CHEST_SLOTS,
+};
+
+/// Blocks in the order of their id in the chunk files
+///
+/// New blocks must be added at the end, so old saves keep their meaning.
+/// The state of a block is saved aside from its id (the id of a custom
+/// block is its state).
+const BLOCKS: [Block; 28] = [
+    Block::Stone,
+    Block::Dirt,
+    Block::Grass,
+    Block::Sand,
+    Block::Water(0),
+    Block::Glass,
+    Block::Brick,
+    Block::Trunk,
+    Block::Leaves,
+    Block::Tnt,
+    Block::Door(0),
+    Block::Trapdoor(0),
+    Block::Torch(0),
+    Block::Sapling(0),
+    Block::Bedrock,
+    Block::Ice,
+    Block::Slime,
+    Block::Custom(0),
+    Block::Chest,
+    Block::Wire(0),
+    Block::Lever(0),
+    Block::Lamp(0),
+    Block::Piston(0),
+    Block::PistonHead(0),
+    Block::Farmland,
+    Block::Crop(0),
+    Block::SoulSand,
+    Block::Glider,
+];
+
+/// Kinds of entities in the order of their id in the entity files
+///
+/// Like the blocks, new kinds must be added at the end. The block of an
+/// item is saved apart, it only stands for every item here.
+const ENTITIES: [EntityKind; 5] = [
+    EntityKind::Projectile,
+    EntityKind::PrimedTnt,
+    EntityKind::Mob,
+    EntityKind::Item(Block::Stone),
+    EntityKind::Boat,
+];
+
+/// Bytes of a saved entity: its kind, position, velocity, age and the id
+/// and state of its block (for an item)
+const ENTITY_LEN: usize = 1 + 3 * 4 + 3 * 4 + 4 + 2;
+
+/// Id of the empty columns in `explored.map`
+const NO_BLOCK: u8 = u8::MAX;
+
+/// Bytes of an explored chunk: its coordinates, then the id, the state and
+/// the altitude of the top block of each column
+const EXPLORED_LEN: usize = 8 + 16 * 16 * 3;
+
+/// Bytes of a chest: its coordinates, then the id, the state and the count
+/// of the block of each slot (the empty ones have no id)
+const CHEST_LEN: usize = 12 + CHEST_SLOTS * 3;
+
+/// What is saved about the world itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Meta {
+    /// Seed of the terrain generator
+    pub seed: u32,
+    /// Value of the world clock
+    pub ticks: u64,
+    /// When the world was last opened (seconds since the unix epoch)
+    pub last_played: u64,
+    pub mode: GameMode,
+    /// Stages of the terrain generator (see [`Generator::from_spec`](super::Generator::from_spec))
+    pub generation: String,
+}
+
+/// Directory where a world is persisted
+///
+/// It contains `world.txt` (the [`Meta`]), `player.txt`, `loaders.txt` (the
+/// anchors), `achievements.txt`, `explored.map` (the surface of the chunks
+/// seen by the player), `chests.bin` (the content of the chests), the
+/// structures copied by the player in `structures/`, one file per chunk
+/// edited by the player in `chunks/` (the others are generated again) and
+/// one file per chunk holding entities in `entities/`.
+///
+/// Every file is first written aside then renamed, so a crash in the middle
+/// of a save never leaves a truncated file behind.
+#[derive(Debug, Clone)]
+pub struct Save {
+    dir: PathBuf,
+}
+
+impl Save {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn load_meta(&self) -> Option<Meta> {
+        let content = fs::read_to_string(self.dir.join("world.txt")).ok()?;
+        let mut seed = None;
+        let mut ticks = None;
+        let mut last_played = None;
+        let mut mode = None;
+        let mut generation = None;
+        for (key, value) in key_values(&content) {
+            match key {
+                "seed" => seed = value.parse().ok(),
+                "ticks" => ticks = value.parse().ok(),
+                "last_played" => last_played = value.parse().ok(),
+                "mode" => mode = value.parse().ok(),
+                "generation" => generation = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        Some(Meta {
+            seed: seed?,
+            ticks: ticks.unwrap_or_default(),
+            last_played: last_played.unwrap_or_default(),
+            mode: mode.unwrap_or(GameMode::Creative),
+            // worlds saved before the stages existed have the original terrain
+            generation: generation.unwrap_or_else(|| DEFAULT_STAGES.to_string()),
+        })
+    }
+
+    pub fn save_meta(&self, meta: &Meta) -> io::Result<()> {
+        let content = format!(
+            "seed = {}\nticks = {}\nlast_played = {}\nmode = {}\ngeneration = {}\n",
+            meta.seed, meta.ticks, meta.last_played, meta.mode, meta.generation
+        );
+        self.write("world.txt", content.as_bytes())
+    }
+
+    /// Lists the worlds saved in the given directory, with their name
+    ///
+    /// The most recently played come first.
+    pub fn list(dir: impl AsRef<Path>) -> Vec<(String, Meta)> {
+        let mut worlds: Vec<_> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let meta = Self::new(entry.path()).load_meta()?;
+                Some((name, meta))
+            })
+            .collect();
+        worlds.sort_by_key(|(_, meta)| std::cmp::Reverse(meta.last_played));
+        worlds
+    }
+
+    /// Restores the saved state of the player into the given one
+    ///
+    /// Returns false if there was nothing to restore.
+    pub fn load_player(&self, player: &mut Player) -> bool {
+        let Ok(content) = fs::read_to_string(self.dir.join("player.txt")) else {
+            return false;
+        };
+        let camera = &mut player.camera;
+        for (key, value) in key_values(&content) {
+            match key {
+                "x" => camera.pos[0] = value.parse().unwrap_or(camera.pos[0]),
+                "y" => camera.pos[1] = value.parse().unwrap_or(camera.pos[1]),
+                "z" => camera.pos[2] = value.parse().unwrap_or(camera.pos[2]),
+                "h_angle" => camera.h_angle = value.parse().unwrap_or(camera.h_angle),
+                "v_angle" => camera.v_angle = value.parse().unwrap_or(camera.v_angle),
+                "fly" => player.fly = value.parse().unwrap_or(player.fly),
+                "stamina" => player.stamina = value.parse().unwrap_or(player.stamina),
+                "placing" => {
+                    // the id, then the state (missing in older saves)
+                    let mut bytes = value.split_whitespace().map(str::parse);
+                    let id = bytes.next().and_then(Result::ok);
+                    let state = bytes.next().and_then(Result::ok).unwrap_or(0);
+                    if let Some(block) = id.and_then(|id| decode(id, state)) {
+                        player.block_placing = block;
+                    }
+                }
+                _ => {}
+            }
+        }
+        true
+    }
+
+    pub fn save_player(&self, player: &Player) -> io::Result<()> {
+        let [x, y, z] = player.camera.pos;
+        let content = format!(
+            "x = {x}\ny = {y}\nz = {z}\nh_angle = {}\nv_angle = {}\nfly = {}\nplacing = {} {}\nstamina = {}\n",
+            player.camera.h_angle,
+            player.camera.v_angle,
+            player.fly,
+            block_id(player.block_placing),
+            player.block_placing.state(),
+            player.stamina,
+        );
+        self.write("player.txt", content.as_bytes())
+    }
+
+    fn chunk_path(ChunkCoords { x, z }: ChunkCoords) -> PathBuf {
+        Path::new("chunks").join(format!("{x}_{z}.chunk"))
+    }
+
+    /// Reads a chunk edited by the player, if it was saved
+    pub fn load_chunk(&self, cc: ChunkCoords) -> Option<BlocksChunk> {
+        decode_chunk(&fs::read(self.dir.join(Self::chunk_path(cc))).ok()?)
+    }
+
+    /// Like [`Save::load_chunk`], without blocking the thread
+    pub async fn load_chunk_async(&self, cc: ChunkCoords) -> Option<BlocksChunk> {
+        decode_chunk(
+            &tokio::fs::read(self.dir.join(Self::chunk_path(cc)))
+                .await
+                .ok()?,
+        )
+    }
+
+    pub fn save_chunk(&self, cc: ChunkCoords, blocks: &BlocksChunk) -> io::Result<()> {
+        self.write(Self::chunk_path(cc), &encode_chunk(blocks))
+    }
+
+    fn entities_path(ChunkCoords { x, z }: ChunkCoords) -> PathBuf {
+        Path::new("entities").join(format!("{x}_{z}.entities"))
+    }
+
+    /// Reads the entities saved in a chunk
+    pub fn load_entities(&self, cc: ChunkCoords) -> Vec<Entity> {
+        let bytes = fs::read(self.dir.join(Self::entities_path(cc))).unwrap_or_default();
+        let f32_at =
+            |entry: &[u8], i: usize| f32::from_le_bytes(entry[i..i + 4].try_into().unwrap());
+        bytes
+            .chunks_exact(ENTITY_LEN)
+            .filter_map(|entry| {
+                let kind = match *ENTITIES.get(entry[0] as usize)? {
+                    EntityKind::Item(_) => EntityKind::Item(decode(entry[29], entry[30])?),
+                    kind => kind,
+                };
+                Some(Entity {
+                    kind,
+                    pos: [1, 5, 9].map(|i| f32_at(entry, i)),
+                    velocity: [13, 17, 21].map(|i| f32_at(entry, i)),
+                    age: u32::from_le_bytes(entry[25..29].try_into().unwrap()),
+                    // a wounded entity heals while its chunk is unloaded
+                    health: kind.max_health(),
+                    hurt: 0,
+                })
+            })
+            .collect()
+    }
+
+    /// Replaces the entities saved in a chunk (none removes the file)
+    pub fn save_entities(&self, cc: ChunkCoords, entities: &[Entity]) -> io::Result<()> {
+        if entities.is_empty() {
+            return match fs::remove_file(self.dir.join(Self::entities_path(cc))) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            };
+        }
+        let mut bytes = Vec::with_capacity(entities.len() * ENTITY_LEN);
+        for entity in entities {
+            let kind = mem::discriminant(&entity.kind);
+            bytes.push(
+                ENTITIES
+                    .iter()
+                    .position(|k| mem::discriminant(k) == kind)
+                    .unwrap() as u8,
+            );
+            for v in entity.pos.into_iter().chain(entity.velocity) {
+                bytes.extend(v.to_le_bytes());
+            }
+            bytes.extend(entity.age.to_le_bytes());
+            match entity.kind {
+                EntityKind::Item(block) => bytes.extend([block_id(block), block.state()]),
+                _ => bytes.extend([0, 0]),
+            }
+        }
+        self.write(Self::entities_path(cc), &bytes)
+    }
+
+    /// Reads the parts of structures waiting for their chunk (in `pending/`)
+    pub fn load_pending(&self) -> Vec<(ChunkCoords, BlocksChunk)> {
+        fs::read_dir(self.dir.join("pending"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let (x, z) = name.strip_suffix(".chunk")?.split_once('_')?;
+                let cc = ChunkCoords {
+                    x: x.parse().ok()?,
+                    z: z.parse().ok()?,
+                };
+                Some((cc, decode_chunk(&fs::read(entry.path()).ok()?)?))
+            })
+            .collect()
+    }
+
+    /// Replaces the saved parts of structures waiting for their chunk
+    pub fn save_pending(&self, pending: &HashMap<ChunkCoords, BlocksChunk>) -> io::Result<()> {
+        match fs::remove_dir_all(self.dir.join("pending")) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+        for (&cc, blocks) in pending {
+            let path = Path::new("pending").join(Self::chunk_path(cc).file_name().unwrap());
+            self.write(path, &encode_chunk(blocks))?;
+        }
+        Ok(())
+    }
+
+    /// Reads the named chunk loaders (`name = x z range` in chunks)
+    pub fn load_loaders(&self) -> Vec<(String, ChunkLoader)> {
+        let content = fs::read_to_string(self.dir.join("loaders.txt")).unwrap_or_default();
+        key_values(&content)
+            .filter_map(|(name, value)| {
+                let mut numbers = value.split_whitespace().map(str::parse);
+                let loader = ChunkLoader {
+                    center: ChunkCoords {
+                        x: numbers.next()?.ok()?,
+                        z: numbers.next()?.ok()?,
+                    },
+                    range: numbers.next()?.ok()?,
+                };
+                Some((name.to_string(), loader))
+            })
+            .collect()
+    }
+
+    pub fn save_loaders(&self, loaders: &[(String, ChunkLoader)]) -> io::Result<()> {
+        let content: String = loaders
+            .iter()
+            .map(|(name, ChunkLoader { center, range })| {
+                format!("{name} = {} {} {range}\n", center.x, center.z)
+            })
+            .collect();
+        self.write("loaders.txt", content.as_bytes())
+    }
+
+    pub fn load_achievements(&self) -> Progress {
+        let content = fs::read_to_string(self.dir.join("achievements.txt")).unwrap_or_default();
+        let mut progress = Progress::default();
+        for (key, value) in key_values(&content) {
+            match key {
+                "traveled" => progress.traveled = value.parse().unwrap_or(0.0),
+                "unlocked" => {
+                    progress.unlocked = value
+                        .split_whitespace()
+                        .filter_map(|name| name.parse().ok())
+                        .collect()
+                }
+                _ => {}
+            }
+        }
+        progress
+    }
+
+    pub fn save_achievements(&self, progress: &Progress) -> io::Result<()> {
+        let unlocked: Vec<String> = progress.unlocked.iter().map(|a| a.to_string()).collect();
+        let content = format!(
+            "traveled = {}\nunlocked = {}\n",
+            progress.traveled,
+            unlocked.join(" ")
+        );
+        self.write("achievements.txt", content.as_bytes())
+    }
+
+    /// Reads the surface of the explored chunks
+    pub fn load_explored(&self) -> Vec<(ChunkCoords, SurfaceChunk)> {
+        let bytes = fs::read(self.dir.join("explored.map")).unwrap_or_default();
+        bytes
+            .chunks_exact(EXPLORED_LEN)
+            .filter_map(|entry| {
+                let cc = ChunkCoords {
+                    x: i32::from_le_bytes(entry[0..4].try_into().unwrap()),
+                    z: i32::from_le_bytes(entry[4..8].try_into().unwrap()),
+                };
+                let mut columns = entry[8..].chunks_exact(3);
+                let mut surface: SurfaceChunk = [[None; 16]; 16];
+                for top in surface.iter_mut().flatten() {
+                    let &[id, state, y] = columns.next()? else {
+                        return None;
+                    };
+                    if id != NO_BLOCK {
+                        *top = Some((y as i32, decode(id, state)?));
+                    }
+                }
+                Some((cc, surface))
+            })
+            .collect()
+    }
+
+    pub fn save_explored(&self, explored: &[(ChunkCoords, SurfaceChunk)]) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(explored.len() * EXPLORED_LEN);
+        for (cc, surface) in explored {
+            bytes.extend(cc.x.to_le_bytes());
+            bytes.extend(cc.z.to_le_bytes());
+            for top in surface.iter().flatten() {
+                match *top {
+                    Some((y, block)) => bytes.extend([block_id(block), block.state(), y as u8]),
+                    None => bytes.extend([NO_BLOCK, 0, 0]),
+                }
+            }
+        }
+        self.write("explored.map", &bytes)
+    }
+
+    /// Reads the content of the chests
+    pub fn load_chests(&self) -> Vec<([i32; 3], Slots)> {
+        let bytes = fs::read(self.dir.join("chests.bin")).unwrap_or_default();
+        bytes
+            .chunks_exact(CHEST_LEN)
+            .filter_map(|entry| {
+                let at = [0, 4, 8].map(|i| i32::from_le_bytes(entry[i..i + 4].try_into().unwrap()));
+                let mut stacks = entry[12..].chunks_exact(3);
+                let mut slots: Slots = [None; CHEST_SLOTS];
+                for slot in &mut slots {
+                    let &[id, state, count] = stacks.next()? else {
+                        return None;
+                    };
+                    if id != NO_BLOCK {
+                        let block = decode(id, state)?;
+                        *slot = Some(Stack { block, count });
+                    }
+                }
+                Some((at, slots))
+            })
+            .collect()
+    }
+
+    pub fn save_chests(&self, chests: &[([i32; 3], Slots)]) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(chests.len() * CHEST_LEN);
+        for (at, slots) in chests {
+            for v in at {
+                bytes.extend(v.to_le_bytes());
+            }
+            for slot in slots {
+                match *slot {
+                    Some(Stack { block, count }) => {
+                        bytes.extend([block_id(block), block.state(), count])
+                    }
+                    None => bytes.extend([NO_BLOCK, 0, 0]),
+                }
+            }
+        }
+        self.write("chests.bin", &bytes)
+    }
+
+    /// Reads a structure copied by the player
+    pub fn load_structure(&self, name: &str) -> Option<Structure> {
+        let bytes = fs::read(
+            self.dir
+                .join("structures")
+                .join(format!("{name}.structure")),
+        )
+        .ok()?;
+        // each block is its offset (3 times 2 bytes), its id and its state
+        let blocks = bytes
+            .chunks_exact(8)
+            .map(|entry| {
+                let offset = [0, 2, 4].map(|i| i16::from_le_bytes([entry[i], entry[i + 1]]) as i32);
+                Some((offset, decode(entry[6], entry[7])?))
+            })
+            .collect::<Option<_>>()?;
+        Some(Structure { blocks })
+    }
+
+    pub fn save_structure(&self, name: &str, structure: &Structure) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(structure.blocks.len() * 8);
+        for &(offset, block) in &structure.blocks {
+            for v in offset {
+                bytes.extend((v as i16).to_le_bytes());
+            }
+            bytes.push(block_id(block));
+            bytes.push(block.state());
+        }
+        self.write(
+            Path::new("structures").join(format!("{name}.structure")),
+            &bytes,
+        )
+    }
+
+    // writes in a temporary file first, then replaces the previous one
+    fn write(&self, file: impl AsRef<Path>, content: &[u8]) -> io::Result<()> {
+        let path = self.dir.join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, content)?;
+        fs::rename(tmp, path)
+    }
+}
+
+/// The blocks of a chunk as stored in its file, each block as its index
+/// (2 bytes), its id and its state
+pub fn encode_chunk(blocks: &BlocksChunk) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(blocks.len() * 4);
+    for (bi, &block) in blocks {
+        bytes.extend(bi.index.to_le_bytes());
+        bytes.push(block_id(block));
+        bytes.push(block.state());
+    }
+    bytes
+}
+
+/// None if a block is unknown (the bytes past the last whole block are
+/// ignored)
+pub fn decode_chunk(bytes: &[u8]) -> Option<BlocksChunk> {
+    bytes
+        .chunks_exact(4)
+        .map(|entry| {
+            let index = u16::from_le_bytes([entry[0], entry[1]]);
+            let block = decode(entry[2], entry[3])?;
+            Some((BlockIndex { index }, block))
+        })
+        .collect()
+}
+
+fn block_id(block: Block) -> u8 {
+    BLOCKS
+        .iter()
+        .position(|&b| b == block.with_state(0))
+        .unwrap() as u8
+}
+
+fn decode(id: u8, state: u8) -> Option<Block> {
+    BLOCKS.get(id as usize).map(|block| block.with_state(state))
+}
+
+fn key_values(content: &str) -> impl Iterator<Item = (&str, &str)> {
+    content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+}
+
+#[cfg(test)]
+mod test {
+    use def::BlockCoords;
+
+    use super::*;
+    use crate::{
+        harness::Harness,
+        world::{Achievement, ChunkStage},
+    };
+
+    #[tokio::test]
+    async fn test_stored_chunk() {
+        let harness = Harness::empty("stored-chunk");
+        let world = &harness.world;
+        let saved = ChunkCoords { x: 2, z: 2 };
+        let bi = BlockIndex::try_from([1, 200, 1]).unwrap();
+        world
+            .save
+            .save_chunk(saved, &BlocksChunk::from([(bi, Block::Glass)]))
+            .unwrap();
+        world.chunk_stage_none_to_stored(saved).await;
+        assert_eq!(world.get_chunk_stage(saved), ChunkStage::Stored);
+        // not loaded yet
+        assert_eq!(world.get_block(BlockCoords(saved, bi)), None);
+
+        world.request_chunk_stage(saved, ChunkStage::Loaded).await;
+        assert_eq!(world.get_chunk_stage(saved), ChunkStage::Loaded);
+        // the saved chunk is not generated again
+        assert_eq!(
+            world.get_block(BlockCoords(saved, bi)),
+            Some(Some(Block::Glass))
+        );
+        assert_eq!(
+            world.get_block(BlockCoords(saved, BlockIndex::try_from([1, 0, 1]).unwrap())),
+            Some(None)
+        );
+        let other = ChunkCoords { x: 0, z: 0 };
+        world.request_chunk_stage(other, ChunkStage::Loaded).await;
+        assert_eq!(
+            world.get_block(BlockCoords(other, BlockIndex::try_from([1, 0, 1]).unwrap())),
+            Some(Some(Block::Bedrock))
+        );
+    }
+
+    #[test]
+    fn test_save_chunk() {
+        let save = Save::new(std::env::temp_dir().join("artcraft-test-save-chunk"));
+        let cc = ChunkCoords { x: -3, z: 7 };
+        let chunk = BlocksChunk::from([
+            ([0, 0, 0].try_into().unwrap(), Block::Stone),
+            ([15, 255, 15].try_into().unwrap(), Block::Leaves),
+            ([4, 64, 9].try_into().unwrap(), Block::Glass),
+            ([4, 65, 9].try_into().unwrap(), Block::Door(0b1011)),
+            ([4, 66, 9].try_into().unwrap(), Block::Custom(3)),
+        ]);
+        save.save_chunk(cc, &chunk).unwrap();
+        assert_eq!(save.load_chunk(cc), Some(chunk.clone()));
+        assert_eq!(save.load_chunk(ChunkCoords { x: 0, z: 0 }), None);
+
+        let meta = Meta {
+            seed: 42,
+            ticks: 1234,
+            last_played: 1_700_000_000,
+            mode: GameMode::Survival,
+            generation: "density, surface, trees".to_string(),
+        };
+        save.save_meta(&meta).unwrap();
+        assert_eq!(save.load_meta(), Some(meta));
+
+        let pending = HashMap::from([(cc, chunk.clone())]);
+        save.save_pending(&pending).unwrap();
+        assert_eq!(save.load_pending(), vec![(cc, chunk)]);
+        save.save_pending(&HashMap::new()).unwrap();
+        assert!(save.load_pending().is_empty());
+
+        let loaders = vec![(
+            "farm".to_string(),
+            ChunkLoader {
+                center: ChunkCoords { x: -2, z: 5 },
+                range: 1,
+            },
+        )];
+        save.save_loaders(&loaders).unwrap();
+        assert_eq!(save.load_loaders(), loaders);
+
+        assert_eq!(save.load_achievements(), Progress::default());
+        let progress = Progress {
+            unlocked: [Achievement::FirstTree, Achievement::Heights].into(),
+            traveled: 512.25,
+        };
+        save.save_achievements(&progress).unwrap();
+        assert_eq!(save.load_achievements(), progress);
+
+        let mut surface: SurfaceChunk = [[None; 16]; 16];
+        surface[2][9] = Some((255, Block::Torch(3)));
+        surface[15][0] = Some((0, Block::Bedrock));
+        let explored = vec![(cc, surface)];
+        save.save_explored(&explored).unwrap();
+        assert_eq!(save.load_explored(), explored);
+
+        let mut slots: Slots = [None; CHEST_SLOTS];
+        slots[0] = Some(Stack {
+            block: Block::Chest,
+            count: 64,
+        });
+        slots[26] = Some(Stack {
+            block: Block::Sapling(1),
+            count: 3,
+        });
+        let chests = vec![([-40, 3, 100], slots)];
+        save.save_chests(&chests).unwrap();
+        assert_eq!(save.load_chests(), chests);
+
+        let hut = Structure::hut();
+        save.save_structure("hut", &hut).unwrap();
+        assert_eq!(save.load_structure("hut").unwrap().blocks, hut.blocks);
+        assert!(save.load_structure("castle").is_none());
+
+        let entities = vec![
+            Entity::projectile([1.5, 80.0, -3.25], [0.0, 1.0, 1.0]),
+            Entity {
+                age: 42,
+                ..Entity::primed_tnt(BlockCoords::try_from([-40, 3, 100]).unwrap())
+            },
+            Entity::item(BlockCoords::try_from([0, 3, 0]).unwrap(), Block::Door(0)),
+        ];
+        save.save_entities(cc, &entities).unwrap();
+        let loaded = save.load_entities(cc);
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded[0].velocity, entities[0].velocity);
+        assert_eq!(
+            (loaded[1].kind, loaded[1].pos),
+            (EntityKind::PrimedTnt, entities[1].pos)
+        );
+        assert_eq!(loaded[1].age, 42);
+        assert_eq!(loaded[2].kind, EntityKind::Item(Block::Door(0)));
+        save.save_entities(cc, &[]).unwrap();
+        assert!(save.load_entities(cc).is_empty());
+        fs::remove_dir_all(save.dir()).unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+use def::{state, Block, BlockCoords, Direction};
+
+use super::{Author, World};
+
+/// What a block does when something changed next to it
+pub type BlockUpdate = fn(&World, BlockCoords, Block);
+
+/// The block update behaviour of a block, if it has one
+pub fn on_update(block: Block) -> Option<BlockUpdate> {
+    match block {
+        Block::Wire(_) => Some(wire),
+        Block::Lamp(_) => Some(lamp),
+        Block::Piston(_) => Some(piston),
+        _ => None,
+    }
+}
+
+/// Signal a block carries (a lever switched on is a full source)
+fn signal(block: Block) -> u8 {
+    match block {
+        Block::Lever(state) if state & state::ON != 0 => state::MAX_SIGNAL,
+        Block::Wire(level) => level,
+        _ => 0,
+    }
+}
+
+impl World {
+    /// Gives a block update to the edited block and its neighbours at the
+    /// next tick
+    pub(super) fn schedule_update(&self, bc: BlockCoords) {
+        let mut updates = self.updates.lock().unwrap();
+        updates.push(bc);
+        updates.extend(Direction::ALL.into_iter().filter_map(|d| bc.step(d)));
+    }
+
+    /// Gives their block update to the blocks scheduled at the previous tick
+    ///
+    /// The edits they make schedule the next updates, so a signal goes one
+    /// block further at each tick.
+    pub(super) fn block_updates(&self) {
+        let mut updates = std::mem::take(&mut *self.updates.lock().unwrap());
+        // in the same order at every run
+        updates.sort_by_key(|&bc| <[i32; 3]>::from(bc));
+        updates.dedup();
+        for bc in updates {
+            if let Some(Some(block)) = self.get_block(bc) {
+                if let Some(update) = on_update(block) {
+                    update(self, bc, block);
+                }
+            }
+        }
+    }
+
+    /// The blocks next to a voxel (not the ones of unloaded chunks)
+    fn neighbours(&self, bc: BlockCoords) -> impl Iterator<Item = Block> + '_ {
+        Direction::ALL
+            .into_iter()
+            .filter_map(move |d| bc.step(d))
+            .filter_map(|neighbour| self.get_block(neighbour).flatten())
+    }
+}
+
+/// A wire takes the strongest signal around, weakened by one (a lever
+/// gives its full signal)
+fn wire(world: &World, bc: BlockCoords, block: Block) {
+    let level = world
+        .neighbours(bc)
+        .map(|neighbour| match neighbour {
+            Block::Wire(level) => level.saturating_sub(1),
+            neighbour => signal(neighbour),
+        })
+        .max()
+        .unwrap_or(0);
+    if level != block.state() {
+        world
+            .replace_block(bc, Block::Wire(level), Author::Nature)
+            .ok();
+    }
+}
+
+/// A lamp is lit by any signal around
+fn lamp(world: &World, bc: BlockCoords, block: Block) {
+    let state = if world.neighbours(bc).any(|neighbour| signal(neighbour) > 0) {
+        state::ON
+    } else {
+        0
+    };
+    if state != block.state() {
+        world
+            .replace_block(bc, Block::Lamp(state), Author::Nature)
+            .ok();
+    }
+}
+
+/// A piston extends while powered, and retracts once it is not
+fn piston(world: &World, bc: BlockCoords, block: Block) {
+    let powered = world.neighbours(bc).any(|neighbour| signal(neighbour) > 0);
+    match (powered, block.state() & state::ON != 0) {
+        (true, false) => {
+            world.extend_piston(bc, block);
+        }
+        (false, true) => world.retract_piston(bc, block),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use def::ChunkCoords;
+
+    use super::*;
+    use crate::{
+        harness::Harness,
+        world::{BlocksChunk, ChunkState, FacesChunk},
+    };
+
+    fn at(x: i32, y: i32, z: i32) -> BlockCoords {
+        [x, y, z].try_into().unwrap()
+    }
+
+    /// A lever, a line of wires going up and a lamp at the end
+    fn circuit(name: &str, wires: i32) -> Harness {
+        let harness = Harness::empty(name);
+        let mut chunk = BlocksChunk::from([(at(0, 10, 0).1, Block::Lever(0))]);
+        for y in 1..=wires {
+            chunk.insert(at(0, 10 + y, 0).1, Block::Wire(0));
+        }
+        chunk.insert(at(0, 11 + wires, 0).1, Block::Lamp(0));
+        harness.world.chunks.insert(
+            ChunkCoords { x: 0, z: 0 },
+            ChunkState::Meshed(chunk, FacesChunk::new()),
+        );
+        harness
+    }
+
+    fn run(world: &World, ticks: usize) {
+        for _ in 0..ticks {
+            world.block_updates();
+        }
+    }
+
+    #[test]
+    fn test_propagation() {
+        let harness = circuit("signal", 3);
+        let world = &harness.world;
+        world.interact(at(0, 10, 0)).unwrap();
+        assert_eq!(
+            world.get_block(at(0, 10, 0)),
+            Some(Some(Block::Lever(state::ON)))
+        );
+        // one block further at each tick, a level lost at each wire
+        run(world, 1);
+        assert_eq!(world.get_block(at(0, 11, 0)), Some(Some(Block::Wire(15))));
+        assert_eq!(world.get_block(at(0, 12, 0)), Some(Some(Block::Wire(0))));
+        run(world, 3);
+        assert_eq!(world.get_block(at(0, 12, 0)), Some(Some(Block::Wire(14))));
+        assert_eq!(world.get_block(at(0, 13, 0)), Some(Some(Block::Wire(13))));
+        assert_eq!(
+            world.get_block(at(0, 14, 0)),
+            Some(Some(Block::Lamp(state::ON)))
+        );
+        assert_eq!(Block::Lamp(state::ON).emission(), 15);
+
+        // switched off, the wires do not keep each other powered
+        world.interact(at(0, 10, 0)).unwrap();
+        run(world, 64);
+        for y in 1..=3 {
+            assert_eq!(
+                world.get_block(at(0, 10 + y, 0)),
+                Some(Some(Block::Wire(0)))
+            );
+        }
+        assert_eq!(world.get_block(at(0, 14, 0)), Some(Some(Block::Lamp(0))));
+    }
+
+    #[test]
+    fn test_decay() {
+        // the signal does not reach a lamp after 15 wires
+        let harness = circuit("signal-decay", 16);
+        let world = &harness.world;
+        world.interact(at(0, 10, 0)).unwrap();
+        run(world, 64);
+        assert_eq!(world.get_block(at(0, 25, 0)), Some(Some(Block::Wire(1))));
+        assert_eq!(world.get_block(at(0, 26, 0)), Some(Some(Block::Wire(0))));
+        assert_eq!(world.get_block(at(0, 27, 0)), Some(Some(Block::Lamp(0))));
+    }
+}
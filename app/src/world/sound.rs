@@ -0,0 +1,162 @@
+use def::{Block, BlockCoords, Direction};
+use mat::VectorTrait;
+use tokio::sync::broadcast;
+
+use super::{EntityKind, World};
+
+/// What a block sounds like when hit, placed or broken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Material {
+    Stone,
+    Soil,
+    Sand,
+    Wood,
+    Glass,
+    Foliage,
+    Water,
+}
+
+impl From<Block> for Material {
+    fn from(block: Block) -> Self {
+        match block {
+            Block::Stone
+            | Block::Brick
+            | Block::Bedrock
+            | Block::Wire(_)
+            | Block::Lever(_)
+            | Block::Piston(_)
+            | Block::Custom(_) => Self::Stone,
+            Block::Dirt | Block::Grass | Block::Farmland | Block::Slime => Self::Soil,
+            Block::Sand | Block::SoulSand => Self::Sand,
+            Block::Trunk
+            | Block::Door(_)
+            | Block::Trapdoor(_)
+            | Block::Torch(_)
+            | Block::Chest
+            | Block::PistonHead(_) => Self::Wood,
+            Block::Glass | Block::Ice | Block::Lamp(_) => Self::Glass,
+            Block::Leaves | Block::Sapling(_) | Block::Crop(_) | Block::Tnt | Block::Glider => {
+                Self::Foliage
+            }
+            Block::Water(_) => Self::Water,
+        }
+    }
+}
+
+/// A sound made by the world, at a position
+///
+/// The world only tells what happened and where: the audio plays it (or
+/// not, without a sound device), nothing else has to know about sounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WorldSound {
+    Placed {
+        pos: [f32; 3],
+        material: Material,
+    },
+    Broken {
+        pos: [f32; 3],
+        material: Material,
+    },
+    Explosion {
+        pos: [f32; 3],
+        radius: f32,
+    },
+    /// An entity hit a block
+    Landed {
+        pos: [f32; 3],
+        kind: EntityKind,
+        material: Material,
+    },
+    /// The player was hurt, where it stands
+    Hurt {
+        pos: [f32; 3],
+    },
+}
+
+impl WorldSound {
+    /// Sound of a block placed or broken, from its middle
+    pub fn block(bc: BlockCoords, block: Block, placed: bool) -> Self {
+        let pos = <[f32; 3]>::from(bc).vector_add([0.5; 3]);
+        let material = block.into();
+        if placed {
+            Self::Placed { pos, material }
+        } else {
+            Self::Broken { pos, material }
+        }
+    }
+
+    /// Sound of an entity hitting a block, from the middle of the hit face
+    pub fn landed(kind: EntityKind, bc: BlockCoords, face: Direction, block: Block) -> Self {
+        let face: [i32; 3] = face.into();
+        let pos = <[f32; 3]>::from(bc)
+            .vector_add([0.5; 3])
+            .vector_add(face.map(|v| v as f32 * 0.5));
+        Self::Landed {
+            pos,
+            kind,
+            material: block.into(),
+        }
+    }
+
+    pub fn pos(&self) -> [f32; 3] {
+        match *self {
+            Self::Placed { pos, .. }
+            | Self::Broken { pos, .. }
+            | Self::Explosion { pos, .. }
+            | Self::Landed { pos, .. }
+            | Self::Hurt { pos } => pos,
+        }
+    }
+}
+
+impl World {
+    /// Makes a sound, for whoever listens
+    pub(super) fn play(&self, sound: WorldSound) {
+        // nobody listening (like without audio) is not an error
+        self.sounds.send(sound).ok();
+    }
+
+    /// Receives the sounds made from now on
+    pub fn listen(&self) -> broadcast::Receiver<WorldSound> {
+        self.sounds.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        harness::Harness,
+        world::{BlocksChunk, ChunkState, FacesChunk},
+    };
+
+    #[test]
+    fn test_block_sounds() {
+        let harness = Harness::empty("sounds");
+        let world = &harness.world;
+        let bc = BlockCoords::try_from([0, 100, 0]).unwrap();
+        world.chunks.insert(
+            bc.0,
+            ChunkState::Meshed(BlocksChunk::new(), FacesChunk::new()),
+        );
+        let mut sounds = world.listen();
+        world.place_block(bc, Block::Glass).unwrap();
+        world.remove_block(bc).unwrap();
+        let pos = [0.5, 100.5, 0.5];
+        assert_eq!(
+            sounds.try_recv(),
+            Ok(WorldSound::Placed {
+                pos,
+                material: Material::Glass
+            })
+        );
+        assert_eq!(
+            sounds.try_recv(),
+            Ok(WorldSound::Broken {
+                pos,
+                material: Material::Glass
+            })
+        );
+        assert!(sounds.try_recv().is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use def::{BlockCoords, Direction};
+use mat::VectorTrait;
+
+use super::{Entity, EntityKind, World};
+
+/// Ticks between two spawning rounds (a second)
+pub const SPAWN_PERIOD: u64 = 60;
+
+/// Voxels tried at each round, most of them are not suitable
+const ATTEMPTS: u32 = 8;
+
+/// Mobs spawn between these distances from the player (in blocks), out of
+/// sight but close enough to come
+const MIN_DISTANCE: u32 = 24;
+const MAX_DISTANCE: u32 = 48;
+
+/// Mobs farther than this from the player are removed
+const DESPAWN_DISTANCE: f32 = 64.0;
+
+/// The world is split in areas of 4 by 4 chunks, each having a few mobs at
+/// most, so they do not pile up where spawning is easy
+const AREA: i32 = 4;
+const AREA_CAP: usize = 4;
+
+/// Block light level above which no mob spawns (torches keep them away)
+const MAX_SPAWN_LIGHT: u8 = 7;
+
+/// Whether the sun is down (`time_of_day` from 0.0, midnight, to 1.0)
+fn is_night(time_of_day: f32) -> bool {
+    !(0.25..0.75).contains(&time_of_day)
+}
+
+/// The area of a position, for the cap of mobs
+fn area(bc: BlockCoords) -> (i32, i32) {
+    let BlockCoords(cc, _) = bc;
+    (cc.x.div_euclid(AREA), cc.z.div_euclid(AREA))
+}
+
+impl World {
+    /// Spawns mobs in the dark around the player, and removes the ones too
+    /// far away or caught by the daylight
+    ///
+    /// With spawning disabled (for builders), every mob is removed.
+    pub(super) fn spawn_mobs(&self) {
+        let spawning = self.config.read().unwrap().spawning;
+        let player = self.pull_player().camera.pos;
+        let night = is_night(self.time_of_day());
+        let mut areas: HashMap<(i32, i32), usize> = HashMap::new();
+        self.entities.lock().unwrap().retain(|_, entity| {
+            if entity.kind != EntityKind::Mob {
+                return true;
+            }
+            let offset = entity.pos.vector_sub(player);
+            let Ok(bc) = BlockCoords::try_from(entity.pos) else {
+                return false;
+            };
+            let keep = spawning
+                && offset.vector_dot(offset) < DESPAWN_DISTANCE * DESPAWN_DISTANCE
+                && (night || !self.sees_sky(bc));
+            if keep {
+                *areas.entry(area(bc)).or_default() += 1;
+            }
+            keep
+        });
+        if !spawning {
+            return;
+        }
+        let mut spawned = Vec::new();
+        {
+            let mut rng = self.rng.lock().unwrap();
+            for _ in 0..ATTEMPTS {
+                let angle = (rng.below(360) as f32).to_radians();
+                let distance = (MIN_DISTANCE + rng.below(MAX_DISTANCE - MIN_DISTANCE)) as f32;
+                let [x, z] = [
+                    player[0] + angle.cos() * distance,
+                    player[2] + angle.sin() * distance,
+                ]
+                .map(|v| v.floor() as i32);
+                // in a cave as well as on the surface
+                let Some(surface) = self.surface(x, z) else {
+                    continue;
+                };
+                let y = 1 + rng.below(surface as u32 + 1) as i32;
+                let Ok(bc) = BlockCoords::try_from([x, y, z]) else {
+                    continue;
+                };
+                let count = areas.entry(area(bc)).or_default();
+                if *count < AREA_CAP && self.can_spawn(bc, night) {
+                    *count += 1;
+                    spawned.push(Entity::mob(bc));
+                }
+            }
+        }
+        for mob in spawned {
+            self.spawn_entity(mob);
+        }
+    }
+
+    /// Whether a mob can spawn with its feet in the voxel
+    ///
+    /// It needs a solid floor, room for its body, darkness and, under the
+    /// sky, the night.
+    fn can_spawn(&self, bc: BlockCoords, night: bool) -> bool {
+        let block = |bc: Option<BlockCoords>| bc.and_then(|bc| self.get_block(bc));
+        let floor = block(bc.step(Direction::Down))
+            .flatten()
+            .is_some_and(|floor| floor.is_full() && floor.is_solid());
+        let room = block(Some(bc)) == Some(None) && block(bc.step(Direction::Up)) == Some(None);
+        floor && room && self.get_light(bc) <= MAX_SPAWN_LIGHT && (night || !self.sees_sky(bc))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::Ordering;
+
+    use def::{Block, ChunkCoords};
+
+    use super::*;
+    use crate::{
+        harness::Harness,
+        world::{BlocksChunk, ChunkState, FacesChunk, DAY_LENGTH},
+    };
+
+    #[test]
+    fn test_spawn_mobs() {
+        let harness = Harness::empty("spawning");
+        let world = &harness.world;
+        // a stone floor all around the player, at midnight
+        for x in -4..4 {
+            for z in -4..4 {
+                let floor = (0..16).flat_map(|i| {
+                    (0..16).map(move |j| {
+                        let BlockCoords(_, bi) = BlockCoords::try_from([i, 9, j]).unwrap();
+                        (bi, Block::Stone)
+                    })
+                });
+                let chunk = BlocksChunk::from_iter(floor);
+                world.chunks.insert(
+                    ChunkCoords { x, z },
+                    ChunkState::Meshed(chunk, FacesChunk::new()),
+                );
+            }
+        }
+        world.player.write().unwrap().camera.pos = [0.5, 11.6, 0.5];
+        world.ticks.store(0, Ordering::Relaxed);
+
+        let mobs = || {
+            world
+                .pull_entities()
+                .into_iter()
+                .filter(|entity| entity.kind == EntityKind::Mob)
+                .collect::<Vec<_>>()
+        };
+        for _ in 0..50 {
+            world.spawn_mobs();
+        }
+        let spawned = mobs();
+        assert!(!spawned.is_empty());
+        for mob in &spawned {
+            // on the floor, away from the player
+            assert_eq!(mob.pos[1], 10.5);
+            let distance = mob.pos[0].hypot(mob.pos[2]);
+            assert!((23.0..50.0).contains(&distance), "{distance}");
+        }
+        let mut areas: HashMap<_, usize> = HashMap::new();
+        for mob in &spawned {
+            *areas
+                .entry(area(BlockCoords::try_from(mob.pos).unwrap()))
+                .or_default() += 1;
+        }
+        assert!(areas.values().all(|&count| count <= AREA_CAP));
+
+        // the sun rises on them
+        world.ticks.store(DAY_LENGTH / 2, Ordering::Relaxed);
+        world.spawn_mobs();
+        assert!(mobs().is_empty());
+
+        // the builders are left alone
+        world.ticks.store(0, Ordering::Relaxed);
+        for _ in 0..50 {
+            world.spawn_mobs();
+        }
+        assert!(!mobs().is_empty());
+        world.config.write().unwrap().spawning = false;
+        world.spawn_mobs();
+        assert!(mobs().is_empty());
+    }
+}
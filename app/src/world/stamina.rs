@@ -0,0 +1,85 @@
+use super::Player;
+
+/// Stamina of a rested player
+pub const MAX_STAMINA: f32 = 1.0;
+
+/// Below this, the player is too tired to sprint
+const SPRINT_STAMINA: f32 = 0.1;
+
+/// What the player did during a tick, and how tiring it was
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exertion {
+    /// Standing still, the player catches its breath
+    Rest,
+    Walk,
+    Sprint,
+    Jump,
+}
+
+impl Exertion {
+    /// Stamina gained (or lost, when negative) by the player
+    fn stamina(self) -> f32 {
+        match self {
+            // back to full in 5 seconds
+            Self::Rest => MAX_STAMINA / 300.0,
+            Self::Walk => 0.0,
+            // exhausted in 15 seconds
+            Self::Sprint => -MAX_STAMINA / 900.0,
+            Self::Jump => -MAX_STAMINA / 50.0,
+        }
+    }
+}
+
+impl Player {
+    /// Spends (or restores) stamina
+    pub fn exert(&mut self, exertion: Exertion) {
+        self.stamina = (self.stamina + exertion.stamina()).clamp(0.0, MAX_STAMINA);
+    }
+
+    pub fn can_sprint(&self) -> bool {
+        self.stamina > SPRINT_STAMINA
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use def::Block;
+
+    use super::*;
+    use crate::camera::Camera;
+
+    #[test]
+    fn test_stamina() {
+        let mut player = Player {
+            camera: Camera {
+                pos: [0.0; 3],
+                h_angle: 0.0,
+                v_angle: 0.0,
+            },
+            fly: false,
+            gravity: 0.0,
+            on_ground: true,
+            block_placing: Block::Stone,
+            stamina: MAX_STAMINA,
+            riding: None,
+            gliding: false,
+        };
+        let mut ticks = 0;
+        while player.can_sprint() {
+            player.exert(Exertion::Sprint);
+            ticks += 1;
+        }
+        // a bit less than 15 seconds of sprint
+        assert!((805..815).contains(&ticks), "{ticks}");
+        player.exert(Exertion::Walk);
+        assert!(!player.can_sprint());
+        for _ in 0..10 {
+            player.exert(Exertion::Jump);
+        }
+        assert_eq!(player.stamina, 0.0);
+        for _ in 0..301 {
+            player.exert(Exertion::Rest);
+        }
+        assert_eq!(player.stamina, MAX_STAMINA);
+    }
+}
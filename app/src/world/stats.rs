@@ -0,0 +1,136 @@
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use super::World;
+
+/// Parts of a frame (and of the worker threads) whose duration is tracked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Keyboard and mouse events
+    Input,
+    /// Tick of the world (entities, random ticks, weather)
+    Simulation,
+    /// Collisions of the player with the terrain
+    Collision,
+    /// Meshes of the chunks sent to the GPU
+    Meshing,
+    /// Draw calls of a frame
+    Draw,
+    /// Waiting for the frame to be displayed
+    Swap,
+    /// Chunks loaded by Cassiope (in its own thread)
+    Loading,
+}
+
+impl Phase {
+    pub const ALL: [Self; 7] = [
+        Self::Input,
+        Self::Simulation,
+        Self::Collision,
+        Self::Meshing,
+        Self::Draw,
+        Self::Swap,
+        Self::Loading,
+    ];
+}
+
+/// Time spent in each phase per frame, averaged over the last frames
+///
+/// The durations measured during a frame add up, then are folded into the
+/// averages at its end (with an exponential moving average, so a hitch
+/// shows for a moment then fades away).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stats {
+    frame: [Duration; Phase::ALL.len()],
+    averages: [f64; Phase::ALL.len()],
+    /// Time taken by the last save (none yet)
+    pub last_save: Option<Duration>,
+}
+
+impl Stats {
+    /// Weight of the last frame in the averages
+    const SMOOTHING: f64 = 1.0 / 30.0;
+
+    /// Average time spent in the phase per frame
+    pub fn average(&self, phase: Phase) -> Duration {
+        Duration::from_secs_f64(self.averages[phase as usize])
+    }
+
+    fn add(&mut self, phase: Phase, elapsed: Duration) {
+        self.frame[phase as usize] += elapsed;
+    }
+
+    fn end_frame(&mut self) {
+        for (average, frame) in self.averages.iter_mut().zip(&mut self.frame) {
+            *average += (frame.as_secs_f64() - *average) * Self::SMOOTHING;
+            *frame = Duration::ZERO;
+        }
+    }
+}
+
+impl World {
+    /// Runs a part of a frame, adding its duration to the stats
+    pub fn measure<T>(&self, phase: Phase, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.add_time(phase, start.elapsed());
+        result
+    }
+
+    /// Adds to the stats a duration measured elsewhere
+    pub fn add_time(&self, phase: Phase, elapsed: Duration) {
+        self.stats.lock().unwrap().add(phase, elapsed);
+    }
+
+    /// Folds the durations of the frame into the averages
+    pub fn end_frame(&self) {
+        self.stats.lock().unwrap().end_frame();
+    }
+
+    /// Average durations of the phases of the last frames
+    pub fn stats(&self) -> Stats {
+        *self.stats.lock().unwrap()
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks.load(Ordering::Relaxed)
+    }
+
+    /// Chunks in memory (whatever their state)
+    pub fn loaded_chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn entity_count(&self) -> usize {
+        self.entities.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut stats = Stats::default();
+        for _ in 0..200 {
+            stats.add(Phase::Draw, Duration::from_millis(4));
+            stats.add(Phase::Draw, Duration::from_millis(6));
+            stats.end_frame();
+        }
+        let draw = stats.average(Phase::Draw).as_secs_f64() * 1000.0;
+        assert!((draw - 10.0).abs() < 0.1, "{draw}");
+        assert_eq!(stats.average(Phase::Swap), Duration::ZERO);
+
+        // a hitch shows, then fades away
+        stats.add(Phase::Swap, Duration::from_millis(300));
+        stats.end_frame();
+        assert!(stats.average(Phase::Swap) > Duration::from_millis(5));
+        for _ in 0..200 {
+            stats.end_frame();
+        }
+        assert!(stats.average(Phase::Swap) < Duration::from_millis(1));
+    }
+}
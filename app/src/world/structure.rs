@@ -0,0 +1,470 @@
+use std::collections::{hash_map::Entry, HashMap};
+
+use def::{Block, BlockCoords, BlockIndex, ChunkCoords};
+use mat::VectorTrait;
+
+use super::{random_tick::Rng, Author, BlocksChunk, ChunkState, GameMode, World};
+use crate::CmdError;
+
+/// Blocks to paste in the world, at positions relative to an origin
+///
+/// A structure may cross chunk borders, it is pasted only when all the
+/// chunks it covers are meshed.
+#[derive(Debug, Clone, Default)]
+pub struct Structure {
+    pub blocks: Vec<([i32; 3], Block)>,
+}
+
+impl Structure {
+    /// A trunk of the given height with a ball of leaves around its top,
+    /// the origin being the base of the trunk
+    pub fn tree(height: i32) -> Self {
+        let mut blocks: Vec<_> = (0..height).map(|y| ([0, y, 0], Block::Trunk)).collect();
+        for x in -2..=2i32 {
+            for y in -2..=1i32 {
+                for z in -2..=2i32 {
+                    // the corners are cut to round it
+                    let far = (x.abs() == 2) as i32 + (z.abs() == 2) as i32 + (y == 1) as i32;
+                    if far < 2 && !(x == 0 && z == 0 && y < 0) {
+                        blocks.push(([x, height + y, z], Block::Leaves));
+                    }
+                }
+            }
+        }
+        Self { blocks }
+    }
+
+    /// A small brick hut with a door opening, windows, a roof of leaves and
+    /// a torch inside, the origin being a corner of its floor
+    pub fn hut() -> Self {
+        const SIZE: i32 = 7;
+        let mut blocks = Vec::new();
+        for x in 0..SIZE {
+            for z in 0..SIZE {
+                let border = [x, z].map(|v| v == 0 || v == SIZE - 1);
+                for y in 0..4 {
+                    let middle = x == SIZE / 2 || z == SIZE / 2;
+                    let block = match border {
+                        [true, true] => Block::Trunk,
+                        [false, false] => continue,
+                        // the door opening, in the middle of the front wall
+                        _ if z == 0 && middle && y < 2 => continue,
+                        _ if middle && y == 1 => Block::Glass,
+                        _ => Block::Brick,
+                    };
+                    blocks.push(([x, y, z], block));
+                }
+            }
+        }
+        for x in -1..=SIZE {
+            for z in -1..=SIZE {
+                blocks.push(([x, 4, z], Block::Leaves));
+            }
+        }
+        blocks.push(([1, 0, SIZE - 2], Block::Torch(0)));
+        Self { blocks }
+    }
+
+    /// What remains of a building: broken walls of brick and stone, of a
+    /// height given by the seed
+    pub fn ruin(seed: u32) -> Self {
+        const SIZE: i32 = 7;
+        let mut rng = Rng::new(seed as u64);
+        let mut blocks = Vec::new();
+        for x in 0..SIZE {
+            for z in 0..SIZE {
+                if !(x == 0 || z == 0 || x == SIZE - 1 || z == SIZE - 1) {
+                    continue;
+                }
+                for y in 0..rng.below(4) as i32 {
+                    let block = if rng.below(3) == 0 {
+                        Block::Stone
+                    } else {
+                        Block::Brick
+                    };
+                    blocks.push(([x, y, z], block));
+                }
+            }
+        }
+        Self { blocks }
+    }
+
+    /// The structure turned around the vertical axis of its origin, by
+    /// quarter turns (clockwise seen from above)
+    ///
+    /// The states of the blocks (like the side of a torch) are kept.
+    pub fn rotated(&self, quarter_turns: i32) -> Self {
+        let blocks = self
+            .blocks
+            .iter()
+            .map(|&(offset, block)| {
+                let [mut x, y, mut z] = offset;
+                for _ in 0..quarter_turns.rem_euclid(4) {
+                    [x, z] = [-z, x];
+                }
+                ([x, y, z], block)
+            })
+            .collect();
+        Self { blocks }
+    }
+
+    /// The structure mirrored along the x axis
+    pub fn mirrored(&self) -> Self {
+        let blocks = self
+            .blocks
+            .iter()
+            .map(|&([x, y, z], block)| ([-x, y, z], block))
+            .collect();
+        Self { blocks }
+    }
+
+    fn positions(&self, origin: BlockCoords) -> impl Iterator<Item = (BlockCoords, Block)> + '_ {
+        let origin = <[i32; 3]>::from(origin);
+        self.blocks.iter().filter_map(move |&(offset, block)| {
+            let bc = BlockCoords::try_from(origin.vector_add(offset)).ok()?;
+            Some((bc, block))
+        })
+    }
+}
+
+/// Largest region captured by [`World::copy_region`], in blocks
+pub const MAX_COPY_VOLUME: i64 = 64 * 64 * 64;
+
+/// A structure only takes the place of air (or of the sapling it grew from)
+fn replaceable(block: Option<Block>) -> bool {
+    matches!(block, None | Some(Block::Sapling(_)))
+}
+
+impl World {
+    /// Places the parts of generated structures that are over other chunks
+    ///
+    /// The parts over a chunk not generated yet wait in the pending queue,
+    /// until [`World::place_pending`] is called for it.
+    pub(super) fn spill_structures(&self, parts: Vec<(BlockCoords, Block)>) {
+        let mut by_chunk: HashMap<ChunkCoords, Vec<(BlockIndex, Block)>> = HashMap::new();
+        for (BlockCoords(cc, bi), block) in parts {
+            by_chunk.entry(cc).or_default().push((bi, block));
+        }
+        for (cc, blocks) in by_chunk {
+            // the queue is held while looking for the chunk, so it cannot be
+            // generated in between (it takes its parts after being inserted)
+            let mut pending = self.pending.entry(cc).or_default();
+            if !self.chunks.contains_key(&cc) {
+                for (bi, block) in blocks {
+                    pending.entry(bi).or_insert(block);
+                }
+                continue;
+            }
+            drop(pending);
+            self.pending.remove_if(&cc, |_, pending| pending.is_empty());
+            self.place_generated(cc, blocks);
+        }
+    }
+
+    /// Places the parts of structures waiting for a chunk just generated
+    pub(super) fn place_pending(&self, cc: ChunkCoords) {
+        if let Some((_, pending)) = self.pending.remove(&cc) {
+            self.place_generated(cc, pending);
+        }
+    }
+
+    /// Blocks of structures waiting for their chunk to be generated
+    pub(super) fn pending_structures(&self) -> HashMap<ChunkCoords, BlocksChunk> {
+        self.pending
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect()
+    }
+
+    // places generated blocks in the air of a chunk, which must be saved then
+    fn place_generated(
+        &self,
+        cc: ChunkCoords,
+        blocks: impl IntoIterator<Item = (BlockIndex, Block)>,
+    ) {
+        self.decompress_chunk(cc);
+        let mut placed = Vec::new();
+        if let Some(mut chunk) = self.chunks.get_mut(&cc) {
+            if let ChunkState::Loaded(ref mut chunk_blocks)
+            | ChunkState::Meshed(ref mut chunk_blocks, _) = *chunk
+            {
+                for (bi, block) in blocks {
+                    if let Entry::Vacant(entry) = chunk_blocks.entry(bi) {
+                        entry.insert(block);
+                        placed.push(BlockCoords(cc, bi));
+                    }
+                }
+            }
+        }
+        if placed.is_empty() {
+            return;
+        }
+        self.dirty.insert(cc);
+        // the chunks next to it may be meshed already
+        self.update_many(&placed);
+        for bc in placed {
+            self.relight(bc);
+        }
+    }
+
+    /// Captures the blocks of a region (both corners included), relative to
+    /// its lowest corner
+    ///
+    /// Fails if a chunk of the region is not loaded, or if the region is
+    /// larger than [`MAX_COPY_VOLUME`] blocks.
+    pub fn copy_region(&self, from: BlockCoords, to: BlockCoords) -> Result<Structure, CmdError> {
+        let [from, to]: [[i32; 3]; 2] = [from.into(), to.into()];
+        let min: [i32; 3] = std::array::from_fn(|i| from[i].min(to[i]));
+        let max: [i32; 3] = std::array::from_fn(|i| from[i].max(to[i]));
+        let volume: i64 = (0..3).map(|i| (max[i] - min[i] + 1) as i64).product();
+        if volume > MAX_COPY_VOLUME {
+            return Err(CmdError::RegionTooLarge(volume));
+        }
+        let mut blocks = Vec::new();
+        for x in min[0]..=max[0] {
+            for y in min[1]..=max[1] {
+                for z in min[2]..=max[2] {
+                    // the corners were checked to be in the world
+                    let bc = BlockCoords::try_from([x, y, z]).unwrap();
+                    match self.get_block(bc) {
+                        Some(Some(block)) => blocks.push(([x, y, z].vector_sub(min), block)),
+                        Some(None) => {}
+                        None => return Err(CmdError::ChunkNotLoaded(bc.0)),
+                    }
+                }
+            }
+        }
+        Ok(Structure { blocks })
+    }
+
+    /// Copies a region into a structure saved with the world, returns the
+    /// number of blocks copied
+    pub fn save_structure(
+        &self,
+        name: &str,
+        from: BlockCoords,
+        to: BlockCoords,
+    ) -> Result<usize, CmdError> {
+        let structure = self.copy_region(from, to)?;
+        self.save
+            .save_structure(name, &structure)
+            .map_err(|err| CmdError::Save(err.to_string()))?;
+        Ok(structure.blocks.len())
+    }
+
+    /// A structure saved by [`World::save_structure`]
+    pub fn load_structure(&self, name: &str) -> Result<Structure, CmdError> {
+        self.save
+            .load_structure(name)
+            .ok_or_else(|| CmdError::UnknownStructure(name.to_string()))
+    }
+
+    /// Whether the whole structure can be pasted, without replacing any block
+    pub fn fits(&self, origin: BlockCoords, structure: &Structure) -> bool {
+        structure
+            .positions(origin)
+            .all(|(bc, _)| self.get_block(bc).is_some_and(replaceable))
+    }
+
+    /// Pastes a structure, keeping the blocks already in place, returns the
+    /// number of blocks placed
+    ///
+    /// Parts above or below the world are cut. Fails if a chunk it covers
+    /// is not meshed, in which case nothing is pasted.
+    pub fn paste(
+        &self,
+        origin: BlockCoords,
+        structure: &Structure,
+        who: Author,
+    ) -> Result<usize, CmdError> {
+        let mut by_chunk: HashMap<ChunkCoords, Vec<(BlockCoords, Block)>> = HashMap::new();
+        for (bc, block) in structure.positions(origin) {
+            let BlockCoords(cc, _) = bc;
+            by_chunk.entry(cc).or_default().push((bc, block));
+        }
+        for &cc in by_chunk.keys() {
+            if !matches!(
+                self.chunks.get(&cc).as_deref(),
+                Some(ChunkState::Meshed(..))
+            ) {
+                return Err(CmdError::ChunkNotLoaded(cc));
+            }
+        }
+        let mut placed = Vec::new();
+        for (cc, blocks) in by_chunk {
+            if let Some(mut chunk) = self.chunks.get_mut(&cc) {
+                if let ChunkState::Meshed(ref mut chunk_blocks, _) = *chunk {
+                    for (bc, block) in blocks {
+                        let BlockCoords(_, bi) = bc;
+                        let before = chunk_blocks.get(&bi).copied();
+                        if replaceable(before) {
+                            chunk_blocks.insert(bi, block);
+                            self.record(bc, before, Some(block), who);
+                            placed.push(bc);
+                        }
+                    }
+                }
+            }
+            self.dirty.insert(cc);
+        }
+        self.update_many(&placed);
+        let count = placed.len();
+        for bc in placed {
+            self.relight(bc);
+        }
+        Ok(count)
+    }
+
+    /// Places a block in the air of a region (both corners included),
+    /// returns the number of blocks placed
+    ///
+    /// Like [`World::copy_region`], the region is at most
+    /// [`MAX_COPY_VOLUME`] blocks.
+    pub fn fill(
+        &self,
+        from: BlockCoords,
+        to: BlockCoords,
+        block: Block,
+    ) -> Result<usize, CmdError> {
+        if self.mode != GameMode::Creative {
+            return Err(CmdError::CreativeOnly);
+        }
+        let [from, to]: [[i32; 3]; 2] = [from.into(), to.into()];
+        let min: [i32; 3] = std::array::from_fn(|i| from[i].min(to[i]));
+        let max: [i32; 3] = std::array::from_fn(|i| from[i].max(to[i]));
+        let volume: i64 = (0..3).map(|i| (max[i] - min[i] + 1) as i64).product();
+        if volume > MAX_COPY_VOLUME {
+            return Err(CmdError::RegionTooLarge(volume));
+        }
+        let blocks = (0..=max[0] - min[0])
+            .flat_map(|x| (0..=max[1] - min[1]).map(move |y| [x, y]))
+            .flat_map(|[x, y]| (0..=max[2] - min[2]).map(move |z| ([x, y, z], block)))
+            .collect();
+        // the corners were checked to be in the world
+        let origin = BlockCoords::try_from(min).unwrap();
+        self.paste(origin, &Structure { blocks }, Author::Player)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::Harness;
+
+    #[test]
+    fn test_pending_structures() {
+        let harness = Harness::empty("pending");
+        let world = &harness.world;
+        let loaded = ChunkCoords { x: 0, z: 0 };
+        let later = ChunkCoords { x: 1, z: 0 };
+        world.chunk_stage_stored_to_loaded(loaded);
+        let at = |x, y, z| BlockCoords::try_from([x, y, z]).unwrap();
+        world.spill_structures(vec![
+            (at(15, 200, 3), Block::Brick),
+            (at(16, 200, 3), Block::Brick),
+            (at(17, 200, 3), Block::Glass),
+        ]);
+        // placed right away in the loaded chunk, the other part waits
+        assert_eq!(world.get_block(at(15, 200, 3)), Some(Some(Block::Brick)));
+        assert_eq!(world.get_block(at(16, 200, 3)), None);
+        assert_eq!(world.pending_structures()[&later].len(), 2);
+
+        world.chunk_stage_stored_to_loaded(later);
+        assert_eq!(world.get_block(at(16, 200, 3)), Some(Some(Block::Brick)));
+        assert_eq!(world.get_block(at(17, 200, 3)), Some(Some(Block::Glass)));
+        assert!(world.pending_structures().is_empty());
+        // edited by the structures, so they are saved
+        assert!(world.dirty.contains(&loaded) && world.dirty.contains(&later));
+    }
+
+    #[test]
+    fn test_templates() {
+        let hut = Structure::hut();
+        assert!(hut.blocks.contains(&([0, 0, 0], Block::Trunk)));
+        // the door opening
+        assert!(!hut.blocks.iter().any(|(offset, _)| *offset == [3, 0, 0]));
+        assert!(hut
+            .blocks
+            .iter()
+            .any(|(_, block)| *block == Block::Torch(0)));
+        // ruins are different from one seed to the other
+        assert_eq!(Structure::ruin(3).blocks, Structure::ruin(3).blocks);
+        assert_ne!(Structure::ruin(3).blocks, Structure::ruin(4).blocks);
+    }
+
+    #[test]
+    fn test_copy_paste() {
+        let harness = Harness::empty("copy");
+        let world = &harness.world;
+        let cc = ChunkCoords { x: 0, z: 0 };
+        let at = |x, y, z| BlockCoords::try_from([x, y, z]).unwrap();
+        let index = |x, y, z| at(x, y, z).1;
+        let blocks = BlocksChunk::from([
+            (index(2, 100, 2), Block::Brick),
+            (index(3, 100, 2), Block::Glass),
+        ]);
+        world
+            .chunks
+            .insert(cc, ChunkState::Meshed(blocks, Default::default()));
+
+        let copy = world.copy_region(at(3, 101, 2), at(2, 100, 2)).unwrap();
+        assert_eq!(
+            copy.blocks,
+            [([0, 0, 0], Block::Brick), ([1, 0, 0], Block::Glass)]
+        );
+        assert_eq!(
+            world.copy_region(at(0, 0, 0), at(16, 0, 0)).err(),
+            Some(CmdError::ChunkNotLoaded(ChunkCoords { x: 1, z: 0 }))
+        );
+        assert!(world.copy_region(at(0, 0, 0), at(100, 100, 100)).is_err());
+
+        // a quarter turn takes +x to +z, mirroring takes it to -x
+        world
+            .paste(at(8, 120, 8), &copy.rotated(1), Author::Player)
+            .unwrap();
+        assert_eq!(world.get_block(at(8, 120, 9)), Some(Some(Block::Glass)));
+        world
+            .paste(at(8, 130, 8), &copy.mirrored(), Author::Player)
+            .unwrap();
+        assert_eq!(world.get_block(at(7, 130, 8)), Some(Some(Block::Glass)));
+        assert_eq!(copy.rotated(4).blocks, copy.blocks);
+    }
+
+    #[test]
+    fn test_fill() {
+        let harness = Harness::empty("fill");
+        let world = &harness.world;
+        let at = |x, y, z| BlockCoords::try_from([x, y, z]).unwrap();
+        let blocks = BlocksChunk::from([(at(2, 100, 2).1, Block::Glass)]);
+        world.chunks.insert(
+            ChunkCoords { x: 0, z: 0 },
+            ChunkState::Meshed(blocks, Default::default()),
+        );
+        // the glass is kept
+        assert_eq!(
+            world.fill(at(3, 101, 2), at(2, 100, 4), Block::Brick),
+            Ok(11)
+        );
+        assert_eq!(world.get_block(at(2, 100, 2)), Some(Some(Block::Glass)));
+        assert_eq!(world.get_block(at(3, 101, 4)), Some(Some(Block::Brick)));
+        assert_eq!(world.get_block(at(4, 100, 2)), Some(None));
+        assert_eq!(
+            world.fill(at(0, 0, 0), at(99, 99, 99), Block::Brick),
+            Err(CmdError::RegionTooLarge(100 * 100 * 100))
+        );
+    }
+
+    #[test]
+    fn test_tree() {
+        let tree = Structure::tree(5);
+        let trunk = tree.blocks.iter().filter(|(_, b)| *b == Block::Trunk);
+        assert_eq!(trunk.count(), 5);
+        // no leaves in place of the trunk
+        for (offset, block) in &tree.blocks {
+            if *block == Block::Leaves {
+                assert!(offset[1] >= 3);
+                assert!(!(offset[0] == 0 && offset[2] == 0 && offset[1] < 5));
+            }
+        }
+    }
+}
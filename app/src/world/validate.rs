@@ -0,0 +1,234 @@
+use def::{Block, BlockCoords, Direction};
+use mat::VectorTrait;
+
+use super::{GameMode, Player, World};
+use crate::CmdError;
+
+/// Distance from the eyes of the player to the blocks it can edit
+pub const REACH: f32 = 10.0;
+
+/// Room left for the rounding of the moves computed by the client
+const SLACK: f32 = 1e-3;
+
+impl World {
+    /// Checks that the player is allowed to edit a block (with the block
+    /// placed, if any), whatever sent the edit
+    ///
+    /// In survival, the block must be within reach. A solid block is never
+    /// placed where the player stands, it would get stuck in it.
+    pub fn check_edit(&self, bc: BlockCoords, placed: Option<Block>) -> Result<(), CmdError> {
+        let pos = self.pull_player().camera.pos;
+        let center = <[f32; 3]>::from(bc).vector_add([0.5; 3]);
+        let offset = center.vector_sub(pos);
+        // a block is within reach as soon as one of its corners is
+        let reach = REACH + 3f32.sqrt() / 2.0;
+        if self.mode == GameMode::Survival && offset.vector_dot(offset) > reach * reach {
+            return Err(CmdError::OutOfReach(bc));
+        }
+        let Some(block) = placed.filter(|block| block.is_solid()) else {
+            return Ok(());
+        };
+        let hit_box = Player::hit_box(pos);
+        let mut cells = vec![bc];
+        // a door takes the block above too
+        if let (Block::Door(_), Some(above)) = (block, bc.step(Direction::Up)) {
+            cells.push(above);
+        }
+        for cell in cells {
+            let min = <[f32; 3]>::from(cell);
+            let overlaps = (0..3).all(|i| {
+                min[i] < hit_box.pos[i] + hit_box.dimensions[i] && hit_box.pos[i] < min[i] + 1.0
+            });
+            if overlaps {
+                return Err(CmdError::InsidePlayer(cell));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a move of the player, from its last accepted position,
+    /// can be done in a tick
+    ///
+    /// The distance is bounded by the speeds of the physics (a slide keeps
+    /// at most the speed of the last move), and, unless flying, the hit box
+    /// can't enter a solid block. In a boat, the boat carries the player and
+    /// nothing is checked.
+    pub fn check_move(&self, player: &Player) -> Result<(), CmdError> {
+        let from = self.pull_player();
+        if from.riding.is_some() || player.riding.is_some() {
+            return Ok(());
+        }
+        if player.fly && self.mode != GameMode::Creative {
+            return Err(CmdError::CreativeOnly);
+        }
+        let physics = self.physics();
+        let offset = player.camera.pos.vector_sub(from.camera.pos);
+        let [x, y, z] = offset;
+        let horizontal = x.hypot(z);
+        let distance = offset.vector_dot(offset).sqrt();
+        let too_fast = if player.fly {
+            // the keys add up, the diagonals are longer
+            distance > physics.fly * 3f32.sqrt() + SLACK
+        } else if from.gliding || player.gliding {
+            distance > physics.max_fall + SLACK
+        } else {
+            let slide = *self.last_move.lock().unwrap();
+            horizontal > (physics.sprint * 2f32.sqrt()).max(slide) + SLACK
+                || y.abs() > physics.max_fall + SLACK
+        };
+        if too_fast {
+            return Err(CmdError::TooFast(distance));
+        }
+        if player.fly {
+            return Ok(());
+        }
+        // the cells the hit box overlaps, as the collisions see it
+        let cells = |pos| {
+            let hit_box = Player::hit_box(pos);
+            let epsilon = physics.collision_epsilon;
+            let min = hit_box.pos.map(|v| (v + epsilon).floor() as i32);
+            let max = hit_box
+                .pos
+                .vector_add(hit_box.dimensions)
+                .map(|v| (v - epsilon).floor() as i32);
+            (min[0]..=max[0]).flat_map(move |x| {
+                (min[1]..=max[1]).flat_map(move |y| (min[2]..=max[2]).map(move |z| [x, y, z]))
+            })
+        };
+        // already stuck in a block, the player is free to get out of it
+        let stuck: Vec<[i32; 3]> = cells(from.camera.pos).collect();
+        for cell in cells(player.camera.pos).filter(|cell| !stuck.contains(cell)) {
+            let Ok(bc) = BlockCoords::try_from(cell) else {
+                continue;
+            };
+            if let Some(Some(block)) = self.get_block(bc) {
+                if block.is_solid() {
+                    return Err(CmdError::InsideBlock(bc));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the player if [`World::check_move`] accepts it, otherwise it
+    /// is put back at its last accepted position, stopped (the other
+    /// changes, like the view, are kept)
+    pub fn move_player(&self, mut player: Player) -> Result<(), CmdError> {
+        let checked = self.check_move(&player);
+        let from = self.pull_player();
+        let slide = match checked {
+            Ok(()) if from.riding.is_none() && player.riding.is_none() && !player.fly => {
+                let [x, _, z] = player.camera.pos.vector_sub(from.camera.pos);
+                x.hypot(z)
+            }
+            Ok(()) => 0.0,
+            Err(_) => {
+                player.camera.pos = from.camera.pos;
+                player.fly = from.fly;
+                player.gravity = 0.0;
+                player.gliding = false;
+                0.0
+            }
+        };
+        *self.last_move.lock().unwrap() = slide;
+        self.push_player(player);
+        checked
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::harness::{Harness, Setup};
+
+    #[test]
+    fn test_check_edit() {
+        let harness = |name, mode| {
+            let setup = Setup {
+                mode,
+                radius: None,
+                ..Setup::default()
+            };
+            let harness = Harness::with(name, setup);
+            harness.teleport([0.5, 11.6, 0.5]);
+            harness
+        };
+        let at = |x, y, z| BlockCoords::try_from([x, y, z]).unwrap();
+        let survival = harness("check-edit-survival", GameMode::Survival);
+        assert_eq!(survival.world.check_edit(at(3, 10, 0), None), Ok(()));
+        assert_eq!(
+            survival.world.check_edit(at(30, 10, 0), None),
+            Err(CmdError::OutOfReach(at(30, 10, 0)))
+        );
+        // the feet and the head of the player are both in the way
+        for y in [10, 11] {
+            assert_eq!(
+                survival.world.check_edit(at(0, y, 0), Some(Block::Stone)),
+                Err(CmdError::InsidePlayer(at(0, y, 0)))
+            );
+        }
+        assert_eq!(
+            survival.world.check_edit(at(0, 9, 0), Some(Block::Stone)),
+            Ok(())
+        );
+        assert_eq!(
+            survival.world.check_edit(at(1, 10, 0), Some(Block::Stone)),
+            Ok(())
+        );
+        assert_eq!(
+            survival.world.check_edit(at(0, 9, 0), Some(Block::Door(0))),
+            Err(CmdError::InsidePlayer(at(0, 10, 0)))
+        );
+        // a torch does not block the way
+        assert_eq!(
+            survival
+                .world
+                .check_edit(at(0, 10, 0), Some(Block::Torch(0))),
+            Ok(())
+        );
+
+        let creative = harness("check-edit-creative", GameMode::Creative);
+        assert_eq!(creative.world.check_edit(at(30, 10, 0), None), Ok(()));
+    }
+
+    #[test]
+    fn test_check_move() {
+        let setup = Setup {
+            mode: GameMode::Survival,
+            ..Setup::default()
+        };
+        let harness = Harness::with("check-move", setup);
+        harness.teleport([0.5, 11.6, 0.5]);
+        let moved = |offset| {
+            let mut player = harness.world.pull_player();
+            player.camera.pos.vector_add_assign(offset);
+            harness.world.move_player(player)
+        };
+        let pos = || harness.world.pull_player().camera.pos;
+
+        assert_eq!(moved([0.1, 0.0, 0.0]), Ok(()));
+        assert_eq!(pos(), [0.6, 11.6, 0.5]);
+        // too far for a tick, the player is put back
+        assert_eq!(moved([3.0, 0.0, 0.0]), Err(CmdError::TooFast(3.0)));
+        assert_eq!(pos(), [0.6, 11.6, 0.5]);
+        assert_eq!(moved([0.0, -3.0, 0.0]), Err(CmdError::TooFast(3.0)));
+        assert_eq!(pos(), [0.6, 11.6, 0.5]);
+
+        // the hit box can't enter a solid block
+        let wall = BlockCoords::try_from([1, 10, 0]).unwrap();
+        harness.world.place_block(wall, Block::Stone).unwrap();
+        assert_eq!(moved([0.11, 0.0, 0.0]), Err(CmdError::InsideBlock(wall)));
+        assert_eq!(pos(), [0.6, 11.6, 0.5]);
+        assert_eq!(moved([-0.1, 0.0, 0.0]), Ok(()));
+
+        // no flying in survival
+        let mut player = harness.world.pull_player();
+        player.fly = true;
+        player.camera.pos.vector_add_assign([0.0, 0.5, 0.0]);
+        assert_eq!(
+            harness.world.move_player(player),
+            Err(CmdError::CreativeOnly)
+        );
+        assert!(!harness.world.pull_player().fly);
+    }
+}
@@ -0,0 +1,146 @@
+use std::sync::atomic::Ordering;
+
+use super::{random_tick::Rng, Biome, World, DAY_LENGTH};
+
+/// The sky over the whole world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Weather {
+    Clear,
+    /// Falls as snow where it is cold
+    Rain,
+    /// Falls as snow everywhere
+    Snow,
+}
+
+impl std::str::FromStr for Weather {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clear" => Ok(Self::Clear),
+            "rain" => Ok(Self::Rain),
+            "snow" => Ok(Self::Snow),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Weather {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Clear => write!(f, "clear"),
+            Self::Rain => write!(f, "rain"),
+            Self::Snow => write!(f, "snow"),
+        }
+    }
+}
+
+/// What falls from the sky at a place
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precipitation {
+    Rain,
+    Snow,
+}
+
+impl Weather {
+    /// What falls in the given biome
+    pub fn precipitation(self, biome: Biome) -> Option<Precipitation> {
+        match self {
+            Self::Clear => None,
+            Self::Rain if biome.temperature() < 0.15 => Some(Precipitation::Snow),
+            Self::Rain => Some(Precipitation::Rain),
+            Self::Snow => Some(Precipitation::Snow),
+        }
+    }
+}
+
+impl Biome {
+    /// From 0.0 (freezing) to 1.0 (hot)
+    pub fn temperature(self) -> f32 {
+        match self {
+            Self::Beach => 0.8,
+            Self::Plains => 0.6,
+            Self::Mountains => 0.1,
+        }
+    }
+}
+
+/// The current weather and the tick when it changes
+#[derive(Debug, Clone, Copy, Hash)]
+pub struct WeatherState {
+    pub weather: Weather,
+    until: u64,
+}
+
+impl WeatherState {
+    pub fn new(ticks: u64) -> Self {
+        Self {
+            weather: Weather::Clear,
+            until: ticks + DAY_LENGTH / 2,
+        }
+    }
+
+    /// Switches between clear sky and rain when the time has come
+    ///
+    /// Clear skies last between half a day and two days, rain between a
+    /// tenth and a half of a day.
+    fn update(&mut self, ticks: u64, rng: &mut Rng) {
+        if ticks < self.until {
+            return;
+        }
+        let (weather, min, max) = match self.weather {
+            Weather::Clear => (Weather::Rain, DAY_LENGTH / 10, DAY_LENGTH / 2),
+            _ => (Weather::Clear, DAY_LENGTH / 2, DAY_LENGTH * 2),
+        };
+        self.weather = weather;
+        self.until = ticks + min + rng.next() % (max - min);
+        tracing::info!(%weather, "weather changed");
+    }
+}
+
+impl World {
+    pub fn weather(&self) -> Weather {
+        self.weather.read().unwrap().weather
+    }
+
+    /// Forces the weather, which lasts a quarter of a day before changing
+    pub fn set_weather(&self, weather: Weather) {
+        *self.weather.write().unwrap() = WeatherState {
+            weather,
+            until: self.ticks.load(Ordering::Relaxed) + DAY_LENGTH / 4,
+        };
+    }
+
+    pub(super) fn update_weather(&self) {
+        let ticks = self.ticks.load(Ordering::Relaxed);
+        let mut rng = self.rng.lock().unwrap();
+        self.weather.write().unwrap().update(ticks, &mut rng);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_weather_cycle() {
+        let mut rng = Rng::new(1);
+        let mut state = WeatherState::new(0);
+        let mut changes = Vec::new();
+        for ticks in 0..DAY_LENGTH * 20 {
+            let previous = state.weather;
+            state.update(ticks, &mut rng);
+            if state.weather != previous {
+                changes.push(state.weather);
+            }
+        }
+        // it rains from time to time, and always clears up
+        assert!(changes.len() >= 4);
+        assert!(changes.chunks(2).all(|c| c[0] == Weather::Rain));
+        assert_eq!(
+            Weather::Rain.precipitation(Biome::Mountains),
+            Some(Precipitation::Snow)
+        );
+        assert_eq!(Weather::Clear.precipitation(Biome::Beach), None);
+    }
+}
@@ -0,0 +1,88 @@
+//! Blocks added by content packs
+//!
+//! The packs are read at startup, and again when reloaded. A
+//! [`Block::Custom`] is the index of its definition in the registry.
+
+use std::sync::RwLock;
+
+use crate::Block;
+
+/// The properties of a block added by a content pack
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomBlock {
+    /// Name of the block in the commands
+    pub name: String,
+    /// Name shown to the player
+    pub label: String,
+    /// Color of the block on the map
+    pub color: [f32; 3],
+    pub solid: bool,
+    pub transparent: bool,
+    /// Light level emitted
+    pub emission: u8,
+    pub friction: f32,
+    pub bounciness: f32,
+    /// Part of its walking speed the player keeps on the block
+    pub speed: f32,
+    pub breakable: bool,
+    /// What the block leaves when broken, each with its chance
+    pub drops: Vec<(Block, f32)>,
+}
+
+/// Stands for a custom block whose pack is no longer loaded (it still has
+/// its place in the saved chunks)
+static MISSING: CustomBlock = CustomBlock {
+    name: String::new(),
+    label: String::new(),
+    color: [0.4; 3],
+    solid: true,
+    transparent: false,
+    emission: 0,
+    friction: 1.0,
+    bounciness: 0.0,
+    speed: 1.0,
+    breakable: true,
+    drops: Vec::new(),
+};
+
+static REGISTRY: RwLock<&'static [CustomBlock]> = RwLock::new(&[]);
+
+/// Most blocks the packs can add, as their id is a byte
+pub const MAX_CUSTOM: usize = 256;
+
+/// Registers the blocks of the packs, in the order of their id, in place
+/// of the ones registered before
+///
+/// The definitions are borrowed for the rest of the program, so the ones
+/// replaced are never freed (they are small, and reloads are rare).
+pub fn register(blocks: Vec<CustomBlock>) {
+    debug_assert!(blocks.len() <= MAX_CUSTOM);
+    *REGISTRY.write().unwrap() = blocks.leak();
+}
+
+fn registry() -> &'static [CustomBlock] {
+    *REGISTRY.read().unwrap()
+}
+
+/// Whether a pack defines the block
+pub fn is_registered(id: u8) -> bool {
+    (id as usize) < registry().len()
+}
+
+/// The definition of a custom block (a default one if it is not registered)
+pub fn get(id: u8) -> &'static CustomBlock {
+    registry().get(id as usize).unwrap_or(&MISSING)
+}
+
+/// The custom block with this name
+pub fn find(name: &str) -> Option<Block> {
+    registry()
+        .iter()
+        .position(|block| block.name == name)
+        .map(|id| Block::Custom(id as u8))
+}
+
+/// Every custom block registered
+pub fn blocks() -> impl Iterator<Item = Block> {
+    (0..registry().len()).map(|id| Block::Custom(id as u8))
+}
@@ -5,10 +5,12 @@ use crate::*;
 impl BlockCoords {
     /// Return the neighbour coordinate
     ///
-    /// Returns `None` if the coordinate is out of the world (`0 <= y < 256`).
+    /// Returns `None` if the coordinate is out of the world (`0 <= y < 256`),
+    /// or past the last one on the horizontal axes.
     pub fn step(self, direction: Direction) -> Option<Self> {
-        <[i32; 3]>::from(self)
-            .vector_add(direction.into())
+        let [x, y, z]: [i32; 3] = self.into();
+        let [dx, dy, dz]: [i32; 3] = direction.into();
+        [x.checked_add(dx)?, y + dy, z.checked_add(dz)?]
             .try_into()
             .ok()
     }
@@ -31,7 +33,7 @@ impl From<BlockIndex> for [i32; 3] {
     /// The compression is as follow: `[y:8][z:4][x:4] == [index:16]`
     fn from(BlockIndex { index }: BlockIndex) -> Self {
         [
-            (index >> 0 & 0xf) as i32,
+            (index & 0xf) as i32,
             (index >> 8 & 0xff) as i32,
             (index >> 4 & 0xf) as i32,
         ]
@@ -47,7 +49,7 @@ impl TryFrom<[i32; 3]> for BlockIndex {
     fn try_from([x, y, z]: [i32; 3]) -> Result<Self, Self::Error> {
         match [x, y, z] {
             [0..=15, 0..=255, 0..=15] => Ok(BlockIndex {
-                index: ((x as u16) << 0) | ((z as u16) << 4) | ((y as u16) << 8),
+                index: (x as u16) | ((z as u16) << 4) | ((y as u16) << 8),
             }),
             _ => Err(()),
         }
@@ -84,7 +86,7 @@ impl TryFrom<[i32; 3]> for BlockCoords {
         Ok(BlockCoords(
             ChunkCoords { x: cx, z: cz },
             BlockIndex {
-                index: (bx as u16) << 0 | (bz as u16) << 4 | (by as u16) << 8,
+                index: (bx as u16) | (bz as u16) << 4 | (by as u16) << 8,
             },
         ))
     }
@@ -125,6 +127,18 @@ impl Direction {
         Self::Down,
     ];
     pub const CARDINAL: [Self; 4] = [Self::North, Self::South, Self::East, Self::West];
+    /// The next cardinal direction, turning clockwise seen from above
+    ///
+    /// Vertical directions are unchanged.
+    pub fn clockwise(self) -> Self {
+        match self {
+            Self::North => Self::East,
+            Self::East => Self::South,
+            Self::South => Self::West,
+            Self::West => Self::North,
+            vertical => vertical,
+        }
+    }
     pub const fn face_vertices(self) -> [[i32; 3]; 4] {
         match self {
             Self::North => [[0, 0, 0], [0, 1, 0], [1, 1, 0], [1, 0, 0]],
@@ -291,10 +305,335 @@ impl Block {
             (Self::Glass, _) => Sprite::Glass,
             (Self::Trunk, Direction::Up | Direction::Down) => Sprite::TrunkTop,
             (Self::Trunk, _) => Sprite::TrunkSide,
-            (Self::Water, _) => Sprite::Water,
+            (Self::Water(_), _) => Sprite::Water,
+            (Self::Tnt, _) => Sprite::Tnt,
+            (Self::Door(state), _) if state & state::UPPER != 0 => Sprite::DoorTop,
+            (Self::Door(_), _) => Sprite::DoorBottom,
+            (Self::Trapdoor(_), _) => Sprite::Trapdoor,
+            (Self::Torch(_), _) => Sprite::Torch,
+            (Self::Sapling(_), _) => Sprite::Sapling,
+            (Self::Bedrock, _) => Sprite::Bedrock,
+            (Self::Ice, _) => Sprite::Ice,
+            (Self::Slime, _) => Sprite::Slime,
+            (Self::Leaves, _) => Sprite::Leaves,
+            (Self::Chest, _) => Sprite::Chest,
+            (Self::Wire(0), _) => Sprite::Wire,
+            (Self::Wire(_), _) => Sprite::WireOn,
+            (Self::Lever(_), _) => Sprite::Lever,
+            (Self::Lamp(state), _) if state & state::ON != 0 => Sprite::LampOn,
+            (Self::Lamp(_), _) => Sprite::LampOff,
+            (Self::Piston(_), side) if side == self.facing() => Sprite::PistonFront,
+            (Self::Piston(_), _) => Sprite::Piston,
+            (Self::PistonHead(_), _) => Sprite::PistonFront,
+            (Self::Farmland, Direction::Up) => Sprite::Farmland,
+            (Self::Farmland, _) => Sprite::Dirt,
+            (Self::Crop(state::RIPE), _) => Sprite::CropRipe,
+            (Self::Crop(_), _) => Sprite::Crop,
+            (Self::SoulSand, _) => Sprite::SoulSand,
+            (Self::Glider, _) => Sprite::Glider,
             _ => unimplemented!(),
         }
     }
+
+    /// Index of the texture of a face, the sprites being followed by the
+    /// textures of the custom blocks
+    ///
+    /// A custom block whose pack is no longer loaded looks like stone.
+    pub fn texture(self, direction: Direction) -> u32 {
+        match self {
+            Self::Custom(id) if custom::is_registered(id) => Sprite::COUNT + id as u32,
+            Self::Custom(_) => Sprite::Stone as u32,
+            block => block.sprite(direction) as u32,
+        }
+    }
+
+    /// The state byte (0 for blocks without state)
+    pub fn state(self) -> u8 {
+        match self {
+            Self::Door(state)
+            | Self::Trapdoor(state)
+            | Self::Torch(state)
+            | Self::Lever(state)
+            | Self::Lamp(state)
+            | Self::Piston(state)
+            | Self::PistonHead(state) => state,
+            Self::Wire(level) => level,
+            Self::Custom(id) => id,
+            Self::Sapling(stage) | Self::Crop(stage) => stage,
+            Self::Water(level) => level,
+            _ => 0,
+        }
+    }
+
+    /// The same block with another state (blocks without state are unchanged)
+    pub fn with_state(self, state: u8) -> Self {
+        match self {
+            Self::Door(_) => Self::Door(state),
+            Self::Trapdoor(_) => Self::Trapdoor(state),
+            Self::Torch(_) => Self::Torch(state),
+            Self::Sapling(_) => Self::Sapling(state),
+            Self::Crop(_) => Self::Crop(state),
+            Self::Water(_) => Self::Water(state),
+            Self::Wire(_) => Self::Wire(state),
+            Self::Lever(_) => Self::Lever(state),
+            Self::Lamp(_) => Self::Lamp(state),
+            Self::Piston(_) => Self::Piston(state),
+            Self::PistonHead(_) => Self::PistonHead(state),
+            Self::Custom(_) => Self::Custom(state),
+            block => block,
+        }
+    }
+
+    /// The side a door or trapdoor is attached to when closed
+    pub fn facing(self) -> Direction {
+        Direction::CARDINAL[((self.state() & state::FACING) >> state::FACING_SHIFT) as usize]
+    }
+
+    /// The same block attached to another side
+    pub fn with_facing(self, facing: Direction) -> Self {
+        let index = Direction::CARDINAL
+            .iter()
+            .position(|&d| d == facing)
+            .unwrap_or(0) as u8;
+        self.with_state(self.state() & !state::FACING | index << state::FACING_SHIFT)
+    }
+
+    pub fn is_open(self) -> bool {
+        self.state() & state::OPEN != 0
+    }
+
+    /// Part of the voxel occupied by the block, as its min and max corners
+    pub fn shape(self) -> [[f32; 3]; 2] {
+        // doors and trapdoors are slabs against a side of the voxel,
+        // opening a door turns it a quarter around its hinge
+        match self {
+            Self::Door(_) if self.is_open() => slab(self.facing().clockwise()),
+            Self::Door(_) => slab(self.facing()),
+            Self::Trapdoor(_) if self.is_open() => slab(self.facing()),
+            Self::Trapdoor(_) => slab(Direction::Down),
+            Self::Torch(state) if state & state::WALL != 0 => torch(Some(self.facing())),
+            Self::Torch(_) => torch(None),
+            Self::Sapling(_) => [[0.25, 0.0, 0.25], [0.75, 0.75, 0.75]],
+            Self::Wire(_) => [[0.0; 3], [1.0, WIRE, 1.0]],
+            Self::Lever(_) => [[0.25, 0.0, 0.25], [0.75, 0.5, 0.75]],
+            Self::PistonHead(_) => slab(self.facing()),
+            Self::Glider => slab(Direction::Down),
+            // higher at each stage, up to a full voxel
+            Self::Crop(stage) => [
+                [0.125, 0.0, 0.125],
+                [0.875, (stage + 1) as f32 / (state::RIPE + 1) as f32, 0.875],
+            ],
+            _ => [[0.0; 3], [1.0; 3]],
+        }
+    }
+
+    /// Whether the block fills its voxel (hiding the faces against it)
+    pub fn is_full(self) -> bool {
+        !matches!(
+            self,
+            Self::Door(_)
+                | Self::Trapdoor(_)
+                | Self::Torch(_)
+                | Self::Sapling(_)
+                | Self::Water(_)
+                | Self::Wire(_)
+                | Self::Lever(_)
+                | Self::PistonHead(_)
+                | Self::Crop(_)
+                | Self::Glider
+        )
+    }
+
+    /// Whether light goes through the block
+    pub fn is_transparent(self) -> bool {
+        match self {
+            Self::Custom(id) => custom::get(id).transparent,
+            _ => !self.is_full() || matches!(self, Self::Glass | Self::Leaves),
+        }
+    }
+
+    /// Height of the surface of a fluid in its voxel (when nothing is above)
+    ///
+    /// A source fills 14/16 of the voxel, the surface goes down as the
+    /// fluid flows away from it.
+    pub fn fluid_height(self) -> Option<f32> {
+        match self {
+            Self::Water(level) => {
+                let level = level.min(state::MAX_FLOW) as f32;
+                Some((14.0 - 12.0 * level / state::MAX_FLOW as f32) / 16.0)
+            }
+            _ => None,
+        }
+    }
+
+    /// Light level emitted by the block (0 for most blocks)
+    pub fn emission(self) -> u8 {
+        match self {
+            Self::Torch(_) => 14,
+            Self::Lamp(state) if state & state::ON != 0 => 15,
+            Self::Custom(id) => custom::get(id).emission,
+            _ => 0,
+        }
+    }
+
+    /// Whether the player can remove the block (or blow it up)
+    pub fn is_breakable(self) -> bool {
+        match self {
+            Self::Bedrock => false,
+            Self::Custom(id) => custom::get(id).breakable,
+            _ => true,
+        }
+    }
+
+    /// Whether a piston can push it
+    ///
+    /// The blocks spread over two voxels and the chests (holding their
+    /// content where they are) stay in place.
+    pub fn is_movable(self) -> bool {
+        match self {
+            Self::Piston(state) => state & state::ON == 0,
+            Self::Door(_) | Self::Chest | Self::PistonHead(_) => false,
+            _ => self.is_breakable(),
+        }
+    }
+
+    /// What the block leaves when the player breaks it, each with its
+    /// chance (from 0.0 to 1.0)
+    pub fn drops(self) -> &'static [(Block, f32)] {
+        match self {
+            Self::Stone => &[(Self::Stone, 1.0)],
+            Self::Dirt | Self::Grass => &[(Self::Dirt, 1.0)],
+            Self::Sand => &[(Self::Sand, 1.0)],
+            Self::SoulSand => &[(Self::SoulSand, 1.0)],
+            Self::Glider => &[(Self::Glider, 1.0)],
+            Self::Brick => &[(Self::Brick, 1.0)],
+            Self::Trunk => &[(Self::Trunk, 1.0)],
+            Self::Leaves => &[(Self::Sapling(0), 0.1)],
+            Self::Tnt => &[(Self::Tnt, 1.0)],
+            Self::Door(_) => &[(Self::Door(0), 1.0)],
+            Self::Trapdoor(_) => &[(Self::Trapdoor(0), 1.0)],
+            Self::Torch(_) => &[(Self::Torch(0), 1.0)],
+            Self::Sapling(_) => &[(Self::Sapling(0), 1.0)],
+            Self::Slime => &[(Self::Slime, 1.0)],
+            Self::Chest => &[(Self::Chest, 1.0)],
+            Self::Wire(_) => &[(Self::Wire(0), 1.0)],
+            Self::Lever(_) => &[(Self::Lever(0), 1.0)],
+            Self::Lamp(_) => &[(Self::Lamp(0), 1.0)],
+            // the head is one half of an extended piston
+            Self::Piston(_) | Self::PistonHead(_) => &[(Self::Piston(0), 1.0)],
+            Self::Farmland => &[(Self::Dirt, 1.0)],
+            // a ripe crop gives more seeds than it took
+            Self::Crop(state::RIPE) => &[
+                (Self::Crop(0), 1.0),
+                (Self::Crop(0), 1.0),
+                (Self::Crop(0), 0.5),
+            ],
+            Self::Crop(_) => &[(Self::Crop(0), 1.0)],
+            Self::Custom(id) => &custom::get(id).drops,
+            // glass and ice shatter
+            Self::Glass | Self::Ice | Self::Water(_) | Self::Bedrock => &[],
+        }
+    }
+
+    /// Whether the block stops the player
+    ///
+    /// Collisions are computed on whole voxels: a closed door or trapdoor
+    /// blocks its voxel, an open one lets the player through.
+    pub fn is_solid(self) -> bool {
+        match self {
+            Self::Door(_) | Self::Trapdoor(_) => !self.is_open(),
+            Self::Torch(_)
+            | Self::Sapling(_)
+            | Self::Crop(_)
+            | Self::Wire(_)
+            | Self::Lever(_)
+            | Self::Glider => false,
+            Self::Custom(id) => custom::get(id).solid,
+            _ => true,
+        }
+    }
+
+    /// How quickly the speed of the player standing on the block follows
+    /// its steps (from 0.0, it slides forever, to 1.0, it stops at once)
+    pub fn friction(self) -> f32 {
+        match self {
+            Self::Ice => 0.05,
+            Self::Slime => 0.5,
+            Self::Custom(id) => custom::get(id).friction,
+            _ => 1.0,
+        }
+    }
+
+    /// Part of its falling speed the player keeps going back up when
+    /// landing on the block (0.0 for most blocks)
+    pub fn bounciness(self) -> f32 {
+        match self {
+            Self::Slime => 0.8,
+            Self::Custom(id) => custom::get(id).bounciness,
+            _ => 0.0,
+        }
+    }
+
+    /// Part of its walking speed the player keeps on the block (1.0 for
+    /// most blocks)
+    pub fn speed(self) -> f32 {
+        match self {
+            Self::SoulSand => 0.4,
+            Self::Custom(id) => custom::get(id).speed,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Thickness of doors and trapdoors
+const SLAB: f32 = 3.0 / 16.0;
+
+/// Width of a torch stick
+const STICK: f32 = 2.0 / 16.0;
+
+/// Thickness of a wire
+const WIRE: f32 = 1.0 / 16.0;
+
+/// The axis of a side and whether it is on the positive end
+fn side_axis(side: Direction) -> (usize, bool) {
+    match side {
+        Direction::West => (0, false),
+        Direction::East => (0, true),
+        Direction::Down => (1, false),
+        Direction::Up => (1, true),
+        Direction::North => (2, false),
+        Direction::South => (2, true),
+    }
+}
+
+/// The box of a slab against the given side of the voxel
+fn slab(side: Direction) -> [[f32; 3]; 2] {
+    let (axis, positive) = side_axis(side);
+    let mut shape = [[0.0; 3], [1.0; 3]];
+    if positive {
+        shape[0][axis] = 1.0 - SLAB;
+    } else {
+        shape[1][axis] = SLAB;
+    }
+    shape
+}
+
+/// The box of a torch stick, standing on the floor or against a wall
+fn torch(wall: Option<Direction>) -> [[f32; 3]; 2] {
+    let [low, high] = [0.5 - STICK / 2.0, 0.5 + STICK / 2.0];
+    let mut shape = [[low, 0.0, low], [high, 10.0 / 16.0, high]];
+    if let Some(side) = wall {
+        let (axis, positive) = side_axis(side);
+        // a bit higher on a wall, to be seen above the blocks
+        shape[0][1] += 3.0 / 16.0;
+        shape[1][1] += 3.0 / 16.0;
+        [shape[0][axis], shape[1][axis]] = if positive {
+            [1.0 - STICK, 1.0]
+        } else {
+            [0.0, STICK]
+        };
+    }
+    shape
 }
 
 impl Boxel {
@@ -308,8 +647,72 @@ impl Boxel {
 
 #[cfg(test)]
 mod test {
+    use quickcheck::{Arbitrary, Gen, QuickCheck};
+
     use super::*;
 
+    impl Arbitrary for Direction {
+        fn arbitrary(g: &mut Gen) -> Self {
+            *g.choose(&Direction::ALL).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_coords_round_trip() {
+        // anywhere horizontally, the chunk being the floor of the division
+        fn property(x: i32, y: u8, z: i32) -> bool {
+            let bc: BlockCoords = [x, y as i32, z].try_into().unwrap();
+            let BlockCoords(cc, bi) = bc;
+            let [bx, _, bz]: [i32; 3] = bi.into();
+            <[i32; 3]>::from(bc) == [x, y as i32, z]
+                && [cc.x, cc.z] == [x.div_euclid(16), z.div_euclid(16)]
+                && [bx, bz] == [x.rem_euclid(16), z.rem_euclid(16)]
+                && BlockIndex::try_from(<[i32; 3]>::from(bi)) == Ok(bi)
+        }
+        QuickCheck::new()
+            .tests(10_000)
+            .quickcheck(property as fn(i32, u8, i32) -> bool);
+        fn out_of_world(y: i32) -> bool {
+            BlockCoords::try_from([0, y, 0]).is_ok() == (0..256).contains(&y)
+        }
+        QuickCheck::new().quickcheck(out_of_world as fn(i32) -> bool);
+        let index = |index| <[i32; 3]>::from(BlockIndex { index });
+        assert!(
+            (0..=u16::MAX).all(|i| BlockIndex::try_from(index(i)) == Ok(BlockIndex { index: i }))
+        );
+    }
+
+    #[test]
+    fn test_step_oposit() {
+        fn property(x: i32, y: u8, z: i32, direction: Direction) -> bool {
+            let bc: BlockCoords = [x, y as i32, z].try_into().unwrap();
+            let [dx, dy, dz]: [i32; 3] = direction.into();
+            let [ox, oy, oz]: [i32; 3] = direction.oposit().into();
+            let inside = x.checked_add(dx).is_some()
+                && z.checked_add(dz).is_some()
+                && (0..256).contains(&(y as i32 + dy));
+            direction.oposit().oposit() == direction
+                && [dx + ox, dy + oy, dz + oz] == [0; 3]
+                && match bc.step(direction) {
+                    // one block away, and back
+                    Some(next) => {
+                        inside
+                            && <[i32; 3]>::from(next) == [x + dx, y as i32 + dy, z + dz]
+                            && next.step(direction.oposit()) == Some(bc)
+                    }
+                    None => !inside,
+                }
+        }
+        QuickCheck::new()
+            .tests(10_000)
+            .quickcheck(property as fn(i32, u8, i32, Direction) -> bool);
+        // the edges of the horizontal axes
+        let edge: BlockCoords = [i32::MAX, 0, i32::MIN].try_into().unwrap();
+        assert_eq!(edge.step(Direction::East), None);
+        assert_eq!(edge.step(Direction::North), None);
+        assert!(edge.step(Direction::West).is_some());
+    }
+
     #[test]
     fn test_coords_convert() {
         for x in -128..128 {
@@ -322,4 +725,140 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_door_state() {
+        let door = Block::Door(state::UPPER).with_facing(Direction::East);
+        assert_eq!(door.facing(), Direction::East);
+        assert_eq!(door.state() & state::UPPER, state::UPPER);
+        assert!(!door.is_open() && door.is_solid());
+        // closed, the door is against the east side
+        assert_eq!(door.shape(), [[1.0 - SLAB, 0.0, 0.0], [1.0; 3]]);
+
+        let open = door.with_state(door.state() | state::OPEN);
+        assert!(open.is_open() && !open.is_solid());
+        assert_eq!(open.facing(), Direction::East);
+        // open, it turned a quarter
+        assert_eq!(open.shape(), [[0.0, 0.0, 1.0 - SLAB], [1.0; 3]]);
+
+        assert!(Block::Stone.is_full() && !Block::Trapdoor(0).is_full());
+        assert_eq!(Block::Stone.with_state(3), Block::Stone);
+    }
+
+    #[test]
+    fn test_drops() {
+        assert_eq!(Block::Grass.drops(), &[(Block::Dirt, 1.0)]);
+        assert!(Block::Glass.drops().is_empty());
+        // a door drops whole, whatever its state
+        assert_eq!(
+            Block::Door(state::UPPER | state::OPEN).drops(),
+            &[(Block::Door(0), 1.0)]
+        );
+        let [(sapling, chance)] = Block::Leaves.drops() else {
+            panic!("leaves drop a single sapling");
+        };
+        assert!(*sapling == Block::Sapling(0) && *chance < 1.0);
+    }
+
+    #[test]
+    fn test_surface() {
+        assert_eq!(Block::Stone.friction(), 1.0);
+        assert!(Block::Ice.friction() < Block::Slime.friction());
+        assert_eq!(Block::Stone.bounciness(), 0.0);
+        assert!((0.0..1.0).contains(&Block::Slime.bounciness()));
+        assert_eq!(Block::Stone.speed(), 1.0);
+        assert!(Block::SoulSand.speed() < 1.0 && Block::SoulSand.is_solid());
+    }
+
+    #[test]
+    fn test_fluid_height() {
+        assert_eq!(Block::Water(0).fluid_height(), Some(14.0 / 16.0));
+        assert_eq!(
+            Block::Water(state::MAX_FLOW).fluid_height(),
+            Some(2.0 / 16.0)
+        );
+        assert!(Block::Water(2).fluid_height() < Block::Water(1).fluid_height());
+        assert_eq!(Block::Stone.fluid_height(), None);
+        assert!(!Block::Water(0).is_full() && Block::Water(0).is_transparent());
+    }
+
+    #[test]
+    fn test_custom() {
+        let marble = custom::CustomBlock {
+            name: "marble".to_string(),
+            label: "Marble".to_string(),
+            color: [0.9; 3],
+            solid: true,
+            transparent: false,
+            emission: 3,
+            friction: 0.5,
+            bounciness: 0.0,
+            speed: 1.0,
+            breakable: true,
+            drops: vec![(Block::Custom(0), 1.0), (Block::Sand, 0.5)],
+        };
+        custom::register(vec![marble.clone()]);
+        let block = custom::find("marble").unwrap();
+        assert_eq!(block, Block::Custom(0));
+        assert_eq!(custom::blocks().collect::<Vec<_>>(), [block]);
+        assert_eq!((block.emission(), block.friction()), (3, 0.5));
+        assert_eq!(block.drops()[1], (Block::Sand, 0.5));
+        assert_eq!(block.texture(Direction::Up), Sprite::COUNT);
+        // its id is kept as its state
+        assert_eq!(block.with_state(block.state()), block);
+
+        // reloaded with other properties
+        custom::register(vec![custom::CustomBlock {
+            emission: 0,
+            ..marble
+        }]);
+        assert_eq!(block.emission(), 0);
+
+        // the pack of this one is gone
+        let missing = Block::Custom(7);
+        assert!(missing.is_solid() && missing.drops().is_empty());
+        assert_eq!(missing.texture(Direction::Up), Sprite::Stone as u32);
+        assert_eq!(custom::find("granite"), None);
+    }
+
+    #[test]
+    fn test_torch_shape() {
+        let floor = Block::Torch(0);
+        assert!(floor.emission() > 0 && floor.is_transparent() && !floor.is_solid());
+        let [min, max] = floor.shape();
+        assert_eq!(min[1], 0.0);
+        assert!(max[0] - min[0] < 0.5);
+
+        let wall = Block::Torch(state::WALL).with_facing(Direction::West);
+        let [min, max] = wall.shape();
+        // against the west side, above the floor
+        assert_eq!([min[0], max[0]], [0.0, STICK]);
+        assert!(min[1] > 0.0);
+    }
+    #[test]
+    fn test_piston() {
+        let piston = Block::Piston(0).with_facing(Direction::South);
+        assert_eq!(piston.texture(Direction::South), Sprite::PistonFront as u32);
+        assert_eq!(piston.texture(Direction::North), Sprite::Piston as u32);
+        assert!(piston.is_movable() && piston.is_full());
+        // extended, it stays with its head
+        let extended = piston.with_state(piston.state() | state::ON);
+        assert_eq!(extended.facing(), Direction::South);
+        assert!(!extended.is_movable() && !Block::PistonHead(0).is_movable());
+        assert!(Block::Sand.is_movable() && !Block::Bedrock.is_movable());
+        assert!(!Block::Chest.is_movable() && !Block::Door(0).is_movable());
+        assert_eq!(Block::PistonHead(0).drops(), &[(Block::Piston(0), 1.0)]);
+    }
+    #[test]
+    fn test_crop() {
+        let [young, ripe] = [Block::Crop(0), Block::Crop(state::RIPE)];
+        assert!(young.shape()[1][1] < ripe.shape()[1][1]);
+        assert_eq!(ripe.shape()[1][1], 1.0);
+        assert!(!young.is_solid() && young.is_transparent());
+        assert_eq!(young.texture(Direction::Up), Sprite::Crop as u32);
+        assert_eq!(ripe.texture(Direction::Up), Sprite::CropRipe as u32);
+        assert_eq!(young.drops(), &[(young, 1.0)]);
+        assert!(ripe.drops().len() > 1);
+        assert_eq!(Block::Farmland.drops(), &[(Block::Dirt, 1.0)]);
+    }
 }
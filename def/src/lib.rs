@@ -1,5 +1,7 @@
 pub mod cube;
+pub mod custom;
 mod implement;
+pub mod plugin;
 mod ray_travel;
 
 pub use ray_travel::RayTravel;
@@ -43,11 +45,74 @@ pub enum Block {
     Dirt,
     Grass,
     Sand,
-    Water,
+    /// The byte is its flow level, 0 for a source
+    Water(u8),
     Glass,
     Brick,
     Trunk,
     Leaves,
+    Tnt,
+    /// Half of a door (see [`state`] for the byte)
+    Door(u8),
+    /// See [`state`] for the byte
+    Trapdoor(u8),
+    /// Emits light, see [`state`] for the byte
+    Torch(u8),
+    /// Grows into a tree, the byte is its growth stage
+    Sapling(u8),
+    /// Bottom of the world, it can't be broken
+    Bedrock,
+    /// Slippery, the player slides on it
+    Ice,
+    /// Bouncy, the player bounces on it
+    Slime,
+    /// Keeps blocks for the player (its content is kept by the world)
+    Chest,
+    /// Carries a signal, the byte is its level (see [`state::MAX_SIGNAL`])
+    Wire(u8),
+    /// Powers the wires and lamps around while switched on (see [`state`])
+    Lever(u8),
+    /// Lights up while powered (see [`state`])
+    Lamp(u8),
+    /// Pushes the blocks in front of it while powered, it is extended with
+    /// its head in front (see [`state`])
+    Piston(u8),
+    /// Stands in front of an extended piston, facing the same way
+    PistonHead(u8),
+    /// Tilled dirt, crops grow on it
+    Farmland,
+    /// Grows on farmland, the byte is its stage (see [`state::RIPE`])
+    Crop(u8),
+    /// Sticky, the player walks slowly on it
+    SoulSand,
+    /// Folded glider, the player holding it glides when falling
+    Glider,
+    /// Added by a content pack, the byte is its id (see [`custom`]), kept
+    /// as its state so it is saved like one
+    Custom(u8),
+}
+
+/// Bits of the state byte of doors, trapdoors and torches
+///
+/// `[unused:4][facing:2][upper:1][open:1]`, facing being 0 for north,
+/// 1 for south, 2 for east and 3 for west. A torch uses the second bit to
+/// tell it is attached to the wall on its facing side (instead of the floor).
+pub mod state {
+    pub const OPEN: u8 = 0b0001;
+    /// The upper half of a door
+    pub const UPPER: u8 = 0b0010;
+    /// A torch on a wall
+    pub const WALL: u8 = 0b0010;
+    pub const FACING_SHIFT: u8 = 2;
+    pub const FACING: u8 = 0b1100;
+    /// Flow level of water the farthest from its source
+    pub const MAX_FLOW: u8 = 7;
+    /// A lever switched on, a lamp lit, a piston extended
+    pub const ON: u8 = 0b0001;
+    /// Signal level of a wire next to a source, it loses a level per wire
+    pub const MAX_SIGNAL: u8 = 15;
+    /// Growth stage of a crop ready to be harvested
+    pub const RIPE: u8 = 3;
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -63,6 +128,35 @@ pub enum Sprite {
     Water = 7,
     TrunkTop = 8,
     TrunkSide = 9,
+    Skin = 10,
+    Tnt = 11,
+    DoorTop = 12,
+    DoorBottom = 13,
+    Trapdoor = 14,
+    Torch = 15,
+    Sapling = 16,
+    Bedrock = 17,
+    Ice = 18,
+    Slime = 19,
+    Leaves = 20,
+    Chest = 21,
+    Wire = 22,
+    WireOn = 23,
+    Lever = 24,
+    LampOff = 25,
+    LampOn = 26,
+    Piston = 27,
+    PistonFront = 28,
+    Farmland = 29,
+    Crop = 30,
+    CropRipe = 31,
+    SoulSand = 32,
+    Glider = 33,
+}
+
+impl Sprite {
+    /// Number of sprites, the textures of the custom blocks come after them
+    pub const COUNT: u32 = 34;
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -86,7 +180,6 @@ pub struct Boxel {
 }
 
 pub mod constant {
-    pub const GRAVITY: f32 = -0.01;
-    pub const JUMP: f32 = 0.15;
-    pub const COLLISION_EPSILON: f32 = 0.001;
+    /// Light level of the brightest voxel, light loses one level per block
+    pub const MAX_LIGHT: u8 = 15;
 }
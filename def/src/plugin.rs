@@ -0,0 +1,121 @@
+//! Interface between the game and its plugins
+//!
+//! A plugin is a dynamic library built against this crate (with the same
+//! compiler), exporting its [`Plugin`] with [`declare_plugin`]. It follows
+//! what happens in the world and may register commands, but it only
+//! reaches the world through a [`Host`], which checks the capabilities the
+//! player granted to it.
+
+use std::{fmt, str::FromStr};
+
+use crate::{Block, BlockCoords};
+
+/// Bumped on every change of this interface, a plugin built against
+/// another version is not loaded
+pub const API_VERSION: u32 = 1;
+
+/// What a plugin can be allowed to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Capability {
+    ReadBlocks,
+    EditBlocks,
+    /// Its commands can be typed in the console
+    Commands,
+}
+
+impl Capability {
+    pub const ALL: [Self; 3] = [Self::ReadBlocks, Self::EditBlocks, Self::Commands];
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::ReadBlocks => "read_blocks",
+            Self::EditBlocks => "edit_blocks",
+            Self::Commands => "commands",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Capability {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|capability| capability.to_string() == s)
+            .ok_or(())
+    }
+}
+
+/// Why a plugin could not do something
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginError {
+    /// The plugin was not granted the capability
+    Denied(Capability),
+    /// The world refused (the message is meant for the player)
+    Failed(String),
+}
+
+/// What happened in the world, as told to the plugins
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WorldEvent {
+    /// The player broke a block
+    BlockBroken(Block),
+    /// The eyes of the player moved
+    PlayerMoved([f32; 3]),
+    Explosion {
+        pos: [f32; 3],
+        radius: f32,
+    },
+}
+
+/// The world as seen by a plugin
+pub trait Host {
+    /// The block at the coordinates (None for air), needs
+    /// [`Capability::ReadBlocks`]
+    fn block(&self, coords: BlockCoords) -> Result<Option<Block>, PluginError>;
+
+    /// Places a block, or removes the one there with None, needs
+    /// [`Capability::EditBlocks`]
+    fn set_block(&self, coords: BlockCoords, block: Option<Block>) -> Result<(), PluginError>;
+}
+
+/// A gameplay mod
+pub trait Plugin {
+    /// Name of the plugin, which its capabilities are granted to
+    fn name(&self) -> &str;
+
+    /// Names of the commands of the plugin, the first word of a console line
+    fn commands(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn on_event(&mut self, _host: &dyn Host, _event: &WorldEvent) {}
+
+    /// Runs one of its commands, `args` being the rest of the line, returns
+    /// what to tell the player
+    fn on_command(
+        &mut self,
+        _host: &dyn Host,
+        name: &str,
+        _args: &str,
+    ) -> Result<String, PluginError> {
+        Err(PluginError::Failed(name.to_string()))
+    }
+}
+
+/// Exports the plugin built by the expression from the library
+#[macro_export]
+macro_rules! declare_plugin {
+    ($plugin:expr) => {
+        #[no_mangle]
+        pub static ARTCRAFT_API_VERSION: u32 = $crate::plugin::API_VERSION;
+
+        #[no_mangle]
+        pub fn artcraft_plugin() -> Box<dyn $crate::plugin::Plugin> {
+            Box::new($plugin)
+        }
+    };
+}
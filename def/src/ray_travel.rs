@@ -3,18 +3,18 @@ use std::cmp::Ordering;
 use crate::{BlockCoords, Direction};
 use arrayvec::ArrayVec;
 use mat::Transmuter;
-use mat::VectorTrait;
-
-const EPSILON: f32 = 0.0001;
 
 /// An iterator over voxels crossed by a ray cast
+///
+/// Each voxel entered is given with the face it is entered by (or None when
+/// it is out of the world), until the ray goes past `limit` (in lengths of
+/// `ray`).
 #[derive(Debug, Clone)]
 pub struct RayTravel {
     travelers: ArrayVec<RayTraveler<f32>, 3>,
-    time: f32,
     limit: f32,
-    origin: [f32; 3],
-    ray: [f32; 3],
+    /// The voxel the ray is in (maybe out of the world)
+    voxel: [i32; 3],
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -49,20 +49,19 @@ impl RayTravel {
                         step: 1.0 / ray.abs(),
                         next: (origin - origin.floor()) / ray.abs(),
                     }),
-                    // Positive direction (east, up or south)
+                    // Positive direction (east, up or south), a ray starting
+                    // on a boundary crosses the next one first
                     Ordering::Greater => Some(RayTraveler {
                         direction: direction.1,
                         step: 1.0 / ray,
-                        next: (origin.ceil() - origin) / ray,
+                        next: (origin.floor() + 1.0 - origin) / ray,
                     }),
                     // Not moving on current axis, so ignore it
                     Ordering::Equal => None,
                 })
                 .collect(),
             limit,
-            time: 0.0,
-            ray,
-            origin,
+            voxel: origin.map(|v| v.floor() as i32),
         }
     }
 }
@@ -71,33 +70,196 @@ impl Iterator for RayTravel {
     type Item = Option<(BlockCoords, Direction)>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.time > self.limit {
-            // Stop if ray trace length (or time, as you prefer) exceeded
-            return None;
-        }
-
         // find the first traveler crossing integer axis value
         let traveler = self
             .travelers
             .iter_mut()
             .min_by(|lhs, rhs| lhs.next.partial_cmp(&rhs.next).unwrap_or(Ordering::Equal))?;
-
-        // the ray have at least reach this time (or length, as you prefer)
-        // we add an epsilon to be sure the position will be inside the desired voxel
-        self.time = traveler.next + EPSILON;
+        if traveler.next > self.limit {
+            // Stop if ray trace length (or time, as you prefer) exceeded
+            return None;
+        }
         // update the traveler
         traveler.next += traveler.step;
 
-        // compute in which voxel to position ends up
-        if let Ok(position) = self
-            .origin
-            .vector_add(self.ray.vector_scale(self.time))
-            .try_into()
-        {
-            Some(Some((position, traveler.direction)))
-        } else {
-            // out of the world
-            Some(None)
+        // the voxel is stepped into rather than found from the position,
+        // which would be rounded to a wrong voxel near the edges
+        let [dx, dy, dz]: [i32; 3] = traveler.direction.oposit().into();
+        let [x, y, z] = self.voxel;
+        self.voxel = [
+            x.saturating_add(dx),
+            y.saturating_add(dy),
+            z.saturating_add(dz),
+        ];
+        // out of the world
+        Some(
+            BlockCoords::try_from(self.voxel)
+                .ok()
+                .map(|position| (position, traveler.direction)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use quickcheck::{Arbitrary, Gen, QuickCheck, TestResult};
+
+    use super::*;
+
+    /// Crossings closer than this (in time) may come in either order, the
+    /// rounding of `f32` deciding
+    const TIE: f64 = 1e-4;
+
+    /// A ray from anywhere in the world within 32768 blocks, in any direction
+    #[derive(Debug, Clone)]
+    struct Ray {
+        origin: [f32; 3],
+        ray: [f32; 3],
+        limit: f32,
+    }
+
+    impl Arbitrary for Ray {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let fraction = |g: &mut Gen| u16::arbitrary(g) as f32 / 65536.0;
+            Self {
+                origin: [
+                    i16::arbitrary(g) as f32 + fraction(g),
+                    u8::arbitrary(g) as f32 + fraction(g),
+                    i16::arbitrary(g) as f32 + fraction(g),
+                ],
+                // some rays are along an axis or in a plane
+                ray: [(); 3].map(|_| match u8::arbitrary(g) % 4 {
+                    0 => 0.0,
+                    _ => i16::arbitrary(g) as f32 / 32768.0,
+                }),
+                limit: (u8::arbitrary(g) % 16) as f32 + fraction(g),
+            }
+        }
+    }
+
+    /// The integer planes crossed by the ray until `limit`, in order, with
+    /// the face entered, computed in `f64`
+    fn crossings(Ray { origin, ray, .. }: &Ray, limit: f64) -> Vec<(f64, Direction)> {
+        let faces = [
+            (Direction::West, Direction::East),
+            (Direction::Down, Direction::Up),
+            (Direction::North, Direction::South),
+        ];
+        let mut crossings = Vec::new();
+        for axis in 0..3 {
+            let [o, d] = [origin[axis], ray[axis]].map(f64::from);
+            let (first, step, face) = match d.partial_cmp(&0.0) {
+                Some(Ordering::Greater) => (o.floor() + 1.0, 1.0, faces[axis].0),
+                Some(Ordering::Less) => (o.floor(), -1.0, faces[axis].1),
+                _ => continue,
+            };
+            let mut plane: f64 = first;
+            loop {
+                let time = (plane - o) / d;
+                if time > limit {
+                    break;
+                }
+                crossings.push((time, face));
+                plane += step;
+            }
+        }
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+        crossings
+    }
+
+    /// The voxels met from the origin through the faces
+    fn walk(origin: [f32; 3], faces: impl IntoIterator<Item = Direction>) -> Vec<[i32; 3]> {
+        let mut voxel = origin.map(|v| v.floor() as i32);
+        faces
+            .into_iter()
+            .map(|face| {
+                let [dx, dy, dz]: [i32; 3] = face.oposit().into();
+                voxel = [voxel[0] + dx, voxel[1] + dy, voxel[2] + dz];
+                voxel
+            })
+            .collect()
+    }
+
+    fn travel(ray: &Ray) -> Vec<Option<(BlockCoords, Direction)>> {
+        RayTravel::new(ray.origin, ray.ray, ray.limit).collect()
+    }
+
+    /// Whether a crossing is too close to the limit to tell if it is reached
+    fn near_limit(ray: &Ray) -> bool {
+        let limit = ray.limit as f64;
+        crossings(ray, limit + TIE)
+            .iter()
+            .any(|&(time, _)| (time - limit).abs() < TIE)
+    }
+
+    #[test]
+    fn test_travel_matches_crossings() {
+        fn property(ray: Ray) -> TestResult {
+            let expected = crossings(&ray, ray.limit as f64);
+            if near_limit(&ray) || expected.windows(2).any(|w| w[1].0 - w[0].0 < TIE) {
+                return TestResult::discard();
+            }
+            let faces: Vec<Direction> = expected.iter().map(|&(_, face)| face).collect();
+            let expected: Vec<_> = walk(ray.origin, faces.iter().copied())
+                .into_iter()
+                .zip(faces)
+                .map(|(voxel, face)| Some((BlockCoords::try_from(voxel).ok()?, face)))
+                .collect();
+            TestResult::from_bool(travel(&ray) == expected)
         }
+        QuickCheck::new()
+            .tests(2000)
+            .quickcheck(property as fn(Ray) -> TestResult);
+    }
+
+    #[test]
+    fn test_travel_steps_to_neighbours() {
+        // whatever the order of nearly simultaneous crossings, no voxel is
+        // skipped nor visited twice
+        fn property(ray: Ray) -> TestResult {
+            let visited = travel(&ray);
+            if near_limit(&ray) || visited.contains(&None) {
+                return TestResult::discard();
+            }
+            let mut expected = [0; 6];
+            for (_, face) in crossings(&ray, ray.limit as f64) {
+                expected[face as usize] += 1;
+            }
+            let mut travelled = [0; 6];
+            let mut previous = ray.origin.map(|v| v.floor() as i32);
+            for (coords, face) in visited.into_iter().flatten() {
+                travelled[face as usize] += 1;
+                let [dx, dy, dz]: [i32; 3] = face.oposit().into();
+                let next = [previous[0] + dx, previous[1] + dy, previous[2] + dz];
+                if <[i32; 3]>::from(coords) != next {
+                    return TestResult::failed();
+                }
+                previous = next;
+            }
+            TestResult::from_bool(travelled == expected)
+        }
+        QuickCheck::new()
+            .tests(2000)
+            .quickcheck(property as fn(Ray) -> TestResult);
+    }
+
+    #[test]
+    fn test_travel_near_a_corner() {
+        // the x and y planes are crossed 0.00001 apart, less than the
+        // epsilon the voxels used to be found with
+        let ray = [1.0, 1.00002, 0.0];
+        let visited: Vec<[i32; 3]> = RayTravel::new([0.5, 0.5, 0.5], ray, 1.2)
+            .flatten()
+            .map(|(coords, _)| coords.into())
+            .collect();
+        assert_eq!(visited, [[0, 1, 0], [1, 1, 0]]);
+        // from a boundary, toward positive coordinates
+        let visited: Vec<_> = RayTravel::new([2.0, 3.5, 0.5], [1.0, 0.0, 0.0], 1.0)
+            .flatten()
+            .collect();
+        assert_eq!(
+            visited,
+            [(BlockCoords::try_from([3, 3, 0]).unwrap(), Direction::West)]
+        );
     }
 }
@@ -3,11 +3,11 @@ use std::{
     ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
 };
 
-use crate::{Affine, AffineTrait, MatrixTrait, Transmuter, VectorCrossTrait, VectorTrait};
+use crate::{Affine, AffineTrait, Float, MatrixTrait, Transmuter, VectorCrossTrait, VectorTrait};
 
 impl<T, const M: usize, const N: usize> MatrixTrait<T, M, N> for [[T; M]; N] {
     fn matrix_map<U, F: FnMut(T) -> U>(self, mut f: F) -> [[U; M]; N] {
-        self.map(|col| col.map(|elem| f(elem)))
+        self.map(|col| col.map(&mut f))
     }
 
     fn matrix_map_index<U, F: FnMut(T, usize, usize) -> U>(self, mut f: F) -> [[U; M]; N] {
@@ -46,6 +46,17 @@ impl<T, const M: usize, const N: usize> MatrixTrait<T, M, N> for [[T; M]; N] {
         })
     }
 
+    fn matrix_lerp(self, rhs: [[T; M]; N], t: T) -> [[T; M]; N]
+    where
+        T: Add<T, Output = T>,
+        T: Sub<T, Output = T>,
+        T: Mul<T, Output = T>,
+        T: Copy,
+    {
+        let mut rhs = rhs.into_iter();
+        self.map(|lhs| lhs.vector_lerp(rhs.next().unwrap(), t))
+    }
+
     fn matrix_mul<const O: usize>(self, rhs: [[T; N]; O]) -> [[T; M]; O]
     where
         T: Mul<T, Output = T>,
@@ -115,6 +126,55 @@ impl<T> Affine<T, 4> {
             [zero, zero, zero, one],
         ]
     }
+
+    /// Creates a matrix that performs a scaling, by a different factor on
+    /// each axis.
+    /// ```
+    /// # use mat::{Affine, AffineTrait};
+    /// let m = Affine::scale_xyz([2.0, 1.0, 0.5]);
+    /// assert_eq!(m.transform_point([1.0, 1.0, 1.0]), [2.0, 1.0, 0.5]);
+    /// ```
+    pub fn scale_xyz([x, y, z]: [T; 3]) -> [[T; 4]; 4]
+    where
+        T: Sum,
+        T: Product,
+        T: Copy,
+    {
+        let zero = std::iter::empty().sum();
+        let one = std::iter::empty().product();
+        [
+            [x, zero, zero, zero],
+            [zero, y, zero, zero],
+            [zero, zero, z, zero],
+            [zero, zero, zero, one],
+        ]
+    }
+
+    /// Creates a matrix that performs a shear: each axis is shifted by the
+    /// two others, `[[xy, xz], [yx, yz], [zx, zy]]` (`xy` being how much x
+    /// moves per unit of y).
+    /// ```
+    /// # use mat::{Affine, AffineTrait};
+    /// // leaning toward x while going up
+    /// let m = Affine::shear([[0.5, 0.0], [0.0, 0.0], [0.0, 0.0]]);
+    /// assert_eq!(m.transform_point([0.0, 2.0, 0.0]), [1.0, 2.0, 0.0]);
+    /// assert_eq!(m.transform_point([3.0, 0.0, 1.0]), [3.0, 0.0, 1.0]);
+    /// ```
+    pub fn shear([[xy, xz], [yx, yz], [zx, zy]]: [[T; 2]; 3]) -> [[T; 4]; 4]
+    where
+        T: Sum,
+        T: Product,
+        T: Copy,
+    {
+        let zero = std::iter::empty().sum();
+        let one = std::iter::empty().product();
+        [
+            [one, yx, zx, zero],
+            [xy, one, zy, zero],
+            [xz, yz, one, zero],
+            [zero, zero, zero, one],
+        ]
+    }
 }
 
 impl Affine<f32, 4> {
@@ -207,6 +267,12 @@ impl AffineTrait<f32, 3, 4> for [[f32; 4]; 4] {
     fn affine_scale(self, scalar: f32) -> [[f32; 4]; 4] {
         self.matrix_mul(Affine::<f32, 4>::scale(scalar))
     }
+    fn affine_scale_xyz(self, vector: [f32; 3]) -> [[f32; 4]; 4] {
+        self.matrix_mul(Affine::<f32, 4>::scale_xyz(vector))
+    }
+    fn affine_shear(self, shear: [[f32; 2]; 3]) -> [[f32; 4]; 4] {
+        self.matrix_mul(Affine::<f32, 4>::shear(shear))
+    }
     fn affine_x_rotate(self, radian: f32) -> [[f32; 4]; 4] {
         self.matrix_mul(Affine::<f32, 4>::x_rotate(radian))
     }
@@ -216,6 +282,17 @@ impl AffineTrait<f32, 3, 4> for [[f32; 4]; 4] {
     fn affine_z_rotate(self, radian: f32) -> [[f32; 4]; 4] {
         self.matrix_mul(Affine::<f32, 4>::z_rotate(radian))
     }
+    fn transform_point(self, [x, y, z]: [f32; 3]) -> [f32; 3] {
+        let [[x, y, z, w]] = self.matrix_mul([[x, y, z, 1.0]]);
+        [x / w, y / w, z / w]
+    }
+    fn transform_vector(self, [x, y, z]: [f32; 3]) -> [f32; 3] {
+        let [[x, y, z, _]] = self.matrix_mul([[x, y, z, 0.0]]);
+        [x, y, z]
+    }
+    fn matrix_normal(self) -> [[f32; 3]; 3] {
+        normal(self)
+    }
 }
 
 impl AffineTrait<f64, 3, 4> for [[f64; 4]; 4] {
@@ -225,6 +302,12 @@ impl AffineTrait<f64, 3, 4> for [[f64; 4]; 4] {
     fn affine_scale(self, scalar: f64) -> [[f64; 4]; 4] {
         self.matrix_mul(Affine::<f64, 4>::scale(scalar))
     }
+    fn affine_scale_xyz(self, vector: [f64; 3]) -> [[f64; 4]; 4] {
+        self.matrix_mul(Affine::<f64, 4>::scale_xyz(vector))
+    }
+    fn affine_shear(self, shear: [[f64; 2]; 3]) -> [[f64; 4]; 4] {
+        self.matrix_mul(Affine::<f64, 4>::shear(shear))
+    }
     fn affine_x_rotate(self, radian: f64) -> [[f64; 4]; 4] {
         self.matrix_mul(Affine::<f64, 4>::x_rotate(radian))
     }
@@ -234,6 +317,28 @@ impl AffineTrait<f64, 3, 4> for [[f64; 4]; 4] {
     fn affine_z_rotate(self, radian: f64) -> [[f64; 4]; 4] {
         self.matrix_mul(Affine::<f64, 4>::z_rotate(radian))
     }
+    fn transform_point(self, [x, y, z]: [f64; 3]) -> [f64; 3] {
+        let [[x, y, z, w]] = self.matrix_mul([[x, y, z, 1.0]]);
+        [x / w, y / w, z / w]
+    }
+    fn transform_vector(self, [x, y, z]: [f64; 3]) -> [f64; 3] {
+        let [[x, y, z, _]] = self.matrix_mul([[x, y, z, 0.0]]);
+        [x, y, z]
+    }
+    fn matrix_normal(self) -> [[f64; 3]; 3] {
+        normal(self)
+    }
+}
+
+/// The inverse transpose of the upper 3x3 of an affine matrix
+///
+/// The inverse transpose is the cofactor matrix divided by the determinant, and
+/// the cofactors of the columns `a`, `b`, `c` are `b × c`, `c × a` and `a × b`.
+fn normal<T: Float>([a, b, c, _]: [[T; 4]; 4]) -> [[T; 3]; 3] {
+    let [a, b, c] = [a, b, c].map(|[x, y, z, _]| [x, y, z]);
+    let cofactors = [b.vector_cross(c), c.vector_cross(a), a.vector_cross(b)];
+    let det = a.vector_dot(cofactors[0]);
+    cofactors.matrix_map(|value| value / det)
 }
 
 impl Affine<f32, 3> {
@@ -321,7 +426,7 @@ impl<T, const N: usize> VectorTrait<T, N> for [T; N] {
     where
         T: AddAssign<T>,
     {
-        for (dst, src) in self.iter_mut().zip(rhs.into_iter()) {
+        for (dst, src) in self.iter_mut().zip(rhs) {
             *dst += src;
         }
     }
@@ -338,20 +443,28 @@ impl<T, const N: usize> VectorTrait<T, N> for [T; N] {
     where
         T: SubAssign<T>,
     {
-        for (dst, src) in self.iter_mut().zip(rhs.into_iter()) {
+        for (dst, src) in self.iter_mut().zip(rhs) {
             *dst -= src;
         }
     }
 
+    fn vector_lerp(self, rhs: [T; N], t: T) -> [T; N]
+    where
+        T: Add<T, Output = T>,
+        T: Sub<T, Output = T>,
+        T: Mul<T, Output = T>,
+        T: Copy,
+    {
+        let mut rhs = rhs.into_iter();
+        self.map(|lhs| lhs + (rhs.next().unwrap() - lhs) * t)
+    }
+
     fn vector_dot(self, rhs: [T; N]) -> T
     where
         T: Mul<T, Output = T>,
         T: Sum,
     {
-        self.into_iter()
-            .zip(rhs.into_iter())
-            .map(|(lhs, rhs)| lhs * rhs)
-            .sum()
+        self.into_iter().zip(rhs).map(|(lhs, rhs)| lhs * rhs).sum()
     }
 
     fn vector_map_index<U>(self, mut f: impl FnMut(T, usize) -> U) -> [U; N] {
@@ -363,7 +476,7 @@ impl<T, const N: usize> VectorTrait<T, N> for [T; N] {
     where
         T: Copy,
     {
-        *self.get(0).unwrap()
+        *self.first().unwrap()
     }
     fn vector_y(self) -> T
     where
@@ -406,7 +519,7 @@ impl<T> VectorCrossTrait<T, 3> for [T; 3] {
     {
         [
             self[1] * rhs[2] - self[2] * rhs[1],
-            self[2] * rhs[0] - self[0] * rhs[0],
+            self[2] * rhs[0] - self[0] * rhs[2],
             self[0] * rhs[1] - self[1] * rhs[0],
         ]
     }
@@ -421,13 +534,49 @@ impl<T> VectorCrossTrait<T, 4> for [T; 4] {
     {
         [
             self[1] * rhs[2] - self[2] * rhs[1],
-            self[2] * rhs[0] - self[0] * rhs[0],
+            self[2] * rhs[0] - self[0] * rhs[2],
             self[0] * rhs[1] - self[1] * rhs[0],
             self[3],
         ]
     }
 }
 
+impl Float for f32 {
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+    fn sqrt(self) -> Self {
+        self.sqrt()
+    }
+    fn sin(self) -> Self {
+        self.sin()
+    }
+    fn cos(self) -> Self {
+        self.cos()
+    }
+    fn acos(self) -> Self {
+        self.acos()
+    }
+}
+
+impl Float for f64 {
+    fn from_f32(value: f32) -> Self {
+        value as f64
+    }
+    fn sqrt(self) -> Self {
+        self.sqrt()
+    }
+    fn sin(self) -> Self {
+        self.sin()
+    }
+    fn cos(self) -> Self {
+        self.cos()
+    }
+    fn acos(self) -> Self {
+        self.acos()
+    }
+}
+
 // 1t 0a
 impl<T> Transmuter for ([T; 0],) {
     type Target = [(T,); 0];
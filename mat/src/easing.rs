@@ -0,0 +1,82 @@
+//! Curves turning a linear progress (from 0 to 1) into a smoother one, to
+//! give to the interpolations (like [`VectorTrait::vector_lerp`]).
+//!
+//! Every curve goes from 0 to 1, the progress being clamped between them.
+//! ```
+//! use mat::{easing, VectorTrait};
+//!
+//! let (from, to) = ([0.0, 0.0], [10.0, 20.0]);
+//! // a quarter of the way in time, less than a quarter of the way in space
+//! let [x, y] = from.vector_lerp(to, easing::smoothstep(0.25));
+//! assert!(x < 2.5 && y < 5.0);
+//! ```
+//!
+//! [`VectorTrait::vector_lerp`]: crate::VectorTrait::vector_lerp
+
+use crate::Float;
+
+fn clamp<T: Float>(t: T) -> T {
+    let (zero, one) = (T::from_f32(0.0), T::from_f32(1.0));
+    if t < zero {
+        zero
+    } else if t > one {
+        one
+    } else {
+        t
+    }
+}
+
+/// Starts and ends slowly (the derivative is zero at both ends).
+///
+/// ```
+/// # use mat::easing::smoothstep;
+/// assert_eq!(smoothstep(0.5), 0.5);
+/// assert_eq!((smoothstep(-1.0), smoothstep(2.0)), (0.0, 1.0));
+/// assert!(smoothstep(0.1) < 0.1 && smoothstep(0.9) > 0.9);
+/// ```
+pub fn smoothstep<T: Float>(t: T) -> T {
+    let t = clamp(t);
+    t * t * (T::from_f32(3.0) - T::from_f32(2.0) * t)
+}
+
+/// Starts slowly, then speeds up.
+///
+/// ```
+/// # use mat::easing::ease_in;
+/// assert_eq!(ease_in(0.5), 0.25);
+/// assert_eq!(ease_in(1.0), 1.0);
+/// ```
+pub fn ease_in<T: Float>(t: T) -> T {
+    let t = clamp(t);
+    t * t
+}
+
+/// Starts fast, then slows down until it stops.
+///
+/// ```
+/// # use mat::easing::ease_out;
+/// assert_eq!(ease_out(0.5), 0.75);
+/// assert_eq!(ease_out(0.0), 0.0);
+/// ```
+pub fn ease_out<T: Float>(t: T) -> T {
+    let t = T::from_f32(1.0) - clamp(t);
+    T::from_f32(1.0) - t * t
+}
+
+/// Eases in for the first half, and out for the second one.
+///
+/// ```
+/// # use mat::easing::ease_in_out;
+/// assert_eq!(ease_in_out(0.25), 0.125);
+/// assert_eq!(ease_in_out(0.5), 0.5);
+/// assert_eq!(ease_in_out(0.75), 0.875);
+/// ```
+pub fn ease_in_out<T: Float>(t: T) -> T {
+    let (t, half) = (clamp(t), T::from_f32(0.5));
+    if t < half {
+        T::from_f32(2.0) * t * t
+    } else {
+        let t = T::from_f32(1.0) - t;
+        T::from_f32(1.0) - T::from_f32(2.0) * t * t
+    }
+}
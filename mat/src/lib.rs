@@ -3,7 +3,7 @@
 //! This implementation does not aim to be fast or optimized but can perfectly
 //! be used to compute uniforms for graphic applications.
 //! ```
-//! use arrayscalar::MatrixTrait;
+//! use mat::MatrixTrait;
 //!
 //! let m1 = [
 //!     [1, 2, 3],
@@ -27,12 +27,52 @@
 //! ```
 //! As matrices are considered to be column major (array of column), it is possible
 //! to write and read them in line major by using transposition to perform conversion.
+//!
+//! With the `serde` feature, the types of this crate can be serialized. The matrices being
+//! plain arrays, they serialize as a sequence of columns, each a sequence of its elements,
+//! and the wrappers ([`Matrix`] and [`Vector`], or [`Mat4`] and [`Vec3`] with the `bytemuck`
+//! feature) serialize as the arrays they wrap. As serde only handles arrays of up to 32
+//! elements, so do [`Matrix`] and [`Vector`]. A [`Quaternion`] serializes as its `w`, `x`,
+//! `y` and `z` fields, in this order. This layout will not change.
+//! ```
+//! # #[cfg(feature = "serde")] {
+//! use mat::{Affine, AffineTrait, Matrix, Quaternion, Vector};
+//!
+//! let m = Affine::identity().affine_translate([1.0, 2.0, 3.0]);
+//! let json = serde_json::to_string(&m).unwrap();
+//! // the translation is the last column
+//! assert!(json.ends_with("[1.0,2.0,3.0,1.0]]"));
+//! // the same as the array wrapped
+//! assert_eq!(serde_json::to_string(&Matrix(m)).unwrap(), json);
+//! assert_eq!(serde_json::from_str::<Matrix<f32, 4, 4>>(&json).unwrap(), Matrix(m));
+//!
+//! let v = Vector([1, 2, 3]);
+//! assert_eq!(serde_json::to_string(&v).unwrap(), "[1,2,3]");
+//!
+//! let q = Quaternion::identity();
+//! let json = serde_json::to_string(&q).unwrap();
+//! assert_eq!(json, r#"{"w":1.0,"x":0.0,"y":0.0,"z":0.0}"#);
+//! assert_eq!(serde_json::from_str::<Quaternion>(&json).unwrap(), q);
+//! # }
+//! ```
 
 mod behavior;
+pub mod easing;
+#[cfg(feature = "bytemuck")]
+pub mod pod;
+mod projection;
+mod quaternion;
+mod wrapper;
+
+#[cfg(feature = "bytemuck")]
+pub use pod::{Mat4, Vec3};
+pub use projection::Projection;
+pub use quaternion::Quaternion;
+pub use wrapper::{Matrix, Vector};
 
 use std::{
     iter::{Product, Sum},
-    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+    ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign},
 };
 
 /// Extends array with matrix operations.
@@ -43,7 +83,7 @@ pub trait MatrixTrait<T, const M: usize, const N: usize> {
     /// Transforms all element of the matrix with the given function.
     ///
     /// ```
-    /// # use arrayscalar::MatrixTrait;
+    /// # use mat::MatrixTrait;
     /// let m1 = [
     ///     [0, 1, 2],
     ///     [3, 4, 5],
@@ -60,7 +100,7 @@ pub trait MatrixTrait<T, const M: usize, const N: usize> {
     /// Transforms all element of the matrix with the given function and current index.
     ///
     /// ```
-    /// # use arrayscalar::MatrixTrait;
+    /// # use mat::MatrixTrait;
     /// let m1 = [
     ///     [10, 13],
     ///     [10, 11],
@@ -77,7 +117,7 @@ pub trait MatrixTrait<T, const M: usize, const N: usize> {
     /// Returns the transposed matrix.
     ///
     /// ```
-    /// # use arrayscalar::MatrixTrait;
+    /// # use mat::MatrixTrait;
     /// let m1 = [
     ///     [1, 2],
     ///     [3, 4],
@@ -94,7 +134,7 @@ pub trait MatrixTrait<T, const M: usize, const N: usize> {
     /// Returns the scaled matrix by a given factor.
     ///
     /// ```
-    /// # use arrayscalar::MatrixTrait;
+    /// # use mat::MatrixTrait;
     /// let m1 = [
     ///     [1, 2, 3],
     ///     [4, 5, 6],
@@ -113,7 +153,7 @@ pub trait MatrixTrait<T, const M: usize, const N: usize> {
     /// Returns the addition of the two matrices.
     ///
     /// ```
-    /// # use arrayscalar::MatrixTrait;
+    /// # use mat::MatrixTrait;
     /// let m1 = [
     ///     [1, 2, 3],
     ///     [4, 5, 6],
@@ -135,7 +175,7 @@ pub trait MatrixTrait<T, const M: usize, const N: usize> {
     /// Returns the subtraction of the two matrices.
     ///
     /// ```
-    /// # use arrayscalar::MatrixTrait;
+    /// # use mat::MatrixTrait;
     /// let m1 = [
     ///     [61, 52, 43],
     ///     [34, 25, 16],
@@ -154,10 +194,35 @@ pub trait MatrixTrait<T, const M: usize, const N: usize> {
     fn matrix_sub(self, rhs: [[T; M]; N]) -> [[T; M]; N]
     where
         T: Sub<T, Output = T>;
+    /// Returns the matrix between the two, element by element, `t` going
+    /// from 0 (the current matrix) to 1 (the other one).
+    ///
+    /// ```
+    /// # use mat::MatrixTrait;
+    /// let m1 = [
+    ///     [0.0, 2.0],
+    ///     [4.0, 6.0],
+    /// ];
+    /// let m2 = [
+    ///     [1.0, 2.0],
+    ///     [0.0, 10.0],
+    /// ];
+    /// assert_eq!(m1.matrix_lerp(m2, 0.25), [
+    ///     [0.25, 2.0],
+    ///     [ 3.0, 7.0],
+    /// ]);
+    /// ```
+    #[must_use]
+    fn matrix_lerp(self, rhs: [[T; M]; N], t: T) -> [[T; M]; N]
+    where
+        T: Add<T, Output = T>,
+        T: Sub<T, Output = T>,
+        T: Mul<T, Output = T>,
+        T: Copy;
     /// Returns the multiplication of the two matrices.
     ///
     /// ```
-    /// # use arrayscalar::MatrixTrait;
+    /// # use mat::MatrixTrait;
     /// let m1 = [
     ///     [1, 2, 3],
     ///     [4, 5, 6],
@@ -185,7 +250,7 @@ pub trait MatrixTrait<T, const M: usize, const N: usize> {
 /// Dummy type used as generic module for affine matrices (4x4 with coords x, y, z and w as homogeneous).
 ///
 /// ```
-/// use arrayscalar::{Affine, AffineTrait};
+/// use mat::{Affine, AffineTrait};
 ///
 /// let m: [[f32; 4]; 4] = Affine::identity()
 ///     .affine_translate([1.0, 10.0, 5.0])
@@ -211,6 +276,32 @@ where
     /// Returns the multiplication of the current matrix and one that performs a scaling.
     #[must_use]
     fn affine_scale(self, scalar: T) -> [[T; O]; O];
+    /// Returns the multiplication of the current matrix and one that performs a scaling, by a
+    /// factor per axis.
+    ///
+    /// ```
+    /// # use mat::{Affine, AffineTrait};
+    /// // a box twice as wide as tall, from its corner
+    /// let m = Affine::identity()
+    ///     .affine_translate([1.0, 0.0, 0.0])
+    ///     .affine_scale_xyz([2.0, 1.0, 1.0]);
+    /// assert_eq!(m.transform_point([1.0, 1.0, 1.0]), [3.0, 1.0, 1.0]);
+    /// ```
+    #[must_use]
+    fn affine_scale_xyz(self, vector: [T; N]) -> [[T; O]; O];
+    /// Returns the multiplication of the current matrix and one that performs a shear (see
+    /// [`Affine::shear`]).
+    ///
+    /// ```
+    /// # use mat::{Affine, AffineTrait};
+    /// let m = Affine::<f64>::identity()
+    ///     .affine_shear([[0.0, 0.0], [0.0, 0.0], [1.0, 0.0]])
+    ///     .affine_scale_xyz([2.0, 1.0, 1.0]);
+    /// // the scaling comes first, the shear moves z by the scaled x
+    /// assert_eq!(m.transform_vector([1.0, 0.0, 0.0]), [2.0, 0.0, 2.0]);
+    /// ```
+    #[must_use]
+    fn affine_shear(self, shear: [[T; 2]; N]) -> [[T; O]; O];
     /// Returns the multiplication of the current matrix and one that performs a rotation on the x axis.
     #[must_use]
     fn affine_x_rotate(self, radian: T) -> [[T; O]; O];
@@ -220,6 +311,63 @@ where
     /// Returns the multiplication of the current matrix and one that performs a rotation on the z axis.
     #[must_use]
     fn affine_z_rotate(self, radian: T) -> [[T; O]; O];
+    /// Returns the point transformed by the matrix (with w = 1), divided by
+    /// the w it ends up with (the perspective divide).
+    ///
+    /// ```
+    /// # use mat::{Affine, AffineTrait, Projection};
+    /// let m = Affine::identity()
+    ///     .affine_translate([1.0, 2.0, 3.0])
+    ///     .affine_scale(2.0);
+    /// assert_eq!(m.transform_point([1.0, 1.0, 1.0]), [3.0, 4.0, 5.0]);
+    ///
+    /// // a projection gives the position on the screen
+    /// let projection = Projection::new(std::f32::consts::FRAC_PI_2, 1.0);
+    /// let [x, y, _] = projection.matrix().transform_point([0.0, 5.0, 5.0]);
+    /// assert!(x.abs() < 1e-6 && (y - 1.0).abs() < 1e-6);
+    /// ```
+    #[must_use]
+    fn transform_point(self, point: [T; N]) -> [T; N];
+    /// Returns the direction transformed by the matrix (with w = 0), which
+    /// translations leave as it is.
+    ///
+    /// ```
+    /// # use mat::{Affine, AffineTrait};
+    /// let m = Affine::identity()
+    ///     .affine_translate([1.0, 2.0, 3.0])
+    ///     .affine_scale(2.0);
+    /// assert_eq!(m.transform_vector([1.0, 0.0, -1.0]), [2.0, 0.0, -2.0]);
+    /// ```
+    #[must_use]
+    fn transform_vector(self, vector: [T; N]) -> [T; N];
+    /// Returns the normal matrix: the inverse transpose of the upper-left 3x3, which transforms
+    /// the normals of a surface so they stay perpendicular to it (unlike
+    /// [`transform_vector`](AffineTrait::transform_vector) with a non uniform scaling). It is
+    /// not defined when the matrix flattens space (a scaling by 0).
+    ///
+    /// ```
+    /// # use mat::{Affine, AffineTrait, MatrixTrait};
+    /// let m = Affine::identity()
+    ///     .affine_translate([5.0, 0.0, 0.0])
+    ///     .affine_scale_xyz([2.0, 1.0, 1.0]);
+    /// // a slope going up along x, flattened by the scaling
+    /// let normal = [-1.0, 1.0, 0.0];
+    /// let [x, y, z] = m.matrix_normal().matrix_mul([normal])[0];
+    /// assert_eq!([x, y, z], [-0.5, 1.0, 0.0]);
+    /// // still perpendicular to the transformed slope
+    /// let slope = m.transform_vector([1.0, 1.0, 0.0]);
+    /// assert_eq!(slope[0] * x + slope[1] * y + slope[2] * z, 0.0);
+    ///
+    /// // a rotation is its own normal matrix
+    /// let r = Affine::<f64>::identity().affine_y_rotate(0.5);
+    /// for (column, normal) in r.into_iter().zip(r.matrix_normal()) {
+    ///     for (a, b) in column.into_iter().zip(normal) {
+    ///         assert!((a - b).abs() < 1e-12);
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    fn matrix_normal(self) -> [[T; N]; N];
 }
 
 /// Extends array with vector operations.
@@ -266,6 +414,23 @@ pub trait VectorTrait<T, const N: usize> {
     where
         T: SubAssign<T>;
 
+    /// Returns the vector between the two, `t` going from 0 (the current
+    /// vector) to 1 (the other one).
+    ///
+    /// ```
+    /// # use mat::VectorTrait;
+    /// assert_eq!([0.0, 10.0].vector_lerp([4.0, 20.0], 0.5), [2.0, 15.0]);
+    /// // beyond 1, it keeps going
+    /// assert_eq!([0.0, 10.0].vector_lerp([4.0, 20.0], 2.0), [8.0, 30.0]);
+    /// ```
+    #[must_use]
+    fn vector_lerp(self, rhs: [T; N], t: T) -> [T; N]
+    where
+        T: Add<T, Output = T>,
+        T: Sub<T, Output = T>,
+        T: Mul<T, Output = T>,
+        T: Copy;
+
     /// Returns the dot product of the two vector.
     #[must_use]
     fn vector_dot(self, rhs: [T; N]) -> T
@@ -312,6 +477,13 @@ pub trait VectorTrait<T, const N: usize> {
 
 /// Extends 3 dimensional vector with cross product operation (including 4 dimension because of homogeneous).
 pub trait VectorCrossTrait<T, const N: usize>: VectorTrait<T, N> {
+    /// Returns the vector orthogonal to both vectors (right handed).
+    ///
+    /// ```
+    /// # use mat::VectorCrossTrait;
+    /// assert_eq!([1, 0, 0].vector_cross([0, 1, 0]), [0, 0, 1]);
+    /// assert_eq!([0, 0, 1].vector_cross([1, 0, 0]), [0, 1, 0]);
+    /// ```
     #[must_use]
     fn vector_cross(self, rhs: [T; N]) -> [T; N]
     where
@@ -320,6 +492,27 @@ pub trait VectorCrossTrait<T, const N: usize>: VectorTrait<T, N> {
         T: Copy;
 }
 
+/// The floating point numbers (`f32` and `f64`), for the operations needing
+/// square roots and trigonometry.
+pub trait Float:
+    Copy
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + Sum
+    + Product
+{
+    /// Converts a constant (like 0.5) to this precision.
+    fn from_f32(value: f32) -> Self;
+    fn sqrt(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn acos(self) -> Self;
+}
+
 pub trait Transmuter {
     type Target;
 
@@ -0,0 +1,97 @@
+//! Matrices and vectors that can be handed to the GPU as they are (with the
+//! `bytemuck` feature).
+//!
+//! The wrappers are transparent, so a matrix keeps its column major layout and
+//! its bytes go straight to a uniform buffer.
+//! ```
+//! use mat::{Affine, AffineTrait, Mat4};
+//!
+//! let model = Mat4(Affine::identity().affine_translate([1.0f32, 2.0, 3.0]));
+//! let bytes: &[u8] = bytemuck::bytes_of(&model);
+//! assert_eq!(bytes.len(), 64);
+//!
+//! // the translation is in the last column
+//! assert_eq!(model.as_flat()[12..15], [1.0, 2.0, 3.0]);
+//! assert_eq!(Mat4::from_flat(model.as_flat()), model);
+//! ```
+
+use bytemuck::{Pod, Zeroable};
+
+/// A 4x4 matrix (column major, like the arrays it wraps) with a plain memory
+/// layout.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+#[repr(transparent)]
+pub struct Mat4<T>(pub [[T; 4]; 4]);
+
+/// A 3D vector with a plain memory layout.
+///
+/// Beware that a `vec3` of a std140 uniform block is aligned on 16 bytes, this
+/// type only takes 12 (for `f32`).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+#[repr(transparent)]
+pub struct Vec3<T>(pub [T; 3]);
+
+// SAFETY: a transparent wrapper of an array of `Pod` values is `Pod` too,
+// without any padding
+unsafe impl<T: Zeroable> Zeroable for Mat4<T> {}
+unsafe impl<T: Pod> Pod for Mat4<T> {}
+unsafe impl<T: Zeroable> Zeroable for Vec3<T> {}
+unsafe impl<T: Pod> Pod for Vec3<T> {}
+
+impl<T: Pod> Mat4<T> {
+    /// Returns the elements, column after column.
+    pub fn as_flat(self) -> [T; 16] {
+        bytemuck::cast(self.0)
+    }
+
+    /// Creates a matrix from its elements, column after column.
+    pub fn from_flat(flat: [T; 16]) -> Self {
+        Self(bytemuck::cast(flat))
+    }
+}
+
+impl<T> Vec3<T> {
+    /// Returns the elements.
+    pub fn as_flat(self) -> [T; 3] {
+        self.0
+    }
+
+    /// Creates a vector from its elements.
+    pub fn from_flat(flat: [T; 3]) -> Self {
+        Self(flat)
+    }
+}
+
+impl<T> From<[[T; 4]; 4]> for Mat4<T> {
+    fn from(matrix: [[T; 4]; 4]) -> Self {
+        Self(matrix)
+    }
+}
+
+impl<T> From<Mat4<T>> for [[T; 4]; 4] {
+    fn from(matrix: Mat4<T>) -> Self {
+        matrix.0
+    }
+}
+
+impl<T> From<[T; 3]> for Vec3<T> {
+    fn from(vector: [T; 3]) -> Self {
+        Self(vector)
+    }
+}
+
+impl<T> From<Vec3<T>> for [T; 3] {
+    fn from(vector: Vec3<T>) -> Self {
+        vector.0
+    }
+}
@@ -0,0 +1,100 @@
+use crate::AffineTrait;
+
+/// A perspective projection, from the view space to the clip space of OpenGL.
+///
+/// The view space looks toward the positive z, with the x going right and
+/// the y going up. Once divided by w, the visible points are between -1.0
+/// and 1.0 on every axis, the depth going from -1.0 (near) to 1.0 (far).
+/// ```
+/// # use mat::Projection;
+/// let projection = Projection::new(std::f32::consts::FRAC_PI_2, 2.0);
+///
+/// // the top of the field of view is the top of the screen
+/// let [x, y, _] = projection.project([0.0, 5.0, 5.0]);
+/// assert!(x.abs() < 1e-6 && (y - 1.0).abs() < 1e-6);
+/// // the screen being twice as wide as high, it sees twice as far on x
+/// let [x, _, _] = projection.project([10.0, 0.0, 5.0]);
+/// assert!((x - 1.0).abs() < 1e-6);
+/// // the near and far planes are the ends of the depth
+/// assert!((projection.project([0.0, 0.0, projection.near])[2] + 1.0).abs() < 1e-6);
+/// assert!((projection.project([0.0, 0.0, projection.far])[2] - 1.0).abs() < 1e-4);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Projection {
+    /// Vertical field of view (in radians).
+    pub fov: f32,
+    /// Width of the screen divided by its height.
+    pub aspect: f32,
+    /// Distance to the closest visible points.
+    pub near: f32,
+    /// Distance to the farthest visible points.
+    pub far: f32,
+    /// The depth goes from 1.0 (near) to -1.0 (far) instead.
+    pub reversed_z: bool,
+}
+
+impl Projection {
+    /// Creates a projection seeing from 0.1 to 1024.0 away.
+    pub fn new(fov: f32, aspect: f32) -> Self {
+        Self {
+            fov,
+            aspect,
+            near: 0.1,
+            far: 1024.0,
+            reversed_z: false,
+        }
+    }
+
+    /// Returns the projection with other distances of view.
+    #[must_use]
+    pub fn depth(self, near: f32, far: f32) -> Self {
+        Self { near, far, ..self }
+    }
+
+    /// Returns the projection with the depth reversed, from 1.0 (near) to
+    /// -1.0 (far). The depth tests must be reversed too.
+    ///
+    /// This alone gains no precision. OpenGL maps the depth to the depth
+    /// buffer with `0.5 * z + 0.5`, and this addition rounds away the
+    /// precision the floats have around zero. The precision is only spread
+    /// evenly with a floating point depth buffer and a clip space depth from
+    /// 0.0 to 1.0 (`glClipControl` with `GL_ZERO_TO_ONE`), which this matrix
+    /// does not project to.
+    /// ```
+    /// # use mat::Projection;
+    /// let projection = Projection::new(1.0, 1.0).reversed_z();
+    ///
+    /// assert!((projection.project([0.0, 0.0, projection.near])[2] - 1.0).abs() < 1e-6);
+    /// assert!((projection.project([0.0, 0.0, projection.far])[2] + 1.0).abs() < 1e-4);
+    /// ```
+    #[must_use]
+    pub fn reversed_z(self) -> Self {
+        Self {
+            reversed_z: true,
+            ..self
+        }
+    }
+
+    /// Returns the matrix of the projection (column major).
+    pub fn matrix(&self) -> [[f32; 4]; 4] {
+        let f = 1.0 / (self.fov / 2.0).tan();
+        let (near, far) = if self.reversed_z {
+            (self.far, self.near)
+        } else {
+            (self.near, self.far)
+        };
+        let deno = far - near;
+        [
+            [f / self.aspect, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, (far + near) / deno, 1.0],
+            [0.0, 0.0, -2.0 * far * near / deno, 0.0],
+        ]
+    }
+
+    /// Projects a point of the view space on the screen (divided by w).
+    pub fn project(&self, point: [f32; 3]) -> [f32; 3] {
+        self.matrix().transform_point(point)
+    }
+}
@@ -0,0 +1,243 @@
+use std::ops::Mul;
+
+use crate::{Float, VectorCrossTrait, VectorTrait};
+
+/// A rotation in 3D space, as a unit quaternion.
+///
+/// Unlike angles, two rotations can be smoothly interpolated (see
+/// [`Quaternion::slerp`]), always through the shortest path.
+/// ```
+/// # use mat::Quaternion;
+/// let quarter = Quaternion::from_axis_angle([0.0, 1.0, 0.0], std::f32::consts::FRAC_PI_2);
+/// let [x, y, z] = quarter.rotate([0.0, 0.0, 1.0]);
+///
+/// assert!((x - 1.0).abs() < 1e-6 && y.abs() < 1e-6 && z.abs() < 1e-6);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quaternion<T = f32> {
+    pub w: T,
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: Float> Quaternion<T> {
+    /// The rotation that does nothing.
+    pub fn identity() -> Self {
+        let zero = T::from_f32(0.0);
+        Self {
+            w: T::from_f32(1.0),
+            x: zero,
+            y: zero,
+            z: zero,
+        }
+    }
+
+    /// Creates a rotation around the given axis (counter clockwise when the
+    /// axis points toward the viewer).
+    pub fn from_axis_angle(axis: [T; 3], radian: T) -> Self {
+        let half = radian / T::from_f32(2.0);
+        let [x, y, z] = axis.vector_scale(half.sin() / axis.vector_dot(axis).sqrt());
+        Self {
+            w: half.cos(),
+            x,
+            y,
+            z,
+        }
+    }
+
+    fn vector(self) -> [T; 3] {
+        [self.x, self.y, self.z]
+    }
+
+    fn scale(self, scalar: T) -> Self {
+        Self {
+            w: self.w * scalar,
+            x: self.x * scalar,
+            y: self.y * scalar,
+            z: self.z * scalar,
+        }
+    }
+
+    /// Returns the cosine of half the angle between the two rotations.
+    pub fn dot(self, rhs: Self) -> T {
+        self.w * rhs.w + self.vector().vector_dot(rhs.vector())
+    }
+
+    /// Returns the rotation of the same axis and the opposite angle.
+    #[must_use]
+    pub fn conjugate(self) -> Self {
+        Self {
+            w: self.w,
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+
+    /// Returns the quaternion scaled to a length of one, as rounding errors
+    /// pile up with the multiplications.
+    #[must_use]
+    pub fn normalize(self) -> Self {
+        self.scale(T::from_f32(1.0) / self.dot(self).sqrt())
+    }
+
+    /// Applies the rotation to a vector.
+    pub fn rotate(self, vector: [T; 3]) -> [T; 3] {
+        let u = self.vector();
+        // v + 2w(u × v) + 2u × (u × v)
+        let t = u.vector_cross(vector).vector_scale(T::from_f32(2.0));
+        vector
+            .vector_add(t.vector_scale(self.w))
+            .vector_add(u.vector_cross(t))
+    }
+
+    /// Interpolates between two rotations at a constant angular speed, `t`
+    /// going from 0.0 (self) to 1.0 (the other rotation).
+    /// ```
+    /// # use mat::Quaternion;
+    /// let start = Quaternion::<f32>::identity();
+    /// let end = Quaternion::from_axis_angle([1.0, 0.0, 0.0], 2.0);
+    /// let half = start.slerp(end, 0.5);
+    ///
+    /// assert!((half.dot(Quaternion::from_axis_angle([1.0, 0.0, 0.0], 1.0)) - 1.0).abs() < 1e-6);
+    /// ```
+    #[must_use]
+    pub fn slerp(self, mut rhs: Self, t: T) -> Self {
+        let one = T::from_f32(1.0);
+        let mut cos = self.dot(rhs);
+        // q and -q are the same rotation, the closest one is taken
+        if cos < T::from_f32(0.0) {
+            rhs = rhs.scale(-one);
+            cos = -cos;
+        }
+        // almost the same rotation, a linear interpolation is precise enough
+        let (a, b) = if cos > T::from_f32(0.9995) {
+            (one - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((one - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        let [w, x, y, z] = [
+            self.w * a + rhs.w * b,
+            self.x * a + rhs.x * b,
+            self.y * a + rhs.y * b,
+            self.z * a + rhs.z * b,
+        ];
+        Self { w, x, y, z }.normalize()
+    }
+
+    /// Returns the matrix performing the rotation (column major, like the
+    /// ones of [`Affine`](crate::Affine)).
+    ///
+    /// A rotation of the opposite angle is the one of the matrices of
+    /// [`AffineTrait`](crate::AffineTrait), which turn the other way.
+    /// ```
+    /// # use mat::{Affine, MatrixTrait, Quaternion};
+    /// let rotation = Quaternion::from_axis_angle([0.0, 1.0, 0.0], -0.5).to_affine();
+    /// let difference = rotation.matrix_sub(Affine::<f64, 4>::y_rotate(0.5));
+    ///
+    /// assert!(difference.iter().flatten().all(|v| v.abs() < 1e-12));
+    /// ```
+    pub fn to_affine(self) -> [[T; 4]; 4] {
+        let (zero, one, two) = (T::from_f32(0.0), T::from_f32(1.0), T::from_f32(2.0));
+        let Self { w, x, y, z } = self;
+        [
+            [
+                one - two * (y * y + z * z),
+                two * (x * y + w * z),
+                two * (x * z - w * y),
+                zero,
+            ],
+            [
+                two * (x * y - w * z),
+                one - two * (x * x + z * z),
+                two * (y * z + w * x),
+                zero,
+            ],
+            [
+                two * (x * z + w * y),
+                two * (y * z - w * x),
+                one - two * (x * x + y * y),
+                zero,
+            ],
+            [zero, zero, zero, one],
+        ]
+    }
+
+    /// Returns the rotation performed by a matrix (only its upper 3x3 part
+    /// is read, which must be a rotation without scaling).
+    /// ```
+    /// # use mat::{Affine, AffineTrait, Quaternion};
+    /// let matrix = Affine::<f32, 4>::x_rotate(1.2).affine_z_rotate(-0.3);
+    /// let rotation = Quaternion::from_affine(matrix);
+    ///
+    /// assert!((rotation.dot(Quaternion::from_affine(rotation.to_affine())) - 1.0).abs() < 1e-6);
+    /// let [x, y, z] = rotation.rotate([0.0, 1.0, 0.0]);
+    /// let [mx, my, mz, _] = matrix[1];
+    /// assert!((x - mx).abs() < 1e-6 && (y - my).abs() < 1e-6 && (z - mz).abs() < 1e-6);
+    /// ```
+    pub fn from_affine(matrix: [[T; 4]; 4]) -> Self {
+        let (zero, one, quarter) = (T::from_f32(0.0), T::from_f32(1.0), T::from_f32(0.25));
+        // the element of a row and a column
+        let m = |row: usize, column: usize| matrix[column][row];
+        let trace = m(0, 0) + m(1, 1) + m(2, 2);
+        // the largest component is computed first, the others are divided
+        // by it (a small one would lose precision)
+        let quaternion = if trace > zero {
+            let s = (trace + one).sqrt() * T::from_f32(2.0);
+            Self {
+                w: quarter * s,
+                x: (m(2, 1) - m(1, 2)) / s,
+                y: (m(0, 2) - m(2, 0)) / s,
+                z: (m(1, 0) - m(0, 1)) / s,
+            }
+        } else if m(0, 0) > m(1, 1) && m(0, 0) > m(2, 2) {
+            let s = (one + m(0, 0) - m(1, 1) - m(2, 2)).sqrt() * T::from_f32(2.0);
+            Self {
+                w: (m(2, 1) - m(1, 2)) / s,
+                x: quarter * s,
+                y: (m(0, 1) + m(1, 0)) / s,
+                z: (m(0, 2) + m(2, 0)) / s,
+            }
+        } else if m(1, 1) > m(2, 2) {
+            let s = (one + m(1, 1) - m(0, 0) - m(2, 2)).sqrt() * T::from_f32(2.0);
+            Self {
+                w: (m(0, 2) - m(2, 0)) / s,
+                x: (m(0, 1) + m(1, 0)) / s,
+                y: quarter * s,
+                z: (m(1, 2) + m(2, 1)) / s,
+            }
+        } else {
+            let s = (one + m(2, 2) - m(0, 0) - m(1, 1)).sqrt() * T::from_f32(2.0);
+            Self {
+                w: (m(1, 0) - m(0, 1)) / s,
+                x: (m(0, 2) + m(2, 0)) / s,
+                y: (m(1, 2) + m(2, 1)) / s,
+                z: quarter * s,
+            }
+        };
+        quaternion.normalize()
+    }
+}
+
+impl<T: Float> Mul for Quaternion<T> {
+    type Output = Self;
+
+    /// Composes two rotations, the right one being applied first.
+    fn mul(self, rhs: Self) -> Self {
+        let [x, y, z] = rhs
+            .vector()
+            .vector_scale(self.w)
+            .vector_add(self.vector().vector_scale(rhs.w))
+            .vector_add(self.vector().vector_cross(rhs.vector()));
+        Self {
+            w: self.w * rhs.w - self.vector().vector_dot(rhs.vector()),
+            x,
+            y,
+            z,
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! Matrices and vectors with operators, for the code heavy on maths.
+//!
+//! They wrap the arrays the traits of this crate are implemented for, and convert from and to
+//! them, so both styles mix. A matrix stays column major.
+//! ```
+//! use mat::{Affine, AffineTrait, Matrix, MatrixTrait, Vector};
+//!
+//! let translate: Matrix<f32, 4, 4> = Affine::identity().affine_translate([1.0, 2.0, 3.0]).into();
+//! let scale: Matrix<f32, 4, 4> = Affine::identity().affine_scale(2.0).into();
+//! let point = Vector([1.0, 1.0, 1.0, 1.0]);
+//!
+//! assert_eq!(translate * scale * point, Vector([3.0, 4.0, 5.0, 1.0]));
+//! assert_eq!(translate * scale, translate.0.matrix_mul(scale.0).into());
+//! // the translation is in the last column
+//! assert_eq!(translate[3], [1.0, 2.0, 3.0, 1.0]);
+//! ```
+
+use std::{
+    iter::Sum,
+    ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign},
+};
+
+use crate::{MatrixTrait, VectorTrait};
+
+/// A matrix of `M` lines and `N` columns, stored as an array of columns.
+///
+/// Indexing gives a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        transparent,
+        bound(
+            serialize = "[[T; M]; N]: serde::Serialize",
+            deserialize = "[[T; M]; N]: serde::Deserialize<'de>"
+        )
+    )
+)]
+#[repr(transparent)]
+pub struct Matrix<T, const M: usize, const N: usize>(pub [[T; M]; N]);
+
+/// A vector of `N` elements.
+///
+/// ```
+/// # use mat::Vector;
+/// let mut v = Vector([1, 2, 3]);
+/// v += Vector([1, 1, 1]);
+/// assert_eq!(-v * 2, Vector([-4, -6, -8]));
+/// assert_eq!(v[2], 4);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        transparent,
+        bound(
+            serialize = "[T; N]: serde::Serialize",
+            deserialize = "[T; N]: serde::Deserialize<'de>"
+        )
+    )
+)]
+#[repr(transparent)]
+pub struct Vector<T, const N: usize>(pub [T; N]);
+
+impl<T, const M: usize, const N: usize> From<[[T; M]; N]> for Matrix<T, M, N> {
+    fn from(matrix: [[T; M]; N]) -> Self {
+        Self(matrix)
+    }
+}
+
+impl<T, const M: usize, const N: usize> From<Matrix<T, M, N>> for [[T; M]; N] {
+    fn from(matrix: Matrix<T, M, N>) -> Self {
+        matrix.0
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for Vector<T, N> {
+    fn from(vector: [T; N]) -> Self {
+        Self(vector)
+    }
+}
+
+impl<T, const N: usize> From<Vector<T, N>> for [T; N] {
+    fn from(vector: Vector<T, N>) -> Self {
+        vector.0
+    }
+}
+
+impl<T, const M: usize, const N: usize> Add for Matrix<T, M, N>
+where
+    T: Add<T, Output = T>,
+{
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.matrix_add(rhs.0))
+    }
+}
+
+impl<T, const M: usize, const N: usize> Sub for Matrix<T, M, N>
+where
+    T: Sub<T, Output = T>,
+{
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.matrix_sub(rhs.0))
+    }
+}
+
+impl<T, const M: usize, const N: usize> Neg for Matrix<T, M, N>
+where
+    T: Neg<Output = T>,
+{
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0.matrix_map(|v| -v))
+    }
+}
+
+/// The matrix scaled by a factor.
+impl<T, const M: usize, const N: usize> Mul<T> for Matrix<T, M, N>
+where
+    T: Mul<T, Output = T>,
+    T: Copy,
+{
+    type Output = Self;
+
+    fn mul(self, scalar: T) -> Self {
+        Self(self.0.matrix_scale(scalar))
+    }
+}
+
+/// The product of two matrices, applying the right one first.
+impl<T, const M: usize, const N: usize, const O: usize> Mul<Matrix<T, N, O>> for Matrix<T, M, N>
+where
+    T: Mul<T, Output = T>,
+    T: Sum,
+    T: Copy,
+{
+    type Output = Matrix<T, M, O>;
+
+    fn mul(self, rhs: Matrix<T, N, O>) -> Matrix<T, M, O> {
+        Matrix(self.0.matrix_mul(rhs.0))
+    }
+}
+
+/// The vector transformed by the matrix.
+impl<T, const M: usize, const N: usize> Mul<Vector<T, N>> for Matrix<T, M, N>
+where
+    T: Mul<T, Output = T>,
+    T: Sum,
+    T: Copy,
+{
+    type Output = Vector<T, M>;
+
+    fn mul(self, rhs: Vector<T, N>) -> Vector<T, M> {
+        let [column] = self.0.matrix_mul([rhs.0]);
+        Vector(column)
+    }
+}
+
+impl<T, const M: usize, const N: usize> Index<usize> for Matrix<T, M, N> {
+    type Output = [T; M];
+
+    fn index(&self, n: usize) -> &[T; M] {
+        &self.0[n]
+    }
+}
+
+impl<T, const M: usize, const N: usize> IndexMut<usize> for Matrix<T, M, N> {
+    fn index_mut(&mut self, n: usize) -> &mut [T; M] {
+        &mut self.0[n]
+    }
+}
+
+impl<T, const N: usize> Add for Vector<T, N>
+where
+    T: Add<T, Output = T>,
+{
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.vector_add(rhs.0))
+    }
+}
+
+impl<T, const N: usize> AddAssign for Vector<T, N>
+where
+    T: AddAssign<T>,
+{
+    fn add_assign(&mut self, rhs: Self) {
+        self.0.vector_add_assign(rhs.0);
+    }
+}
+
+impl<T, const N: usize> Sub for Vector<T, N>
+where
+    T: Sub<T, Output = T>,
+{
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.vector_sub(rhs.0))
+    }
+}
+
+impl<T, const N: usize> SubAssign for Vector<T, N>
+where
+    T: SubAssign<T>,
+{
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0.vector_sub_assign(rhs.0);
+    }
+}
+
+impl<T, const N: usize> Neg for Vector<T, N>
+where
+    T: Neg<Output = T>,
+{
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0.vector_neg())
+    }
+}
+
+/// The vector scaled by a factor.
+impl<T, const N: usize> Mul<T> for Vector<T, N>
+where
+    T: Mul<T, Output = T>,
+    T: Copy,
+{
+    type Output = Self;
+
+    fn mul(self, scalar: T) -> Self {
+        Self(self.0.vector_scale(scalar))
+    }
+}
+
+impl<T, const N: usize> Index<usize> for Vector<T, N> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        &self.0[i]
+    }
+}
+
+impl<T, const N: usize> IndexMut<usize> for Vector<T, N> {
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        &mut self.0[i]
+    }
+}